@@ -118,6 +118,7 @@ defaults = {
     "LABJACK_IDENTIFIER": "",
     "LABJACK_SERIAL": "",
     "LABJACK_NAME": "",
+    "LABJACK_DEVICES": "",
     "LABJACK_IP": "",
     "LABJACK_USB_ID": "ANY",
     "LABJACK_OPEN_ORDER": "ethernet,usb",
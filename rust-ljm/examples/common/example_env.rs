@@ -44,17 +44,13 @@ fn find_config_path() -> Option<PathBuf> {
         }
     }
 
-    for candidate in [
+    [
         PathBuf::from("streamer.env.json"),
         PathBuf::from("rust-ljm/streamer.env.json"),
         PathBuf::from("../rust-ljm/streamer.env.json"),
-    ] {
-        if candidate.exists() {
-            return Some(candidate);
-        }
-    }
-
-    None
+    ]
+    .into_iter()
+    .find(|candidate| candidate.exists())
 }
 
 pub fn config_hint() -> &'static str {
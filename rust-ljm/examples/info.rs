@@ -4,6 +4,8 @@ use ljmrs::LJMLibrary;
 mod example_env;
 #[path = "../src/labjack.rs"]
 mod labjack;
+#[path = "../src/ljm_discovery.rs"]
+mod ljm_discovery;
 #[path = "../src/ljm_mode.rs"]
 mod ljm_mode;
 
@@ -0,0 +1,489 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::channel_spec::{DigitalChannel, PublishedChannel};
+use crate::device_model::Capabilities;
+
+/// `STREAM_TRIGGER_INDEX` of DIO_EF0; DIOn triggers at `2000 + n`.
+const STREAM_TRIGGER_DIO_EF_BASE: u32 = 2000;
+/// `DIOn_EF_INDEX` values whose first edge starts a triggered stream.
+const EF_RISING_EDGE: u8 = 3;
+const EF_FALLING_EDGE: u8 = 4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    #[default]
+    Rising,
+    Falling,
+}
+
+/// What starts a triggered capture.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Trigger {
+    /// Hardware trigger: the device holds the stream until the edge arrives
+    /// on a DIO_EF line, so there are no pre-trigger scans.
+    Dio {
+        dio: u8,
+        #[serde(default)]
+        edge: Edge,
+    },
+    /// Software trigger: the stream runs continuously and the host watches an
+    /// analog channel cross `level` volts.
+    Threshold {
+        channel: u8,
+        level: f64,
+        #[serde(default)]
+        edge: Edge,
+    },
+}
+
+/// How a source acquires scans, from the top-level `acquisition` KV field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum Acquisition {
+    /// Free-running stream published batch by batch.
+    #[default]
+    Continuous,
+    /// `num_scans` scans through `STREAM_NUM_SCANS`, re-armed every
+    /// `interval_secs` (immediately when `0`).
+    Burst {
+        num_scans: u32,
+        #[serde(default)]
+        interval_secs: u64,
+    },
+    /// One capture per trigger: `pre_trigger_scans` before the trigger scan
+    /// and `post_trigger_scans` from it on. Re-armed after every capture.
+    Triggered {
+        trigger: Trigger,
+        #[serde(default)]
+        pre_trigger_scans: u32,
+        post_trigger_scans: u32,
+    },
+}
+
+impl Acquisition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Continuous => "continuous",
+            Self::Burst { .. } => "burst",
+            Self::Triggered { .. } => "triggered",
+        }
+    }
+
+    pub fn is_continuous(&self) -> bool {
+        matches!(self, Self::Continuous)
+    }
+
+    /// Scans after which the device stops on its own, for the modes that use
+    /// `STREAM_NUM_SCANS`.
+    pub fn finite_scans(&self) -> Option<u64> {
+        match *self {
+            Self::Burst { num_scans, .. } => Some(u64::from(num_scans)),
+            Self::Triggered {
+                trigger: Trigger::Dio { .. },
+                post_trigger_scans,
+                ..
+            } => Some(u64::from(post_trigger_scans)),
+            _ => None,
+        }
+    }
+
+    pub fn trigger(&self) -> Option<Trigger> {
+        match *self {
+            Self::Triggered { trigger, .. } => Some(trigger),
+            _ => None,
+        }
+    }
+
+    /// Whether `stream_read` may block indefinitely waiting for a hardware
+    /// trigger.
+    pub fn waits_for_trigger(&self) -> bool {
+        matches!(
+            self,
+            Self::Triggered {
+                trigger: Trigger::Dio { .. },
+                ..
+            }
+        )
+    }
+
+    /// Delay before a finished burst is re-armed.
+    pub fn rearm_delay(&self) -> std::time::Duration {
+        match *self {
+            Self::Burst { interval_secs, .. } => std::time::Duration::from_secs(interval_secs),
+            _ => std::time::Duration::ZERO,
+        }
+    }
+
+    /// Checks the mode against the model, the scan list and the digital
+    /// channels. Finite streams are read `scans_per_read` at a time, so their
+    /// length must be a multiple of it.
+    pub fn validate(
+        &self,
+        caps: &Capabilities,
+        analog: &[u8],
+        digital: &[DigitalChannel],
+        scans_per_read: i32,
+    ) -> Result<(), String> {
+        if let Some(scans) = self.finite_scans() {
+            let per_read = u64::try_from(scans_per_read.max(1)).unwrap_or(1);
+            if scans == 0 || !scans.is_multiple_of(per_read) {
+                return Err(format!(
+                    "{} mode needs a positive scan count that is a multiple of scans_per_read ({scans_per_read}), got {scans}",
+                    self.as_str()
+                ));
+            }
+        }
+        let Self::Triggered {
+            trigger,
+            pre_trigger_scans,
+            post_trigger_scans,
+        } = *self
+        else {
+            return Ok(());
+        };
+        if post_trigger_scans == 0 {
+            return Err("post_trigger_scans must be positive".to_string());
+        }
+        match trigger {
+            Trigger::Dio { dio, .. } => {
+                if pre_trigger_scans > 0 {
+                    return Err(
+                        "pre_trigger_scans needs a threshold trigger; a DIO trigger starts the stream on the edge"
+                            .to_string(),
+                    );
+                }
+                if !caps.dio_ef_lines.contains(&dio) {
+                    return Err(format!(
+                        "DIO{dio} cannot trigger a stream on a {}; DIO_EF lines are {:?}",
+                        caps.model.as_str(),
+                        caps.dio_ef_lines
+                    ));
+                }
+                if digital.iter().any(|channel| channel.ef_lines().contains(&dio)) {
+                    return Err(format!(
+                        "DIO{dio} is both the stream trigger and a digital channel's extended feature"
+                    ));
+                }
+            }
+            Trigger::Threshold { channel, level, .. } => {
+                if !analog.contains(&channel) {
+                    return Err(format!(
+                        "threshold trigger channel {channel} is not in channels_enabled"
+                    ));
+                }
+                if !level.is_finite() {
+                    return Err(format!("threshold trigger level {level} is not a number"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Register writes for the stream trigger and burst length. Continuous
+    /// and software-triggered streams write zeros so a previous config's
+    /// trigger or burst does not linger on the device.
+    pub fn register_writes(&self) -> Vec<(String, f64)> {
+        let mut writes = Vec::new();
+        match *self {
+            Self::Triggered {
+                trigger: Trigger::Dio { dio, edge },
+                ..
+            } => {
+                let index = match edge {
+                    Edge::Rising => EF_RISING_EDGE,
+                    Edge::Falling => EF_FALLING_EDGE,
+                };
+                writes.push((format!("DIO{dio}_EF_ENABLE"), 0.0));
+                writes.push((format!("DIO{dio}_EF_INDEX"), f64::from(index)));
+                writes.push((format!("DIO{dio}_EF_ENABLE"), 1.0));
+                writes.push((
+                    "STREAM_TRIGGER_INDEX".to_string(),
+                    f64::from(STREAM_TRIGGER_DIO_EF_BASE + u32::from(dio)),
+                ));
+            }
+            _ => writes.push(("STREAM_TRIGGER_INDEX".to_string(), 0.0)),
+        }
+        let num_scans = self.finite_scans().unwrap_or(0);
+        writes.push(("STREAM_NUM_SCANS".to_string(), num_scans as f64));
+        writes
+    }
+}
+
+/// One finished burst or triggered capture, interleaved like a stream batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub id: String,
+    pub first_sample_unix_ns: u64,
+    pub sample_interval_ns: u64,
+    pub pre_trigger_scans: usize,
+    pub values: Vec<f64>,
+}
+
+impl Capture {
+    pub fn scans(&self, width: usize) -> usize {
+        self.values.len() / width.max(1)
+    }
+}
+
+/// JSON event published once a capture's frames are queued.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureEvent<'a> {
+    pub capture_id: &'a str,
+    pub run_id: &'a str,
+    pub mode: &'static str,
+    pub trigger: Option<Trigger>,
+    pub first_sample_unix_ns: u64,
+    pub trigger_unix_ns: u64,
+    pub sample_interval_ns: u64,
+    pub scans: usize,
+    pub pre_trigger_scans: usize,
+    pub frames: usize,
+    pub channels: Vec<String>,
+}
+
+struct ActiveCapture {
+    first_sample_unix_ns: u64,
+    pre_trigger_scans: usize,
+    total_scans: usize,
+    values: Vec<f64>,
+}
+
+/// Cuts the decoded scan stream into captures. Hardware-finite modes turn
+/// every scan into one capture; threshold triggers keep a ring of the last
+/// `pre_trigger_scans` scans and start a capture on the crossing scan.
+pub struct CaptureRecorder {
+    width: usize,
+    threshold: Option<(usize, f64, Edge)>,
+    pre_trigger_scans: usize,
+    post_trigger_scans: usize,
+    /// Oldest first; the timestamp of each scan rides along with it.
+    history: VecDeque<(u64, Vec<f64>)>,
+    previous_level: Option<f64>,
+    active: Option<ActiveCapture>,
+}
+
+impl CaptureRecorder {
+    /// `None` for continuous acquisition.
+    pub fn new(acquisition: &Acquisition, published: &[PublishedChannel]) -> Option<Self> {
+        let width = published.len();
+        let (threshold, pre, post) = match *acquisition {
+            Acquisition::Continuous => return None,
+            Acquisition::Burst { num_scans, .. } => (None, 0, num_scans as usize),
+            Acquisition::Triggered {
+                trigger,
+                pre_trigger_scans,
+                post_trigger_scans,
+            } => {
+                let threshold = match trigger {
+                    Trigger::Dio { .. } => None,
+                    Trigger::Threshold {
+                        channel,
+                        level,
+                        edge,
+                    } => {
+                        let column = published
+                            .iter()
+                            .position(|p| *p == PublishedChannel::Analog(channel))?;
+                        Some((column, level, edge))
+                    }
+                };
+                (
+                    threshold,
+                    pre_trigger_scans as usize,
+                    post_trigger_scans as usize,
+                )
+            }
+        };
+        Some(Self {
+            width,
+            threshold,
+            pre_trigger_scans: pre,
+            post_trigger_scans: post,
+            history: VecDeque::with_capacity(pre),
+            previous_level: None,
+            active: None,
+        })
+    }
+
+    /// Feeds contiguous decoded scans and returns the captures they finish.
+    pub fn push(
+        &mut self,
+        first_sample_unix_ns: u64,
+        sample_interval_ns: u64,
+        values: &[f64],
+    ) -> Vec<Capture> {
+        let mut finished = Vec::new();
+        for (index, scan) in values.chunks_exact(self.width.max(1)).enumerate() {
+            let timestamp = first_sample_unix_ns + index as u64 * sample_interval_ns;
+            let crossed = self.crossed(scan);
+            if self.active.is_none() && crossed {
+                let pre_trigger_scans = self.history.len();
+                let first = self.history.front().map_or(timestamp, |(ts, _)| *ts);
+                let mut captured =
+                    Vec::with_capacity((pre_trigger_scans + self.post_trigger_scans) * self.width);
+                for (_, old) in self.history.drain(..) {
+                    captured.extend(old);
+                }
+                self.active = Some(ActiveCapture {
+                    first_sample_unix_ns: first,
+                    pre_trigger_scans,
+                    total_scans: pre_trigger_scans + self.post_trigger_scans,
+                    values: captured,
+                });
+            }
+
+            match self.active.as_mut() {
+                Some(active) => {
+                    active.values.extend_from_slice(scan);
+                    if active.values.len() >= active.total_scans * self.width {
+                        let active = self.active.take().expect("active capture");
+                        finished.push(Capture {
+                            id: uuid::Uuid::new_v4().to_string(),
+                            first_sample_unix_ns: active.first_sample_unix_ns,
+                            sample_interval_ns,
+                            pre_trigger_scans: active.pre_trigger_scans,
+                            values: active.values,
+                        });
+                    }
+                }
+                None if self.pre_trigger_scans > 0 => {
+                    if self.history.len() == self.pre_trigger_scans {
+                        self.history.pop_front();
+                    }
+                    self.history.push_back((timestamp, scan.to_vec()));
+                }
+                None => {}
+            }
+        }
+        finished
+    }
+
+    /// Drops the pre-trigger history and any partial capture after skipped
+    /// scans, which would otherwise be stitched across the gap. Returns
+    /// whether a partial capture was dropped.
+    pub fn discontinuity(&mut self) -> bool {
+        self.history.clear();
+        self.previous_level = None;
+        self.active.take().is_some()
+    }
+
+    /// Tracks the trigger channel on every scan, so a crossing right after a
+    /// capture ends is not missed.
+    fn crossed(&mut self, scan: &[f64]) -> bool {
+        let Some((column, level, edge)) = self.threshold else {
+            // Hardware-started streams: the first scan is the trigger.
+            return true;
+        };
+        let current = scan[column];
+        let previous = self.previous_level.replace(current);
+        match (previous, edge) {
+            (Some(previous), Edge::Rising) => previous < level && current >= level,
+            (Some(previous), Edge::Falling) => previous > level && current <= level,
+            (None, _) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_model::DeviceModel;
+
+    fn threshold(pre: u32, post: u32) -> Acquisition {
+        Acquisition::Triggered {
+            trigger: Trigger::Threshold {
+                channel: 11,
+                level: 1.0,
+                edge: Edge::Rising,
+            },
+            pre_trigger_scans: pre,
+            post_trigger_scans: post,
+        }
+    }
+
+    #[test]
+    fn modes_parse_and_validate() {
+        let burst: Acquisition =
+            serde_json::from_str(r#"{"mode":"burst","num_scans":1000}"#).unwrap();
+        assert_eq!(burst.finite_scans(), Some(1000));
+        let t7 = DeviceModel::T7.capabilities();
+        assert!(burst.validate(t7, &[11], &[], 100).is_ok());
+        assert!(burst.validate(t7, &[11], &[], 300).is_err());
+
+        let dio: Acquisition = serde_json::from_str(
+            r#"{"mode":"triggered","trigger":{"type":"dio","dio":0},"post_trigger_scans":500}"#,
+        )
+        .unwrap();
+        assert!(dio.waits_for_trigger());
+        assert!(dio.validate(t7, &[11], &[], 100).is_ok());
+        assert!(
+            dio.validate(t7, &[11], &[DigitalChannel::Counter { dio: 0 }], 100)
+                .is_err()
+        );
+        assert!(threshold(10, 50).validate(t7, &[11], &[], 100).is_ok());
+        assert!(threshold(10, 50).validate(t7, &[3], &[], 100).is_err());
+        assert!(Acquisition::default().is_continuous());
+    }
+
+    #[test]
+    fn register_writes_arm_and_clear_the_trigger() {
+        let dio = Acquisition::Triggered {
+            trigger: Trigger::Dio {
+                dio: 2,
+                edge: Edge::Falling,
+            },
+            pre_trigger_scans: 0,
+            post_trigger_scans: 200,
+        };
+        let writes = dio.register_writes();
+        assert!(writes.contains(&("DIO2_EF_INDEX".to_string(), 4.0)));
+        assert!(writes.contains(&("STREAM_TRIGGER_INDEX".to_string(), 2002.0)));
+        assert!(writes.contains(&("STREAM_NUM_SCANS".to_string(), 200.0)));
+
+        assert_eq!(
+            Acquisition::Continuous.register_writes(),
+            vec![
+                ("STREAM_TRIGGER_INDEX".to_string(), 0.0),
+                ("STREAM_NUM_SCANS".to_string(), 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn threshold_capture_includes_pre_trigger_scans() {
+        let published = [PublishedChannel::Analog(11)];
+        let mut recorder = CaptureRecorder::new(&threshold(2, 3), &published).unwrap();
+
+        assert!(recorder.push(0, 10, &[0.0, 0.2, 0.4, 0.6]).is_empty());
+        assert!(recorder.push(40, 10, &[1.5, 1.6]).is_empty());
+        let captures = recorder.push(60, 10, &[1.7, 0.0, 2.0]);
+        assert_eq!(captures.len(), 1);
+        let capture = &captures[0];
+        assert_eq!(capture.values, vec![0.4, 0.6, 1.5, 1.6, 1.7]);
+        assert_eq!(capture.first_sample_unix_ns, 20);
+        assert_eq!(capture.pre_trigger_scans, 2);
+
+        // Re-armed: 0.0 -> 2.0 crosses again, and a gap drops that capture.
+        assert!(recorder.discontinuity());
+        assert!(!recorder.discontinuity());
+    }
+
+    #[test]
+    fn burst_capture_covers_every_scan() {
+        let burst = Acquisition::Burst {
+            num_scans: 2,
+            interval_secs: 0,
+        };
+        let published = [PublishedChannel::Analog(0), PublishedChannel::Analog(1)];
+        let mut recorder = CaptureRecorder::new(&burst, &published).unwrap();
+        let captures = recorder.push(100, 5, &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures[0].scans(2), 2);
+        assert_eq!(captures[0].first_sample_unix_ns, 100);
+        assert!(CaptureRecorder::new(&Acquisition::Continuous, &published).is_none());
+    }
+}
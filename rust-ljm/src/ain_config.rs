@@ -0,0 +1,387 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::device_model::{Capabilities, DeviceModel, InputRanges, NegativeChannel};
+
+/// `AINn_NEGATIVE_CH` value that selects single-ended (GND referenced) input.
+pub const SINGLE_ENDED_NEGATIVE_CH: u8 = 199;
+
+/// Optional per-channel overrides from `sensor_settings.ain_settings`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct AinChannelOverride {
+    #[serde(default)]
+    pub range: Option<f64>,
+    #[serde(default)]
+    pub resolution_index: Option<u8>,
+    #[serde(default)]
+    pub settling_us: Option<u32>,
+    #[serde(default)]
+    pub negative_channel: Option<u8>,
+}
+
+/// Fully resolved analog input settings written to the `AINn_*` registers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AinChannelConfig {
+    pub channel: u8,
+    pub range: f64,
+    pub resolution_index: u8,
+    pub settling_us: u32,
+    pub negative_channel: u8,
+}
+
+impl AinChannelConfig {
+    pub fn is_differential(&self) -> bool {
+        self.negative_channel != SINGLE_ENDED_NEGATIVE_CH
+    }
+}
+
+/// Maps the legacy scalar `gains` field onto the smallest input range that
+/// still covers ±10 V / gain. Fixed-range inputs only accept `gains=1`.
+fn range_for_gain(caps: &Capabilities, gain: i32, channel: u8) -> Result<f64, String> {
+    if gain <= 0 {
+        return Err(format!("gains={gain} is not supported; expected a positive gain"));
+    }
+    match caps.ranges {
+        InputRanges::Fixed { .. } if gain != 1 => Err(format!(
+            "gains={gain} is not supported on a {}; its input ranges are fixed",
+            caps.model.as_str()
+        )),
+        InputRanges::Fixed { .. } => Ok(caps.fixed_range(channel).unwrap_or(10.0)),
+        InputRanges::Selectable(ranges) => {
+            let wanted = 10.0 / gain as f64;
+            ranges
+                .iter()
+                .copied()
+                .filter(|range| *range >= wanted * (1.0 - 1e-9))
+                .reduce(f64::min)
+                .ok_or_else(|| {
+                    format!(
+                        "gains={gain} has no matching {} range",
+                        caps.model.as_str()
+                    )
+                })
+        }
+    }
+}
+
+fn validate(caps: &Capabilities, cfg: &AinChannelConfig) -> Result<(), String> {
+    let ch = cfg.channel;
+    let model = caps.model.as_str();
+    if !caps.has_ain(ch) {
+        return Err(format!(
+            "AIN{ch} does not exist on a {model}; expected one of {:?}",
+            caps.ain_channels
+        ));
+    }
+    match caps.ranges {
+        InputRanges::Selectable(ranges) if !ranges.contains(&cfg.range) => {
+            return Err(format!(
+                "AIN{ch} range {} is not a {model} range; expected one of {:?}",
+                cfg.range, ranges
+            ));
+        }
+        InputRanges::Fixed { .. } if caps.fixed_range(ch) != Some(cfg.range) => {
+            return Err(format!(
+                "AIN{ch} range {} is not available on a {model}; the input is fixed at {:?}",
+                cfg.range,
+                caps.fixed_range(ch)
+            ));
+        }
+        _ => {}
+    }
+    if cfg.resolution_index > caps.max_stream_resolution_index {
+        return Err(format!(
+            "AIN{ch} resolution_index {} exceeds the {model} stream maximum of {}",
+            cfg.resolution_index, caps.max_stream_resolution_index
+        ));
+    }
+    if cfg.settling_us > caps.max_settling_us {
+        return Err(if caps.max_settling_us == 0 {
+            format!("AIN{ch} settling_us is not configurable on a {model}")
+        } else {
+            format!(
+                "AIN{ch} settling_us {} exceeds the {model} maximum of {}",
+                cfg.settling_us, caps.max_settling_us
+            )
+        });
+    }
+    if !cfg.is_differential() {
+        return Ok(());
+    }
+    match caps.negative_channel {
+        NegativeChannel::Pairs => match caps.differential_partner(ch) {
+            Some(partner) if partner == cfg.negative_channel => Ok(()),
+            Some(partner) => Err(format!(
+                "AIN{ch} negative_channel {} is invalid; a {model} can only pair AIN{ch} with AIN{partner}",
+                cfg.negative_channel
+            )),
+            None => Err(format!(
+                "AIN{ch} cannot be the positive side of a {model} differential pair"
+            )),
+        },
+        NegativeChannel::SingleEnded => Err(format!(
+            "AIN{ch} negative_channel {} is invalid; {model} inputs are single-ended",
+            cfg.negative_channel
+        )),
+        NegativeChannel::Isolated => Err(format!(
+            "AIN{ch} negative_channel {} is invalid; {model} inputs are always differential",
+            cfg.negative_channel
+        )),
+    }
+}
+
+/// Rejects scan rates above what `model` can stream for `channels` inputs.
+pub fn validate_scan_rate(model: DeviceModel, scan_rate_hz: f64, channels: usize) -> Result<(), String> {
+    let max = model.capabilities().max_scan_rate_hz(channels);
+    if scan_rate_hz > max {
+        return Err(format!(
+            "scan_rate_hz {scan_rate_hz} exceeds the {} maximum of {max} Hz for {channels} channel(s)",
+            model.as_str()
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves the analog settings for every enabled channel.
+///
+/// `gains` supplies the default range and a `data_formats` entry of
+/// `"differential"` pairs the channel with its T7 partner; T8 inputs are
+/// differential regardless. Entries in `overrides` (keyed by channel number)
+/// take precedence over both, and the result is validated against `model`.
+pub fn resolve_channels(
+    model: DeviceModel,
+    channels: &[u8],
+    gains: i32,
+    data_formats: &[String],
+    overrides: Option<&HashMap<String, AinChannelOverride>>,
+) -> Result<Vec<AinChannelConfig>, String> {
+    let caps = model.capabilities();
+
+    let mut by_channel: HashMap<u8, &AinChannelOverride> = HashMap::new();
+    for (key, value) in overrides.into_iter().flatten() {
+        let ch = key
+            .parse::<u8>()
+            .map_err(|_| format!("invalid ain_settings channel key '{key}', expected u8"))?;
+        if !channels.contains(&ch) {
+            return Err(format!(
+                "ain_settings has an entry for AIN{ch}, which is not in channels_enabled"
+            ));
+        }
+        by_channel.insert(ch, value);
+    }
+
+    if gains > 0 && !matches!(gains, 1 | 10 | 100 | 1000) {
+        warn!(
+            gains,
+            "gains is not one of 1, 10, 100 or 1000; using the smallest range covering ±10 V / gain"
+        );
+    }
+
+    channels
+        .iter()
+        .enumerate()
+        .map(|(index, &channel)| {
+            let overrides = by_channel
+                .get(&channel)
+                .copied()
+                .cloned()
+                .unwrap_or_default();
+            let differential = data_formats
+                .get(index)
+                .map(|format| format.trim().eq_ignore_ascii_case("differential"))
+                .unwrap_or(false);
+            let negative_channel = match overrides.negative_channel {
+                Some(negative) => negative,
+                None if differential && caps.negative_channel != NegativeChannel::Isolated => {
+                    caps.differential_partner(channel).ok_or_else(|| {
+                        format!(
+                            "AIN{channel} is marked differential but has no {} partner channel",
+                            model.as_str()
+                        )
+                    })?
+                }
+                None => SINGLE_ENDED_NEGATIVE_CH,
+            };
+            let range = match overrides.range {
+                Some(range) => range,
+                None => range_for_gain(caps, gains, channel)?,
+            };
+
+            let cfg = AinChannelConfig {
+                channel,
+                range,
+                resolution_index: overrides.resolution_index.unwrap_or(0),
+                settling_us: overrides.settling_us.unwrap_or(0),
+                negative_channel,
+            };
+            validate(caps, &cfg)?;
+            Ok(cfg)
+        })
+        .collect()
+}
+
+/// Stream mode uses one resolution index for the whole scan list, so the
+/// finest per-channel request wins.
+pub fn stream_resolution_index(channels: &[AinChannelConfig]) -> u8 {
+    channels
+        .iter()
+        .map(|cfg| cfg.resolution_index)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Stream mode also settles every channel for the same time; use the longest.
+pub fn stream_settling_us(channels: &[AinChannelConfig]) -> u32 {
+    channels
+        .iter()
+        .map(|cfg| cfg.settling_us)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formats(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn defaults_match_previous_hardcoded_settings() {
+        let resolved =
+            resolve_channels(DeviceModel::T7, &[7, 11], 1, &formats(&["voltage", "voltage"]), None).unwrap();
+        assert_eq!(resolved.len(), 2);
+        for cfg in &resolved {
+            assert_eq!(cfg.range, 10.0);
+            assert_eq!(cfg.resolution_index, 0);
+            assert_eq!(cfg.settling_us, 0);
+            assert_eq!(cfg.negative_channel, SINGLE_ENDED_NEGATIVE_CH);
+        }
+    }
+
+    #[test]
+    fn gain_and_differential_format_are_honored() {
+        let resolved = resolve_channels(
+            DeviceModel::T7,
+            &[2, 11],
+            100,
+            &formats(&["differential", "temperature"]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(resolved[0].range, 0.1);
+        assert_eq!(resolved[0].negative_channel, 3);
+        assert_eq!(resolved[1].negative_channel, SINGLE_ENDED_NEGATIVE_CH);
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "4".to_string(),
+            AinChannelOverride {
+                range: Some(0.01),
+                resolution_index: Some(8),
+                settling_us: Some(100),
+                negative_channel: Some(5),
+            },
+        );
+        let resolved = resolve_channels(DeviceModel::T7, &[4, 6], 1, &[], Some(&overrides)).unwrap();
+        assert_eq!(resolved[0].range, 0.01);
+        assert_eq!(resolved[0].negative_channel, 5);
+        assert_eq!(stream_resolution_index(&resolved), 8);
+        assert_eq!(stream_settling_us(&resolved), 100);
+    }
+
+    #[test]
+    fn off_table_gains_use_the_nearest_covering_range() {
+        let range = |gains| resolve_channels(DeviceModel::T7, &[0], gains, &[], None).unwrap()[0].range;
+        assert_eq!(range(2), 10.0);
+        assert_eq!(range(20), 1.0);
+        assert_eq!(range(5000), 0.01);
+    }
+
+    #[test]
+    fn invalid_t7_settings_are_rejected() {
+        assert!(resolve_channels(DeviceModel::T7, &[1], 1, &formats(&["differential"]), None).is_err());
+        assert!(resolve_channels(DeviceModel::T7, &[0], 0, &[], None).is_err());
+        assert!(resolve_channels(DeviceModel::T7, &[20], 1, &[], None).is_err());
+
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "0".to_string(),
+            AinChannelOverride {
+                negative_channel: Some(2),
+                ..Default::default()
+            },
+        );
+        assert!(resolve_channels(DeviceModel::T7, &[0], 1, &[], Some(&overrides)).is_err());
+
+        overrides.insert(
+            "0".to_string(),
+            AinChannelOverride {
+                resolution_index: Some(9),
+                ..Default::default()
+            },
+        );
+        assert!(resolve_channels(DeviceModel::T7, &[0], 1, &[], Some(&overrides)).is_err());
+    }
+
+    #[test]
+    fn mux80_pairs_are_offset_by_eight() {
+        let resolved = resolve_channels(DeviceModel::T7, &[48], 1, &formats(&["differential"]), None).unwrap();
+        assert_eq!(resolved[0].negative_channel, 56);
+        assert!(resolve_channels(DeviceModel::T7, &[56], 1, &formats(&["differential"]), None).is_err());
+    }
+
+    #[test]
+    fn t4_inputs_have_fixed_ranges() {
+        let resolved = resolve_channels(DeviceModel::T4, &[0, 4], 1, &[], None).unwrap();
+        assert_eq!(resolved[0].range, 10.0);
+        assert_eq!(resolved[1].range, 2.5);
+        assert!(resolve_channels(DeviceModel::T4, &[0], 10, &[], None).is_err());
+        assert!(resolve_channels(DeviceModel::T4, &[12], 1, &[], None).is_err());
+        assert!(
+            resolve_channels(DeviceModel::T4, &[4], 1, &formats(&["differential"]), None).is_err()
+        );
+    }
+
+    #[test]
+    fn t8_uses_its_own_ranges_and_is_always_differential() {
+        let resolved = resolve_channels(
+            DeviceModel::T8,
+            &[0, 1],
+            10,
+            &formats(&["differential", "voltage"]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(resolved[0].range, 1.2);
+        assert_eq!(resolved[0].negative_channel, SINGLE_ENDED_NEGATIVE_CH);
+        assert_eq!(
+            resolve_channels(DeviceModel::T8, &[7], 1, &[], None).unwrap()[0].range,
+            11.0
+        );
+        assert!(resolve_channels(DeviceModel::T8, &[8], 1, &[], None).is_err());
+
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "0".to_string(),
+            AinChannelOverride {
+                settling_us: Some(10),
+                ..Default::default()
+            },
+        );
+        assert!(resolve_channels(DeviceModel::T8, &[0], 1, &[], Some(&overrides)).is_err());
+    }
+
+    #[test]
+    fn scan_rate_limits_follow_the_model() {
+        assert!(validate_scan_rate(DeviceModel::T7, 25_000.0, 4).is_ok());
+        assert!(validate_scan_rate(DeviceModel::T7, 25_001.0, 4).is_err());
+        assert!(validate_scan_rate(DeviceModel::T4, 20_000.0, 4).is_err());
+        assert!(validate_scan_rate(DeviceModel::T8, 40_000.0, 8).is_ok());
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::channel_spec::PublishedChannel;
+
+fn default_margin() -> f64 {
+    0.02
+}
+
+/// Condition an alarm rule watches for on one analog channel.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// Value above `limit`; clears below `limit - hysteresis`.
+    High { limit: f64 },
+    /// Value below `limit`; clears above `limit + hysteresis`.
+    Low { limit: f64 },
+    /// The value stays within `tolerance` of where it settled for
+    /// `duration_secs`, e.g. a dead sensor reading a constant 0 V.
+    Flatline { tolerance: f64, duration_secs: f64 },
+    /// Within `margin` (a fraction of the range) of the configured AIN range,
+    /// i.e. pegged at the rail.
+    OutOfRange {
+        #[serde(default = "default_margin")]
+        margin: f64,
+    },
+    /// Change between consecutive scans faster than `limit_per_sec`.
+    RateOfChange { limit_per_sec: f64 },
+}
+
+impl Condition {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::High { .. } => "high",
+            Self::Low { .. } => "low",
+            Self::Flatline { .. } => "flatline",
+            Self::OutOfRange { .. } => "out_of_range",
+            Self::RateOfChange { .. } => "rate_of_change",
+        }
+    }
+}
+
+/// One rule from `sensor_settings.alarms`, keyed by AIN number.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AlarmRule {
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// Dead band a value must move back through before a raised alarm
+    /// clears, in volts (volts per second for `rate_of_change`).
+    #[serde(default)]
+    pub hysteresis: f64,
+    /// How long a condition must hold before the alarm raises, and its clear
+    /// condition before it clears.
+    #[serde(default)]
+    pub debounce_secs: f64,
+}
+
+impl AlarmRule {
+    fn validate(&self) -> Result<(), String> {
+        let non_negative = |name: &str, value: f64| {
+            if value.is_finite() && value >= 0.0 {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{} {name} must be zero or more",
+                    self.condition.as_str()
+                ))
+            }
+        };
+        non_negative("hysteresis", self.hysteresis)?;
+        non_negative("debounce_secs", self.debounce_secs)?;
+        match self.condition {
+            Condition::High { limit } | Condition::Low { limit } if !limit.is_finite() => Err(
+                format!("{} limit must be a number", self.condition.as_str()),
+            ),
+            Condition::Flatline {
+                tolerance,
+                duration_secs,
+            } => {
+                non_negative("tolerance", tolerance)?;
+                if duration_secs.is_finite() && duration_secs > 0.0 {
+                    Ok(())
+                } else {
+                    Err("flatline duration_secs must be greater than zero".to_string())
+                }
+            }
+            Condition::OutOfRange { margin } if !(0.0..1.0).contains(&margin) => {
+                Err("out_of_range margin must be at least 0 and below 1".to_string())
+            }
+            Condition::RateOfChange { limit_per_sec }
+                if !(limit_per_sec.is_finite() && limit_per_sec > 0.0) =>
+            {
+                Err("rate_of_change limit_per_sec must be greater than zero".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parses the KV `alarms` map (keyed by channel number as a string), skipping
+/// keys that are not valid channels like `calibrations` does.
+pub fn parse_channel_alarms(
+    raw: Option<&HashMap<String, Vec<AlarmRule>>>,
+) -> HashMap<u8, Vec<AlarmRule>> {
+    let mut out = HashMap::new();
+    for (key, rules) in raw.into_iter().flatten() {
+        match key.parse::<u8>() {
+            Ok(ch) => {
+                out.insert(ch, rules.clone());
+            }
+            Err(_) => warn!("Invalid alarm channel key '{key}', expected u8."),
+        }
+    }
+    out
+}
+
+pub fn validate(alarms: &HashMap<u8, Vec<AlarmRule>>, analog: &[u8]) -> Result<(), String> {
+    for (channel, rules) in alarms {
+        if !analog.contains(channel) {
+            return Err(format!(
+                "alarms configured for AIN{channel}, which is not enabled"
+            ));
+        }
+        for rule in rules {
+            rule.validate().map_err(|e| format!("AIN{channel}: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Raise/clear event published on the source's `.alarms` subject.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AlarmEvent {
+    /// `raised` or `cleared`.
+    pub state: &'static str,
+    pub run_id: usize,
+    pub channel: u8,
+    pub channel_name: String,
+    /// `high`, `low`, `flatline`, `out_of_range` or `rate_of_change`.
+    pub rule: &'static str,
+    /// Position of the rule in the channel's list, to tell similar rules apart.
+    pub rule_index: usize,
+    pub rule_config: AlarmRule,
+    /// Scan that completed the debounce.
+    pub timestamp_unix_ns: u64,
+    /// The value checked: the reading, or its rate for `rate_of_change`.
+    pub value: f64,
+    /// When the alarm was raised; set on `cleared` events.
+    pub raised_unix_ns: Option<u64>,
+}
+
+struct RuleState {
+    channel: u8,
+    column: usize,
+    index: usize,
+    rule: AlarmRule,
+    /// Full-scale range for `out_of_range`.
+    range: f64,
+    raised_unix_ns: Option<u64>,
+    /// Since when the condition that would flip the alarm has held.
+    pending_since: Option<u64>,
+    /// Previous scan, for `rate_of_change`.
+    previous: Option<(u64, f64)>,
+    /// Value and time the signal last moved by more than the tolerance,
+    /// for `flatline`.
+    settled: Option<(u64, f64)>,
+}
+
+impl RuleState {
+    /// `(raise, clear)` conditions for one scan and the value reported.
+    fn check(&mut self, timestamp: u64, value: f64) -> (bool, bool, f64) {
+        let hysteresis = self.rule.hysteresis;
+        match self.rule.condition {
+            Condition::High { limit } => (value > limit, value < limit - hysteresis, value),
+            Condition::Low { limit } => (value < limit, value > limit + hysteresis, value),
+            Condition::OutOfRange { margin } => {
+                let rail = self.range * (1.0 - margin);
+                let magnitude = value.abs();
+                (magnitude >= rail, magnitude < rail - hysteresis, value)
+            }
+            Condition::RateOfChange { limit_per_sec } => {
+                let rate = match self.previous.replace((timestamp, value)) {
+                    Some((t0, v0)) if timestamp > t0 => {
+                        (value - v0).abs() / ((timestamp - t0) as f64 * 1e-9)
+                    }
+                    _ => return (false, false, 0.0),
+                };
+                (
+                    rate > limit_per_sec,
+                    rate < limit_per_sec - hysteresis,
+                    rate,
+                )
+            }
+            Condition::Flatline {
+                tolerance,
+                duration_secs,
+            } => {
+                let (since, reference) = match self.settled {
+                    Some((since, reference)) if (value - reference).abs() <= tolerance => {
+                        (since, reference)
+                    }
+                    _ => (timestamp, value),
+                };
+                self.settled = Some((since, reference));
+                let flat = timestamp.saturating_sub(since) as f64 * 1e-9 >= duration_secs;
+                (flat, !flat, value)
+            }
+        }
+    }
+
+    fn observe(&mut self, run_id: usize, timestamp: u64, value: f64) -> Option<AlarmEvent> {
+        if !value.is_finite() {
+            return None;
+        }
+        let (raise, clear, checked) = self.check(timestamp, value);
+        let flipping = if self.raised_unix_ns.is_some() {
+            clear
+        } else {
+            raise
+        };
+        if !flipping {
+            self.pending_since = None;
+            return None;
+        }
+        let since = *self.pending_since.get_or_insert(timestamp);
+        if (timestamp.saturating_sub(since) as f64 * 1e-9) < self.rule.debounce_secs {
+            return None;
+        }
+        self.pending_since = None;
+        let raised_unix_ns = match self.raised_unix_ns.take() {
+            Some(raised) => Some(raised),
+            None => {
+                self.raised_unix_ns = Some(timestamp);
+                None
+            }
+        };
+        Some(AlarmEvent {
+            state: if raised_unix_ns.is_some() {
+                "cleared"
+            } else {
+                "raised"
+            },
+            run_id,
+            channel: self.channel,
+            channel_name: PublishedChannel::Analog(self.channel).token(),
+            rule: self.rule.condition.as_str(),
+            rule_index: self.index,
+            rule_config: self.rule,
+            timestamp_unix_ns: timestamp,
+            value: checked,
+            raised_unix_ns,
+        })
+    }
+}
+
+/// Evaluates every configured rule against the published scans.
+pub struct AlarmMonitor {
+    width: usize,
+    rules: Vec<RuleState>,
+}
+
+impl AlarmMonitor {
+    /// `ranges` maps each analog input to its configured range in volts.
+    pub fn new(
+        alarms: &HashMap<u8, Vec<AlarmRule>>,
+        published: &[PublishedChannel],
+        ranges: &HashMap<u8, f64>,
+    ) -> Self {
+        let mut rules = Vec::new();
+        for (channel, channel_rules) in alarms {
+            let Some(column) = published
+                .iter()
+                .position(|p| *p == PublishedChannel::Analog(*channel))
+            else {
+                continue;
+            };
+            for (index, rule) in channel_rules.iter().enumerate() {
+                rules.push(RuleState {
+                    channel: *channel,
+                    column,
+                    index,
+                    rule: *rule,
+                    range: ranges.get(channel).copied().unwrap_or(10.0),
+                    raised_unix_ns: None,
+                    pending_since: None,
+                    previous: None,
+                    settled: None,
+                });
+            }
+        }
+        rules.sort_by_key(|state| (state.channel, state.index));
+        Self {
+            width: published.len(),
+            rules,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Feeds contiguous scans and returns the raise/clear transitions.
+    pub fn observe(
+        &mut self,
+        run_id: usize,
+        first_sample_unix_ns: u64,
+        sample_interval_ns: u64,
+        scans: &[f64],
+    ) -> Vec<AlarmEvent> {
+        let mut events = Vec::new();
+        for (index, scan) in scans.chunks_exact(self.width.max(1)).enumerate() {
+            let timestamp = first_sample_unix_ns + index as u64 * sample_interval_ns;
+            for state in &mut self.rules {
+                events.extend(state.observe(run_id, timestamp, scan[state.column]));
+            }
+        }
+        events
+    }
+
+    /// Forgets per-scan history after skipped scans, so a rate or flatline is
+    /// not measured across the gap. Raised alarms stay raised.
+    pub fn discontinuity(&mut self) {
+        for state in &mut self.rules {
+            state.pending_since = None;
+            state.previous = None;
+            state.settled = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(rule: &str) -> AlarmMonitor {
+        let rule: AlarmRule = serde_json::from_str(rule).unwrap();
+        let mut alarms = HashMap::new();
+        alarms.insert(11, vec![rule]);
+        let mut ranges = HashMap::new();
+        ranges.insert(11, 10.0);
+        AlarmMonitor::new(&alarms, &[PublishedChannel::Analog(11)], &ranges)
+    }
+
+    /// Feeds one scan per millisecond and returns `(scan, state)` transitions.
+    fn states(monitor: &mut AlarmMonitor, values: &[f64]) -> Vec<(u64, &'static str)> {
+        monitor
+            .observe(1, 0, 1_000_000, values)
+            .into_iter()
+            .map(|event| (event.timestamp_unix_ns / 1_000_000, event.state))
+            .collect()
+    }
+
+    #[test]
+    fn rules_parse_and_validate() {
+        let mut alarms = HashMap::new();
+        alarms.insert(
+            11,
+            serde_json::from_str::<Vec<AlarmRule>>(
+                r#"[{ "type": "high", "limit": 4.5, "hysteresis": 0.1, "debounce_secs": 2 },
+                    { "type": "out_of_range" }]"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            alarms[&11][1].condition,
+            Condition::OutOfRange { margin: 0.02 }
+        );
+        assert!(validate(&alarms, &[11]).is_ok());
+        assert!(validate(&alarms, &[7]).is_err());
+
+        alarms.insert(
+            11,
+            vec![
+                serde_json::from_str(
+                    r#"{ "type": "flatline", "tolerance": 0.01, "duration_secs": 0 }"#,
+                )
+                .unwrap(),
+            ],
+        );
+        assert!(validate(&alarms, &[11]).is_err());
+    }
+
+    #[test]
+    fn high_limit_uses_hysteresis() {
+        let mut high = monitor(r#"{ "type": "high", "limit": 5.0, "hysteresis": 0.5 }"#);
+        assert_eq!(
+            states(&mut high, &[4.0, 5.1, 4.8, 5.2, 4.6, 4.4]),
+            vec![(1, "raised"), (5, "cleared")]
+        );
+    }
+
+    #[test]
+    fn debounce_ignores_short_excursions() {
+        let mut low = monitor(r#"{ "type": "low", "limit": 1.0, "debounce_secs": 0.002 }"#);
+        assert_eq!(
+            states(
+                &mut low,
+                &[2.0, 0.5, 0.5, 2.0, 0.5, 0.5, 0.5, 2.0, 2.0, 2.0]
+            ),
+            vec![(6, "raised"), (9, "cleared")]
+        );
+    }
+
+    #[test]
+    fn flatline_rail_and_rate_rules() {
+        let mut flat =
+            monitor(r#"{ "type": "flatline", "tolerance": 0.01, "duration_secs": 0.003 }"#);
+        assert_eq!(
+            states(&mut flat, &[0.0, 0.005, 0.0, 0.0, 0.5, 0.6]),
+            vec![(3, "raised"), (4, "cleared")]
+        );
+
+        let mut rail = monitor(r#"{ "type": "out_of_range", "margin": 0.05 }"#);
+        assert_eq!(
+            states(&mut rail, &[3.0, -9.6, -9.9, 3.0]),
+            vec![(1, "raised"), (3, "cleared")]
+        );
+
+        // 0.1 V per millisecond is 100 V/s.
+        let mut rate = monitor(r#"{ "type": "rate_of_change", "limit_per_sec": 50.0 }"#);
+        assert_eq!(
+            states(&mut rate, &[0.0, 0.01, 0.11, 0.12]),
+            vec![(2, "raised"), (3, "cleared")]
+        );
+        rate.discontinuity();
+        assert!(states(&mut rate, &[5.0]).is_empty());
+    }
+
+    #[test]
+    fn clear_events_carry_the_raise_time() {
+        let mut high = monitor(r#"{ "type": "high", "limit": 5.0 }"#);
+        let events = high.observe(3, 0, 1_000_000, &[6.0, 4.0]);
+        assert_eq!(events[0].raised_unix_ns, None);
+        assert_eq!(events[1].raised_unix_ns, Some(0));
+        assert_eq!(events[1].channel_name, "ch11");
+        assert_eq!(events[1].run_id, 3);
+    }
+}
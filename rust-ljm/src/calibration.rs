@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -42,3 +44,60 @@ impl Default for CalibrationSpec {
         }
     }
 }
+
+/// Converts the KV `calibrations` map (keyed by channel number as a string)
+/// into a per-channel lookup, skipping keys that are not valid channels.
+#[allow(dead_code)]
+pub fn parse_channel_calibrations(
+    raw: Option<&HashMap<String, CalibrationSpec>>,
+) -> HashMap<u8, CalibrationSpec> {
+    let mut out = HashMap::new();
+    let Some(calibrations) = raw else {
+        return out;
+    };
+    for (key, spec) in calibrations {
+        match key.parse::<u8>() {
+            Ok(ch) => {
+                out.insert(ch, spec.clone());
+            }
+            Err(_) => {
+                warn!("Invalid calibration channel key '{key}', expected u8.");
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_and_polynomial_apply() {
+        let linear = CalibrationSpec {
+            id: Some("lin".to_string()),
+            formula: CalibrationFormula::Linear { a: 2.0, b: 1.0 },
+        };
+        assert_eq!(linear.apply(3.0), 7.0);
+
+        let poly = CalibrationSpec {
+            id: None,
+            formula: CalibrationFormula::Polynomial {
+                coeffs: vec![1.0, 0.0, 2.0],
+            },
+        };
+        assert_eq!(poly.apply(2.0), 9.0);
+        assert_eq!(poly.id_or_default(), "identity");
+    }
+
+    #[test]
+    fn channel_calibrations_skip_invalid_keys() {
+        let mut raw = HashMap::new();
+        raw.insert("11".to_string(), CalibrationSpec::default());
+        raw.insert("ain3".to_string(), CalibrationSpec::default());
+
+        let parsed = parse_channel_calibrations(Some(&raw));
+        assert_eq!(parsed.len(), 1);
+        assert!(parsed.contains_key(&11));
+    }
+}
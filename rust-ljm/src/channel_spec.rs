@@ -0,0 +1,440 @@
+use serde::{Deserialize, Serialize};
+
+use crate::device_model::{Capabilities, InputRanges};
+use crate::subjects;
+
+/// Register that streams DIO0-DIO15 as one 16-bit word.
+const DIO_STATE_REGISTER: &str = "FIO_EIO_STATE";
+/// Upper 16 bits of the 32-bit register scanned just before it.
+const CAPTURE_REGISTER: &str = "STREAM_DATA_CAPTURE_16";
+
+/// `DIOn_EF_INDEX` values for the extended features the streamer drives.
+const EF_FREQUENCY_IN_RISING: u8 = 3;
+const EF_HIGH_SPEED_COUNTER: u8 = 7;
+const EF_INTERRUPT_COUNTER: u8 = 8;
+const EF_QUADRATURE_IN: u8 = 10;
+
+/// A non-analog channel from `sensor_settings.digital_channels`, streamed
+/// after the analog inputs and published on its own subject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DigitalChannel {
+    /// One line of `FIO_EIO_STATE`, published as 0 or 1 on `.dioN`.
+    Dio { dio: u8 },
+    /// Edge count on `.ctrN`: the high-speed counter on lines that have one,
+    /// the DIO_EF interrupt counter elsewhere.
+    Counter { dio: u8 },
+    /// Rising-edge frequency in Hz on `.freqN`, from the DIO_EF period
+    /// measurement clocked by `DIO_EF_CLOCK0`.
+    Frequency { dio: u8 },
+    /// Signed quadrature count on `.quadN`, phase A on `dio` and phase B on
+    /// `dio + 1`.
+    Quadrature { dio: u8 },
+}
+
+impl DigitalChannel {
+    pub fn dio(&self) -> u8 {
+        match *self {
+            Self::Dio { dio }
+            | Self::Counter { dio }
+            | Self::Frequency { dio }
+            | Self::Quadrature { dio } => dio,
+        }
+    }
+
+    /// Last token of the channel's subject, e.g. `dio0` or `ctr1`.
+    pub fn token(&self) -> String {
+        let dio = self.dio();
+        match self {
+            Self::Dio { .. } => format!("dio{dio}"),
+            Self::Counter { .. } => format!("ctr{dio}"),
+            Self::Frequency { .. } => format!("freq{dio}"),
+            Self::Quadrature { .. } => format!("quad{dio}"),
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Dio { .. } => "state",
+            Self::Counter { .. } | Self::Quadrature { .. } => "counts",
+            Self::Frequency { .. } => "Hz",
+        }
+    }
+
+    /// DIO lines the extended feature claims, empty for plain state reads.
+    pub fn ef_lines(&self) -> Vec<u8> {
+        match *self {
+            Self::Dio { .. } => Vec::new(),
+            Self::Counter { dio } | Self::Frequency { dio } => vec![dio],
+            Self::Quadrature { dio } => vec![dio, dio + 1],
+        }
+    }
+
+    fn validate(&self, caps: &Capabilities) -> Result<(), String> {
+        let dio = self.dio();
+        let model = caps.model.as_str();
+        let ok = match self {
+            Self::Dio { .. } => caps.has_dio_state(dio),
+            Self::Counter { .. } => {
+                caps.dio_ef_lines.contains(&dio) || caps.high_speed_counters.contains(&dio)
+            }
+            Self::Frequency { .. } => caps.dio_ef_lines.contains(&dio),
+            Self::Quadrature { .. } => {
+                dio.is_multiple_of(2)
+                    && caps.dio_ef_lines.contains(&dio)
+                    && caps.dio_ef_lines.contains(&(dio + 1))
+            }
+        };
+        if ok {
+            return Ok(());
+        }
+        Err(match self {
+            Self::Dio { .. } => format!(
+                "{} is not readable through {DIO_STATE_REGISTER} on a {model}; expected one of {:?}",
+                self.token(),
+                caps.dio_state_lines
+            ),
+            Self::Quadrature { .. } => format!(
+                "{} needs an even DIO_EF line paired with the next one on a {model}; DIO_EF lines are {:?}",
+                self.token(),
+                caps.dio_ef_lines
+            ),
+            _ => format!(
+                "{} is not supported on a {model}; DIO_EF lines are {:?}, high-speed counters {:?}",
+                self.token(),
+                caps.dio_ef_lines,
+                caps.high_speed_counters
+            ),
+        })
+    }
+
+    /// Register writes that set up the extended feature, in order. The
+    /// feature is disabled first because `DIOn_EF_INDEX` only changes while
+    /// disabled.
+    fn ef_writes(&self) -> Vec<(String, f64)> {
+        let index = match *self {
+            Self::Dio { .. } => return Vec::new(),
+            Self::Counter { dio } if (16..=19).contains(&dio) => EF_HIGH_SPEED_COUNTER,
+            Self::Counter { .. } => EF_INTERRUPT_COUNTER,
+            Self::Frequency { .. } => EF_FREQUENCY_IN_RISING,
+            Self::Quadrature { .. } => EF_QUADRATURE_IN,
+        };
+        let lines = self.ef_lines();
+        let mut writes = Vec::new();
+        for line in &lines {
+            writes.push((format!("DIO{line}_EF_ENABLE"), 0.0));
+            writes.push((format!("DIO{line}_EF_INDEX"), f64::from(index)));
+            if let Self::Frequency { .. } = self {
+                // Clock source 0, continuous measurement.
+                writes.push((format!("DIO{line}_EF_OPTIONS"), 0.0));
+                writes.push((format!("DIO{line}_EF_CONFIG_A"), 0.0));
+            }
+        }
+        for line in &lines {
+            writes.push((format!("DIO{line}_EF_ENABLE"), 1.0));
+        }
+        writes
+    }
+}
+
+/// Checks every digital channel against the model and against each other.
+/// On a T4 the flexible lines are either analog or digital, so a line also
+/// listed in `channels_enabled` is rejected.
+pub fn validate(
+    caps: &Capabilities,
+    analog: &[u8],
+    digital: &[DigitalChannel],
+) -> Result<(), String> {
+    let mut claimed: Vec<u8> = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    for channel in digital {
+        channel.validate(caps)?;
+        let token = channel.token();
+        if tokens.contains(&token) {
+            return Err(format!("{token} is listed more than once"));
+        }
+        tokens.push(token);
+        for line in channel.ef_lines() {
+            if claimed.contains(&line) {
+                return Err(format!(
+                    "DIO{line} is used by more than one extended feature"
+                ));
+            }
+            claimed.push(line);
+        }
+        if let InputRanges::Fixed { low_voltage_from } = caps.ranges {
+            let lines = match channel.ef_lines() {
+                lines if lines.is_empty() => vec![channel.dio()],
+                lines => lines,
+            };
+            if let Some(dio) = lines
+                .into_iter()
+                .find(|dio| *dio >= low_voltage_from && analog.contains(dio))
+            {
+                return Err(format!(
+                    "DIO{dio} is enabled as AIN{dio}; a {} line is either analog or digital",
+                    caps.model.as_str()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Register writes for every extended feature in `digital`, `DIO_EF_CLOCK0`
+/// first when a frequency input needs it.
+pub fn ef_writes(digital: &[DigitalChannel]) -> Vec<(String, f64)> {
+    let mut writes = Vec::new();
+    if digital
+        .iter()
+        .any(|channel| matches!(channel, DigitalChannel::Frequency { .. }))
+    {
+        // Divisor 1 and roll value 0 (the full 32 bits) keep the clock at the
+        // core frequency the period is converted with.
+        writes.push(("DIO_EF_CLOCK0_ENABLE".to_string(), 0.0));
+        writes.push(("DIO_EF_CLOCK0_DIVISOR".to_string(), 1.0));
+        writes.push(("DIO_EF_CLOCK0_ROLL_VALUE".to_string(), 0.0));
+        writes.push(("DIO_EF_CLOCK0_ENABLE".to_string(), 1.0));
+    }
+    writes.extend(digital.iter().flat_map(DigitalChannel::ef_writes));
+    writes
+}
+
+/// One published channel, in the order `ScanLayout::decode` emits them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishedChannel {
+    Analog(u8),
+    Digital(DigitalChannel),
+    /// Derived by `dsp::Pipeline`; never part of the decoded scan.
+    Virtual { id: u8, unit: &'static str },
+}
+
+impl PublishedChannel {
+    pub fn token(&self) -> String {
+        match self {
+            Self::Analog(ch) => subjects::pad_channel(*ch),
+            Self::Digital(channel) => channel.token(),
+            Self::Virtual { id, .. } => subjects::pad_virtual(*id),
+        }
+    }
+
+    /// The `channel` field of `Scan` and `ScanFrame`: the AIN number, or -1.
+    pub fn ain_number(&self) -> i16 {
+        match self {
+            Self::Analog(ch) => i16::from(*ch),
+            Self::Digital(_) | Self::Virtual { .. } => -1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Raw(usize),
+    Bit {
+        index: usize,
+        bit: u8,
+    },
+    /// A 32-bit register followed by its `STREAM_DATA_CAPTURE_16` high word.
+    Unsigned {
+        low: usize,
+    },
+    Signed {
+        low: usize,
+    },
+    Frequency {
+        low: usize,
+        clock_hz: f64,
+    },
+}
+
+/// Maps the published channels onto the stream's scan list. Analog inputs
+/// come first, one register each, so their columns line up with
+/// `channels_enabled`; 32-bit extended feature registers take two entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanLayout {
+    registers: Vec<String>,
+    published: Vec<PublishedChannel>,
+    columns: Vec<Column>,
+}
+
+impl ScanLayout {
+    pub fn new(caps: &Capabilities, analog: &[u8], digital: &[DigitalChannel]) -> Self {
+        let mut registers: Vec<String> = analog.iter().map(|ch| format!("AIN{ch}")).collect();
+        let mut published: Vec<PublishedChannel> = analog
+            .iter()
+            .map(|ch| PublishedChannel::Analog(*ch))
+            .collect();
+        let mut columns: Vec<Column> = (0..analog.len()).map(Column::Raw).collect();
+
+        for channel in digital {
+            let column = match *channel {
+                DigitalChannel::Dio { dio } => {
+                    let index = match registers.iter().position(|r| r == DIO_STATE_REGISTER) {
+                        Some(index) => index,
+                        None => {
+                            registers.push(DIO_STATE_REGISTER.to_string());
+                            registers.len() - 1
+                        }
+                    };
+                    Column::Bit { index, bit: dio }
+                }
+                _ => {
+                    let low = registers.len();
+                    registers.push(format!("DIO{}_EF_READ_A", channel.dio()));
+                    registers.push(CAPTURE_REGISTER.to_string());
+                    match channel {
+                        DigitalChannel::Frequency { .. } => Column::Frequency {
+                            low,
+                            clock_hz: caps.dio_ef_clock_hz,
+                        },
+                        DigitalChannel::Quadrature { .. } => Column::Signed { low },
+                        _ => Column::Unsigned { low },
+                    }
+                }
+            };
+            published.push(PublishedChannel::Digital(*channel));
+            columns.push(column);
+        }
+
+        Self {
+            registers,
+            published,
+            columns,
+        }
+    }
+
+    /// Register names to stream, in scan-list order.
+    pub fn registers(&self) -> &[String] {
+        &self.registers
+    }
+
+    pub fn published(&self) -> &[PublishedChannel] {
+        &self.published
+    }
+
+    /// Turns interleaved raw scans (`registers().len()` values each) into
+    /// interleaved published values (`published().len()` values each).
+    pub fn decode(&self, raw: &[f64]) -> Vec<f64> {
+        let width = self.registers.len();
+        let mut values = Vec::with_capacity(raw.len() / width.max(1) * self.columns.len());
+        for scan in raw.chunks_exact(width) {
+            for column in &self.columns {
+                values.push(match *column {
+                    Column::Raw(index) => scan[index],
+                    Column::Bit { index, bit } => f64::from((scan[index] as u32 >> bit) & 1),
+                    Column::Unsigned { low } => f64::from(combine(scan, low)),
+                    Column::Signed { low } => f64::from(combine(scan, low) as i32),
+                    Column::Frequency { low, clock_hz } => match combine(scan, low) {
+                        0 => 0.0,
+                        period_ticks => clock_hz / f64::from(period_ticks),
+                    },
+                });
+            }
+        }
+        values
+    }
+}
+
+fn combine(scan: &[f64], low: usize) -> u32 {
+    (scan[low] as u32 & 0xFFFF) | ((scan[low + 1] as u32 & 0xFFFF) << 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_model::DeviceModel;
+
+    #[test]
+    fn specs_parse_and_validate_per_model() {
+        let digital: Vec<DigitalChannel> = serde_json::from_str(
+            r#"[{"type":"dio","dio":0},{"type":"counter","dio":1},{"type":"quadrature","dio":2}]"#,
+        )
+        .unwrap();
+        let t7 = DeviceModel::T7.capabilities();
+        assert!(validate(t7, &[0], &digital).is_ok());
+        assert_eq!(digital[1].token(), "ctr1");
+
+        let overlapping = [
+            DigitalChannel::Quadrature { dio: 2 },
+            DigitalChannel::Counter { dio: 3 },
+        ];
+        assert!(validate(t7, &[], &overlapping).is_err());
+        assert!(validate(t7, &[], &[DigitalChannel::Frequency { dio: 4 }]).is_err());
+        assert!(validate(t7, &[], &[DigitalChannel::Counter { dio: 17 }]).is_ok());
+
+        let t4 = DeviceModel::T4.capabilities();
+        assert!(validate(t4, &[5], &[DigitalChannel::Counter { dio: 5 }]).is_err());
+        assert!(validate(t4, &[7], &[DigitalChannel::Quadrature { dio: 6 }]).is_err());
+        assert!(
+            validate(
+                DeviceModel::T8.capabilities(),
+                &[],
+                &[DigitalChannel::Counter { dio: 0 }]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn ef_writes_configure_clock_and_both_quadrature_lines() {
+        let writes = ef_writes(&[
+            DigitalChannel::Frequency { dio: 0 },
+            DigitalChannel::Quadrature { dio: 6 },
+        ]);
+        assert_eq!(writes[0], ("DIO_EF_CLOCK0_ENABLE".to_string(), 0.0));
+        assert!(writes.contains(&("DIO0_EF_INDEX".to_string(), 3.0)));
+        let enables: Vec<&str> = writes
+            .iter()
+            .filter(|(name, value)| name.ends_with("_EF_ENABLE") && *value == 1.0)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            enables,
+            vec!["DIO0_EF_ENABLE", "DIO6_EF_ENABLE", "DIO7_EF_ENABLE"]
+        );
+    }
+
+    #[test]
+    fn layout_decodes_bits_captures_and_periods() {
+        let layout = ScanLayout::new(
+            DeviceModel::T7.capabilities(),
+            &[11],
+            &[
+                DigitalChannel::Dio { dio: 0 },
+                DigitalChannel::Dio { dio: 2 },
+                DigitalChannel::Counter { dio: 1 },
+                DigitalChannel::Frequency { dio: 3 },
+                DigitalChannel::Quadrature { dio: 6 },
+            ],
+        );
+        assert_eq!(
+            layout.registers(),
+            [
+                "AIN11",
+                "FIO_EIO_STATE",
+                "DIO1_EF_READ_A",
+                "STREAM_DATA_CAPTURE_16",
+                "DIO3_EF_READ_A",
+                "STREAM_DATA_CAPTURE_16",
+                "DIO6_EF_READ_A",
+                "STREAM_DATA_CAPTURE_16",
+            ]
+        );
+        let raw = [
+            1.5,
+            0b101 as f64,
+            3.0,
+            1.0,
+            8_000.0,
+            0.0,
+            65_535.0,
+            65_535.0,
+        ];
+        assert_eq!(
+            layout.decode(&raw),
+            vec![1.5, 1.0, 1.0, 65_539.0, 10_000.0, -1.0]
+        );
+        assert_eq!(layout.published()[3].token(), "ctr1");
+        assert_eq!(layout.published()[3].ain_number(), -1);
+    }
+}
@@ -0,0 +1,272 @@
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Kernel `adjtimex` values, from `<sys/timex.h>`.
+const TIME_ERROR: i32 = 5;
+const STA_UNSYNC: i32 = 0x0040;
+const STA_NANO: i32 = 0x2000;
+
+const DEFAULT_MAX_ERROR_MS: u64 = 100;
+const DEFAULT_CHECK_SECS: u64 = 60;
+
+/// Host clock discipline, from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockQuality {
+    /// NTP/PTP reports the clock synchronized within `STREAM_CLOCK_MAX_ERROR_MS`.
+    Synchronized,
+    /// Synchronized, but the kernel's maximum error exceeds the limit.
+    Degraded,
+    /// The state could not be read, e.g. on a non-Linux host.
+    Unknown,
+    /// The kernel flags the clock unsynchronized.
+    Unsynchronized,
+}
+
+impl ClockQuality {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Synchronized => "synchronized",
+            Self::Degraded => "degraded",
+            Self::Unknown => "unknown",
+            Self::Unsynchronized => "unsynchronized",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "synchronized" => Some(Self::Synchronized),
+            "degraded" => Some(Self::Degraded),
+            "unknown" => Some(Self::Unknown),
+            "unsynchronized" => Some(Self::Unsynchronized),
+            _ => None,
+        }
+    }
+}
+
+/// One reading of the kernel clock discipline state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ClockCheck {
+    pub quality: ClockQuality,
+    /// Kernel maximum error bound of the system clock.
+    pub max_error_ns: Option<u64>,
+    /// Kernel estimated error of the system clock.
+    pub est_error_ns: Option<u64>,
+    /// Last offset the NTP/PTP daemon measured against its reference.
+    pub offset_ns: Option<i64>,
+}
+
+impl ClockCheck {
+    pub fn unknown() -> Self {
+        Self {
+            quality: ClockQuality::Unknown,
+            max_error_ns: None,
+            est_error_ns: None,
+            offset_ns: None,
+        }
+    }
+
+    /// Error bound stamped on published batches, `-1` when unknown.
+    pub fn error_bound_ns(&self) -> i64 {
+        self.max_error_ns
+            .and_then(|ns| i64::try_from(ns).ok())
+            .unwrap_or(-1)
+    }
+}
+
+/// What the streamer does when the host clock is not synchronized at start.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClockPolicy {
+    /// Stream anyway; the quality is flagged on every batch and on `.status`.
+    #[default]
+    Flag,
+    /// Do not start a run until the clock is synchronized.
+    Refuse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockQualityConfig {
+    pub policy: ClockPolicy,
+    /// Maximum error bound still treated as synchronized.
+    pub max_error: Duration,
+    /// How often the clock state is re-read while streaming.
+    pub check_interval: Duration,
+}
+
+impl Default for ClockQualityConfig {
+    fn default() -> Self {
+        Self {
+            policy: ClockPolicy::Flag,
+            max_error: Duration::from_millis(DEFAULT_MAX_ERROR_MS),
+            check_interval: Duration::from_secs(DEFAULT_CHECK_SECS),
+        }
+    }
+}
+
+impl ClockQualityConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let policy = match std::env::var("STREAM_CLOCK_POLICY")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "" | "flag" => ClockPolicy::Flag,
+            "refuse" => ClockPolicy::Refuse,
+            other => {
+                return Err(format!(
+                    "invalid STREAM_CLOCK_POLICY '{other}', expected flag or refuse"
+                ));
+            }
+        };
+        Ok(Self {
+            policy,
+            max_error: Duration::from_millis(env_positive(
+                "STREAM_CLOCK_MAX_ERROR_MS",
+                DEFAULT_MAX_ERROR_MS,
+            )?),
+            check_interval: Duration::from_secs(env_positive(
+                "STREAM_CLOCK_CHECK_SECS",
+                DEFAULT_CHECK_SECS,
+            )?),
+        })
+    }
+
+    /// Whether a run may start with the clock in this state. An unreadable
+    /// state is never refused, so non-Linux hosts keep working.
+    pub fn allows(&self, check: &ClockCheck) -> bool {
+        match self.policy {
+            ClockPolicy::Flag => true,
+            ClockPolicy::Refuse => matches!(
+                check.quality,
+                ClockQuality::Synchronized | ClockQuality::Unknown
+            ),
+        }
+    }
+}
+
+fn env_positive(name: &str, default: u64) -> Result<u64, String> {
+    match std::env::var(name) {
+        Ok(raw) if !raw.trim().is_empty() => raw
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|value| *value > 0)
+            .ok_or_else(|| format!("invalid {name} '{raw}', expected a positive integer")),
+        _ => Ok(default),
+    }
+}
+
+/// Interprets `adjtimex` output: its return value, `status` bits, and the
+/// `maxerror`/`esterror` (µs) and `offset` (µs, or ns with `STA_NANO`) fields.
+pub fn classify(
+    clock_state: i32,
+    status: i32,
+    max_error_us: i64,
+    est_error_us: i64,
+    offset: i64,
+    max_error: Duration,
+) -> ClockCheck {
+    let max_error_ns = max_error_us.max(0) as u64 * 1_000;
+    let quality = if clock_state == TIME_ERROR || status & STA_UNSYNC != 0 {
+        ClockQuality::Unsynchronized
+    } else if u128::from(max_error_ns) > max_error.as_nanos() {
+        ClockQuality::Degraded
+    } else {
+        ClockQuality::Synchronized
+    };
+    let offset_ns = if status & STA_NANO != 0 {
+        offset
+    } else {
+        offset.saturating_mul(1_000)
+    };
+    ClockCheck {
+        quality,
+        max_error_ns: Some(max_error_ns),
+        est_error_ns: Some(est_error_us.max(0) as u64 * 1_000),
+        offset_ns: Some(offset_ns),
+    }
+}
+
+/// Reads the kernel clock state without adjusting anything.
+#[cfg(target_os = "linux")]
+pub fn check(max_error: Duration) -> ClockCheck {
+    // SAFETY: `timex` is plain data and `modes == 0` makes adjtimex read-only.
+    let mut timex: libc::timex = unsafe { std::mem::zeroed() };
+    let clock_state = unsafe { libc::adjtimex(&mut timex) };
+    if clock_state < 0 {
+        warn!(
+            "adjtimex failed: {}",
+            std::io::Error::last_os_error()
+        );
+        return ClockCheck::unknown();
+    }
+    classify(
+        clock_state,
+        timex.status,
+        timex.maxerror as i64,
+        timex.esterror as i64,
+        timex.offset as i64,
+        max_error,
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn check(_max_error: Duration) -> ClockCheck {
+    ClockCheck::unknown()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn classifies_kernel_clock_state() {
+        let synced = classify(0, 0x2001, 12_000, 300, 45_000, LIMIT);
+        assert_eq!(synced.quality, ClockQuality::Synchronized);
+        assert_eq!(synced.max_error_ns, Some(12_000_000));
+        assert_eq!(synced.offset_ns, Some(45_000));
+        assert_eq!(synced.error_bound_ns(), 12_000_000);
+
+        let loose = classify(0, 0x0001, 250_000, 300, 45, LIMIT);
+        assert_eq!(loose.quality, ClockQuality::Degraded);
+        assert_eq!(loose.offset_ns, Some(45_000));
+
+        assert_eq!(
+            classify(TIME_ERROR, 0, 0, 0, 0, LIMIT).quality,
+            ClockQuality::Unsynchronized
+        );
+        assert_eq!(
+            classify(0, STA_UNSYNC, 0, 0, 0, LIMIT).quality,
+            ClockQuality::Unsynchronized
+        );
+    }
+
+    #[test]
+    fn refuse_policy_only_blocks_known_bad_clocks() {
+        let refuse = ClockQualityConfig {
+            policy: ClockPolicy::Refuse,
+            ..Default::default()
+        };
+        let mut check = ClockCheck::unknown();
+        assert!(refuse.allows(&check));
+        check.quality = ClockQuality::Degraded;
+        assert!(!refuse.allows(&check));
+        assert!(ClockQualityConfig::default().allows(&check));
+    }
+
+    #[test]
+    fn quality_orders_from_best_to_worst() {
+        let worst = [ClockQuality::Degraded, ClockQuality::Unsynchronized]
+            .into_iter()
+            .max();
+        assert_eq!(worst, Some(ClockQuality::Unsynchronized));
+        assert_eq!(ClockQuality::parse("degraded"), Some(ClockQuality::Degraded));
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ljmrs::LJMError;
+use serde::Serialize;
+
+use crate::device::StreamDevice;
+use crate::publisher::env_number;
+
+/// `CORE_TIMER` counts at half the 80 MHz core clock on the T-series.
+pub const CORE_TIMER_HZ: f64 = 40_000_000.0;
+const CORE_TIMER_WRAP: f64 = 4_294_967_296.0;
+/// The 32-bit timer wraps every ~107 s; correlating at least this often keeps
+/// the wrap count unambiguous even if one read is lost.
+const MAX_SYNC_INTERVAL_SECS: u64 = 50;
+const DEFAULT_WINDOW: usize = 60;
+const MIN_FIT_SAMPLES: usize = 3;
+/// Below this span the host timestamp jitter dominates the fitted skew.
+const MIN_FIT_SPAN_NS: u64 = 120_000_000_000;
+/// Reads slower than this say more about the network than about the clocks.
+const MAX_ROUND_TRIP_NS: u64 = 20_000_000;
+/// Reads slower than this multiple of the fastest one seen are skipped.
+const ROUND_TRIP_FILTER: u64 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSyncConfig {
+    /// How often `CORE_TIMER` is read against host time.
+    pub interval: Duration,
+    /// Correlations kept for the skew fit.
+    pub window: usize,
+}
+
+impl ClockSyncConfig {
+    /// `None` unless `STREAM_CLOCK_SYNC_SECS` is set, which keeps timestamps
+    /// extrapolated from the nominal scan rate.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let secs: u64 = env_number("STREAM_CLOCK_SYNC_SECS", 0, 0)?;
+        if secs == 0 {
+            return Ok(None);
+        }
+        if secs > MAX_SYNC_INTERVAL_SECS {
+            return Err(format!(
+                "STREAM_CLOCK_SYNC_SECS must be at most {MAX_SYNC_INTERVAL_SECS} so CORE_TIMER wraps stay unambiguous, got {secs}"
+            ));
+        }
+        Ok(Some(Self {
+            interval: Duration::from_secs(secs),
+            window: env_number("STREAM_CLOCK_SYNC_WINDOW", DEFAULT_WINDOW, MIN_FIT_SAMPLES)?,
+        }))
+    }
+}
+
+/// One `CORE_TIMER` read, stamped with the host time halfway through it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Correlation {
+    pub host_unix_ns: u64,
+    pub core_timer: u32,
+    pub round_trip_ns: u64,
+}
+
+fn host_unix_ns() -> Result<u64, LJMError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| LJMError::LibraryError(format!("system clock before Unix epoch: {e}")))?;
+    u64::try_from(now.as_nanos())
+        .map_err(|_| LJMError::LibraryError("system time nanoseconds overflowed u64".to_string()))
+}
+
+/// Reads the device timer while streaming. Blocks for one command round trip.
+pub fn correlate(device: &dyn StreamDevice) -> Result<Correlation, LJMError> {
+    let before = host_unix_ns()?;
+    let core_timer = device.read_core_timer()?;
+    let after = host_unix_ns()?;
+    let round_trip_ns = after.saturating_sub(before);
+    Ok(Correlation {
+        host_unix_ns: before + round_trip_ns / 2,
+        core_timer,
+        round_trip_ns,
+    })
+}
+
+/// Device clock rate against host time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SkewEstimate {
+    /// How fast the device crystal runs relative to nominal, in parts per
+    /// million; positive means scans arrive faster than `actual_rate`.
+    pub skew_ppm: f64,
+    /// Change in skew between the older and newer half of the window, e.g.
+    /// from the enclosure warming up. `None` until the window is full enough.
+    pub drift_ppm_per_hour: Option<f64>,
+    pub samples: usize,
+    pub span_ns: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Point {
+    host_unix_ns: u64,
+    ticks: u64,
+}
+
+/// Least-squares fit of unwrapped `CORE_TIMER` ticks against host time over a
+/// sliding window of correlations.
+#[derive(Debug, Clone)]
+pub struct DriftEstimator {
+    window: usize,
+    points: VecDeque<Point>,
+    last_raw: Option<u32>,
+    min_round_trip_ns: u64,
+}
+
+impl DriftEstimator {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(MIN_FIT_SAMPLES),
+            points: VecDeque::new(),
+            last_raw: None,
+            min_round_trip_ns: u64::MAX,
+        }
+    }
+
+    /// Adds a correlation and returns the updated estimate. Slow reads are
+    /// skipped and a host clock step backwards restarts the window.
+    pub fn observe(&mut self, correlation: Correlation) -> Option<SkewEstimate> {
+        self.min_round_trip_ns = self.min_round_trip_ns.min(correlation.round_trip_ns);
+        if correlation.round_trip_ns > MAX_ROUND_TRIP_NS
+            || correlation.round_trip_ns > self.min_round_trip_ns.max(1) * ROUND_TRIP_FILTER
+        {
+            return self.estimate();
+        }
+
+        let ticks = match (self.points.back(), self.last_raw) {
+            (Some(last), Some(last_raw)) if correlation.host_unix_ns > last.host_unix_ns => {
+                let elapsed_ns = (correlation.host_unix_ns - last.host_unix_ns) as f64;
+                let expected = elapsed_ns * CORE_TIMER_HZ / 1e9;
+                let delta = correlation.core_timer.wrapping_sub(last_raw) as f64;
+                let wraps = ((expected - delta) / CORE_TIMER_WRAP).round().max(0.0);
+                last.ticks + (delta + wraps * CORE_TIMER_WRAP) as u64
+            }
+            (Some(_), _) => {
+                self.points.clear();
+                0
+            }
+            (None, _) => 0,
+        };
+
+        self.points.push_back(Point {
+            host_unix_ns: correlation.host_unix_ns,
+            ticks,
+        });
+        while self.points.len() > self.window {
+            self.points.pop_front();
+        }
+        self.last_raw = Some(correlation.core_timer);
+        self.estimate()
+    }
+
+    pub fn estimate(&self) -> Option<SkewEstimate> {
+        let points: Vec<Point> = self.points.iter().copied().collect();
+        let span = span_ns(&points);
+        if points.len() < MIN_FIT_SAMPLES || span < MIN_FIT_SPAN_NS {
+            return None;
+        }
+        let skew_ppm = fit_skew_ppm(&points)?;
+
+        let half = points.len() / 2;
+        let (older, newer) = points.split_at(half);
+        let drift_ppm_per_hour = if older.len() >= MIN_FIT_SAMPLES
+            && newer.len() >= MIN_FIT_SAMPLES
+            && span_ns(older) >= MIN_FIT_SPAN_NS / 2
+            && span_ns(newer) >= MIN_FIT_SPAN_NS / 2
+        {
+            let hours = (midpoint_ns(newer) - midpoint_ns(older)) / 3.6e12;
+            match (fit_skew_ppm(older), fit_skew_ppm(newer)) {
+                (Some(a), Some(b)) if hours > 0.0 => Some((b - a) / hours),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        Some(SkewEstimate {
+            skew_ppm,
+            drift_ppm_per_hour,
+            samples: points.len(),
+            span_ns: span,
+        })
+    }
+}
+
+fn span_ns(points: &[Point]) -> u64 {
+    match (points.first(), points.last()) {
+        (Some(first), Some(last)) => last.host_unix_ns.saturating_sub(first.host_unix_ns),
+        _ => 0,
+    }
+}
+
+fn midpoint_ns(points: &[Point]) -> f64 {
+    points.iter().map(|p| p.host_unix_ns as f64).sum::<f64>() / points.len() as f64
+}
+
+/// Slope of ticks over host nanoseconds, as a ppm offset from `CORE_TIMER_HZ`.
+/// Coordinates are taken relative to the first point to keep f64 precision.
+fn fit_skew_ppm(points: &[Point]) -> Option<f64> {
+    let origin = points.first()?;
+    let n = points.len() as f64;
+    let xs: Vec<f64> = points
+        .iter()
+        .map(|p| (p.host_unix_ns - origin.host_unix_ns) as f64)
+        .collect();
+    let ys: Vec<f64> = points.iter().map(|p| (p.ticks - origin.ticks) as f64).collect();
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for (x, y) in xs.iter().zip(&ys) {
+        sxy += (x - mean_x) * (y - mean_y);
+        sxx += (x - mean_x) * (x - mean_x);
+    }
+    if sxx <= 0.0 {
+        return None;
+    }
+    let ticks_per_second = sxy / sxx * 1e9;
+    Some((ticks_per_second / CORE_TIMER_HZ - 1.0) * 1e6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Correlations every `step_secs` from a device running `skew_ppm` fast.
+    fn correlations(skew_ppm: f64, step_secs: u64, count: u64) -> Vec<Correlation> {
+        let start_ns = 1_700_000_000_000_000_000u64;
+        let rate = CORE_TIMER_HZ * (1.0 + skew_ppm * 1e-6);
+        (0..count)
+            .map(|i| {
+                let elapsed_ns = i * step_secs * 1_000_000_000;
+                let ticks = (elapsed_ns as f64 * rate / 1e9) as u64 + 123_456;
+                Correlation {
+                    host_unix_ns: start_ns + elapsed_ns,
+                    core_timer: ticks as u32,
+                    round_trip_ns: 500_000,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn estimates_skew_across_timer_wraps() {
+        let mut estimator = DriftEstimator::new(60);
+        let mut estimate = None;
+        for correlation in correlations(25.0, 120, 10) {
+            estimate = estimator.observe(correlation);
+        }
+        let estimate = estimate.expect("eighteen minutes of correlations");
+        assert!((estimate.skew_ppm - 25.0).abs() < 0.01, "{estimate:?}");
+        assert!(estimate.drift_ppm_per_hour.unwrap().abs() < 0.1);
+    }
+
+    #[test]
+    fn needs_enough_span_before_estimating() {
+        let mut estimator = DriftEstimator::new(60);
+        for correlation in correlations(10.0, 10, 10) {
+            assert!(estimator.observe(correlation).is_none());
+        }
+    }
+
+    #[test]
+    fn skips_slow_reads_and_restarts_after_host_step_back() {
+        let mut estimator = DriftEstimator::new(60);
+        let mut samples = correlations(-15.0, 30, 10);
+        for correlation in &samples {
+            estimator.observe(*correlation);
+        }
+        let mut slow = samples[9];
+        slow.host_unix_ns += 30_000_000_000;
+        slow.core_timer = slow.core_timer.wrapping_add(999);
+        slow.round_trip_ns = 50_000_000;
+        let estimate = estimator.observe(slow).unwrap();
+        assert_eq!(estimate.samples, 10);
+        assert!((estimate.skew_ppm + 15.0).abs() < 0.01);
+
+        samples[0].host_unix_ns -= 1;
+        assert!(estimator.observe(samples[0]).is_none());
+    }
+}
@@ -39,6 +39,18 @@ impl<'a> Scan<'a> {
   pub const VT_ACTUAL_SCAN_RATE_HZ: flatbuffers::VOffsetT = 8;
   pub const VT_SEQUENCE: flatbuffers::VOffsetT = 10;
   pub const VT_VALUES: flatbuffers::VOffsetT = 12;
+  pub const VT_CHANNEL: flatbuffers::VOffsetT = 14;
+  pub const VT_RUN_ID: flatbuffers::VOffsetT = 16;
+  pub const VT_DEVICE_SERIAL: flatbuffers::VOffsetT = 18;
+  pub const VT_UNIT: flatbuffers::VOffsetT = 20;
+  pub const VT_CALIBRATION_ID: flatbuffers::VOffsetT = 22;
+  pub const VT_CALIBRATED: flatbuffers::VOffsetT = 24;
+  pub const VT_CLOCK_QUALITY: flatbuffers::VOffsetT = 26;
+  pub const VT_CLOCK_ERROR_BOUND_NS: flatbuffers::VOffsetT = 28;
+  pub const VT_CHANNEL_NAME: flatbuffers::VOffsetT = 30;
+  pub const VT_DECIMATION: flatbuffers::VOffsetT = 32;
+  pub const VT_MIN_VALUES: flatbuffers::VOffsetT = 34;
+  pub const VT_MAX_VALUES: flatbuffers::VOffsetT = 36;
 
   #[inline]
   pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
@@ -50,11 +62,23 @@ impl<'a> Scan<'a> {
     args: &'args ScanArgs<'args>
   ) -> flatbuffers::WIPOffset<Scan<'bldr>> {
     let mut builder = ScanBuilder::new(_fbb);
+    builder.add_clock_error_bound_ns(args.clock_error_bound_ns);
     builder.add_sequence(args.sequence);
     builder.add_actual_scan_rate_hz(args.actual_scan_rate_hz);
     builder.add_sample_interval_ns(args.sample_interval_ns);
     builder.add_first_sample_unix_ns(args.first_sample_unix_ns);
+    if let Some(x) = args.max_values { builder.add_max_values(x); }
+    if let Some(x) = args.min_values { builder.add_min_values(x); }
+    builder.add_decimation(args.decimation);
+    if let Some(x) = args.channel_name { builder.add_channel_name(x); }
+    if let Some(x) = args.clock_quality { builder.add_clock_quality(x); }
+    if let Some(x) = args.calibration_id { builder.add_calibration_id(x); }
+    if let Some(x) = args.unit { builder.add_unit(x); }
+    builder.add_device_serial(args.device_serial);
+    if let Some(x) = args.run_id { builder.add_run_id(x); }
     if let Some(x) = args.values { builder.add_values(x); }
+    builder.add_channel(args.channel);
+    builder.add_calibrated(args.calibrated);
     builder.finish()
   }
 
@@ -94,6 +118,90 @@ impl<'a> Scan<'a> {
     // which contains a valid value in this slot
     unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, f64>>>(Scan::VT_VALUES, None)}
   }
+  #[inline]
+  pub fn channel(&self) -> i16 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<i16>(Scan::VT_CHANNEL, Some(-1)).unwrap()}
+  }
+  #[inline]
+  pub fn run_id(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(Scan::VT_RUN_ID, None)}
+  }
+  #[inline]
+  pub fn device_serial(&self) -> i32 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<i32>(Scan::VT_DEVICE_SERIAL, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn unit(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(Scan::VT_UNIT, None)}
+  }
+  #[inline]
+  pub fn calibration_id(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(Scan::VT_CALIBRATION_ID, None)}
+  }
+  #[inline]
+  pub fn calibrated(&self) -> bool {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<bool>(Scan::VT_CALIBRATED, Some(false)).unwrap()}
+  }
+  #[inline]
+  pub fn clock_quality(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(Scan::VT_CLOCK_QUALITY, None)}
+  }
+  #[inline]
+  pub fn clock_error_bound_ns(&self) -> i64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<i64>(Scan::VT_CLOCK_ERROR_BOUND_NS, Some(-1)).unwrap()}
+  }
+  #[inline]
+  pub fn channel_name(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(Scan::VT_CHANNEL_NAME, None)}
+  }
+  #[inline]
+  pub fn decimation(&self) -> u32 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u32>(Scan::VT_DECIMATION, Some(1)).unwrap()}
+  }
+  #[inline]
+  pub fn min_values(&self) -> Option<flatbuffers::Vector<'a, f64>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, f64>>>(Scan::VT_MIN_VALUES, None)}
+  }
+  #[inline]
+  pub fn max_values(&self) -> Option<flatbuffers::Vector<'a, f64>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, f64>>>(Scan::VT_MAX_VALUES, None)}
+  }
 }
 
 impl flatbuffers::Verifiable for Scan<'_> {
@@ -108,6 +216,18 @@ impl flatbuffers::Verifiable for Scan<'_> {
      .visit_field::<f64>("actual_scan_rate_hz", Self::VT_ACTUAL_SCAN_RATE_HZ, false)?
      .visit_field::<u64>("sequence", Self::VT_SEQUENCE, false)?
      .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, f64>>>("values", Self::VT_VALUES, false)?
+     .visit_field::<i16>("channel", Self::VT_CHANNEL, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("run_id", Self::VT_RUN_ID, false)?
+     .visit_field::<i32>("device_serial", Self::VT_DEVICE_SERIAL, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("unit", Self::VT_UNIT, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("calibration_id", Self::VT_CALIBRATION_ID, false)?
+     .visit_field::<bool>("calibrated", Self::VT_CALIBRATED, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("clock_quality", Self::VT_CLOCK_QUALITY, false)?
+     .visit_field::<i64>("clock_error_bound_ns", Self::VT_CLOCK_ERROR_BOUND_NS, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("channel_name", Self::VT_CHANNEL_NAME, false)?
+     .visit_field::<u32>("decimation", Self::VT_DECIMATION, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, f64>>>("min_values", Self::VT_MIN_VALUES, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, f64>>>("max_values", Self::VT_MAX_VALUES, false)?
      .finish();
     Ok(())
   }
@@ -118,6 +238,18 @@ pub struct ScanArgs<'a> {
     pub actual_scan_rate_hz: f64,
     pub sequence: u64,
     pub values: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, f64>>>,
+    pub channel: i16,
+    pub run_id: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub device_serial: i32,
+    pub unit: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub calibration_id: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub calibrated: bool,
+    pub clock_quality: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub clock_error_bound_ns: i64,
+    pub channel_name: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub decimation: u32,
+    pub min_values: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, f64>>>,
+    pub max_values: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, f64>>>,
 }
 impl<'a> Default for ScanArgs<'a> {
   #[inline]
@@ -128,6 +260,18 @@ impl<'a> Default for ScanArgs<'a> {
       actual_scan_rate_hz: 0.0,
       sequence: 0,
       values: None,
+      channel: -1,
+      run_id: None,
+      device_serial: 0,
+      unit: None,
+      calibration_id: None,
+      calibrated: false,
+      clock_quality: None,
+      clock_error_bound_ns: -1,
+      channel_name: None,
+      decimation: 1,
+      min_values: None,
+      max_values: None,
     }
   }
 }
@@ -158,6 +302,54 @@ impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> ScanBuilder<'a, 'b, A> {
     self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Scan::VT_VALUES, values);
   }
   #[inline]
+  pub fn add_channel(&mut self, channel: i16) {
+    self.fbb_.push_slot::<i16>(Scan::VT_CHANNEL, channel, -1);
+  }
+  #[inline]
+  pub fn add_run_id(&mut self, run_id: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Scan::VT_RUN_ID, run_id);
+  }
+  #[inline]
+  pub fn add_device_serial(&mut self, device_serial: i32) {
+    self.fbb_.push_slot::<i32>(Scan::VT_DEVICE_SERIAL, device_serial, 0);
+  }
+  #[inline]
+  pub fn add_unit(&mut self, unit: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Scan::VT_UNIT, unit);
+  }
+  #[inline]
+  pub fn add_calibration_id(&mut self, calibration_id: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Scan::VT_CALIBRATION_ID, calibration_id);
+  }
+  #[inline]
+  pub fn add_calibrated(&mut self, calibrated: bool) {
+    self.fbb_.push_slot::<bool>(Scan::VT_CALIBRATED, calibrated, false);
+  }
+  #[inline]
+  pub fn add_clock_quality(&mut self, clock_quality: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Scan::VT_CLOCK_QUALITY, clock_quality);
+  }
+  #[inline]
+  pub fn add_clock_error_bound_ns(&mut self, clock_error_bound_ns: i64) {
+    self.fbb_.push_slot::<i64>(Scan::VT_CLOCK_ERROR_BOUND_NS, clock_error_bound_ns, -1);
+  }
+  #[inline]
+  pub fn add_channel_name(&mut self, channel_name: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Scan::VT_CHANNEL_NAME, channel_name);
+  }
+  #[inline]
+  pub fn add_decimation(&mut self, decimation: u32) {
+    self.fbb_.push_slot::<u32>(Scan::VT_DECIMATION, decimation, 1);
+  }
+  #[inline]
+  pub fn add_min_values(&mut self, min_values: flatbuffers::WIPOffset<flatbuffers::Vector<'b , f64>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Scan::VT_MIN_VALUES, min_values);
+  }
+  #[inline]
+  pub fn add_max_values(&mut self, max_values: flatbuffers::WIPOffset<flatbuffers::Vector<'b , f64>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Scan::VT_MAX_VALUES, max_values);
+  }
+  #[inline]
   pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> ScanBuilder<'a, 'b, A> {
     let start = _fbb.start_table();
     ScanBuilder {
@@ -180,6 +372,387 @@ impl core::fmt::Debug for Scan<'_> {
       ds.field("actual_scan_rate_hz", &self.actual_scan_rate_hz());
       ds.field("sequence", &self.sequence());
       ds.field("values", &self.values());
+      ds.field("channel", &self.channel());
+      ds.field("run_id", &self.run_id());
+      ds.field("device_serial", &self.device_serial());
+      ds.field("unit", &self.unit());
+      ds.field("calibration_id", &self.calibration_id());
+      ds.field("calibrated", &self.calibrated());
+      ds.field("clock_quality", &self.clock_quality());
+      ds.field("clock_error_bound_ns", &self.clock_error_bound_ns());
+      ds.field("channel_name", &self.channel_name());
+      ds.field("decimation", &self.decimation());
+      ds.field("min_values", &self.min_values());
+      ds.field("max_values", &self.max_values());
+      ds.finish()
+  }
+}
+pub enum ScanFrameOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct ScanFrame<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for ScanFrame<'a> {
+  type Inner = ScanFrame<'a>;
+  #[inline]
+  unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    Self { _tab: unsafe { flatbuffers::Table::new(buf, loc) } }
+  }
+}
+
+impl<'a> ScanFrame<'a> {
+  pub const VT_FIRST_SAMPLE_UNIX_NS: flatbuffers::VOffsetT = 4;
+  pub const VT_SAMPLE_INTERVAL_NS: flatbuffers::VOffsetT = 6;
+  pub const VT_ACTUAL_SCAN_RATE_HZ: flatbuffers::VOffsetT = 8;
+  pub const VT_SEQUENCE: flatbuffers::VOffsetT = 10;
+  pub const VT_CHANNELS: flatbuffers::VOffsetT = 12;
+  pub const VT_VALUES: flatbuffers::VOffsetT = 14;
+  pub const VT_RUN_ID: flatbuffers::VOffsetT = 16;
+  pub const VT_DEVICE_SERIAL: flatbuffers::VOffsetT = 18;
+  pub const VT_UNITS: flatbuffers::VOffsetT = 20;
+  pub const VT_CALIBRATION_IDS: flatbuffers::VOffsetT = 22;
+  pub const VT_CALIBRATED: flatbuffers::VOffsetT = 24;
+  pub const VT_CLOCK_QUALITY: flatbuffers::VOffsetT = 26;
+  pub const VT_CLOCK_ERROR_BOUND_NS: flatbuffers::VOffsetT = 28;
+  pub const VT_CHANNEL_NAMES: flatbuffers::VOffsetT = 30;
+  pub const VT_CAPTURE_ID: flatbuffers::VOffsetT = 32;
+  pub const VT_CAPTURE_SCANS: flatbuffers::VOffsetT = 34;
+  pub const VT_PRE_TRIGGER_SCANS: flatbuffers::VOffsetT = 36;
+
+  #[inline]
+  pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+    ScanFrame { _tab: table }
+  }
+  #[allow(unused_mut)]
+  pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+    _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+    args: &'args ScanFrameArgs<'args>
+  ) -> flatbuffers::WIPOffset<ScanFrame<'bldr>> {
+    let mut builder = ScanFrameBuilder::new(_fbb);
+    builder.add_pre_trigger_scans(args.pre_trigger_scans);
+    builder.add_capture_scans(args.capture_scans);
+    builder.add_clock_error_bound_ns(args.clock_error_bound_ns);
+    builder.add_sequence(args.sequence);
+    builder.add_actual_scan_rate_hz(args.actual_scan_rate_hz);
+    builder.add_sample_interval_ns(args.sample_interval_ns);
+    builder.add_first_sample_unix_ns(args.first_sample_unix_ns);
+    if let Some(x) = args.capture_id { builder.add_capture_id(x); }
+    if let Some(x) = args.channel_names { builder.add_channel_names(x); }
+    if let Some(x) = args.clock_quality { builder.add_clock_quality(x); }
+    if let Some(x) = args.calibration_ids { builder.add_calibration_ids(x); }
+    if let Some(x) = args.units { builder.add_units(x); }
+    builder.add_device_serial(args.device_serial);
+    if let Some(x) = args.run_id { builder.add_run_id(x); }
+    if let Some(x) = args.values { builder.add_values(x); }
+    if let Some(x) = args.channels { builder.add_channels(x); }
+    builder.add_calibrated(args.calibrated);
+    builder.finish()
+  }
+
+
+  #[inline]
+  pub fn first_sample_unix_ns(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(ScanFrame::VT_FIRST_SAMPLE_UNIX_NS, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn sample_interval_ns(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(ScanFrame::VT_SAMPLE_INTERVAL_NS, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn actual_scan_rate_hz(&self) -> f64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<f64>(ScanFrame::VT_ACTUAL_SCAN_RATE_HZ, Some(0.0)).unwrap()}
+  }
+  #[inline]
+  pub fn sequence(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(ScanFrame::VT_SEQUENCE, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn channels(&self) -> Option<flatbuffers::Vector<'a, i16>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, i16>>>(ScanFrame::VT_CHANNELS, None)}
+  }
+  #[inline]
+  pub fn values(&self) -> Option<flatbuffers::Vector<'a, f64>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, f64>>>(ScanFrame::VT_VALUES, None)}
+  }
+  #[inline]
+  pub fn run_id(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(ScanFrame::VT_RUN_ID, None)}
+  }
+  #[inline]
+  pub fn device_serial(&self) -> i32 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<i32>(ScanFrame::VT_DEVICE_SERIAL, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn units(&self) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>>>(ScanFrame::VT_UNITS, None)}
+  }
+  #[inline]
+  pub fn calibration_ids(&self) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>>>(ScanFrame::VT_CALIBRATION_IDS, None)}
+  }
+  #[inline]
+  pub fn calibrated(&self) -> bool {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<bool>(ScanFrame::VT_CALIBRATED, Some(false)).unwrap()}
+  }
+  #[inline]
+  pub fn clock_quality(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(ScanFrame::VT_CLOCK_QUALITY, None)}
+  }
+  #[inline]
+  pub fn clock_error_bound_ns(&self) -> i64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<i64>(ScanFrame::VT_CLOCK_ERROR_BOUND_NS, Some(-1)).unwrap()}
+  }
+  #[inline]
+  pub fn channel_names(&self) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>>>(ScanFrame::VT_CHANNEL_NAMES, None)}
+  }
+  #[inline]
+  pub fn capture_id(&self) -> Option<&'a str> {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(ScanFrame::VT_CAPTURE_ID, None)}
+  }
+  #[inline]
+  pub fn capture_scans(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(ScanFrame::VT_CAPTURE_SCANS, Some(0)).unwrap()}
+  }
+  #[inline]
+  pub fn pre_trigger_scans(&self) -> u64 {
+    // Safety:
+    // Created from valid Table for this object
+    // which contains a valid value in this slot
+    unsafe { self._tab.get::<u64>(ScanFrame::VT_PRE_TRIGGER_SCANS, Some(0)).unwrap()}
+  }
+}
+
+impl flatbuffers::Verifiable for ScanFrame<'_> {
+  #[inline]
+  fn run_verifier(
+    v: &mut flatbuffers::Verifier, pos: usize
+  ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+    use self::flatbuffers::Verifiable;
+    v.visit_table(pos)?
+     .visit_field::<u64>("first_sample_unix_ns", Self::VT_FIRST_SAMPLE_UNIX_NS, false)?
+     .visit_field::<u64>("sample_interval_ns", Self::VT_SAMPLE_INTERVAL_NS, false)?
+     .visit_field::<f64>("actual_scan_rate_hz", Self::VT_ACTUAL_SCAN_RATE_HZ, false)?
+     .visit_field::<u64>("sequence", Self::VT_SEQUENCE, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, i16>>>("channels", Self::VT_CHANNELS, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, f64>>>("values", Self::VT_VALUES, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("run_id", Self::VT_RUN_ID, false)?
+     .visit_field::<i32>("device_serial", Self::VT_DEVICE_SERIAL, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<&'_ str>>>>("units", Self::VT_UNITS, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<&'_ str>>>>("calibration_ids", Self::VT_CALIBRATION_IDS, false)?
+     .visit_field::<bool>("calibrated", Self::VT_CALIBRATED, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("clock_quality", Self::VT_CLOCK_QUALITY, false)?
+     .visit_field::<i64>("clock_error_bound_ns", Self::VT_CLOCK_ERROR_BOUND_NS, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<&'_ str>>>>("channel_names", Self::VT_CHANNEL_NAMES, false)?
+     .visit_field::<flatbuffers::ForwardsUOffset<&str>>("capture_id", Self::VT_CAPTURE_ID, false)?
+     .visit_field::<u64>("capture_scans", Self::VT_CAPTURE_SCANS, false)?
+     .visit_field::<u64>("pre_trigger_scans", Self::VT_PRE_TRIGGER_SCANS, false)?
+     .finish();
+    Ok(())
+  }
+}
+pub struct ScanFrameArgs<'a> {
+    pub first_sample_unix_ns: u64,
+    pub sample_interval_ns: u64,
+    pub actual_scan_rate_hz: f64,
+    pub sequence: u64,
+    pub channels: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, i16>>>,
+    pub values: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, f64>>>,
+    pub run_id: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub device_serial: i32,
+    pub units: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>>>,
+    pub calibration_ids: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>>>,
+    pub calibrated: bool,
+    pub clock_quality: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub clock_error_bound_ns: i64,
+    pub channel_names: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<&'a str>>>>,
+    pub capture_id: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub capture_scans: u64,
+    pub pre_trigger_scans: u64,
+}
+impl<'a> Default for ScanFrameArgs<'a> {
+  #[inline]
+  fn default() -> Self {
+    ScanFrameArgs {
+      first_sample_unix_ns: 0,
+      sample_interval_ns: 0,
+      actual_scan_rate_hz: 0.0,
+      sequence: 0,
+      channels: None,
+      values: None,
+      run_id: None,
+      device_serial: 0,
+      units: None,
+      calibration_ids: None,
+      calibrated: false,
+      clock_quality: None,
+      clock_error_bound_ns: -1,
+      channel_names: None,
+      capture_id: None,
+      capture_scans: 0,
+      pre_trigger_scans: 0,
+    }
+  }
+}
+
+pub struct ScanFrameBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> ScanFrameBuilder<'a, 'b, A> {
+  #[inline]
+  pub fn add_first_sample_unix_ns(&mut self, first_sample_unix_ns: u64) {
+    self.fbb_.push_slot::<u64>(ScanFrame::VT_FIRST_SAMPLE_UNIX_NS, first_sample_unix_ns, 0);
+  }
+  #[inline]
+  pub fn add_sample_interval_ns(&mut self, sample_interval_ns: u64) {
+    self.fbb_.push_slot::<u64>(ScanFrame::VT_SAMPLE_INTERVAL_NS, sample_interval_ns, 0);
+  }
+  #[inline]
+  pub fn add_actual_scan_rate_hz(&mut self, actual_scan_rate_hz: f64) {
+    self.fbb_.push_slot::<f64>(ScanFrame::VT_ACTUAL_SCAN_RATE_HZ, actual_scan_rate_hz, 0.0);
+  }
+  #[inline]
+  pub fn add_sequence(&mut self, sequence: u64) {
+    self.fbb_.push_slot::<u64>(ScanFrame::VT_SEQUENCE, sequence, 0);
+  }
+  #[inline]
+  pub fn add_channels(&mut self, channels: flatbuffers::WIPOffset<flatbuffers::Vector<'b , i16>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ScanFrame::VT_CHANNELS, channels);
+  }
+  #[inline]
+  pub fn add_values(&mut self, values: flatbuffers::WIPOffset<flatbuffers::Vector<'b , f64>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ScanFrame::VT_VALUES, values);
+  }
+  #[inline]
+  pub fn add_run_id(&mut self, run_id: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ScanFrame::VT_RUN_ID, run_id);
+  }
+  #[inline]
+  pub fn add_device_serial(&mut self, device_serial: i32) {
+    self.fbb_.push_slot::<i32>(ScanFrame::VT_DEVICE_SERIAL, device_serial, 0);
+  }
+  #[inline]
+  pub fn add_units(&mut self, units: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<&'b  str>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ScanFrame::VT_UNITS, units);
+  }
+  #[inline]
+  pub fn add_calibration_ids(&mut self, calibration_ids: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<&'b  str>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ScanFrame::VT_CALIBRATION_IDS, calibration_ids);
+  }
+  #[inline]
+  pub fn add_calibrated(&mut self, calibrated: bool) {
+    self.fbb_.push_slot::<bool>(ScanFrame::VT_CALIBRATED, calibrated, false);
+  }
+  #[inline]
+  pub fn add_clock_quality(&mut self, clock_quality: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ScanFrame::VT_CLOCK_QUALITY, clock_quality);
+  }
+  #[inline]
+  pub fn add_clock_error_bound_ns(&mut self, clock_error_bound_ns: i64) {
+    self.fbb_.push_slot::<i64>(ScanFrame::VT_CLOCK_ERROR_BOUND_NS, clock_error_bound_ns, -1);
+  }
+  #[inline]
+  pub fn add_channel_names(&mut self, channel_names: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<&'b  str>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ScanFrame::VT_CHANNEL_NAMES, channel_names);
+  }
+  #[inline]
+  pub fn add_capture_id(&mut self, capture_id: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ScanFrame::VT_CAPTURE_ID, capture_id);
+  }
+  #[inline]
+  pub fn add_capture_scans(&mut self, capture_scans: u64) {
+    self.fbb_.push_slot::<u64>(ScanFrame::VT_CAPTURE_SCANS, capture_scans, 0);
+  }
+  #[inline]
+  pub fn add_pre_trigger_scans(&mut self, pre_trigger_scans: u64) {
+    self.fbb_.push_slot::<u64>(ScanFrame::VT_PRE_TRIGGER_SCANS, pre_trigger_scans, 0);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> ScanFrameBuilder<'a, 'b, A> {
+    let start = _fbb.start_table();
+    ScanFrameBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<ScanFrame<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+impl core::fmt::Debug for ScanFrame<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let mut ds = f.debug_struct("ScanFrame");
+      ds.field("first_sample_unix_ns", &self.first_sample_unix_ns());
+      ds.field("sample_interval_ns", &self.sample_interval_ns());
+      ds.field("actual_scan_rate_hz", &self.actual_scan_rate_hz());
+      ds.field("sequence", &self.sequence());
+      ds.field("channels", &self.channels());
+      ds.field("values", &self.values());
+      ds.field("run_id", &self.run_id());
+      ds.field("device_serial", &self.device_serial());
+      ds.field("units", &self.units());
+      ds.field("calibration_ids", &self.calibration_ids());
+      ds.field("calibrated", &self.calibrated());
+      ds.field("clock_quality", &self.clock_quality());
+      ds.field("clock_error_bound_ns", &self.clock_error_bound_ns());
+      ds.field("channel_names", &self.channel_names());
+      ds.field("capture_id", &self.capture_id());
+      ds.field("capture_scans", &self.capture_scans());
+      ds.field("pre_trigger_scans", &self.pre_trigger_scans());
       ds.finish()
   }
 }
@@ -0,0 +1,276 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+/// Largest factor accepted in `decimation`; the filter grows with the factor.
+pub const MAX_FACTOR: u32 = 1000;
+/// Filter length in output periods. Longer filters roll off more steeply but
+/// delay the first output of a run.
+const TAPS_PER_FACTOR: usize = 8;
+/// Cutoff as a fraction of the decimated Nyquist rate, leaving room for the
+/// filter's transition band below it.
+const CUTOFF_FRACTION: f64 = 0.8;
+
+/// Checks the top-level `decimation` factors.
+pub fn validate_factors(factors: &[u32]) -> Result<(), String> {
+    for (i, factor) in factors.iter().enumerate() {
+        if !(2..=MAX_FACTOR).contains(factor) {
+            return Err(format!(
+                "decimation factor {factor} must be between 2 and {MAX_FACTOR}"
+            ));
+        }
+        if factors[..i].contains(factor) {
+            return Err(format!("decimation factor {factor} is listed twice"));
+        }
+    }
+    Ok(())
+}
+
+/// Blackman-windowed sinc low-pass with unity DC gain, cut off below the
+/// Nyquist rate of the decimated signal.
+fn low_pass_taps(factor: usize) -> Vec<f64> {
+    let len = TAPS_PER_FACTOR * factor + 1;
+    let cutoff = CUTOFF_FRACTION * 0.5 / factor as f64;
+    let middle = (len - 1) as f64 / 2.0;
+    let mut taps: Vec<f64> = (0..len)
+        .map(|n| {
+            let x = n as f64 - middle;
+            let sinc = if x == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * PI * cutoff * x).sin() / (PI * x)
+            };
+            let phase = 2.0 * PI * n as f64 / (len - 1) as f64;
+            let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+            sinc * window
+        })
+        .collect();
+    let sum: f64 = taps.iter().sum();
+    taps.iter_mut().for_each(|tap| *tap /= sum);
+    taps
+}
+
+/// Output of one decimator for one stream batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decimated {
+    /// Timestamp of the first output, at the centre of its filter window.
+    pub first_sample_unix_ns: u64,
+    pub values: Vec<f64>,
+    /// Raw extremes of the `factor` scans each output stands for.
+    pub min_values: Vec<f64>,
+    pub max_values: Vec<f64>,
+}
+
+/// Filters and downsamples one channel by `factor`. State carries across
+/// batches, so output timing does not depend on how reads are split.
+pub struct Decimator {
+    factor: usize,
+    taps: Vec<f64>,
+    history: VecDeque<f64>,
+    /// Inputs since the last output, counted once the filter is full; the
+    /// first full filter produces an output.
+    since_output: usize,
+}
+
+impl Decimator {
+    pub fn new(factor: u32) -> Self {
+        let factor = factor.max(1) as usize;
+        let taps = low_pass_taps(factor);
+        Self {
+            factor,
+            history: VecDeque::with_capacity(taps.len()),
+            taps,
+            since_output: 0,
+        }
+    }
+
+    /// Forgets buffered samples, e.g. after skipped scans, so the filter does
+    /// not smear across the gap. The next output waits for a full filter.
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.since_output = 0;
+    }
+
+    /// Inputs between an output's filter centre and the newest input.
+    fn delay(&self) -> usize {
+        (self.taps.len() - 1) / 2
+    }
+
+    /// Feeds contiguous samples; `None` when the batch completes no output.
+    pub fn push(
+        &mut self,
+        first_sample_unix_ns: u64,
+        sample_interval_ns: u64,
+        values: &[f64],
+    ) -> Option<Decimated> {
+        let mut out: Option<Decimated> = None;
+        for (index, value) in values.iter().copied().enumerate() {
+            if self.history.len() == self.taps.len() {
+                self.history.pop_front();
+            }
+            self.history.push_back(value);
+            if self.history.len() < self.taps.len() {
+                continue;
+            }
+            let emit = self.since_output == 0;
+            self.since_output = (self.since_output + 1) % self.factor;
+            if !emit {
+                continue;
+            }
+
+            let filtered: f64 = self
+                .taps
+                .iter()
+                .zip(self.history.iter())
+                .map(|(tap, sample)| tap * sample)
+                .sum();
+            let start = self.delay() - self.factor / 2;
+            let (min, max) = self
+                .history
+                .range(start..start + self.factor)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                    (lo.min(*v), hi.max(*v))
+                });
+            let decimated = out.get_or_insert_with(|| {
+                let offset = index as i128 - self.delay() as i128;
+                let first = first_sample_unix_ns as i128 + offset * sample_interval_ns as i128;
+                Decimated {
+                    first_sample_unix_ns: first.max(0) as u64,
+                    values: Vec::new(),
+                    min_values: Vec::new(),
+                    max_values: Vec::new(),
+                }
+            });
+            decimated.values.push(filtered);
+            decimated.min_values.push(min);
+            decimated.max_values.push(max);
+        }
+        out
+    }
+}
+
+/// One decimator per configured factor and published channel.
+pub struct DecimationBank {
+    width: usize,
+    stages: Vec<(u32, Vec<Decimator>)>,
+}
+
+impl DecimationBank {
+    pub fn new(factors: &[u32], width: usize) -> Self {
+        Self {
+            width,
+            stages: factors
+                .iter()
+                .map(|factor| {
+                    (
+                        *factor,
+                        (0..width).map(|_| Decimator::new(*factor)).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn reset(&mut self) {
+        for (_, decimators) in &mut self.stages {
+            decimators.iter_mut().for_each(Decimator::reset);
+        }
+    }
+
+    /// Feeds interleaved scans and returns `(factor, column, output)` for
+    /// every decimator the batch produced output from.
+    pub fn push(
+        &mut self,
+        first_sample_unix_ns: u64,
+        sample_interval_ns: u64,
+        scans: &[f64],
+    ) -> Vec<(u32, usize, Decimated)> {
+        let width = self.width.max(1);
+        let columns: Vec<Vec<f64>> = (0..width)
+            .map(|column| scans.iter().skip(column).step_by(width).copied().collect())
+            .collect();
+        let mut outputs = Vec::new();
+        for (factor, decimators) in &mut self.stages {
+            for (column, decimator) in decimators.iter_mut().enumerate() {
+                if let Some(out) =
+                    decimator.push(first_sample_unix_ns, sample_interval_ns, &columns[column])
+                {
+                    outputs.push((*factor, column, out));
+                }
+            }
+        }
+        outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factors_are_validated() {
+        assert!(validate_factors(&[10, 100]).is_ok());
+        assert!(validate_factors(&[1]).is_err());
+        assert!(validate_factors(&[10, 10]).is_err());
+        assert!(validate_factors(&[MAX_FACTOR + 1]).is_err());
+    }
+
+    #[test]
+    fn output_is_independent_of_batch_boundaries() {
+        let input: Vec<f64> = (0..1000).map(|i| (i as f64 * 0.05).sin()).collect();
+        let mut whole = Decimator::new(10);
+        let all = whole.push(0, 100, &input).unwrap();
+
+        let mut split = Decimator::new(10);
+        let mut values = Vec::new();
+        let mut first = None;
+        for (index, chunk) in input.chunks(37).enumerate() {
+            if let Some(out) = split.push(index as u64 * 37 * 100, 100, chunk) {
+                first.get_or_insert(out.first_sample_unix_ns);
+                values.extend(out.values);
+            }
+        }
+        assert_eq!(first, Some(all.first_sample_unix_ns));
+        assert_eq!(values, all.values);
+        // The first output is centred on the first full filter window.
+        assert_eq!(all.first_sample_unix_ns, 40 * 100);
+        assert_eq!(all.values.len(), (1000 - 81) / 10 + 1);
+    }
+
+    #[test]
+    fn filter_passes_dc_and_rejects_aliases() {
+        let mut dc = Decimator::new(10);
+        let out = dc.push(0, 1, &[2.5; 200]).unwrap();
+        assert!(out.values.iter().all(|v| (v - 2.5).abs() < 1e-9));
+
+        // A tone at 0.45 cycles/sample would alias after decimating by 10.
+        let tone: Vec<f64> = (0..2000)
+            .map(|i| (2.0 * PI * 0.45 * i as f64).sin())
+            .collect();
+        let mut decimator = Decimator::new(10);
+        let out = decimator.push(0, 1, &tone).unwrap();
+        assert!(
+            out.values.iter().all(|v| v.abs() < 1e-3),
+            "{:?}",
+            out.values
+        );
+        assert!(out.min_values.iter().all(|v| *v < -0.9));
+        assert!(out.max_values.iter().all(|v| *v > 0.9));
+    }
+
+    #[test]
+    fn bank_splits_columns_and_resets() {
+        let scans: Vec<f64> = (0..200).flat_map(|_| [1.0, -1.0]).collect();
+        let mut bank = DecimationBank::new(&[10], 2);
+        let outputs = bank.push(0, 1, &scans);
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs[0].2.values.iter().all(|v| (v - 1.0).abs() < 1e-9));
+        assert!(outputs[1].2.values.iter().all(|v| (v + 1.0).abs() < 1e-9));
+
+        bank.reset();
+        assert!(bank.push(0, 1, &scans[..2 * 80]).is_empty());
+    }
+}
@@ -0,0 +1,393 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ljmrs::handle::DeviceHandleInfo;
+use ljmrs::{LJMError, LJMLibrary};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::acquisition::Acquisition;
+use crate::ain_config::{self, AinChannelConfig};
+use crate::channel_spec::{self, DigitalChannel};
+use crate::device_model::{DeviceModel, InputRanges, NegativeChannel};
+use crate::labjack::{self, LabJackTarget};
+use crate::ljm_stream::{self, StreamRead};
+use crate::sim;
+
+/// Blocking device operations the streamer needs from a LabJack.
+///
+/// Methods take `&self` because `stream_read` runs on a dedicated blocking
+/// thread while the async side may call `stream_stop` to interrupt it.
+pub trait StreamDevice: Send + Sync {
+    fn backend_name(&self) -> &'static str;
+    fn info(&self) -> &DeviceHandleInfo;
+    fn configure(&self, channels: &[AinChannelConfig]) -> Result<(), LJMError>;
+    /// Sets up the DIO extended features the digital channels read.
+    fn configure_digital(&self, channels: &[DigitalChannel]) -> Result<(), LJMError>;
+    /// Arms or clears the stream trigger and burst length.
+    fn configure_acquisition(&self, acquisition: &Acquisition) -> Result<(), LJMError>;
+    /// Starts streaming `registers`, the scan list from `ScanLayout`.
+    fn stream_start(
+        &self,
+        scans_per_read: i32,
+        scan_rate_hz: f64,
+        registers: &[String],
+    ) -> Result<f64, LJMError>;
+    fn stream_read(&self) -> Result<StreamRead, LJMError>;
+    fn stream_stop(&self) -> Result<(), LJMError>;
+    /// Raw `CORE_TIMER` value, readable while streaming.
+    fn read_core_timer(&self) -> Result<u32, LJMError>;
+    /// `FIRMWARE_VERSION`, e.g. `1.0299`.
+    fn read_firmware_version(&self) -> Result<f64, LJMError>;
+    /// `TEMPERATURE_DEVICE_K`, readable while streaming.
+    fn read_temperature_k(&self) -> Result<f64, LJMError>;
+}
+
+/// Which implementation `open` hands out, chosen by `LABJACK_BACKEND`.
+#[derive(Debug, Clone)]
+pub enum DeviceBackend {
+    Ljm,
+    Simulated(sim::SimConfig),
+}
+
+impl DeviceBackend {
+    pub fn from_env() -> Result<Self, LJMError> {
+        let raw = std::env::var("LABJACK_BACKEND").unwrap_or_default();
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "ljm" | "labjack" => Ok(Self::Ljm),
+            "sim" | "simulated" => Ok(Self::Simulated(
+                sim::SimConfig::from_env().map_err(LJMError::LibraryError)?,
+            )),
+            other => Err(LJMError::LibraryError(format!(
+                "Invalid LABJACK_BACKEND '{other}', expected ljm or sim"
+            ))),
+        }
+    }
+
+    /// The simulated backend never touches the LJM shared library.
+    pub fn requires_ljm(&self) -> bool {
+        matches!(self, Self::Ljm)
+    }
+
+    /// The simulated backend takes its serial from `target` when one is set,
+    /// so several simulated devices can be told apart.
+    pub fn open(&self, target: &LabJackTarget) -> Result<Arc<dyn StreamDevice>, LJMError> {
+        match self {
+            Self::Ljm => Ok(Arc::new(LjmDevice::open(target)?)),
+            Self::Simulated(cfg) => {
+                let mut cfg = cfg.clone();
+                if let Some(serial) = target.serial {
+                    cfg.serial_number = serial;
+                }
+                Ok(Arc::new(sim::SimulatedDevice::new(cfg)))
+            }
+        }
+    }
+}
+
+const DEFAULT_CFG_KEY: &str = "v1.macbook.unknown-source.config";
+
+/// One LabJack managed by the streamer, with its own sampler config key.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DeviceSpec {
+    /// Local KV key holding this device's `SampleConfig`.
+    pub cfg_key: String,
+    /// Central KV key mirrored into `cfg_key`; defaults to `cfg_key`.
+    #[serde(default)]
+    pub central_cfg_key: Option<String>,
+    #[serde(flatten)]
+    pub target: LabJackTarget,
+}
+
+impl DeviceSpec {
+    pub fn central_key(&self) -> &str {
+        self.central_cfg_key.as_deref().unwrap_or(&self.cfg_key)
+    }
+}
+
+/// Devices from the `LABJACK_DEVICES` JSON array, or the single device
+/// described by `CFG_KEY`, `CENTRAL_CFG_KEY` and the `LABJACK_*` variables.
+pub fn device_specs_from_env() -> Result<Vec<DeviceSpec>, String> {
+    let env = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    if let Some(raw) = env("LABJACK_DEVICES") {
+        return parse_device_specs(&raw);
+    }
+    Ok(vec![DeviceSpec {
+        cfg_key: env("CFG_KEY").unwrap_or_else(|| DEFAULT_CFG_KEY.to_string()),
+        central_cfg_key: env("CENTRAL_CFG_KEY"),
+        target: LabJackTarget::from_env()?,
+    }])
+}
+
+pub fn parse_device_specs(raw: &str) -> Result<Vec<DeviceSpec>, String> {
+    let specs: Vec<DeviceSpec> =
+        serde_json::from_str(raw).map_err(|e| format!("invalid LABJACK_DEVICES: {e}"))?;
+    if specs.is_empty() {
+        return Err("LABJACK_DEVICES must list at least one device".to_string());
+    }
+    let mut keys = HashSet::new();
+    let mut ips = HashSet::new();
+    for spec in &specs {
+        if spec.cfg_key.trim().is_empty() {
+            return Err("LABJACK_DEVICES entry has an empty cfg_key".to_string());
+        }
+        if !keys.insert(spec.cfg_key.as_str()) {
+            return Err(format!(
+                "LABJACK_DEVICES lists cfg_key '{}' more than once",
+                spec.cfg_key
+            ));
+        }
+        if let Some(ip) = spec.target.ip.as_deref()
+            && !ips.insert(ip)
+        {
+            return Err(format!("LABJACK_DEVICES lists ip '{ip}' more than once"));
+        }
+    }
+    Ok(specs)
+}
+
+/// `LJM_STREAM_RECEIVE_TIMEOUT_MODE_CALCULATED`, the LJM default.
+const LJM_STREAM_RECEIVE_TIMEOUT_MODE_CALCULATED: f64 = 1.0;
+
+/// A real LabJack reached through the LJM library.
+pub struct LjmDevice {
+    handle: i32,
+    info: DeviceHandleInfo,
+    model: DeviceModel,
+    values_per_read: AtomicUsize,
+}
+
+impl LjmDevice {
+    pub fn open(target: &LabJackTarget) -> Result<Self, LJMError> {
+        let handle = labjack::open_streamer_labjack(target)?;
+        let info = match labjack::handle_info(handle) {
+            Ok(info) => info,
+            Err(err) => {
+                let _ = LJMLibrary::close_jack(handle);
+                return Err(err);
+            }
+        };
+        let Some(model) = DeviceModel::from_device_type(&info.device_type) else {
+            let _ = LJMLibrary::close_jack(handle);
+            return Err(LJMError::LibraryError(format!(
+                "Unsupported LabJack model {:?}",
+                info.device_type
+            )));
+        };
+        let ip = labjack::handle_ip_address(&info)?.unwrap_or_else(|| "N/A".to_string());
+        info!(
+            "connected to {} via {:?}, serial {}, ip {}",
+            model.as_str(),
+            info.connection_type,
+            info.serial_number,
+            ip
+        );
+        Ok(Self {
+            handle,
+            info,
+            model,
+            values_per_read: AtomicUsize::new(0),
+        })
+    }
+
+    fn write_register(&self, name: String, value: f64) -> Result<(), LJMError> {
+        LJMLibrary::write_name(self.handle, name.as_str(), value).map_err(|e| {
+            LJMError::LibraryError(format!("Failed to write {}={}: {:?}", name, value, e))
+        })
+    }
+}
+
+impl StreamDevice for LjmDevice {
+    fn backend_name(&self) -> &'static str {
+        "ljm"
+    }
+
+    fn info(&self) -> &DeviceHandleInfo {
+        &self.info
+    }
+
+    /// Writes the per-channel `AINn_*` registers before `stream_start`. Stream
+    /// mode reads the stream-wide resolution and settling registers rather than
+    /// the per-channel ones, so those are set from the strictest channel request.
+    /// Registers a model does not have, per its capability table, are skipped.
+    fn configure(&self, channels: &[AinChannelConfig]) -> Result<(), LJMError> {
+        let caps = self.model.capabilities();
+        if let InputRanges::Fixed { low_voltage_from } = caps.ranges {
+            // T4 flexible I/O lines only read analog once switched over.
+            let analog_lines = channels
+                .iter()
+                .filter(|ain| ain.channel >= low_voltage_from)
+                .fold(0u32, |mask, ain| mask | (1 << ain.channel));
+            if analog_lines != 0 {
+                let enabled = LJMLibrary::read_name(self.handle, "DIO_ANALOG_ENABLE")
+                    .map_err(|e| {
+                        LJMError::LibraryError(format!("Failed to read DIO_ANALOG_ENABLE: {:?}", e))
+                    })? as u32;
+                self.write_register(
+                    "DIO_ANALOG_ENABLE".to_string(),
+                    (enabled | analog_lines) as f64,
+                )?;
+            }
+        }
+
+        for ain in channels {
+            let ch = ain.channel;
+            if caps.negative_channel == NegativeChannel::Pairs {
+                self.write_register(format!("AIN{ch}_NEGATIVE_CH"), ain.negative_channel as f64)?;
+            }
+            if matches!(caps.ranges, InputRanges::Selectable(_)) {
+                self.write_register(format!("AIN{ch}_RANGE"), ain.range)?;
+            }
+            self.write_register(
+                format!("AIN{ch}_RESOLUTION_INDEX"),
+                ain.resolution_index as f64,
+            )?;
+            if caps.max_settling_us > 0 {
+                self.write_register(format!("AIN{ch}_SETTLING_US"), ain.settling_us as f64)?;
+            }
+        }
+
+        self.write_register(
+            "STREAM_RESOLUTION_INDEX".to_string(),
+            ain_config::stream_resolution_index(channels) as f64,
+        )?;
+        if caps.max_settling_us > 0 {
+            self.write_register(
+                "STREAM_SETTLING_US".to_string(),
+                ain_config::stream_settling_us(channels) as f64,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn configure_digital(&self, channels: &[DigitalChannel]) -> Result<(), LJMError> {
+        for (name, value) in channel_spec::ef_writes(channels) {
+            self.write_register(name, value)?;
+        }
+        Ok(())
+    }
+
+    /// LJM's stream receive timeout is library-wide: a hardware-triggered
+    /// stream waits for its edge indefinitely, every other stream goes back
+    /// to the calculated timeout.
+    fn configure_acquisition(&self, acquisition: &Acquisition) -> Result<(), LJMError> {
+        for (name, value) in acquisition.register_writes() {
+            self.write_register(name, value)?;
+        }
+        let (config, value) = if acquisition.waits_for_trigger() {
+            ("LJM_STREAM_RECEIVE_TIMEOUT_MS", 0.0)
+        } else {
+            ("LJM_STREAM_RECEIVE_TIMEOUT_MODE", LJM_STREAM_RECEIVE_TIMEOUT_MODE_CALCULATED)
+        };
+        LJMLibrary::set_config(config, value).map_err(|e| {
+            LJMError::LibraryError(format!("Failed to set {}={}: {:?}", config, value, e))
+        })
+    }
+
+    fn stream_start(
+        &self,
+        scans_per_read: i32,
+        scan_rate_hz: f64,
+        registers: &[String],
+    ) -> Result<f64, LJMError> {
+        let addresses = registers
+            .iter()
+            .map(|name| {
+                LJMLibrary::name_to_address(name.clone())
+                    .map(|(addr, _)| addr)
+                    .map_err(|e| LJMError::LibraryError(format!("Invalid register {}: {:?}", name, e)))
+            })
+            .collect::<Result<Vec<i32>, LJMError>>()?;
+
+        let actual_rate =
+            LJMLibrary::stream_start(self.handle, scans_per_read, scan_rate_hz, addresses)?;
+        self.values_per_read.store(
+            scans_per_read.max(0) as usize * registers.len(),
+            Ordering::Relaxed,
+        );
+        Ok(actual_rate)
+    }
+
+    fn stream_read(&self) -> Result<StreamRead, LJMError> {
+        ljm_stream::stream_read(self.handle, self.values_per_read.load(Ordering::Relaxed))
+    }
+
+    fn stream_stop(&self) -> Result<(), LJMError> {
+        self.values_per_read.store(0, Ordering::Relaxed);
+        LJMLibrary::stream_stop(self.handle).map(|_| ())
+    }
+
+    fn read_core_timer(&self) -> Result<u32, LJMError> {
+        LJMLibrary::read_name(self.handle, "CORE_TIMER")
+            .map(|ticks| ticks as u32)
+            .map_err(|e| LJMError::LibraryError(format!("Failed to read CORE_TIMER: {:?}", e)))
+    }
+
+    fn read_firmware_version(&self) -> Result<f64, LJMError> {
+        LJMLibrary::read_name(self.handle, "FIRMWARE_VERSION").map_err(|e| {
+            LJMError::LibraryError(format!("Failed to read FIRMWARE_VERSION: {:?}", e))
+        })
+    }
+
+    fn read_temperature_k(&self) -> Result<f64, LJMError> {
+        LJMLibrary::read_name(self.handle, "TEMPERATURE_DEVICE_K").map_err(|e| {
+            LJMError::LibraryError(format!("Failed to read TEMPERATURE_DEVICE_K: {:?}", e))
+        })
+    }
+}
+
+impl Drop for LjmDevice {
+    fn drop(&mut self) {
+        let _ = LJMLibrary::stream_stop(self.handle);
+        let _ = LJMLibrary::close_jack(self.handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_device_list() {
+        let specs = parse_device_specs(
+            r#"[
+                {"cfg_key": "v1.box.lj1.config", "ip": "192.168.1.111", "serial": "470036312", "name": "lj1"},
+                {"cfg_key": "v1.box.lj2.config", "central_cfg_key": "v1.box.lj2.central", "ip": "192.168.1.112", "serial": 470036313}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].target.serial, Some(470036312));
+        assert_eq!(specs[0].central_key(), "v1.box.lj1.config");
+        assert_eq!(specs[1].target.serial, Some(470036313));
+        assert_eq!(specs[1].central_key(), "v1.box.lj2.central");
+        assert_eq!(specs[1].target.label(), "192.168.1.112");
+    }
+
+    #[test]
+    fn rejects_empty_and_duplicate_devices() {
+        assert!(parse_device_specs("[]").is_err());
+        assert!(
+            parse_device_specs(
+                r#"[{"cfg_key": "a", "ip": "10.0.0.1"}, {"cfg_key": "a", "ip": "10.0.0.2"}]"#
+            )
+            .is_err()
+        );
+        assert!(
+            parse_device_specs(
+                r#"[{"cfg_key": "a", "ip": "10.0.0.1"}, {"cfg_key": "b", "ip": "10.0.0.1"}]"#
+            )
+            .is_err()
+        );
+        assert!(parse_device_specs(r#"[{"cfg_key": "a", "serial": "ANY"}]"#).unwrap()[0]
+            .target
+            .serial
+            .is_none());
+    }
+}
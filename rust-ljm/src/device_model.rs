@@ -0,0 +1,222 @@
+use std::ops::RangeInclusive;
+
+use ljmrs::handle::DeviceType;
+use serde::{Deserialize, Serialize};
+
+/// LabJack T-series models the streamer can drive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeviceModel {
+    #[serde(alias = "t4")]
+    T4,
+    #[default]
+    #[serde(alias = "t7")]
+    T7,
+    #[serde(alias = "t8")]
+    T8,
+}
+
+/// What `AINn_NEGATIVE_CH` means on a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegativeChannel {
+    /// Single-ended (199) or the fixed T7 partner: the next odd channel for
+    /// AIN0-AIN13, the channel eight above for Mux80 inputs.
+    Pairs,
+    /// Single-ended only; the register is never written.
+    SingleEnded,
+    /// Every input is isolated and differential; the register does not exist.
+    Isolated,
+}
+
+/// Input ranges a model offers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputRanges {
+    /// ± volts written to `AINn_RANGE`.
+    Selectable(&'static [f64]),
+    /// Set by the hardware: ±10 V below `low_voltage_from`, 0-2.5 V from it on.
+    Fixed { low_voltage_from: u8 },
+}
+
+/// Per-model limits the config is validated against and that decide which
+/// registers `configure` writes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    pub model: DeviceModel,
+    pub ain_channels: &'static [RangeInclusive<u8>],
+    pub ranges: InputRanges,
+    pub max_stream_resolution_index: u8,
+    /// `0` when the model has no settling registers.
+    pub max_settling_us: u32,
+    /// Aggregate samples per second, or scans per second when `simultaneous`.
+    pub max_stream_rate_hz: f64,
+    /// All channels are sampled at once, so the rate limit does not divide
+    /// across the scan list.
+    pub simultaneous: bool,
+    pub negative_channel: NegativeChannel,
+    /// DIO lines readable through `FIO_EIO_STATE`.
+    pub dio_state_lines: &'static [RangeInclusive<u8>],
+    /// DIO lines with extended features (interrupt counter, frequency in,
+    /// quadrature). Empty where the streamer does not drive DIO_EF.
+    pub dio_ef_lines: &'static [u8],
+    /// Lines with a dedicated high-speed counter (DIO_EF index 7).
+    pub high_speed_counters: &'static [u8],
+    /// `DIO_EF_CLOCK0` frequency with a divisor of 1.
+    pub dio_ef_clock_hz: f64,
+}
+
+const T4: Capabilities = Capabilities {
+    model: DeviceModel::T4,
+    ain_channels: &[0..=11],
+    ranges: InputRanges::Fixed {
+        low_voltage_from: 4,
+    },
+    max_stream_resolution_index: 5,
+    max_settling_us: 50_000,
+    max_stream_rate_hz: 40_000.0,
+    simultaneous: false,
+    negative_channel: NegativeChannel::SingleEnded,
+    dio_state_lines: &[4..=15],
+    dio_ef_lines: &[4, 5, 6, 7, 8, 9],
+    high_speed_counters: &[16, 17, 18, 19],
+    dio_ef_clock_hz: 80_000_000.0,
+};
+
+const T7: Capabilities = Capabilities {
+    model: DeviceModel::T7,
+    ain_channels: &[0..=13, 48..=127],
+    ranges: InputRanges::Selectable(&[10.0, 1.0, 0.1, 0.01]),
+    max_stream_resolution_index: 8,
+    max_settling_us: 50_000,
+    max_stream_rate_hz: 100_000.0,
+    simultaneous: false,
+    negative_channel: NegativeChannel::Pairs,
+    dio_state_lines: &[0..=15],
+    dio_ef_lines: &[0, 1, 2, 3, 6, 7],
+    high_speed_counters: &[16, 17, 18, 19],
+    dio_ef_clock_hz: 80_000_000.0,
+};
+
+const T8: Capabilities = Capabilities {
+    model: DeviceModel::T8,
+    ain_channels: &[0..=7],
+    ranges: InputRanges::Selectable(&[
+        11.0, 9.6, 4.8, 2.4, 1.2, 0.6, 0.3, 0.15, 0.075, 0.036, 0.018,
+    ]),
+    max_stream_resolution_index: 16,
+    max_settling_us: 0,
+    max_stream_rate_hz: 40_000.0,
+    simultaneous: true,
+    negative_channel: NegativeChannel::Isolated,
+    dio_state_lines: &[0..=7],
+    dio_ef_lines: &[],
+    high_speed_counters: &[],
+    dio_ef_clock_hz: 0.0,
+};
+
+impl DeviceModel {
+    /// `None` for devices the streamer cannot drive, e.g. a Digit.
+    pub fn from_device_type(device_type: &DeviceType) -> Option<Self> {
+        match device_type {
+            DeviceType::T4 => Some(Self::T4),
+            DeviceType::T7 => Some(Self::T7),
+            DeviceType::T8 => Some(Self::T8),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::T4 => "T4",
+            Self::T7 => "T7",
+            Self::T8 => "T8",
+        }
+    }
+
+    pub fn capabilities(self) -> &'static Capabilities {
+        match self {
+            Self::T4 => &T4,
+            Self::T7 => &T7,
+            Self::T8 => &T8,
+        }
+    }
+}
+
+impl Capabilities {
+    pub fn has_ain(&self, channel: u8) -> bool {
+        self.ain_channels
+            .iter()
+            .any(|channels| channels.contains(&channel))
+    }
+
+    pub fn has_dio_state(&self, line: u8) -> bool {
+        self.dio_state_lines
+            .iter()
+            .any(|lines| lines.contains(&line))
+    }
+
+    /// Range of a fixed-range input, `None` where the range is selectable.
+    pub fn fixed_range(&self, channel: u8) -> Option<f64> {
+        match self.ranges {
+            InputRanges::Selectable(_) => None,
+            InputRanges::Fixed { low_voltage_from } if channel < low_voltage_from => Some(10.0),
+            InputRanges::Fixed { .. } => Some(2.5),
+        }
+    }
+
+    /// The T7 pairs an even AIN0-AIN12 with the next channel, and Mux80
+    /// channels with the channel eight above them.
+    pub fn differential_partner(&self, channel: u8) -> Option<u8> {
+        if self.negative_channel != NegativeChannel::Pairs {
+            return None;
+        }
+        if channel <= 13 {
+            return channel.is_multiple_of(2).then_some(channel + 1);
+        }
+        if (48..=127).contains(&channel) && (channel - 48) % 16 < 8 {
+            return Some(channel + 8);
+        }
+        None
+    }
+
+    /// Highest scan rate for `channels` inputs.
+    pub fn max_scan_rate_hz(&self, channels: usize) -> f64 {
+        if self.simultaneous {
+            self.max_stream_rate_hz
+        } else {
+            self.max_stream_rate_hz / channels.max(1) as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_supported_models() {
+        assert_eq!(
+            DeviceModel::from_device_type(&DeviceType::T8),
+            Some(DeviceModel::T8)
+        );
+        assert_eq!(DeviceModel::from_device_type(&DeviceType::DIGIT), None);
+        let model: DeviceModel = serde_json::from_str("\"t4\"").unwrap();
+        assert_eq!(model, DeviceModel::T4);
+    }
+
+    #[test]
+    fn tables_differ_where_the_hardware_does() {
+        let t4 = DeviceModel::T4.capabilities();
+        assert_eq!(t4.fixed_range(3), Some(10.0));
+        assert_eq!(t4.fixed_range(4), Some(2.5));
+        assert!(!t4.has_ain(12));
+        assert_eq!(t4.max_scan_rate_hz(4), 10_000.0);
+
+        let t7 = DeviceModel::T7.capabilities();
+        assert!(t7.has_ain(13) && t7.has_ain(48) && !t7.has_ain(20));
+        assert_eq!(t7.differential_partner(2), Some(3));
+        assert_eq!(t7.differential_partner(56), None);
+
+        let t8 = DeviceModel::T8.capabilities();
+        assert_eq!(t8.max_scan_rate_hz(8), 40_000.0);
+        assert_eq!(t8.differential_partner(0), None);
+    }
+}
@@ -0,0 +1,600 @@
+use std::collections::{HashMap, VecDeque};
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::channel_spec::PublishedChannel;
+
+fn default_q() -> f64 {
+    FRAC_1_SQRT_2
+}
+
+fn default_notch_hz() -> f64 {
+    60.0
+}
+
+fn default_notch_q() -> f64 {
+    30.0
+}
+
+fn default_dc_cutoff_hz() -> f64 {
+    0.1
+}
+
+/// One stage of a channel's filter chain, from `sensor_settings.filters`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FilterSpec {
+    LowPass {
+        cutoff_hz: f64,
+        #[serde(default = "default_q")]
+        q: f64,
+    },
+    HighPass {
+        cutoff_hz: f64,
+        #[serde(default = "default_q")]
+        q: f64,
+    },
+    BandPass {
+        center_hz: f64,
+        #[serde(default = "default_q")]
+        q: f64,
+    },
+    Notch {
+        #[serde(default = "default_notch_hz")]
+        frequency_hz: f64,
+        #[serde(default = "default_notch_q")]
+        q: f64,
+    },
+    MovingAverage {
+        window_scans: u32,
+    },
+    DcRemoval {
+        #[serde(default = "default_dc_cutoff_hz")]
+        cutoff_hz: f64,
+    },
+}
+
+impl FilterSpec {
+    fn validate(&self, scan_rate_hz: f64) -> Result<(), String> {
+        let nyquist = scan_rate_hz / 2.0;
+        let (frequency, q) = match *self {
+            Self::LowPass { cutoff_hz, q } | Self::HighPass { cutoff_hz, q } => (cutoff_hz, q),
+            Self::BandPass { center_hz, q } => (center_hz, q),
+            Self::Notch { frequency_hz, q } => (frequency_hz, q),
+            Self::MovingAverage { window_scans } => {
+                return if window_scans == 0 {
+                    Err("moving_average window_scans must be greater than zero".to_string())
+                } else {
+                    Ok(())
+                };
+            }
+            Self::DcRemoval { cutoff_hz } => (cutoff_hz, 1.0),
+        };
+        if !(frequency.is_finite() && frequency > 0.0 && frequency < nyquist) {
+            return Err(format!(
+                "filter frequency {frequency} Hz must be above 0 and below the {nyquist} Hz Nyquist rate"
+            ));
+        }
+        if !(q.is_finite() && q > 0.0) {
+            return Err(format!("filter q {q} must be greater than zero"));
+        }
+        Ok(())
+    }
+}
+
+/// Derived channel computed from the filtered analog inputs of each scan.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Expression {
+    /// `a - b`
+    Difference { a: u8, b: u8 },
+    /// `a + b`
+    Sum { a: u8, b: u8 },
+    /// `a / b`
+    Ratio { a: u8, b: u8 },
+    /// Root mean square of `channel` over the last `window_scans` scans.
+    Rms { channel: u8, window_scans: u32 },
+}
+
+impl Expression {
+    fn inputs(&self) -> Vec<u8> {
+        match *self {
+            Self::Difference { a, b } | Self::Sum { a, b } | Self::Ratio { a, b } => vec![a, b],
+            Self::Rms { channel, .. } => vec![channel],
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Ratio { .. } => "ratio",
+            _ => "V",
+        }
+    }
+}
+
+/// A virtual channel from `sensor_settings.virtual_channels`, published as
+/// `vch<id>` after the physical channels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VirtualChannel {
+    pub id: u8,
+    #[serde(flatten)]
+    pub expression: Expression,
+}
+
+/// Checks filter chains and virtual channels against the enabled analog
+/// inputs and the scan rate.
+pub fn validate(
+    filters: &HashMap<u8, Vec<FilterSpec>>,
+    virtual_channels: &[VirtualChannel],
+    analog: &[u8],
+    scan_rate_hz: f64,
+) -> Result<(), String> {
+    for (channel, chain) in filters {
+        if !analog.contains(channel) {
+            return Err(format!("filters configured for AIN{channel}, which is not enabled"));
+        }
+        for filter in chain {
+            filter
+                .validate(scan_rate_hz)
+                .map_err(|e| format!("AIN{channel}: {e}"))?;
+        }
+    }
+    for (i, virtual_channel) in virtual_channels.iter().enumerate() {
+        let id = virtual_channel.id;
+        if virtual_channels[..i].iter().any(|other| other.id == id) {
+            return Err(format!("virtual channel id {id} is used twice"));
+        }
+        if let Some(missing) = virtual_channel
+            .expression
+            .inputs()
+            .into_iter()
+            .find(|input| !analog.contains(input))
+        {
+            return Err(format!(
+                "virtual channel {id} reads AIN{missing}, which is not enabled"
+            ));
+        }
+        if let Expression::Rms { window_scans: 0, .. } = virtual_channel.expression {
+            return Err(format!(
+                "virtual channel {id}: rms window_scans must be greater than zero"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Parses the KV `filters` map (keyed by channel number as a string), skipping
+/// keys that are not valid channels like `calibrations` does.
+pub fn parse_channel_filters(
+    raw: Option<&HashMap<String, Vec<FilterSpec>>>,
+) -> HashMap<u8, Vec<FilterSpec>> {
+    let mut out = HashMap::new();
+    for (key, chain) in raw.into_iter().flatten() {
+        match key.parse::<u8>() {
+            Ok(ch) => {
+                out.insert(ch, chain.clone());
+            }
+            Err(_) => warn!("Invalid filter channel key '{key}', expected u8."),
+        }
+    }
+    out
+}
+
+/// Direct form II transposed biquad with RBJ cookbook coefficients.
+#[derive(Debug, Clone)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+    primed: bool,
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            z: [0.0; 2],
+            primed: false,
+        }
+    }
+
+    fn design(spec: FilterSpec, scan_rate_hz: f64) -> Option<Self> {
+        let (frequency, q) = match spec {
+            FilterSpec::LowPass { cutoff_hz, q } | FilterSpec::HighPass { cutoff_hz, q } => {
+                (cutoff_hz, q)
+            }
+            FilterSpec::BandPass { center_hz, q } => (center_hz, q),
+            FilterSpec::Notch { frequency_hz, q } => (frequency_hz, q),
+            _ => return None,
+        };
+        let w0 = 2.0 * PI * frequency / scan_rate_hz;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a = [1.0 + alpha, -2.0 * cos, 1.0 - alpha];
+        let b = match spec {
+            FilterSpec::LowPass { .. } => [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            FilterSpec::HighPass { .. } => [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            FilterSpec::BandPass { .. } => [alpha, 0.0, -alpha],
+            _ => [1.0, -2.0 * cos, 1.0],
+        };
+        Some(Self::new(b, a))
+    }
+
+    /// Starts from the steady state for a constant input of `x`, so a filter
+    /// that passes DC does not ramp up from zero at the start of a run.
+    fn prime(&mut self, x: f64) {
+        let gain = (self.b[0] + self.b[1] + self.b[2]) / (1.0 + self.a[0] + self.a[1]);
+        let y = x * gain;
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.primed = true;
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        if !self.primed {
+            self.prime(x);
+        }
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// Mean of the last `len` samples, or of every sample so far while fewer
+/// have arrived.
+#[derive(Debug, Clone)]
+struct Window {
+    len: usize,
+    samples: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Window {
+    fn new(len: u32) -> Self {
+        let len = len.max(1) as usize;
+        Self {
+            len,
+            samples: VecDeque::with_capacity(len),
+            sum: 0.0,
+        }
+    }
+
+    fn mean(&mut self, x: f64) -> f64 {
+        if self.samples.len() == self.len
+            && let Some(oldest) = self.samples.pop_front()
+        {
+            self.sum -= oldest;
+        }
+        self.samples.push_back(x);
+        self.sum += x;
+        self.sum / self.samples.len() as f64
+    }
+
+    fn reset(&mut self) {
+        self.samples.clear();
+        self.sum = 0.0;
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Stage {
+    Biquad(Biquad),
+    MovingAverage(Window),
+    /// One-pole DC blocker: `y = x - x[n-1] + r * y[n-1]`.
+    DcRemoval {
+        r: f64,
+        previous: Option<(f64, f64)>,
+    },
+}
+
+impl Stage {
+    fn new(spec: FilterSpec, scan_rate_hz: f64) -> Self {
+        match spec {
+            FilterSpec::MovingAverage { window_scans } => {
+                Self::MovingAverage(Window::new(window_scans))
+            }
+            FilterSpec::DcRemoval { cutoff_hz } => Self::DcRemoval {
+                r: (-2.0 * PI * cutoff_hz / scan_rate_hz).exp(),
+                previous: None,
+            },
+            _ => Self::Biquad(Biquad::design(spec, scan_rate_hz).expect("biquad filter")),
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        match self {
+            Self::Biquad(biquad) => biquad.process(x),
+            Self::MovingAverage(window) => window.mean(x),
+            Self::DcRemoval { r, previous } => {
+                let y = match *previous {
+                    Some((x1, y1)) => x - x1 + *r * y1,
+                    None => 0.0,
+                };
+                *previous = Some((x, y));
+                y
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Self::Biquad(biquad) => {
+                biquad.z = [0.0; 2];
+                biquad.primed = false;
+            }
+            Self::MovingAverage(window) => window.reset(),
+            Self::DcRemoval { previous, .. } => *previous = None,
+        }
+    }
+}
+
+enum Derived {
+    Binary {
+        expression: Expression,
+        a: usize,
+        b: usize,
+    },
+    Rms {
+        column: usize,
+        squares: Window,
+    },
+}
+
+/// Runs each channel's filter chain over decoded scans and appends the
+/// virtual channels, which read the filtered values.
+pub struct Pipeline {
+    width: usize,
+    published: Vec<PublishedChannel>,
+    chains: Vec<(usize, Vec<Stage>)>,
+    derived: Vec<Derived>,
+}
+
+impl Pipeline {
+    /// `physical` is the decoded scan layout; `scan_rate_hz` the rate LJM
+    /// actually streams at, which the filter coefficients are designed for.
+    pub fn new(
+        physical: &[PublishedChannel],
+        filters: &HashMap<u8, Vec<FilterSpec>>,
+        virtual_channels: &[VirtualChannel],
+        scan_rate_hz: f64,
+    ) -> Self {
+        let column = |ch: u8| {
+            physical
+                .iter()
+                .position(|p| *p == PublishedChannel::Analog(ch))
+                .expect("validated analog input")
+        };
+        let mut chains: Vec<(usize, Vec<Stage>)> = filters
+            .iter()
+            .filter(|(_, chain)| !chain.is_empty())
+            .map(|(ch, chain)| {
+                let stages = chain
+                    .iter()
+                    .map(|spec| Stage::new(*spec, scan_rate_hz))
+                    .collect();
+                (column(*ch), stages)
+            })
+            .collect();
+        chains.sort_by_key(|(column, _)| *column);
+
+        let mut published = physical.to_vec();
+        let mut derived = Vec::with_capacity(virtual_channels.len());
+        for virtual_channel in virtual_channels {
+            published.push(PublishedChannel::Virtual {
+                id: virtual_channel.id,
+                unit: virtual_channel.expression.unit(),
+            });
+            derived.push(match virtual_channel.expression {
+                Expression::Rms {
+                    channel,
+                    window_scans,
+                } => Derived::Rms {
+                    column: column(channel),
+                    squares: Window::new(window_scans),
+                },
+                Expression::Difference { a, b }
+                | Expression::Sum { a, b }
+                | Expression::Ratio { a, b } => Derived::Binary {
+                    expression: virtual_channel.expression,
+                    a: column(a),
+                    b: column(b),
+                },
+            });
+        }
+
+        Self {
+            width: physical.len(),
+            published,
+            chains,
+            derived,
+        }
+    }
+
+    /// Physical channels followed by the virtual channels.
+    pub fn published(&self) -> &[PublishedChannel] {
+        &self.published
+    }
+
+    /// Whether `process` changes the decoded scans at all.
+    pub fn is_passthrough(&self) -> bool {
+        self.chains.is_empty() && self.derived.is_empty()
+    }
+
+    /// Filters `scans` in place and returns them widened by the virtual
+    /// channels.
+    pub fn process(&mut self, scans: Vec<f64>) -> Vec<f64> {
+        if self.is_passthrough() {
+            return scans;
+        }
+        let width = self.width.max(1);
+        let mut out = Vec::with_capacity(scans.len() / width * self.published.len());
+        for scan in scans.chunks_exact(width) {
+            let start = out.len();
+            out.extend_from_slice(scan);
+            let filtered = &mut out[start..];
+            for (column, stages) in &mut self.chains {
+                filtered[*column] = stages
+                    .iter_mut()
+                    .fold(filtered[*column], |x, stage| stage.process(x));
+            }
+            for derived in &mut self.derived {
+                let value = match derived {
+                    Derived::Binary { expression, a, b } => {
+                        let (a, b) = (out[start + *a], out[start + *b]);
+                        match expression {
+                            Expression::Difference { .. } => a - b,
+                            Expression::Sum { .. } => a + b,
+                            _ => a / b,
+                        }
+                    }
+                    Derived::Rms { column, squares } => {
+                        let x = out[start + *column];
+                        squares.mean(x * x).max(0.0).sqrt()
+                    }
+                };
+                out.push(value);
+            }
+        }
+        out
+    }
+
+    /// Clears filter and window state after skipped scans, so nothing is
+    /// smoothed across the gap.
+    pub fn reset(&mut self) {
+        for (_, stages) in &mut self.chains {
+            stages.iter_mut().for_each(Stage::reset);
+        }
+        for derived in &mut self.derived {
+            if let Derived::Rms { squares, .. } = derived {
+                squares.reset();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frequency_hz: f64, rate_hz: f64, scans: usize) -> Vec<f64> {
+        (0..scans)
+            .map(|i| (2.0 * PI * frequency_hz * i as f64 / rate_hz).sin())
+            .collect()
+    }
+
+    fn amplitude(values: &[f64]) -> f64 {
+        values.iter().fold(0.0_f64, |peak, v| peak.max(v.abs()))
+    }
+
+    fn run(spec: FilterSpec, input: Vec<f64>) -> Vec<f64> {
+        let mut filters = HashMap::new();
+        filters.insert(0, vec![spec]);
+        let mut pipeline = Pipeline::new(&[PublishedChannel::Analog(0)], &filters, &[], 1000.0);
+        pipeline.process(input)
+    }
+
+    #[test]
+    fn config_parses_and_validates() {
+        let chain: Vec<FilterSpec> = serde_json::from_str(
+            r#"[{ "type": "notch" }, { "type": "low_pass", "cutoff_hz": 100 }, { "type": "moving_average", "window_scans": 5 }]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            chain[0],
+            FilterSpec::Notch {
+                frequency_hz: 60.0,
+                q: 30.0
+            }
+        );
+        let virtual_channel: VirtualChannel =
+            serde_json::from_str(r#"{ "id": 0, "type": "difference", "a": 7, "b": 11 }"#).unwrap();
+        assert_eq!(
+            virtual_channel.expression,
+            Expression::Difference { a: 7, b: 11 }
+        );
+
+        let mut filters = HashMap::new();
+        filters.insert(11, chain);
+        assert!(validate(&filters, &[virtual_channel], &[7, 11], 1000.0).is_ok());
+        assert!(validate(&filters, &[virtual_channel], &[7, 11], 150.0).is_err());
+        assert!(validate(&filters, &[virtual_channel], &[11], 1000.0).is_err());
+        assert!(validate(&HashMap::new(), &[virtual_channel; 2], &[7, 11], 1000.0).is_err());
+    }
+
+    #[test]
+    fn notch_removes_mains_and_keeps_other_tones() {
+        let notch = FilterSpec::Notch {
+            frequency_hz: 60.0,
+            q: 30.0,
+        };
+        let hum = run(notch, tone(60.0, 1000.0, 4000));
+        assert!(amplitude(&hum[3000..]) < 0.01);
+        let signal = run(notch, tone(10.0, 1000.0, 4000));
+        assert!(amplitude(&signal[3000..]) > 0.95);
+    }
+
+    #[test]
+    fn low_and_high_pass_split_the_band() {
+        let low = FilterSpec::LowPass {
+            cutoff_hz: 20.0,
+            q: FRAC_1_SQRT_2,
+        };
+        let high = FilterSpec::HighPass {
+            cutoff_hz: 20.0,
+            q: FRAC_1_SQRT_2,
+        };
+        assert!(amplitude(&run(low, tone(200.0, 1000.0, 2000))[1000..]) < 0.02);
+        assert!(amplitude(&run(high, tone(2.0, 1000.0, 2000))[1000..]) < 0.02);
+        // Low-pass filters start settled on a constant input.
+        assert!(run(low, vec![1.5; 10]).iter().all(|v| (v - 1.5).abs() < 1e-9));
+    }
+
+    #[test]
+    fn dc_removal_and_moving_average() {
+        let offset: Vec<f64> = tone(50.0, 1000.0, 5000).iter().map(|v| v + 3.0).collect();
+        let centred = run(FilterSpec::DcRemoval { cutoff_hz: 1.0 }, offset);
+        let tail = &centred[4000..];
+        assert!((tail.iter().sum::<f64>() / tail.len() as f64).abs() < 0.01);
+
+        let averaged = run(
+            FilterSpec::MovingAverage { window_scans: 2 },
+            vec![1.0, 3.0, 5.0],
+        );
+        assert_eq!(averaged, vec![1.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn virtual_channels_follow_the_filtered_inputs() {
+        let physical = [PublishedChannel::Analog(7), PublishedChannel::Analog(11)];
+        let virtual_channels = [
+            VirtualChannel {
+                id: 0,
+                expression: Expression::Difference { a: 7, b: 11 },
+            },
+            VirtualChannel {
+                id: 1,
+                expression: Expression::Ratio { a: 7, b: 11 },
+            },
+            VirtualChannel {
+                id: 2,
+                expression: Expression::Rms {
+                    channel: 11,
+                    window_scans: 2,
+                },
+            },
+        ];
+        let mut pipeline = Pipeline::new(&physical, &HashMap::new(), &virtual_channels, 1000.0);
+        assert_eq!(pipeline.published().len(), 5);
+        assert_eq!(pipeline.published()[4].token(), "vch2");
+
+        let out = pipeline.process(vec![6.0, 3.0, 4.0, -4.0]);
+        assert_eq!(out.len(), 10);
+        assert_eq!(&out[..5], &[6.0, 3.0, 3.0, 2.0, 3.0]);
+        assert_eq!(out[5..9], [4.0, -4.0, 8.0, -1.0]);
+        assert!((out[9] - 12.5_f64.sqrt()).abs() < 1e-12);
+    }
+}
@@ -24,7 +24,10 @@ use parquet::{
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use tracing::{Instrument, error, info, info_span, warn};
 mod calibration;
+mod logging;
+mod metrics;
 mod nats_config;
 
 use calibration::CalibrationSpec;
@@ -134,15 +137,20 @@ fn default_format() -> ExportFormat {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    logging::init().map_err(|e| anyhow!(e))?;
     let mode = ExporterMode::from_env()?;
+    register_metrics();
+    metrics::serve_from_env("exporter")
+        .await
+        .map_err(|e| anyhow!(e))?;
     let listen_addr =
         std::env::var("EXPORTER_ADDR").unwrap_or_else(|_| DEFAULT_EXPORTER_ADDR.into());
     let parquet_root = std::env::var("PARQUET_DIR").unwrap_or_else(|_| "parquet".into());
     let root_path = PathBuf::from(parquet_root);
 
     if matches!(mode, ExporterMode::Direct | ExporterMode::Worker) && !root_path.exists() {
-        println!(
-            "[exporter] Warning: parquet directory '{}' does not exist.",
+        warn!(
+            "Warning: parquet directory '{}' does not exist.",
             root_path.display()
         );
     }
@@ -159,8 +167,8 @@ async fn main() -> Result<()> {
                 .route("/export", get(handle_ws))
                 .with_state(state);
 
-            println!(
-                "[exporter] mode={} listening on ws://{listen_addr}/export",
+            info!(
+                "mode={} listening on ws://{listen_addr}/export",
                 match mode {
                     ExporterMode::Direct => "direct",
                     ExporterMode::Worker => "worker",
@@ -237,14 +245,14 @@ async fn run_worker(parquet_root: PathBuf) -> Result<()> {
         .await
         .map_err(|e| anyhow!("failed to subscribe to export worker subject '{subject}': {e}"))?;
 
-    println!("[exporter] worker listening on NATS subject '{subject}'");
+    info!("worker listening on NATS subject '{subject}'");
 
     while let Some(message) = subscriber.next().await {
         let client = client.clone();
         let parquet_root = parquet_root.clone();
         tokio::spawn(async move {
             if let Err(err) = handle_worker_request(client, parquet_root, message).await {
-                eprintln!("[exporter] worker request failed: {err:#}");
+                error!("worker request failed: {err:#}");
             }
         });
     }
@@ -257,8 +265,10 @@ async fn handle_worker_request(
     parquet_root: PathBuf,
     message: async_nats::Message,
 ) -> Result<()> {
-    let req: NatsExportRequest = serde_json::from_slice(&message.payload)
-        .map_err(|e| anyhow!("invalid export request payload: {e}"))?;
+    let req: NatsExportRequest = serde_json::from_slice(&message.payload).map_err(|e| {
+        invalid_requests().inc();
+        anyhow!("invalid export request payload: {e}")
+    })?;
     let export_req = ExportRequest {
         asset: req.asset,
         channels: req.channels,
@@ -269,15 +279,25 @@ async fn handle_worker_request(
         box_id: None,
     };
 
-    let mut sink = NatsReplySink::new(client, req.response_subject.clone());
-    if let Err(err) = serve_export_request(&parquet_root, &mut sink, &export_req).await {
-        eprintln!(
-            "[exporter] job {} failed for response subject {}: {err:#}",
-            req.job_id, req.response_subject
-        );
-        sink.send_error(&err.to_string()).await.ok();
-        sink.send_complete().await.ok();
+    let span = info_span!(
+        "export_job",
+        job_id = %req.job_id,
+        subject = %req.response_subject,
+        asset = req.asset
+    );
+    async {
+        let mut sink = NatsReplySink::new(client, req.response_subject.clone());
+        if let Err(err) = serve_export_request(&parquet_root, &mut sink, &export_req).await {
+            error!(
+                "job {} failed for response subject {}: {err:#}",
+                req.job_id, req.response_subject
+            );
+            sink.send_error(&err.to_string()).await.ok();
+            sink.send_complete().await.ok();
+        }
     }
+    .instrument(span)
+    .await;
 
     Ok(())
 }
@@ -285,7 +305,7 @@ async fn handle_worker_request(
 async fn handle_ws(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
     ws.on_upgrade(move |socket| async move {
         if let Err(err) = process_socket(socket, state).await {
-            eprintln!("[exporter] websocket error: {err:#}");
+            error!("websocket error: {err:#}");
         }
     })
 }
@@ -295,12 +315,19 @@ async fn process_socket(mut socket: WebSocket, state: AppState) -> Result<()> {
 
     match state.mode {
         ExporterMode::Direct => {
-            let mut sink = WebSocketSink::new(socket);
-            if let Err(err) = serve_export_request(&state.parquet_root, &mut sink, &request).await {
-                sink.send_error(&err.to_string()).await.ok();
-                sink.send_complete().await.ok();
-                sink.send_close().await.ok();
+            let span = info_span!("export_job", mode = "direct", asset = request.asset);
+            async {
+                let mut sink = WebSocketSink::new(socket);
+                if let Err(err) =
+                    serve_export_request(&state.parquet_root, &mut sink, &request).await
+                {
+                    sink.send_error(&err.to_string()).await.ok();
+                    sink.send_complete().await.ok();
+                    sink.send_close().await.ok();
+                }
             }
+            .instrument(span)
+            .await;
         }
         ExporterMode::Worker => {
             return Err(anyhow!("worker mode does not serve websocket exports"));
@@ -325,15 +352,84 @@ async fn read_export_request(socket: &mut WebSocket) -> Result<ExportRequest> {
         return Err(anyhow!("expected JSON request"));
     };
 
-    let req: ExportRequest =
-        serde_json::from_str(&text).map_err(|e| anyhow!("invalid request payload: {e}"))?;
+    let req: ExportRequest = serde_json::from_str(&text).map_err(|e| {
+        invalid_requests().inc();
+        anyhow!("invalid request payload: {e}")
+    })?;
     Ok(req)
 }
 
+fn invalid_requests() -> Arc<metrics::Counter> {
+    metrics::global().counter(
+        "exporter_invalid_requests_total",
+        "Export requests rejected before a job started, e.g. malformed JSON.",
+        &[],
+    )
+}
+
+fn jobs_total(outcome: &str) -> Arc<metrics::Counter> {
+    metrics::global().counter(
+        "exporter_jobs_total",
+        "Export jobs finished, by outcome.",
+        &[("outcome", outcome)],
+    )
+}
+
+fn jobs_in_progress() -> Arc<metrics::Gauge> {
+    metrics::global().gauge(
+        "exporter_jobs_in_progress",
+        "Exports currently streaming.",
+        &[],
+    )
+}
+
+fn bytes_streamed() -> Arc<metrics::Counter> {
+    metrics::global().counter(
+        "exporter_bytes_streamed_total",
+        "CSV bytes sent to export clients.",
+        &[],
+    )
+}
+
+/// Creates the job series up front so they scrape as zero before the first
+/// export.
+fn register_metrics() {
+    invalid_requests();
+    jobs_total("ok");
+    jobs_total("error");
+    jobs_in_progress();
+    bytes_streamed();
+}
+
+/// Streams one export and records it in the job metrics.
 async fn serve_export_request<S: ExportSink + Send>(
     parquet_root: &Path,
     sink: &mut S,
     req: &ExportRequest,
+) -> Result<()> {
+    let in_progress = jobs_in_progress();
+    in_progress.add(1.0);
+    let started = std::time::Instant::now();
+    let result = stream_export_request(parquet_root, sink, req).await;
+    in_progress.add(-1.0);
+
+    let outcome = if result.is_ok() { "ok" } else { "error" };
+    jobs_total(outcome).inc();
+    metrics::global()
+        .histogram(
+            "exporter_job_duration_seconds",
+            "Time to stream one export, by outcome.",
+            &[("outcome", outcome)],
+            metrics::DURATION_BUCKETS,
+        )
+        .observe(started.elapsed().as_secs_f64());
+    result
+}
+
+async fn stream_export_request<S: ExportSink + Send>(
+    parquet_root: &Path,
+    sink: &mut S,
+    req: &ExportRequest,
 ) -> Result<()> {
     let mut req = req.clone();
 
@@ -565,6 +661,7 @@ impl<'a, S: ExportSink + Send> CsvStreamer<'a, S> {
         }
         let data = std::mem::take(&mut self.chunk);
         self.bytes_sent += data.len();
+        bytes_streamed().inc_by(data.len() as u64);
         self.sink.send_chunk(data).await?;
         self.chunk = Vec::with_capacity(Self::CHUNK_SIZE);
         Ok(())
@@ -623,7 +720,7 @@ impl<'a, S: ExportSink + Send> CsvStreamer<'a, S> {
 
             for path in files {
                 if let Err(err) = self.stream_parquet_file(&path, channel, &mut found).await {
-                    eprintln!("[exporter] skipping {} due to error: {err}", path.display());
+                    warn!("skipping {} due to error: {err}", path.display());
                 }
             }
         }
@@ -642,8 +739,7 @@ impl<'a, S: ExportSink + Send> CsvStreamer<'a, S> {
             .with_context(|| format!("failed to create reader for {}", path.display()))?;
         let calibration = read_calibration_from_metadata(&reader, path);
         let calibration_id = calibration.id_or_default().to_string();
-        let mut iter = reader.get_row_iter(None)?;
-        while let Some(row) = iter.next() {
+        for row in reader.get_row_iter(None)? {
             let row = row?;
             let timestamp_unix_ns = row.get_long(0)?;
             let ts = match timestamp_unix_ns_to_rfc3339(timestamp_unix_ns) {
@@ -694,8 +790,8 @@ fn read_calibration_from_metadata(
     match serde_json::from_str::<CalibrationSpec>(json) {
         Ok(spec) => spec,
         Err(err) => {
-            eprintln!(
-                "[exporter] invalid calibration metadata in {}: {err}",
+            warn!(
+                "invalid calibration metadata in {}: {err}",
                 path.display()
             );
             CalibrationSpec::default()
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use ljmrs::handle::ConnectionType;
+use serde::Serialize;
+
+use crate::publisher::{PublishStats, env_number};
+
+const DEFAULT_INTERVAL_SECS: u64 = 5;
+
+/// Heartbeat interval from `STREAM_STATUS_SECS`; `None` when set to `0`.
+pub fn interval_from_env() -> Result<Option<Duration>, String> {
+    let secs: u64 = env_number("STREAM_STATUS_SECS", DEFAULT_INTERVAL_SECS, 0)?;
+    Ok((secs > 0).then(|| Duration::from_secs(secs)))
+}
+
+pub fn connection_type_name(connection_type: &ConnectionType) -> String {
+    match connection_type {
+        ConnectionType::USB => "usb".to_string(),
+        ConnectionType::ETHERNET => "ethernet".to_string(),
+        ConnectionType::WIFI => "wifi".to_string(),
+        ConnectionType::ANY => "any".to_string(),
+        ConnectionType::UNKNOWN(code) => format!("unknown({code})"),
+    }
+}
+
+/// Periodic streamer and device health, published on `.status` while a run
+/// is streaming or waiting for its next capture.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Heartbeat {
+    pub run_id: usize,
+    /// `run_id` carried by this run's `Scan` and `ScanFrame` payloads.
+    pub run_uuid: String,
+    pub serial: i32,
+    /// `None` when `FIRMWARE_VERSION` could not be read at connect.
+    pub firmware_version: Option<f64>,
+    pub connection_type: String,
+    pub backend: &'static str,
+    pub acquisition: &'static str,
+    pub scan_rate_hz: f64,
+    /// Backlogs reported by the latest `stream_read`.
+    pub device_backlog: i32,
+    pub ljm_backlog: i32,
+    /// `TEMPERATURE_DEVICE_K`; `None` when the read failed.
+    pub temperature_k: Option<f64>,
+    pub skipped_scans: u64,
+    /// Publisher counters since the streamer started; `retried`, `dropped`
+    /// and `evicted` count publish errors.
+    pub publish: PublishStats,
+    /// Sequence of the latest batch published this run.
+    pub last_sequence: Option<u64>,
+    pub uptime_secs: u64,
+    pub run_uptime_secs: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::supervisor::ConnectionState;
+
+    #[test]
+    fn heartbeat_serializes_as_status_state() {
+        let heartbeat = Heartbeat {
+            run_id: 3,
+            run_uuid: "8d2e".to_string(),
+            serial: 470012345,
+            firmware_version: Some(1.0299),
+            connection_type: connection_type_name(&ConnectionType::ETHERNET),
+            backend: "ljm",
+            acquisition: "continuous",
+            scan_rate_hz: 1000.0,
+            device_backlog: 0,
+            ljm_backlog: 12,
+            temperature_k: None,
+            skipped_scans: 0,
+            publish: PublishStats::default(),
+            last_sequence: Some(41),
+            uptime_secs: 600,
+            run_uptime_secs: 60,
+        };
+        let json = serde_json::to_value(ConnectionState::Heartbeat(heartbeat)).unwrap();
+        assert_eq!(json["state"], "heartbeat");
+        assert_eq!(json["connection_type"], "ethernet");
+        assert_eq!(json["publish"]["dropped"], 0);
+        assert!(json["temperature_k"].is_null());
+        assert_eq!(
+            connection_type_name(&ConnectionType::UNKNOWN(7)),
+            "unknown(7)"
+        );
+    }
+}
@@ -3,6 +3,10 @@ use std::str::FromStr;
 
 use ljmrs::handle::{ConnectionType, DeviceHandleInfo, DeviceType};
 use ljmrs::{LJMError, LJMLibrary};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::ljm_discovery;
 
 const STREAM_IS_ACTIVE_ERROR: i32 = 2605;
 
@@ -21,21 +25,150 @@ fn parse_ipv4(value: &str) -> Option<Ipv4Addr> {
     Ipv4Addr::from_str(value).ok()
 }
 
-fn required_labjack_ip_from_env() -> Result<String, LJMError> {
-    env_identifier("LABJACK_IP")
-        .or_else(|| {
-            env_identifier("LABJACK_IDENTIFIER").filter(|value| parse_ipv4(value).is_some())
-        })
-        .ok_or_else(|| {
-            LJMError::LibraryError(
-                "LABJACK_IP is required; direct Ethernet IP open is the only supported path"
-                    .to_string(),
-            )
+/// Which LabJack a sampler opens. The streamer builds one per entry of
+/// `LABJACK_DEVICES`, or a single one from the `LABJACK_*` variables.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct LabJackTarget {
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_serial")]
+    pub serial: Option<i32>,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// USB identifier, a serial number; defaults to `serial`.
+    #[serde(default)]
+    pub usb_id: Option<String>,
+    /// Open strategies tried in order; `None` keeps the Ethernet-only path.
+    #[serde(default, deserialize_with = "deserialize_open_order")]
+    pub open_order: Option<Vec<OpenStrategy>>,
+}
+
+/// How a sampler reaches its LabJack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenStrategy {
+    /// Direct open of `LABJACK_IP`.
+    Ethernet,
+    /// USB open of `LABJACK_USB_ID`, or of `LABJACK_SERIAL`.
+    Usb,
+    /// `LJM_ListAll` over USB and the local network, matched by serial and
+    /// name, for boxes whose DHCP lease moves.
+    Discover,
+}
+
+impl OpenStrategy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ethernet => "ethernet",
+            Self::Usb => "usb",
+            Self::Discover => "discover",
+        }
+    }
+}
+
+/// Parses a comma separated `LABJACK_OPEN_ORDER` such as `ethernet,usb`.
+pub fn parse_open_order(raw: &str) -> Result<Vec<OpenStrategy>, String> {
+    let mut order = Vec::new();
+    for token in raw.split(',').map(str::trim).filter(|token| !token.is_empty()) {
+        let strategy = match token.to_ascii_lowercase().as_str() {
+            "ethernet" | "ip" => OpenStrategy::Ethernet,
+            "usb" => OpenStrategy::Usb,
+            "discover" | "listall" => OpenStrategy::Discover,
+            other => {
+                return Err(format!(
+                    "invalid LABJACK_OPEN_ORDER entry '{other}', expected ethernet, usb or discover"
+                ));
+            }
+        };
+        if order.contains(&strategy) {
+            return Err(format!("LABJACK_OPEN_ORDER lists '{token}' more than once"));
+        }
+        order.push(strategy);
+    }
+    if order.is_empty() {
+        return Err("LABJACK_OPEN_ORDER is empty".to_string());
+    }
+    Ok(order)
+}
+
+fn deserialize_open_order<'de, D>(deserializer: D) -> Result<Option<Vec<OpenStrategy>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(raw) if !raw.trim().is_empty() => parse_open_order(&raw)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+impl LabJackTarget {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Self {
+            ip: env_identifier("LABJACK_IP").or_else(|| {
+                env_identifier("LABJACK_IDENTIFIER").filter(|value| parse_ipv4(value).is_some())
+            }),
+            serial: env_identifier("LABJACK_SERIAL").and_then(|value| value.parse::<i32>().ok()),
+            name: env_identifier("LABJACK_NAME"),
+            usb_id: env_identifier("LABJACK_USB_ID"),
+            open_order: env_var("LABJACK_OPEN_ORDER")
+                .map(|raw| parse_open_order(&raw))
+                .transpose()?,
         })
+    }
+
+    pub fn open_order(&self) -> Vec<OpenStrategy> {
+        self.open_order
+            .clone()
+            .unwrap_or_else(|| vec![OpenStrategy::Ethernet])
+    }
+
+    /// Short name for log lines.
+    pub fn label(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| self.ip.clone())
+            .or_else(|| self.serial.map(|serial| serial.to_string()))
+            .unwrap_or_else(|| "labjack".to_string())
+    }
 }
 
-fn requested_labjack_serial_from_env() -> Option<i32> {
-    env_identifier("LABJACK_SERIAL").and_then(|value| value.parse::<i32>().ok())
+/// Accepts `LABJACK_SERIAL` style strings as well as JSON numbers, with
+/// empty and `ANY` meaning no serial check.
+fn deserialize_serial<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Serial {
+        Number(i32),
+        Text(String),
+    }
+
+    match Option::<Serial>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Serial::Number(serial)) => Ok(Some(serial)),
+        Some(Serial::Text(raw)) => {
+            let raw = raw.trim();
+            if raw.is_empty() || raw.eq_ignore_ascii_case("ANY") {
+                return Ok(None);
+            }
+            raw.parse::<i32>()
+                .map(Some)
+                .map_err(|_| serde::de::Error::custom(format!("invalid LabJack serial '{raw}'")))
+        }
+    }
+}
+
+/// Register the post-open self-test reads and writes back; the T8 has no
+/// stream settling control. `None` for models the streamer cannot drive.
+fn self_test_register(device_type: &DeviceType) -> Option<&'static str> {
+    match device_type {
+        DeviceType::T4 | DeviceType::T7 => Some("STREAM_SETTLING_US"),
+        DeviceType::T8 => Some("STREAM_RESOLUTION_INDEX"),
+        _ => None,
+    }
 }
 
 fn ljm_error_code(err: &LJMError) -> Option<i32> {
@@ -49,115 +182,224 @@ fn is_stream_active_error(err: &LJMError) -> bool {
     ljm_error_code(err) == Some(STREAM_IS_ACTIVE_ERROR)
 }
 
+#[allow(dead_code)]
 pub fn open_labjack_from_env() -> Result<i32, LJMError> {
-    open_streamer_labjack_from_env()
+    open_streamer_labjack(&LabJackTarget::from_env().map_err(LJMError::LibraryError)?)
 }
 
-pub fn open_streamer_labjack_from_env() -> Result<i32, LJMError> {
-    let requested_ip = required_labjack_ip_from_env()?;
-    let expected_serial = requested_labjack_serial_from_env();
-    let requested_name = env_identifier("LABJACK_NAME");
+/// Opens the first device the target's open order reaches. Whatever path
+/// found it, the handle is only returned after `verify_opened` accepted it.
+pub fn open_streamer_labjack(target: &LabJackTarget) -> Result<i32, LJMError> {
+    if let Some(name) = target.name.as_deref() {
+        info!("requested logical device name '{name}'");
+    }
 
-    println!("[labjack] trying ethernet identifier '{requested_ip}'");
-    if let Some(name) = requested_name.as_deref() {
-        println!("[labjack] requested logical device name '{name}'");
+    let mut failures = Vec::new();
+    for strategy in target.open_order() {
+        match open_with(strategy, target) {
+            Ok(handle) => return Ok(handle),
+            Err(err) => {
+                warn!("{} open failed: {:?}", strategy.as_str(), err);
+                failures.push(format!("{}: {:?}", strategy.as_str(), err));
+            }
+        }
     }
+    Err(LJMError::LibraryError(format!(
+        "Could not open LabJack {} via {}",
+        target.label(),
+        failures.join("; ")
+    )))
+}
 
-    let handle = LJMLibrary::open_jack(
-        DeviceType::T7,
-        ConnectionType::ETHERNET,
-        requested_ip.as_str(),
-    )
-    .map_err(|err| {
-        LJMError::LibraryError(format!(
-            "Could not open LabJack via LABJACK_IP='{}': {:?}",
-            requested_ip, err
-        ))
-    })?;
+fn open_with(strategy: OpenStrategy, target: &LabJackTarget) -> Result<i32, LJMError> {
+    let (handle, described) = match strategy {
+        OpenStrategy::Ethernet => {
+            let requested_ip = target.ip.clone().ok_or_else(|| {
+                LJMError::LibraryError("ethernet open needs LABJACK_IP".to_string())
+            })?;
+            info!("trying ethernet identifier '{requested_ip}'");
+            let handle = LJMLibrary::open_jack(
+                DeviceType::TSERIES,
+                ConnectionType::ETHERNET,
+                requested_ip.as_str(),
+            )
+            .map_err(|err| {
+                LJMError::LibraryError(format!(
+                    "Could not open LabJack via LABJACK_IP='{}': {:?}",
+                    requested_ip, err
+                ))
+            })?;
+            (handle, requested_ip)
+        }
+        OpenStrategy::Usb => {
+            // An `ANY` USB open would take whichever device enumerates first.
+            let identifier = target
+                .usb_id
+                .clone()
+                .or_else(|| target.serial.map(|serial| serial.to_string()))
+                .ok_or_else(|| {
+                    LJMError::LibraryError(
+                        "usb open needs LABJACK_USB_ID or LABJACK_SERIAL".to_string(),
+                    )
+                })?;
+            info!("trying usb identifier '{identifier}'");
+            let handle =
+                LJMLibrary::open_jack(DeviceType::TSERIES, ConnectionType::USB, identifier.as_str())
+                    .map_err(|err| {
+                        LJMError::LibraryError(format!(
+                            "Could not open LabJack via USB '{}': {:?}",
+                            identifier, err
+                        ))
+                    })?;
+            (handle, format!("usb:{identifier}"))
+        }
+        OpenStrategy::Discover => discover(target)?,
+    };
 
-    let verification = (|| -> Result<DeviceHandleInfo, LJMError> {
-        let info = handle_info(handle).map_err(|err| {
-            LJMError::LibraryError(format!(
-                "Opened LabJack at '{}' but failed to read handle info: {:?}",
-                requested_ip, err
-            ))
-        })?;
+    if let Err(err) = verify_opened(handle, strategy, target, &described) {
+        let _ = LJMLibrary::close_jack(handle);
+        return Err(err);
+    }
+    Ok(handle)
+}
 
-        if !matches!(info.device_type, DeviceType::T7) {
-            return Err(LJMError::LibraryError(format!(
-                "Connected device at '{}' is not a T7: {:?}",
-                requested_ip, info.device_type
-            )));
-        }
+/// Finds the target among the devices `LJM_ListAll` reports, by serial and,
+/// when set, by `DEVICE_NAME_DEFAULT`.
+fn discover(target: &LabJackTarget) -> Result<(i32, String), LJMError> {
+    if target.serial.is_none() && target.name.is_none() {
+        return Err(LJMError::LibraryError(
+            "discover open needs LABJACK_SERIAL or LABJACK_NAME".to_string(),
+        ));
+    }
 
-        let actual_ip = handle_ip_address(&info)?.unwrap_or_else(|| "N/A".to_string());
-        if actual_ip != "N/A" && actual_ip != requested_ip {
-            return Err(LJMError::LibraryError(format!(
-                "Connected device IP mismatch: requested '{}', got '{}'",
-                requested_ip, actual_ip
-            )));
+    let found = ljm_discovery::list_all()?;
+    info!("ListAll found {} connection(s)", found.len());
+    let mut serials: Vec<i32> = Vec::new();
+    for device in &found {
+        let serial = device.serial_number;
+        if target.serial.is_none_or(|expected| expected == serial) && !serials.contains(&serial) {
+            serials.push(serial);
         }
+    }
 
-        if let Some(expected_serial) = expected_serial {
-            if info.serial_number != expected_serial {
-                return Err(LJMError::LibraryError(format!(
-                    "Connected LabJack serial mismatch at '{}': expected {}, got {}",
-                    requested_ip, expected_serial, info.serial_number
-                )));
+    for serial in serials {
+        let identifier = serial.to_string();
+        let handle =
+            match LJMLibrary::open_jack(DeviceType::TSERIES, ConnectionType::ANY, identifier.as_str())
+            {
+                Ok(handle) => handle,
+                Err(err) => {
+                    warn!("discovered serial {serial} but open failed: {:?}", err);
+                    continue;
+                }
+            };
+        let Some(name) = target.name.as_deref() else {
+            return Ok((handle, format!("serial:{identifier}")));
+        };
+        match ljm_discovery::read_name_string(handle, "DEVICE_NAME_DEFAULT") {
+            Ok(device_name) if device_name == name => {
+                return Ok((handle, format!("name:{name}")));
             }
-        }
-
-        let settling_us = LJMLibrary::read_name(handle, "STREAM_SETTLING_US").map_err(|err| {
-            LJMError::LibraryError(format!(
-                "LabJack self-test read failed for '{}': STREAM_SETTLING_US: {:?}",
-                requested_ip, err
-            ))
-        })?;
-
-        match LJMLibrary::write_name(handle, "STREAM_SETTLING_US", settling_us) {
-            Ok(_) => {}
-            Err(err) if is_stream_active_error(&err) => {
-                eprintln!(
-                    "[labjack] stale active stream detected on '{}'; sending stream_stop and retrying self-test",
-                    requested_ip
-                );
-                LJMLibrary::stream_stop(handle).map_err(|stop_err| {
-                    LJMError::LibraryError(format!(
-                        "LabJack stream_stop failed for stale stream on '{}': {:?}",
-                        requested_ip, stop_err
-                    ))
-                })?;
-                LJMLibrary::write_name(handle, "STREAM_SETTLING_US", settling_us).map_err(
-                    |retry_err| {
-                        LJMError::LibraryError(format!(
-                            "LabJack self-test write failed after stream_stop for '{}': STREAM_SETTLING_US={}: {:?}",
-                            requested_ip, settling_us, retry_err
-                        ))
-                    },
-                )?;
+            Ok(device_name) => {
+                info!("discovered serial {serial} is named '{device_name}', skipping");
             }
             Err(err) => {
-                return Err(LJMError::LibraryError(format!(
-                    "LabJack self-test write failed for '{}': STREAM_SETTLING_US={}: {:?}",
-                    requested_ip, settling_us, err
-                )));
+                warn!("failed to read the name of serial {serial}: {:?}", err);
             }
         }
+        let _ = LJMLibrary::close_jack(handle);
+    }
 
-        println!(
-            "[labjack] connected via {:?}, serial {}, ip {}, self-test ok",
-            info.connection_type, info.serial_number, actual_ip
-        );
+    Err(LJMError::LibraryError(format!(
+        "ListAll found no device matching serial {:?} and name {:?}",
+        target.serial, target.name
+    )))
+}
+
+fn verify_opened(
+    handle: i32,
+    strategy: OpenStrategy,
+    target: &LabJackTarget,
+    described: &str,
+) -> Result<DeviceHandleInfo, LJMError> {
+    let info = handle_info(handle).map_err(|err| {
+        LJMError::LibraryError(format!(
+            "Opened LabJack at '{}' but failed to read handle info: {:?}",
+            described, err
+        ))
+    })?;
 
-        Ok(info)
-    })();
+    let Some(self_test_register) = self_test_register(&info.device_type) else {
+        return Err(LJMError::LibraryError(format!(
+            "Connected device at '{}' is not a T4, T7 or T8: {:?}",
+            described, info.device_type
+        )));
+    };
 
-    if let Err(err) = verification {
-        let _ = LJMLibrary::close_jack(handle);
-        return Err(err);
+    let actual_ip = handle_ip_address(&info)?.unwrap_or_else(|| "N/A".to_string());
+    if strategy == OpenStrategy::Ethernet && actual_ip != "N/A" && actual_ip != described {
+        return Err(LJMError::LibraryError(format!(
+            "Connected device IP mismatch: requested '{}', got '{}'",
+            described, actual_ip
+        )));
     }
 
-    Ok(handle)
+    if let Some(expected_serial) = target.serial
+        && info.serial_number != expected_serial
+    {
+        return Err(LJMError::LibraryError(format!(
+            "Connected LabJack serial mismatch at '{}': expected {}, got {}",
+            described, expected_serial, info.serial_number
+        )));
+    }
+
+    let self_test_value = LJMLibrary::read_name(handle, self_test_register).map_err(|err| {
+        LJMError::LibraryError(format!(
+            "LabJack self-test read failed for '{}': {}: {:?}",
+            described, self_test_register, err
+        ))
+    })?;
+
+    match LJMLibrary::write_name(handle, self_test_register, self_test_value) {
+        Ok(_) => {}
+        Err(err) if is_stream_active_error(&err) => {
+            warn!(
+                "stale active stream detected on '{}'; sending stream_stop and retrying self-test",
+                described
+            );
+            LJMLibrary::stream_stop(handle).map_err(|stop_err| {
+                LJMError::LibraryError(format!(
+                    "LabJack stream_stop failed for stale stream on '{}': {:?}",
+                    described, stop_err
+                ))
+            })?;
+            LJMLibrary::write_name(handle, self_test_register, self_test_value).map_err(
+                |retry_err| {
+                    LJMError::LibraryError(format!(
+                        "LabJack self-test write failed after stream_stop for '{}': {}={}: {:?}",
+                        described, self_test_register, self_test_value, retry_err
+                    ))
+                },
+            )?;
+        }
+        Err(err) => {
+            return Err(LJMError::LibraryError(format!(
+                "LabJack self-test write failed for '{}': {}={}: {:?}",
+                described, self_test_register, self_test_value, err
+            )));
+        }
+    }
+
+    info!(
+        "connected to {:?} via {:?} ({}), serial {}, ip {}, self-test ok",
+        info.device_type,
+        info.connection_type,
+        strategy.as_str(),
+        info.serial_number,
+        actual_ip
+    );
+
+    Ok(info)
 }
 
 pub fn handle_info(handle: i32) -> Result<DeviceHandleInfo, LJMError> {
@@ -207,4 +449,16 @@ mod tests {
         let ip = handle_ip_address(&info).expect("zero should be treated as missing");
         assert_eq!(ip, None);
     }
+
+    #[test]
+    fn open_order_parses_in_order_and_rejects_typos() {
+        assert_eq!(
+            parse_open_order("ethernet, usb,discover").unwrap(),
+            vec![OpenStrategy::Ethernet, OpenStrategy::Usb, OpenStrategy::Discover]
+        );
+        assert!(parse_open_order("ethernet,wifi").is_err());
+        assert!(parse_open_order("usb,usb").is_err());
+        assert!(parse_open_order(" , ").is_err());
+        assert_eq!(LabJackTarget::default().open_order(), vec![OpenStrategy::Ethernet]);
+    }
 }
@@ -0,0 +1,122 @@
+use std::ffi::{CStr, CString, c_char};
+
+use ljmrs::{LJMError, LJMLibrary};
+
+/// `LJM_LIST_ALL_SIZE`: the most devices one `LJM_ListAll` call reports.
+const LIST_ALL_SIZE: usize = 128;
+/// `LJM_STRING_ALLOCATION_SIZE`: buffer size for string registers.
+const STRING_ALLOCATION_SIZE: usize = 50;
+/// `LJM_dtTSERIES` and `LJM_ctANY`.
+const DEVICE_TYPE_TSERIES: i32 = 84;
+const CONNECTION_TYPE_ANY: i32 = 0;
+
+/// One device `LJM_ListAll` reported. A device reachable over both USB and
+/// Ethernet is listed once per connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoundDevice {
+    pub device_type: i32,
+    pub connection_type: i32,
+    pub serial_number: i32,
+    pub ip_address: i32,
+}
+
+type ListAll = unsafe extern "C" fn(i32, i32, *mut i32, *mut i32, *mut i32, *mut i32, *mut i32) -> i32;
+type ReadNameString = unsafe extern "C" fn(i32, *const c_char, *mut c_char) -> i32;
+
+#[cfg(feature = "dynlink")]
+fn symbols() -> Result<(ListAll, ReadNameString), LJMError> {
+    use std::sync::OnceLock;
+
+    // Same approach as `ljm_stream`: the second load returns the library
+    // handle ljmrs already holds.
+    static SYMBOLS: OnceLock<Result<(ListAll, ReadNameString), String>> = OnceLock::new();
+    SYMBOLS
+        .get_or_init(|| {
+            let path = std::env::var("LJM_PATH").unwrap_or_else(|_| LJMLibrary::get_library_path());
+            unsafe {
+                let library = libloading::Library::new(&path)
+                    .map_err(|e| format!("Failed to load {path}: {e}"))?;
+                let list_all = *library
+                    .get::<ListAll>(b"LJM_ListAll")
+                    .map_err(|e| format!("Failed to resolve LJM_ListAll: {e}"))?;
+                let read_name_string = *library
+                    .get::<ReadNameString>(b"LJM_eReadNameString")
+                    .map_err(|e| format!("Failed to resolve LJM_eReadNameString: {e}"))?;
+                std::mem::forget(library);
+                Ok((list_all, read_name_string))
+            }
+        })
+        .clone()
+        .map_err(LJMError::LibraryError)
+}
+
+#[cfg(feature = "staticlib")]
+unsafe extern "C" {
+    fn LJM_ListAll(
+        device_type: i32,
+        connection_type: i32,
+        num_found: *mut i32,
+        device_types: *mut i32,
+        connection_types: *mut i32,
+        serial_numbers: *mut i32,
+        ip_addresses: *mut i32,
+    ) -> i32;
+    fn LJM_eReadNameString(handle: i32, name: *const c_char, string: *mut c_char) -> i32;
+}
+
+#[cfg(feature = "staticlib")]
+fn symbols() -> Result<(ListAll, ReadNameString), LJMError> {
+    Ok((LJM_ListAll, LJM_eReadNameString))
+}
+
+fn check(error_code: i32) -> Result<(), LJMError> {
+    if error_code != 0 {
+        return Err(LJMError::ErrorCode(
+            error_code.into(),
+            LJMLibrary::error_to_string(error_code).unwrap_or_default(),
+        ));
+    }
+    Ok(())
+}
+
+/// Lists every T-series device reachable over USB or the local network.
+/// Blocks for the LJM discovery timeout when scanning the network.
+pub fn list_all() -> Result<Vec<FoundDevice>, LJMError> {
+    let (list_all, _) = symbols()?;
+    let mut num_found = 0;
+    let mut device_types = [0; LIST_ALL_SIZE];
+    let mut connection_types = [0; LIST_ALL_SIZE];
+    let mut serial_numbers = [0; LIST_ALL_SIZE];
+    let mut ip_addresses = [0; LIST_ALL_SIZE];
+    check(unsafe {
+        list_all(
+            DEVICE_TYPE_TSERIES,
+            CONNECTION_TYPE_ANY,
+            &mut num_found,
+            device_types.as_mut_ptr(),
+            connection_types.as_mut_ptr(),
+            serial_numbers.as_mut_ptr(),
+            ip_addresses.as_mut_ptr(),
+        )
+    })?;
+
+    let found = (num_found.max(0) as usize).min(LIST_ALL_SIZE);
+    Ok((0..found)
+        .map(|i| FoundDevice {
+            device_type: device_types[i],
+            connection_type: connection_types[i],
+            serial_number: serial_numbers[i],
+            ip_address: ip_addresses[i],
+        })
+        .collect())
+}
+
+/// Reads a string register such as `DEVICE_NAME_DEFAULT`.
+pub fn read_name_string(handle: i32, name: &str) -> Result<String, LJMError> {
+    let (_, read_name_string) = symbols()?;
+    let name = CString::new(name).map_err(|_| LJMError::CStringConversionFailed)?;
+    let mut buffer = [0 as c_char; STRING_ALLOCATION_SIZE];
+    check(unsafe { read_name_string(handle, name.as_ptr(), buffer.as_mut_ptr()) })?;
+    let value = unsafe { CStr::from_ptr(buffer.as_ptr()) };
+    Ok(value.to_string_lossy().trim().to_string())
+}
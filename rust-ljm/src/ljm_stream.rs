@@ -0,0 +1,83 @@
+use ljmrs::{LJMError, LJMLibrary};
+
+/// One `LJM_eStreamRead` result with the backlog counters that
+/// `LJMLibrary::stream_read` discards.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamRead {
+    pub values: Vec<f64>,
+    /// Scans left in the device buffer after this read.
+    pub device_backlog: i32,
+    /// Scans already pulled off the device and waiting in the LJM buffer.
+    pub ljm_backlog: i32,
+}
+
+type EStreamRead = unsafe extern "C" fn(i32, *mut f64, *mut i32, *mut i32) -> i32;
+
+#[cfg(feature = "dynlink")]
+fn e_stream_read() -> Result<EStreamRead, LJMError> {
+    use std::sync::OnceLock;
+
+    // Opening the library a second time returns the handle ljmrs already
+    // holds, so this shares the stream state started by `stream_start`.
+    static SYMBOL: OnceLock<Result<EStreamRead, String>> = OnceLock::new();
+    SYMBOL
+        .get_or_init(|| {
+            let path = std::env::var("LJM_PATH").unwrap_or_else(|_| LJMLibrary::get_library_path());
+            unsafe {
+                let library = libloading::Library::new(&path)
+                    .map_err(|e| format!("Failed to load {path}: {e}"))?;
+                let symbol = *library
+                    .get::<EStreamRead>(b"LJM_eStreamRead")
+                    .map_err(|e| format!("Failed to resolve LJM_eStreamRead: {e}"))?;
+                std::mem::forget(library);
+                Ok(symbol)
+            }
+        })
+        .clone()
+        .map_err(LJMError::LibraryError)
+}
+
+#[cfg(feature = "staticlib")]
+unsafe extern "C" {
+    fn LJM_eStreamRead(
+        handle: i32,
+        data: *mut f64,
+        device_scan_backlog: *mut i32,
+        ljm_scan_backlog: *mut i32,
+    ) -> i32;
+}
+
+#[cfg(feature = "staticlib")]
+fn e_stream_read() -> Result<EStreamRead, LJMError> {
+    Ok(LJM_eStreamRead)
+}
+
+/// Reads one batch of `values_per_read` samples (scans per read times the
+/// number of scanned addresses) from a stream started with `stream_start`.
+pub fn stream_read(handle: i32, values_per_read: usize) -> Result<StreamRead, LJMError> {
+    if values_per_read == 0 {
+        return Err(LJMError::StreamNotStarted);
+    }
+
+    let read = e_stream_read()?;
+    let mut out = StreamRead {
+        values: vec![0.0; values_per_read],
+        ..Default::default()
+    };
+    let error_code = unsafe {
+        read(
+            handle,
+            out.values.as_mut_ptr(),
+            &mut out.device_backlog,
+            &mut out.ljm_backlog,
+        )
+    };
+
+    if error_code != 0 {
+        return Err(LJMError::ErrorCode(
+            error_code.into(),
+            LJMLibrary::error_to_string(error_code).unwrap_or_default(),
+        ));
+    }
+    Ok(out)
+}
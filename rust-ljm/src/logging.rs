@@ -0,0 +1,38 @@
+use std::io::IsTerminal;
+
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_FILTER: &str = "info";
+
+/// Installs the global `tracing` subscriber for a binary.
+///
+/// `RUST_LOG` takes an `EnvFilter` directive (default `info`), and
+/// `LOG_FORMAT` selects `text` (default) or `json` output on stdout.
+pub fn init() -> Result<(), String> {
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(raw) if !raw.trim().is_empty() => EnvFilter::try_new(raw.trim())
+            .map_err(|e| format!("Invalid RUST_LOG '{raw}': {e}"))?,
+        _ => EnvFilter::new(DEFAULT_FILTER),
+    };
+    let format = std::env::var("LOG_FORMAT").unwrap_or_default();
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stdout);
+
+    match format.trim().to_ascii_lowercase().as_str() {
+        "" | "text" => builder
+            .with_ansi(std::io::stdout().is_terminal())
+            .try_init(),
+        "json" => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .try_init(),
+        other => {
+            return Err(format!(
+                "Invalid LOG_FORMAT '{other}', expected text or json"
+            ));
+        }
+    }
+    .map_err(|e| format!("Failed to install log subscriber: {e}"))
+}
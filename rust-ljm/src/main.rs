@@ -1,27 +1,82 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::{mpsc, watch};
 use tokio::time::Duration;
 
-use ljmrs::handle::DeviceType;
-use ljmrs::{LJMError, LJMLibrary};
+use ljmrs::LJMError;
 
 use async_nats::ConnectOptions;
 use async_nats::jetstream::kv::Operation;
 use async_nats::jetstream::{self, kv, stream::Config as StreamConfig};
 use flatbuffers::FlatBufferBuilder;
 use futures_util::StreamExt;
-
+use tracing::{Instrument, error, info, info_span, warn};
+
+mod acquisition;
+mod alarms;
+mod ain_config;
+mod calibration;
+mod channel_spec;
+mod clock_quality;
+mod clock_sync;
+mod decimation;
+mod device;
+mod device_model;
+mod dsp;
+mod heartbeat;
 mod labjack;
+mod logging;
+mod ljm_discovery;
 mod ljm_mode;
+mod ljm_stream;
+mod metrics;
 mod nats_config;
+mod publisher;
+mod scan_frame;
+mod sim;
+mod spool;
+mod stream_health;
 mod subjects;
+mod supervisor;
 mod sample_data_generated {
     #![allow(dead_code, unused_imports)]
     include!("data_generated.rs");
 }
 use sample_data_generated::sampler::{self, ScanArgs};
 
+use acquisition::{Acquisition, Capture, CaptureEvent, CaptureRecorder};
+use alarms::{AlarmEvent, AlarmMonitor, AlarmRule};
+use ain_config::{AinChannelConfig, AinChannelOverride};
+use calibration::CalibrationSpec;
+use channel_spec::{DigitalChannel, PublishedChannel, ScanLayout};
+use clock_quality::{ClockCheck, ClockQualityConfig};
+use clock_sync::{ClockSyncConfig, DriftEstimator};
+use decimation::{DecimationBank, Decimated};
+use device::DeviceBackend;
+use device_model::DeviceModel;
+use dsp::{FilterSpec, Pipeline, VirtualChannel};
+use heartbeat::Heartbeat;
+use labjack::LabJackTarget;
+use ljm_stream::StreamRead;
+use publisher::{OutboundMessage, Publisher, PublisherConfig};
+use scan_frame::{FrameCapture, FrameIdentity, PublishMode};
+use stream_health::{BacklogMonitor, BatchSegment, HealthEvent};
+use supervisor::{ConnectionState, ReconnectPolicy, Supervisor};
+
+/// How long `main` waits after Ctrl+C for the sampler to stop and the
+/// publisher to flush queued scans.
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+const DEFAULT_DUPLICATE_WINDOW: Duration = Duration::from_secs(600);
+
+static STREAM_RECONCILE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+/// Run numbers are unique across devices so the `run_id` log span field stays unambiguous.
+static NEXT_RUN_ID: AtomicUsize = AtomicUsize::new(1);
+/// Process start, for the heartbeat's `uptime_secs`.
+static STARTED_AT: std::sync::LazyLock<std::time::Instant> =
+    std::sync::LazyLock::new(std::time::Instant::now);
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize)]
 struct NestedConfig {
@@ -39,6 +94,17 @@ struct NestedConfig {
     nats_subject: String,
     nats_stream: String,
     rotate_secs: u64,
+    #[serde(default)]
+    publish_mode: PublishMode,
+    /// Model the settings are written for; configs without it are T7 configs.
+    #[serde(default)]
+    device_type: Option<DeviceModel>,
+    /// Continuous when absent; see `acquisition::Acquisition`.
+    #[serde(default)]
+    acquisition: Acquisition,
+    /// Factors of the filtered `.chNN.dN` companions, e.g. `[10, 100]`.
+    #[serde(default)]
+    decimation: Vec<u32>,
     sensor_settings: SensorSettings,
 }
 
@@ -54,6 +120,22 @@ struct SensorSettings {
     data_formats: Vec<String>,
     measurement_units: Vec<String>,
     labjack_on_off: bool,
+    calibrations: Option<HashMap<String, CalibrationSpec>>,
+    #[serde(default)]
+    ain_settings: Option<HashMap<String, AinChannelOverride>>,
+    /// DIO states, counters and other extended features streamed after the
+    /// analog inputs.
+    #[serde(default)]
+    digital_channels: Vec<DigitalChannel>,
+    /// Filter chains keyed by AIN number, applied before publishing.
+    #[serde(default)]
+    filters: Option<HashMap<String, Vec<FilterSpec>>>,
+    /// Channels derived from the filtered analog inputs.
+    #[serde(default)]
+    virtual_channels: Vec<VirtualChannel>,
+    /// Alarm rules keyed by AIN number.
+    #[serde(default)]
+    alarms: Option<HashMap<String, Vec<AlarmRule>>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -61,6 +143,7 @@ struct SampleConfig {
     scans_per_read: i32,
     scan_rate_hz: f64,
     channels: Vec<u8>,
+    digital_channels: Vec<DigitalChannel>,
     asset_number: u32,
     labjack_name: String,
     site_id: Option<String>,
@@ -70,14 +153,82 @@ struct SampleConfig {
     nats_subject: String,
     nats_stream: String,
     rotate_secs: u64,
+    calibrations: HashMap<u8, CalibrationSpec>,
+    ain_channels: Vec<AinChannelConfig>,
+    measurement_units: Vec<String>,
+    publish_mode: PublishMode,
+    device_type: Option<DeviceModel>,
+    acquisition: Acquisition,
+    decimation: Vec<u32>,
+    filters: HashMap<u8, Vec<FilterSpec>>,
+    virtual_channels: Vec<VirtualChannel>,
+    alarms: HashMap<u8, Vec<AlarmRule>>,
 }
 
-fn sample_config_from_nested(nested: NestedConfig) -> SampleConfig {
+impl SampleConfig {
+    /// Whether moving from `self` to `next` needs the LJM stream to be torn
+    /// down. Calibration, unit, publish mode, decimation and alarm edits only
+    /// affect how batches are published, so they are applied to the running
+    /// stream instead.
+    fn requires_stream_restart(&self, next: &SampleConfig) -> bool {
+        let mut stream_settings = next.clone();
+        stream_settings.calibrations = self.calibrations.clone();
+        stream_settings.measurement_units = self.measurement_units.clone();
+        stream_settings.publish_mode = self.publish_mode;
+        stream_settings.decimation = self.decimation.clone();
+        stream_settings.alarms = self.alarms.clone();
+        *self != stream_settings
+    }
+}
+
+fn sample_config_from_nested(nested: NestedConfig) -> Result<SampleConfig, LJMError> {
+    let calibrations =
+        calibration::parse_channel_calibrations(nested.sensor_settings.calibrations.as_ref());
     let raw = nested.sensor_settings;
-    SampleConfig {
+    let model = nested.device_type.unwrap_or_default();
+    let ain_channels = ain_config::resolve_channels(
+        model,
+        &raw.channels_enabled,
+        raw.gains,
+        &raw.data_formats,
+        raw.ain_settings.as_ref(),
+    )
+    .map_err(|e| LJMError::LibraryError(format!("Invalid AIN settings: {}", e)))?;
+    let caps = model.capabilities();
+    channel_spec::validate(caps, &raw.channels_enabled, &raw.digital_channels)
+        .map_err(|e| LJMError::LibraryError(format!("Invalid digital channels: {}", e)))?;
+    let registers = ScanLayout::new(caps, &raw.channels_enabled, &raw.digital_channels)
+        .registers()
+        .len();
+    ain_config::validate_scan_rate(model, raw.scan_rate_hz, registers)
+        .map_err(|e| LJMError::LibraryError(format!("Invalid sensor settings: {}", e)))?;
+    nested
+        .acquisition
+        .validate(
+            caps,
+            &raw.channels_enabled,
+            &raw.digital_channels,
+            raw.scans_per_read,
+        )
+        .map_err(|e| LJMError::LibraryError(format!("Invalid acquisition: {}", e)))?;
+    decimation::validate_factors(&nested.decimation)
+        .map_err(|e| LJMError::LibraryError(format!("Invalid decimation: {}", e)))?;
+    let filters = dsp::parse_channel_filters(raw.filters.as_ref());
+    dsp::validate(
+        &filters,
+        &raw.virtual_channels,
+        &raw.channels_enabled,
+        raw.scan_rate_hz,
+    )
+    .map_err(|e| LJMError::LibraryError(format!("Invalid DSP settings: {}", e)))?;
+    let alarms = alarms::parse_channel_alarms(raw.alarms.as_ref());
+    alarms::validate(&alarms, &raw.channels_enabled)
+        .map_err(|e| LJMError::LibraryError(format!("Invalid alarms: {}", e)))?;
+    Ok(SampleConfig {
         scans_per_read: raw.scans_per_read,
         scan_rate_hz: raw.scan_rate_hz,
         channels: raw.channels_enabled,
+        digital_channels: raw.digital_channels,
         asset_number: nested.asset_number,
         labjack_name: nested.labjack_name,
         site_id: nested.site_id,
@@ -87,13 +238,23 @@ fn sample_config_from_nested(nested: NestedConfig) -> SampleConfig {
         nats_subject: nested.nats_subject,
         nats_stream: nested.nats_stream,
         rotate_secs: nested.rotate_secs,
-    }
+        calibrations,
+        ain_channels,
+        measurement_units: raw.measurement_units,
+        publish_mode: nested.publish_mode,
+        device_type: nested.device_type,
+        acquisition: nested.acquisition,
+        decimation: nested.decimation,
+        filters,
+        virtual_channels: raw.virtual_channels,
+        alarms,
+    })
 }
 
 fn sample_config_from_json(bytes: &[u8]) -> Result<SampleConfig, LJMError> {
     let nested_cfg: NestedConfig = serde_json::from_slice(bytes)
         .map_err(|e| LJMError::LibraryError(format!("Config JSON parse error: {}", e)))?;
-    Ok(sample_config_from_nested(nested_cfg))
+    sample_config_from_nested(nested_cfg)
 }
 
 #[derive(Debug, Clone)]
@@ -101,21 +262,9 @@ struct CentralKvSyncConfig {
     servers: Vec<async_nats::ServerAddr>,
     creds_path: String,
     bucket: String,
-    key: String,
     domain: Option<String>,
 }
 
-struct LabJackGuard {
-    handle: i32,
-}
-
-impl Drop for LabJackGuard {
-    fn drop(&mut self) {
-        let _ = LJMLibrary::stream_stop(self.handle);
-        let _ = LJMLibrary::close_jack(self.handle);
-    }
-}
-
 fn stream_max_bytes_from_env() -> Result<i64, LJMError> {
     let Some(raw) = env_nonempty("STREAM_MAX_BYTES") else {
         return Ok(-1);
@@ -138,45 +287,70 @@ fn stream_max_bytes_from_env() -> Result<i64, LJMError> {
     Ok(parsed)
 }
 
+/// JetStream drops a republished message whose `Nats-Msg-Id` it has seen
+/// within this window. Defaults to 10 minutes so publish retries and short
+/// spool replays are covered.
+fn stream_duplicate_window_from_env() -> Result<Duration, LJMError> {
+    let Some(raw) = env_nonempty("STREAM_DUPLICATE_WINDOW_SECS") else {
+        return Ok(DEFAULT_DUPLICATE_WINDOW);
+    };
+
+    match raw.parse::<u64>() {
+        Ok(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+        _ => Err(LJMError::LibraryError(format!(
+            "Invalid STREAM_DUPLICATE_WINDOW_SECS value '{}': must be a positive integer",
+            raw
+        ))),
+    }
+}
+
 async fn ensure_stream_exists(
     js: &jetstream::Context,
     stream_name: &str,
     subject: &str,
 ) -> Result<(), LJMError> {
     let max_bytes = stream_max_bytes_from_env()?;
-    let desired_subjects = vec![subject.to_string()];
+    let duplicate_window = stream_duplicate_window_from_env()?;
+    // Samplers of one box share the stream; serialize their read-merge-update.
+    let _reconcile = STREAM_RECONCILE_LOCK.lock().await;
+    let mut desired_subjects = vec![subject.to_string()];
 
     if let Ok(stream) = js.get_stream(stream_name).await {
         let info = stream.cached_info();
+        desired_subjects = subjects::merge_stream_subjects(&info.config.subjects, subject);
         let already_configured = info.config.subjects == desired_subjects
             && info.config.storage == jetstream::stream::StorageType::File
             && info.config.retention == jetstream::stream::RetentionPolicy::Limits
             && info.config.max_bytes == max_bytes
-            && info.config.discard == jetstream::stream::DiscardPolicy::Old;
+            && info.config.discard == jetstream::stream::DiscardPolicy::Old
+            && info.config.duplicate_window == duplicate_window;
 
         if already_configured {
-            println!(
-                "JetStream stream '{}' already matches subject(s) {:?}, storage {:?}, max_bytes {}, discard {:?}.",
+            info!(
+                "JetStream stream '{}' already matches subject(s) {:?}, storage {:?}, max_bytes {}, discard {:?}, duplicate_window {:?}.",
                 stream_name,
                 info.config.subjects,
                 info.config.storage,
                 info.config.max_bytes,
-                info.config.discard
+                info.config.discard,
+                info.config.duplicate_window
             );
             return Ok(());
         }
 
-        println!(
-            "Reconciling JetStream stream '{}': subjects {:?} -> {:?}, max_bytes {} -> {}.",
+        info!(
+            "Reconciling JetStream stream '{}': subjects {:?} -> {:?}, max_bytes {} -> {}, duplicate_window {:?} -> {:?}.",
             stream_name,
             info.config.subjects,
             desired_subjects,
             info.config.max_bytes,
-            max_bytes
+            max_bytes,
+            info.config.duplicate_window,
+            duplicate_window
         );
     }
 
-    println!(
+    info!(
         "Ensuring JetStream stream '{}' is configured for subject '{}'",
         stream_name, subject
     );
@@ -190,6 +364,7 @@ async fn ensure_stream_exists(
         max_messages: -1,
         max_bytes,
         discard: jetstream::stream::DiscardPolicy::Old,
+        duplicate_window,
         ..Default::default()
     };
 
@@ -205,10 +380,10 @@ async fn ensure_stream_exists(
 
 async fn ensure_kv_bucket(js: &jetstream::Context, bucket: &str) -> Result<kv::Store, LJMError> {
     if let Ok(store) = js.get_key_value(bucket).await {
-        println!("KV bucket '{}' already exists.", bucket);
+        info!("KV bucket '{}' already exists.", bucket);
         return Ok(store);
     }
-    println!("Creating KV bucket '{}'", bucket);
+    info!("Creating KV bucket '{}'", bucket);
     let cfg = kv::Config {
         bucket: bucket.to_string(),
         history: 5,
@@ -256,16 +431,12 @@ fn central_kv_sync_config_from_env() -> Result<Option<CentralKvSyncConfig>, LJME
     let bucket = env_nonempty("CENTRAL_CFG_BUCKET")
         .or_else(|| env_nonempty("CFG_BUCKET"))
         .unwrap_or_else(|| "avenabox".to_string());
-    let key = env_nonempty("CENTRAL_CFG_KEY")
-        .or_else(|| env_nonempty("CFG_KEY"))
-        .unwrap_or_else(|| "v1.macbook.unknown-source.config".to_string());
     let domain = env_nonempty("CENTRAL_JS_DOMAIN");
 
     Ok(Some(CentralKvSyncConfig {
         servers,
         creds_path,
         bucket,
-        key,
         domain,
     }))
 }
@@ -333,8 +504,8 @@ async fn mirror_remote_kv_entry_to_local(
             ))
         })?;
 
-    println!(
-        "[central_kv_sync] mirrored '{}:{}' into local '{}'",
+    info!(
+        "mirrored '{}:{}' into local '{}'",
         remote_bucket,
         remote_key,
         local_key
@@ -344,13 +515,14 @@ async fn mirror_remote_kv_entry_to_local(
 
 async fn run_central_kv_sync(
     sync_cfg: CentralKvSyncConfig,
+    remote_key: String,
     local_store: kv::Store,
     local_key: String,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
     loop {
         if *shutdown_rx.borrow() {
-            println!("[central_kv_sync] shutdown");
+            info!("shutdown");
             break;
         }
 
@@ -359,12 +531,12 @@ async fn run_central_kv_sync(
         let client = match connect_result {
             Ok(client) => client,
             Err(err) => {
-                eprintln!("[central_kv_sync] connect failed: {:?}", err);
+                warn!("connect failed: {:?}", err);
                 tokio::select! {
                     _ = tokio::time::sleep(Duration::from_secs(5)) => {}
                     _ = shutdown_rx.changed() => {
                         if *shutdown_rx.borrow() {
-                            println!("[central_kv_sync] shutdown");
+                            info!("shutdown");
                             break;
                         }
                     }
@@ -378,12 +550,12 @@ async fn run_central_kv_sync(
         let remote_store = match ensure_kv_bucket(&remote_js, &sync_cfg.bucket).await {
             Ok(store) => store,
             Err(err) => {
-                eprintln!("[central_kv_sync] remote bucket setup failed: {:?}", err);
+                warn!("remote bucket setup failed: {:?}", err);
                 tokio::select! {
                     _ = tokio::time::sleep(Duration::from_secs(5)) => {}
                     _ = shutdown_rx.changed() => {
                         if *shutdown_rx.borrow() {
-                            println!("[central_kv_sync] shutdown");
+                            info!("shutdown");
                             break;
                         }
                     }
@@ -396,24 +568,24 @@ async fn run_central_kv_sync(
             mirror_remote_kv_entry_to_local(
                 &remote_store,
                 &sync_cfg.bucket,
-                &sync_cfg.key,
+                &remote_key,
                 &local_store,
                 &local_key,
             )
                 .await
         {
-            eprintln!("[central_kv_sync] initial mirror failed: {:?}", err);
+            warn!("initial mirror failed: {:?}", err);
         }
 
-        let mut watch = match remote_store.watch(&sync_cfg.key).await {
+        let mut watch = match remote_store.watch(&remote_key).await {
             Ok(watch) => watch,
             Err(err) => {
-                eprintln!("[central_kv_sync] watch setup failed: {}", err);
+                warn!("watch setup failed: {}", err);
                 tokio::select! {
                     _ = tokio::time::sleep(Duration::from_secs(5)) => {}
                     _ = shutdown_rx.changed() => {
                         if *shutdown_rx.borrow() {
-                            println!("[central_kv_sync] shutdown");
+                            info!("shutdown");
                             break;
                         }
                     }
@@ -422,9 +594,9 @@ async fn run_central_kv_sync(
             }
         };
 
-        println!(
-            "[central_kv_sync] watching remote '{}:{}' for local key '{}'",
-            sync_cfg.bucket, sync_cfg.key, local_key
+        info!(
+            "watching remote '{}:{}' for local key '{}'",
+            sync_cfg.bucket, remote_key, local_key
         );
 
         loop {
@@ -434,8 +606,8 @@ async fn run_central_kv_sync(
                         Some(Ok(entry)) => {
                             if entry.operation == Operation::Put {
                                 if let Err(err) = sample_config_from_json(entry.value.as_ref()) {
-                                    eprintln!(
-                                        "[central_kv_sync] ignoring invalid remote config for key '{}': {:?}",
+                                    warn!(
+                                        "ignoring invalid remote config for key '{}': {:?}",
                                         entry.key, err
                                     );
                                     continue;
@@ -445,44 +617,44 @@ async fn run_central_kv_sync(
                                     Ok(Some(local_entry)) if local_entry.value.as_ref() == entry.value.as_ref() => {}
                                     Ok(_) => {
                                         if let Err(err) = local_store.put(local_key.as_str(), entry.value.clone()).await {
-                                            eprintln!(
-                                                "[central_kv_sync] failed to mirror remote update for key '{}': {}",
+                                            warn!(
+                                                "failed to mirror remote update for key '{}': {}",
                                                 entry.key, err
                                             );
                                         } else {
-                                            println!(
-                                                "[central_kv_sync] mirrored remote update rev {} for '{}'",
+                                            info!(
+                                                "mirrored remote update rev {} for '{}'",
                                                 entry.revision, entry.key
                                             );
                                         }
                                     }
                                     Err(err) => {
-                                        eprintln!(
-                                            "[central_kv_sync] failed to inspect local key '{}': {}",
+                                        warn!(
+                                            "failed to inspect local key '{}': {}",
                                             local_key, err
                                         );
                                     }
                                 }
                             } else {
-                                eprintln!(
-                                    "[central_kv_sync] {:?} for remote key '{}', ignoring.",
+                                warn!(
+                                    "{:?} for remote key '{}', ignoring.",
                                     entry.operation, entry.key
                                 );
                             }
                         }
                         Some(Err(err)) => {
-                            eprintln!("[central_kv_sync] watch stream error: {}", err);
+                            warn!("watch stream error: {}", err);
                             break;
                         }
                         None => {
-                            eprintln!("[central_kv_sync] watch ended");
+                            warn!("watch ended");
                             break;
                         }
                     }
                 }
                 _ = shutdown_rx.changed() => {
                     if *shutdown_rx.borrow() {
-                        println!("[central_kv_sync] shutdown");
+                        info!("shutdown");
                         return;
                     }
                 }
@@ -493,7 +665,7 @@ async fn run_central_kv_sync(
             _ = tokio::time::sleep(Duration::from_secs(5)) => {}
             _ = shutdown_rx.changed() => {
                 if *shutdown_rx.borrow() {
-                    println!("[central_kv_sync] shutdown");
+                    info!("shutdown");
                     break;
                 }
             }
@@ -510,11 +682,11 @@ async fn watch_kv_config(
     let mut watch = match store.watch(&key).await {
         Ok(w) => w,
         Err(e) => {
-            eprintln!("[watch_kv_config] watch error: {}", e);
+            warn!("watch error: {}", e);
             return;
         }
     };
-    println!("[watch_kv_config] Watching KV key '{}'", key);
+    info!("Watching KV key '{}'", key);
 
     loop {
         tokio::select! {
@@ -525,27 +697,27 @@ async fn watch_kv_config(
                             match sample_config_from_json(entry.value.as_ref()) {
                                 Ok(new_cfg) => {
                                     if new_cfg != *config_tx.borrow() {
-                                        println!(
-                                            "[watch_kv_config] KV config updated (rev {}): {:?}",
+                                        info!(
+                                            "KV config updated (rev {}): {:?}",
                                             entry.revision, new_cfg
                                         );
                                         let _ = config_tx.send(new_cfg);
                                     }
                                 }
                                 Err(e) => {
-                                    eprintln!("[watch_kv_config] Failed to parse JSON for key '{}': {:?}", entry.key, e);
+                                    warn!("Failed to parse JSON for key '{}': {:?}", entry.key, e);
                                 }
                             }
                         } else {
-                            eprintln!("[watch_kv_config] {:?} for key '{}', ignoring.", entry.operation, entry.key);
+                            warn!("{:?} for key '{}', ignoring.", entry.operation, entry.key);
                         }
                     }
-                    Some(Err(e)) => eprintln!("[watch_kv_config] stream err: {}", e),
-                    None => { eprintln!("[watch_kv_config] watch ended"); break; }
+                    Some(Err(e)) => warn!("stream err: {}", e),
+                    None => { warn!("watch ended"); break; }
                 }
             }
             _ = shutdown_rx.changed() => {
-                if *shutdown_rx.borrow() { println!("[watch_kv_config] shutdown"); break; }
+                if *shutdown_rx.borrow() { info!("shutdown"); break; }
             }
         }
     }
@@ -553,12 +725,21 @@ async fn watch_kv_config(
 
 use std::sync::{
     Arc,
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
 #[derive(Debug, Clone, Copy)]
 struct StreamClock {
+    /// Interval implied by the `actual_rate` LJM reported.
     sample_interval_ns: u64,
+    /// Measured device clock error applied to the interval; see `set_skew_ppm`.
+    skew_ppm: f64,
+    /// Timestamp and scan count since the skew last changed. Timestamps are
+    /// extrapolated from here so a new estimate never moves earlier scans.
+    rate_anchor_unix_ns: u64,
+    scans_since_rate_anchor: u64,
+    first_sample_unix_ns: u64,
+    total_scans: u64,
     next_first_sample_unix_ns: u64,
     sequence: u64,
     last_batch_samples: usize,
@@ -569,6 +750,11 @@ impl StreamClock {
     fn new(sample_interval_ns: u64) -> Self {
         Self {
             sample_interval_ns,
+            skew_ppm: 0.0,
+            rate_anchor_unix_ns: 0,
+            scans_since_rate_anchor: 0,
+            first_sample_unix_ns: 0,
+            total_scans: 0,
             next_first_sample_unix_ns: 0,
             sequence: 0,
             last_batch_samples: 0,
@@ -576,38 +762,92 @@ impl StreamClock {
         }
     }
 
-    fn next_batch(&mut self, batch_samples: usize) -> Result<(u64, u64), LJMError> {
-        if batch_samples == 0 {
-            return Err(LJMError::LibraryError(
-                "Received empty batch; refusing to guess timestamps".to_string(),
-            ));
+    /// Scan interval in host time once the device skew is taken out.
+    fn corrected_interval_ns(&self) -> f64 {
+        self.sample_interval_ns as f64 / (1.0 + self.skew_ppm * 1e-6)
+    }
+
+    /// Interval to publish alongside batches timed by this clock.
+    fn published_interval_ns(&self) -> u64 {
+        self.corrected_interval_ns().round() as u64
+    }
+
+    /// Applies a new device clock skew estimate from `clock_sync`. Scans from
+    /// the next batch on are timed at the corrected interval.
+    fn set_skew_ppm(&mut self, skew_ppm: f64) {
+        self.rate_anchor_unix_ns = self.next_first_sample_unix_ns;
+        self.scans_since_rate_anchor = 0;
+        self.skew_ppm = skew_ppm;
+    }
+
+    /// How far the corrected timeline has moved from plain extrapolation at
+    /// the nominal interval.
+    fn correction_ns(&self) -> i64 {
+        let nominal = (self.first_sample_unix_ns as i128)
+            + (self.total_scans as i128) * (self.sample_interval_ns as i128);
+        (self.next_first_sample_unix_ns as i128 - nominal) as i64
+    }
+
+    /// Pins the first read of a run to wall-clock time, treating "now" as the
+    /// last scan of a read of `read_scans` scans. Later reads are timed purely
+    /// from the scan count, so this is a no-op once the run has started.
+    fn anchor(&mut self, read_scans: usize) -> Result<(), LJMError> {
+        if self.run_started {
+            return Ok(());
         }
+        let now_ns = unix_time_now_ns()?;
+        let offset_ns =
+            (read_scans.saturating_sub(1) as u128).saturating_mul(self.sample_interval_ns as u128);
+        let first = (now_ns as u128).saturating_sub(offset_ns);
+        self.next_first_sample_unix_ns = u64::try_from(first).map_err(|_| {
+            LJMError::LibraryError("Initial stream timestamp overflowed u64".to_string())
+        })?;
+        self.first_sample_unix_ns = self.next_first_sample_unix_ns;
+        self.rate_anchor_unix_ns = self.next_first_sample_unix_ns;
+        self.run_started = true;
+        Ok(())
+    }
 
-        let first_sample_unix_ns = if !self.run_started {
-            let now_ns = unix_time_now_ns()?;
-            let offset_ns = (batch_samples.saturating_sub(1) as u128)
-                .saturating_mul(self.sample_interval_ns as u128);
-            let first = (now_ns as u128).saturating_sub(offset_ns);
-            u64::try_from(first).map_err(|_| {
-                LJMError::LibraryError("Initial stream timestamp overflowed u64".to_string())
-            })?
+    fn advance(&mut self, scans: usize) -> Result<u64, LJMError> {
+        let first_sample_unix_ns = self.next_first_sample_unix_ns;
+        self.scans_since_rate_anchor = self.scans_since_rate_anchor.saturating_add(scans as u64);
+        self.total_scans = self.total_scans.saturating_add(scans as u64);
+        let span_ns = if self.skew_ppm == 0.0 {
+            (self.scans_since_rate_anchor as u128).saturating_mul(self.sample_interval_ns as u128)
         } else {
-            self.next_first_sample_unix_ns
+            (self.scans_since_rate_anchor as f64 * self.corrected_interval_ns()).round() as u128
         };
-
-        let batch_span_ns = (batch_samples as u128).saturating_mul(self.sample_interval_ns as u128);
-        let next = (first_sample_unix_ns as u128).saturating_add(batch_span_ns);
+        let next = (self.rate_anchor_unix_ns as u128).saturating_add(span_ns);
         self.next_first_sample_unix_ns = u64::try_from(next).map_err(|_| {
             LJMError::LibraryError("Next stream timestamp overflowed u64".to_string())
         })?;
+        Ok(first_sample_unix_ns)
+    }
+
+    fn next_batch(&mut self, batch_samples: usize) -> Result<(u64, u64), LJMError> {
+        if batch_samples == 0 {
+            return Err(LJMError::LibraryError(
+                "Received empty batch; refusing to guess timestamps".to_string(),
+            ));
+        }
+
+        self.anchor(batch_samples)?;
+        let first_sample_unix_ns = self.advance(batch_samples)?;
 
         let sequence = self.sequence;
         self.sequence = self.sequence.saturating_add(1);
         self.last_batch_samples = batch_samples;
-        self.run_started = true;
 
         Ok((first_sample_unix_ns, sequence))
     }
+
+    /// Advances the clock over scans LJM reported as skipped without using a
+    /// sequence number, so the next published batch keeps its true timestamp.
+    /// Returns the timestamp the first skipped scan would have had.
+    fn skip_scans(&mut self, scans: usize) -> Result<u64, LJMError> {
+        self.anchor(scans)?;
+        self.advance(scans)
+    }
 }
 
 fn unix_time_now_ns() -> Result<u64, LJMError> {
@@ -635,15 +875,585 @@ fn derive_sample_interval_ns(actual_rate: f64) -> Result<u64, LJMError> {
     Ok(interval as u64)
 }
 
+/// LabJack analog inputs always stream volts; configured `measurement_units`
+/// describe the calibrated companion values.
+const RAW_UNIT: &str = "V";
+
+/// Identity stamped on every published `Scan`, so consumers do not have to
+/// recover it from the subject or guess run boundaries from `sequence`.
+#[derive(Debug, Clone, Copy)]
+struct ScanIdentity<'a> {
+    /// AIN number, -1 for digital channels.
+    channel: i16,
+    channel_name: &'a str,
+    run_id: &'a str,
+    device_serial: i32,
+    unit: Option<&'a str>,
+    /// Set for calibrated companion payloads only.
+    calibration_id: Option<&'a str>,
+    /// Host clock quality and error bound when the batch was timed.
+    clock_quality: &'a str,
+    clock_error_bound_ns: i64,
+}
+
+/// Decimation factor and min/max envelope of a decimated companion `Scan`.
+struct ScanEnvelope<'a> {
+    decimation: u32,
+    min_values: &'a [f64],
+    max_values: &'a [f64],
+}
+
+#[allow(clippy::too_many_arguments)]
+fn encode_scan(
+    builder: &mut FlatBufferBuilder,
+    first_sample_unix_ns: u64,
+    sample_interval_ns: u64,
+    actual_scan_rate_hz: f64,
+    sequence: u64,
+    values: &[f64],
+    identity: &ScanIdentity,
+    envelope: Option<&ScanEnvelope>,
+) -> Vec<u8> {
+    builder.reset();
+    let values_fb = builder.create_vector(values);
+    let min_values = envelope.map(|e| builder.create_vector(e.min_values));
+    let max_values = envelope.map(|e| builder.create_vector(e.max_values));
+    let run_id = builder.create_string(identity.run_id);
+    let unit = identity.unit.map(|unit| builder.create_string(unit));
+    let calibration_id = identity.calibration_id.map(|id| builder.create_string(id));
+    let clock_quality = builder.create_string(identity.clock_quality);
+    let channel_name = builder.create_string(identity.channel_name);
+    let scan_args = ScanArgs {
+        first_sample_unix_ns,
+        sample_interval_ns,
+        actual_scan_rate_hz,
+        sequence,
+        values: Some(values_fb),
+        channel: identity.channel,
+        run_id: Some(run_id),
+        device_serial: identity.device_serial,
+        unit,
+        calibration_id,
+        calibrated: identity.calibration_id.is_some(),
+        clock_quality: Some(clock_quality),
+        clock_error_bound_ns: identity.clock_error_bound_ns,
+        channel_name: Some(channel_name),
+        decimation: envelope.map_or(1, |e| e.decimation),
+        min_values,
+        max_values,
+    };
+    let scan_offset = sampler::Scan::create(builder, &scan_args);
+    builder.finish(scan_offset, None);
+    builder.finished_data().to_vec()
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn publish_channel_scans(
+    cfg: &SampleConfig,
+    published: &[PublishedChannel],
+    publisher: &Publisher,
+    client: &async_nats::Client,
+    builder: &mut FlatBufferBuilder<'_>,
+    run_uuid: &str,
+    device_serial: i32,
+    host_clock: &ClockCheck,
+    first_sample_unix_ns: u64,
+    sample_interval_ns: u64,
+    actual_rate: f64,
+    sequence: u64,
+    batch: &[f64],
+) {
+    let num_channels = published.len();
+    let scans = batch.chunks(num_channels);
+    let mut per_channel: Vec<Vec<f64>> = (0..num_channels)
+        .map(|_| Vec::with_capacity(scans.len()))
+        .collect();
+
+    for scan in batch.chunks(num_channels) {
+        for (i, v) in scan.iter().enumerate() {
+            per_channel[i].push(*v);
+        }
+    }
+
+    let source_subject = first_channel_subject(cfg);
+    for (i, values) in per_channel.into_iter().enumerate() {
+        let channel = published[i];
+        let channel_name = channel.token();
+        let identity = ScanIdentity {
+            channel: channel.ain_number(),
+            channel_name: &channel_name,
+            run_id: run_uuid,
+            device_serial,
+            unit: Some(published_unit(&channel)),
+            calibration_id: None,
+            clock_quality: host_clock.quality.as_str(),
+            clock_error_bound_ns: host_clock.error_bound_ns(),
+        };
+        let data = encode_scan(
+            builder,
+            first_sample_unix_ns,
+            sample_interval_ns,
+            actual_rate,
+            sequence,
+            &values,
+            &identity,
+            None,
+        );
+
+        let subject = subjects::source_event_subject(&source_subject, &channel_name);
+
+        let calibrated_subject = subjects::calibrated_channel_subject(&subject);
+        let _ = publisher.enqueue(OutboundMessage {
+            subject,
+            msg_id: publisher::message_id(run_uuid, &channel_name, sequence),
+            payload: data.into(),
+        });
+
+        if let PublishedChannel::Analog(ch_num) = channel
+            && let Some(calibration) = cfg.calibrations.get(&ch_num)
+        {
+            let calibrated: Vec<f64> =
+                values.iter().map(|v| calibration.apply(*v)).collect();
+            let identity = ScanIdentity {
+                unit: cfg.measurement_units.get(i).map(String::as_str),
+                calibration_id: Some(calibration.id_or_default()),
+                ..identity
+            };
+            let data = encode_scan(
+                builder,
+                first_sample_unix_ns,
+                sample_interval_ns,
+                actual_rate,
+                sequence,
+                &calibrated,
+                &identity,
+                None,
+            );
+            if let Err(e) = client.publish(calibrated_subject, data.into()).await {
+                error!(
+                    "Failed to publish calibrated channel {ch_num} to NATS: {}",
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Publishes the filtered, decimated companions of each channel on
+/// `.chNN.dN`. Like `.cal`, they sit below the channel subject, stay out of
+/// JetStream and go out on core NATS whatever the publish mode.
+#[allow(clippy::too_many_arguments)]
+async fn publish_decimated(
+    cfg: &SampleConfig,
+    published: &[PublishedChannel],
+    client: &async_nats::Client,
+    builder: &mut FlatBufferBuilder<'_>,
+    run_uuid: &str,
+    device_serial: i32,
+    host_clock: &ClockCheck,
+    sample_interval_ns: u64,
+    actual_rate: f64,
+    sequence: u64,
+    outputs: Vec<(u32, usize, Decimated)>,
+) {
+    let source_subject = first_channel_subject(cfg);
+    for (factor, column, decimated) in outputs {
+        let channel = published[column];
+        let channel_name = channel.token();
+        let identity = ScanIdentity {
+            channel: channel.ain_number(),
+            channel_name: &channel_name,
+            run_id: run_uuid,
+            device_serial,
+            unit: Some(published_unit(&channel)),
+            calibration_id: None,
+            clock_quality: host_clock.quality.as_str(),
+            clock_error_bound_ns: host_clock.error_bound_ns(),
+        };
+        let data = encode_scan(
+            builder,
+            decimated.first_sample_unix_ns,
+            sample_interval_ns * u64::from(factor),
+            actual_rate / f64::from(factor),
+            sequence,
+            &decimated.values,
+            &identity,
+            Some(&ScanEnvelope {
+                decimation: factor,
+                min_values: &decimated.min_values,
+                max_values: &decimated.max_values,
+            }),
+        );
+        let subject = subjects::decimated_channel_subject(
+            &subjects::source_event_subject(&source_subject, &channel_name),
+            factor,
+        );
+        if let Err(e) = client.publish(subject, data.into()).await {
+            error!(
+                "Failed to publish {channel_name} decimated by {factor} to NATS: {}",
+                e
+            );
+        }
+    }
+}
+
+/// Frame-mode counterpart of `publish_channel_scans`: the whole batch goes out
+/// as one `ScanFrame`, plus one calibrated frame covering the channels that
+/// have a calibration.
+#[allow(clippy::too_many_arguments)]
+async fn publish_frame(
+    cfg: &SampleConfig,
+    published: &[PublishedChannel],
+    publisher: &Publisher,
+    client: &async_nats::Client,
+    builder: &mut FlatBufferBuilder<'_>,
+    frame_subject: &str,
+    run_uuid: &str,
+    device_serial: i32,
+    host_clock: &ClockCheck,
+    first_sample_unix_ns: u64,
+    sample_interval_ns: u64,
+    actual_rate: f64,
+    sequence: u64,
+    batch: &[f64],
+) {
+    let num_channels = published.len();
+    let channels: Vec<i16> = published.iter().map(PublishedChannel::ain_number).collect();
+    let names: Vec<String> = published.iter().map(PublishedChannel::token).collect();
+    let channel_names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let units: Vec<&str> = published.iter().map(published_unit).collect();
+    let identity = FrameIdentity {
+        channels: &channels,
+        channel_names: &channel_names,
+        run_id: run_uuid,
+        device_serial,
+        units: &units,
+        calibration_ids: None,
+        clock_quality: host_clock.quality.as_str(),
+        clock_error_bound_ns: host_clock.error_bound_ns(),
+        capture: None,
+    };
+    let data = scan_frame::encode_frame(
+        builder,
+        first_sample_unix_ns,
+        sample_interval_ns,
+        actual_rate,
+        sequence,
+        batch,
+        &identity,
+    );
+    let _ = publisher.enqueue(OutboundMessage {
+        subject: frame_subject.to_string(),
+        msg_id: publisher::message_id(run_uuid, "frame", sequence),
+        payload: data.into(),
+    });
+
+    // Analog inputs lead the scan, so `i` also indexes `measurement_units`.
+    let calibrated: Vec<(usize, u8, &CalibrationSpec)> = cfg
+        .channels
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ch)| cfg.calibrations.get(ch).map(|spec| (i, *ch, spec)))
+        .collect();
+    if calibrated.is_empty() {
+        return;
+    }
+
+    let mut values = Vec::with_capacity(batch.len() / num_channels * calibrated.len());
+    for scan in batch.chunks(num_channels) {
+        for (i, _, spec) in &calibrated {
+            values.push(spec.apply(scan[*i]));
+        }
+    }
+    let channels: Vec<i16> = calibrated.iter().map(|(_, ch, _)| i16::from(*ch)).collect();
+    let names: Vec<String> = calibrated
+        .iter()
+        .map(|(_, ch, _)| subjects::pad_channel(*ch))
+        .collect();
+    let channel_names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let units: Vec<&str> = calibrated
+        .iter()
+        .map(|(i, _, _)| cfg.measurement_units.get(*i).map(String::as_str).unwrap_or(""))
+        .collect();
+    let calibration_ids: Vec<&str> = calibrated
+        .iter()
+        .map(|(_, _, spec)| spec.id_or_default())
+        .collect();
+    let identity = FrameIdentity {
+        channels: &channels,
+        channel_names: &channel_names,
+        units: &units,
+        calibration_ids: Some(&calibration_ids),
+        ..identity
+    };
+    let data = scan_frame::encode_frame(
+        builder,
+        first_sample_unix_ns,
+        sample_interval_ns,
+        actual_rate,
+        sequence,
+        &values,
+        &identity,
+    );
+    let calibrated_subject = subjects::calibrated_channel_subject(frame_subject);
+    if let Err(e) = client.publish(calibrated_subject, data.into()).await {
+        error!("Failed to publish calibrated frame to NATS: {}", e);
+    }
+}
+
+/// Publishes a finished capture as `ScanFrame`s of up to `scans_per_read`
+/// scans on the source's `capture` subject, then announces it with a JSON
+/// `CaptureEvent`. Every frame carries the capture id and length, so the
+/// archiver can tell when a capture's file set is complete.
+#[allow(clippy::too_many_arguments)]
+async fn publish_capture(
+    cfg: &SampleConfig,
+    published: &[PublishedChannel],
+    publisher: &Publisher,
+    client: &async_nats::Client,
+    builder: &mut FlatBufferBuilder<'_>,
+    capture_subject: &str,
+    capture_event_subject: &str,
+    run_uuid: &str,
+    device_serial: i32,
+    host_clock: &ClockCheck,
+    actual_rate: f64,
+    capture: &Capture,
+) {
+    let width = published.len();
+    let scans = capture.scans(width);
+    let channels: Vec<i16> = published.iter().map(PublishedChannel::ain_number).collect();
+    let names: Vec<String> = published.iter().map(PublishedChannel::token).collect();
+    let channel_names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let units: Vec<&str> = published.iter().map(published_unit).collect();
+    let identity = FrameIdentity {
+        channels: &channels,
+        channel_names: &channel_names,
+        run_id: run_uuid,
+        device_serial,
+        units: &units,
+        calibration_ids: None,
+        clock_quality: host_clock.quality.as_str(),
+        clock_error_bound_ns: host_clock.error_bound_ns(),
+        capture: Some(FrameCapture {
+            id: &capture.id,
+            scans: scans as u64,
+            pre_trigger_scans: capture.pre_trigger_scans as u64,
+        }),
+    };
+
+    let frame_scans = usize::try_from(cfg.scans_per_read).unwrap_or(1).max(1);
+    let stream = format!("capture-{}", capture.id);
+    let mut frames = 0;
+    for (index, chunk) in capture.values.chunks(frame_scans * width).enumerate() {
+        let first_sample_unix_ns =
+            capture.first_sample_unix_ns + (index * frame_scans) as u64 * capture.sample_interval_ns;
+        let data = scan_frame::encode_frame(
+            builder,
+            first_sample_unix_ns,
+            capture.sample_interval_ns,
+            actual_rate,
+            index as u64,
+            chunk,
+            &identity,
+        );
+        let _ = publisher.enqueue(OutboundMessage {
+            subject: capture_subject.to_string(),
+            msg_id: publisher::message_id(run_uuid, &stream, index as u64),
+            payload: data.into(),
+        });
+        frames += 1;
+    }
+
+    let event = CaptureEvent {
+        capture_id: &capture.id,
+        run_id: run_uuid,
+        mode: cfg.acquisition.as_str(),
+        trigger: cfg.acquisition.trigger(),
+        first_sample_unix_ns: capture.first_sample_unix_ns,
+        trigger_unix_ns: capture.first_sample_unix_ns
+            + capture.pre_trigger_scans as u64 * capture.sample_interval_ns,
+        sample_interval_ns: capture.sample_interval_ns,
+        scans,
+        pre_trigger_scans: capture.pre_trigger_scans,
+        frames,
+        channels: names,
+    };
+    info!(
+        "Capture {} ({} scans, {} pre-trigger) queued as {} frame(s)",
+        capture.id, scans, capture.pre_trigger_scans, frames
+    );
+    match serde_json::to_vec(&event) {
+        Ok(payload) => {
+            if let Err(e) = client.publish(capture_event_subject.to_string(), payload.into()).await {
+                warn!("Failed to publish capture event to '{capture_event_subject}': {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to encode capture event: {}", e),
+    }
+}
+
+async fn publish_health_event(
+    client: &async_nats::Client,
+    subject: &str,
+    event: &HealthEvent,
+) {
+    match serde_json::to_vec(event) {
+        Ok(payload) => {
+            if let Err(e) = client.publish(subject.to_string(), payload.into()).await {
+                warn!("Failed to publish health event to '{subject}': {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to encode health event: {}", e),
+    }
+}
+
+async fn publish_alarm_event(client: &async_nats::Client, subject: &str, event: &AlarmEvent) {
+    info!(
+        channel = event.channel,
+        rule = %event.rule,
+        state = %event.state,
+        "Alarm {} {} on {}: value {} at {} ns",
+        event.rule, event.state, event.channel_name, event.value, event.timestamp_unix_ns
+    );
+    match serde_json::to_vec(event) {
+        Ok(payload) => {
+            if let Err(e) = client.publish(subject.to_string(), payload.into()).await {
+                warn!("Failed to publish alarm event to '{subject}': {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to encode alarm event: {}", e),
+    }
+}
+
+async fn publish_status(client: &async_nats::Client, subject: &str, state: &ConnectionState) {
+    match serde_json::to_vec(state) {
+        Ok(payload) => {
+            if let Err(e) = client.publish(subject.to_string(), payload.into()).await {
+                warn!("Failed to publish connection state to '{subject}': {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to encode connection state: {}", e),
+    }
+}
+
+/// Resolves at `deadline`, or never when nothing is waiting to be re-armed.
+async fn sleep_until(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Resolves on the ticker's next tick, or never when clock sync is off.
+async fn next_tick(ticker: &mut Option<tokio::time::Interval>) {
+    match ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Raw unit of a published channel; calibrated companions use the configured
+/// measurement unit instead.
+fn published_unit(channel: &PublishedChannel) -> &'static str {
+    match channel {
+        PublishedChannel::Analog(_) => RAW_UNIT,
+        PublishedChannel::Digital(digital) => digital.unit(),
+        PublishedChannel::Virtual { unit, .. } => unit,
+    }
+}
+
+fn first_channel_subject(cfg: &SampleConfig) -> String {
+    subjects::live_labjack_channel_subject(
+        &cfg.nats_subject,
+        cfg.asset_number,
+        cfg.channels.first().copied().unwrap_or_default(),
+        cfg.site_id.as_deref(),
+        cfg.box_id.as_deref(),
+        Some(&cfg.labjack_name),
+        cfg.source_type.as_deref(),
+        cfg.source_id.as_deref(),
+    )
+}
+
+/// Blocking `stream_read` loop feeding batches to the sampler. Finite
+/// acquisitions stop after `limit_values` values, since LJM errors on reads
+/// past `STREAM_NUM_SCANS`.
+struct StreamReader {
+    running: Arc<AtomicBool>,
+    rx: mpsc::Receiver<StreamRead>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl StreamReader {
+    fn spawn(device: Arc<dyn device::StreamDevice>, limit_values: Option<usize>) -> Self {
+        let (tx, rx) = mpsc::channel::<StreamRead>(32);
+        let running = Arc::new(AtomicBool::new(true));
+        let running_reader = running.clone();
+        let span = tracing::Span::current();
+        let handle = tokio::task::spawn_blocking(move || {
+            let _entered = span.enter();
+            let mut read_values = 0;
+            while running_reader.load(Ordering::Relaxed) {
+                match device.stream_read() {
+                    Ok(batch) => {
+                        read_values += batch.values.len();
+                        if tx.blocking_send(batch).is_err() {
+                            break; // receiver gone
+                        }
+                        if limit_values.is_some_and(|limit| read_values >= limit) {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!("Error reading stream: {:?}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        Self { running, rx, handle }
+    }
+}
+
+/// Next batch from the reader, or never while the stream is stopped between
+/// captures.
+async fn next_read(reader: &mut Option<StreamReader>) -> Option<StreamRead> {
+    match reader {
+        Some(reader) => reader.rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Stops the stream and waits for the reader task; a no-op once stopped.
+async fn stop_reader(reader: &mut Option<StreamReader>, device: &dyn device::StreamDevice) {
+    if let Some(reader) = reader.take() {
+        reader.running.store(false, Ordering::Relaxed);
+        let _ = device.stream_stop();
+        let _ = reader.handle.await;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn sample_with_config(
     run_id: usize,
-    cfg: SampleConfig,
+    mut cfg: SampleConfig,
     config_rx: &mut watch::Receiver<SampleConfig>,
     shutdown_rx: &mut watch::Receiver<bool>,
     js: &jetstream::Context,
+    client: &async_nats::Client,
+    publisher: &Publisher,
+    backend: &DeviceBackend,
+    target: &LabJackTarget,
+    supervisor: &mut Supervisor,
 ) -> Result<(), LJMError> {
+    let first_channel_subject = first_channel_subject(&cfg);
+    let status_subject = subjects::source_event_subject(&first_channel_subject, "status");
+
     ensure_stream_exists(
-        &js,
+        js,
         &cfg.nats_stream,
         &subjects::live_labjack_stream_subject(
             &cfg.nats_subject,
@@ -656,118 +1466,245 @@ async fn sample_with_config(
     )
     .await?;
 
-    let handle = labjack::open_labjack_from_env()?;
-    let info = labjack::handle_info(handle)?;
-    let ip = labjack::handle_ip_address(&info)?.unwrap_or_else(|| "N/A".to_string());
-    println!(
-        "[labjack] connected via {:?}, serial {}, ip {}",
-        info.connection_type, info.serial_number, ip
+    let clock_sync = ClockSyncConfig::from_env().map_err(LJMError::LibraryError)?;
+    let clock_quality_cfg = ClockQualityConfig::from_env().map_err(LJMError::LibraryError)?;
+    let status_interval = heartbeat::interval_from_env().map_err(LJMError::LibraryError)?;
+    let mut host_clock = clock_quality::check(clock_quality_cfg.max_error);
+    info!(
+        "Host clock {}: max error {:?} ns, estimated error {:?} ns, offset {:?} ns",
+        host_clock.quality.as_str(),
+        host_clock.max_error_ns,
+        host_clock.est_error_ns,
+        host_clock.offset_ns
     );
-    let _guard = LabJackGuard { handle };
+    publish_status(
+        client,
+        &status_subject,
+        &ConnectionState::HostClock {
+            run_id,
+            check: host_clock,
+        },
+    )
+    .await;
+    if !clock_quality_cfg.allows(&host_clock) {
+        return Err(LJMError::LibraryError(format!(
+            "Host clock is {} (max error {:?} ns); not starting with STREAM_CLOCK_POLICY=refuse",
+            host_clock.quality.as_str(),
+            host_clock.max_error_ns
+        )));
+    }
 
-    let info = LJMLibrary::get_handle_info(handle)?;
-    println!(
-        "[run #{run_id}] Connected to {:?} (serial {})",
-        info.device_type, info.serial_number
+    let device = backend.open(target)?;
+    let info = device.info().clone();
+    let run_uuid = uuid::Uuid::new_v4().to_string();
+    info!(
+        "Connected to {:?} (serial {}) via {} backend, run id {}",
+        info.device_type,
+        info.serial_number,
+        device.backend_name(),
+        run_uuid
     );
+    publish_status(
+        client,
+        &status_subject,
+        &ConnectionState::Connected {
+            run_id,
+            serial: info.serial_number,
+            device_type: format!("{:?}", info.device_type),
+            backend: device.backend_name().to_string(),
+        },
+    )
+    .await;
+    let firmware_version = match device.read_firmware_version() {
+        Ok(version) => {
+            info!("Firmware version {version}");
+            Some(version)
+        }
+        Err(e) => {
+            warn!("{:?}", e);
+            None
+        }
+    };
 
-    if matches!(info.device_type, DeviceType::T7) {
-        LJMLibrary::write_name(handle, "AIN_ALL_NEGATIVE_CH", 199_u32)?;
+    let model = DeviceModel::from_device_type(&info.device_type).ok_or_else(|| {
+        LJMError::LibraryError(format!(
+            "Unsupported LabJack model {:?}; expected a T4, T7 or T8",
+            info.device_type
+        ))
+    })?;
+    let configured_model = cfg.device_type.unwrap_or_default();
+    if model != configured_model {
+        return Err(LJMError::LibraryError(format!(
+            "Connected LabJack is a {} but the config is for a {}; set \"device_type\": \"{}\" and matching sensor settings",
+            model.as_str(),
+            configured_model.as_str(),
+            model.as_str()
+        )));
     }
-    LJMLibrary::write_name(handle, "AIN_ALL_RANGE", 10.0_f64)?;
-    LJMLibrary::write_name(handle, "AIN_ALL_RESOLUTION_INDEX", 0_u32)?;
-    LJMLibrary::write_name(handle, "STREAM_SETTLING_US", 0_u32)?;
 
-    let channel_addresses: Result<Vec<i32>, LJMError> = cfg
-        .channels
-        .iter()
-        .map(|ch| {
-            LJMLibrary::name_to_address(&format!("AIN{}", ch))
-                .map(|(addr, _)| addr)
-                .map_err(|e| LJMError::LibraryError(format!("Invalid channel {}: {:?}", ch, e)))
-        })
-        .collect();
-    let channel_addresses = channel_addresses?;
-    let num_channels = channel_addresses.len();
-
-    let actual_rate = LJMLibrary::stream_start(
-        handle,
-        cfg.scans_per_read,
-        cfg.scan_rate_hz,
-        channel_addresses.clone(),
-    )?;
-    println!(
-        "[run #{run_id}] Streaming started: {} scans/read @ {} Hz",
+    device.configure(&cfg.ain_channels)?;
+    info!(
+        "Configured {} analog input(s): {:?}",
+        cfg.ain_channels.len(),
+        cfg.ain_channels
+    );
+    if !cfg.digital_channels.is_empty() {
+        device.configure_digital(&cfg.digital_channels)?;
+        info!(
+            "Configured {} digital channel(s): {:?}",
+            cfg.digital_channels.len(),
+            cfg.digital_channels
+        );
+    }
+
+    device.configure_acquisition(&cfg.acquisition)?;
+    if !cfg.acquisition.is_continuous() {
+        info!(
+            "Acquisition mode {}: {:?}",
+            cfg.acquisition.as_str(),
+            cfg.acquisition
+        );
+    }
+
+    let layout = ScanLayout::new(model.capabilities(), &cfg.channels, &cfg.digital_channels);
+    // Raw scans are `registers` wide; decoded scans have one value per
+    // physical channel, and the DSP pipeline appends the virtual channels.
+    let num_channels = layout.registers().len();
+    let actual_rate = device.stream_start(cfg.scans_per_read, cfg.scan_rate_hz, layout.registers())?;
+    let mut pipeline = Pipeline::new(
+        layout.published(),
+        &cfg.filters,
+        &cfg.virtual_channels,
+        actual_rate,
+    );
+    info!(
+        "Streaming started: {} scans/read @ {} Hz",
         cfg.scans_per_read, actual_rate
     );
+    supervisor.reset();
+    let run_started = std::time::Instant::now();
+    publish_status(
+        client,
+        &status_subject,
+        &ConnectionState::Streaming {
+            run_id,
+            scan_rate_hz: actual_rate,
+            channels: pipeline.published().len(),
+        },
+    )
+    .await;
     let sample_interval_ns = derive_sample_interval_ns(actual_rate)?;
-    println!(
-        "[run #{run_id}] Derived sample interval: {} ns from actual scan rate {} Hz",
+    info!(
+        "Derived sample interval: {} ns from actual scan rate {} Hz",
         sample_interval_ns, actual_rate
     );
 
-    let (scan_tx, mut scan_rx) = mpsc::channel::<Vec<f64>>(32);
-
-    // Shared running flag for stopping the blocking loop
-    let running = Arc::new(AtomicBool::new(true));
-    let running_reader = running.clone();
-    let scan_tx_reader = scan_tx.clone();
-
-    // Single long-lived blocking task for reading
-    let read_handle = tokio::task::spawn_blocking(move || {
-        while running_reader.load(Ordering::Relaxed) {
-            match LJMLibrary::stream_read(handle) {
-                Ok(batch) => {
-                    if scan_tx_reader.blocking_send(batch).is_err() {
-                        break; // receiver gone
-                    }
-                }
-                Err(e) => {
-                    eprintln!("[run #{run_id}] Error reading stream: {:?}", e);
-                    break;
-                }
-            }
-        }
-    });
-    drop(scan_tx);
+    let finite_values = cfg
+        .acquisition
+        .finite_scans()
+        .map(|scans| scans as usize * num_channels);
+    let mut reader = Some(StreamReader::spawn(device.clone(), finite_values));
+    let mut recorder = CaptureRecorder::new(&cfg.acquisition, pipeline.published());
+    let mut rearm_at: Option<tokio::time::Instant> = None;
+    let mut decimators = DecimationBank::new(&cfg.decimation, pipeline.published().len());
+    let ain_ranges: HashMap<u8, f64> = cfg
+        .ain_channels
+        .iter()
+        .map(|ain| (ain.channel, ain.range))
+        .collect();
+    let mut alarm_monitor = AlarmMonitor::new(&cfg.alarms, pipeline.published(), &ain_ranges);
+    if cfg.acquisition.waits_for_trigger() {
+        info!("Waiting for the DIO trigger");
+    }
 
     let mut builder = FlatBufferBuilder::new();
     let mut clock = StreamClock::new(sample_interval_ns);
+    let mut sync_ticker = clock_sync.map(|sync| tokio::time::interval(sync.interval));
+    let mut drift = clock_sync.map(|sync| DriftEstimator::new(sync.window));
+    let mut clock_check_ticker = tokio::time::interval_at(
+        tokio::time::Instant::now() + clock_quality_cfg.check_interval,
+        clock_quality_cfg.check_interval,
+    );
+    if let Some(sync) = clock_sync {
+        info!(
+            "Correlating CORE_TIMER with host time every {:?} over {} reads",
+            sync.interval, sync.window
+        );
+    }
+    let mut backlog =
+        BacklogMonitor::from_env(cfg.scans_per_read).map_err(LJMError::LibraryError)?;
+    let mut total_skipped_scans: u64 = 0;
+    let mut latest_backlog = (0, 0);
+    let device_label = target.label();
+    let registry = metrics::global();
+    let scans_read = registry.counter(
+        "streamer_scans_read_total",
+        "Scans returned by stream_read, skipped scans included.",
+        &[("device", &device_label)],
+    );
+    let skipped_scans = registry.counter(
+        "streamer_skipped_scans_total",
+        "Scans LJM reported as skipped after a device buffer overflow.",
+        &[("device", &device_label)],
+    );
+    let backlog_gauges = ["device", "ljm"].map(|buffer| {
+        registry.gauge(
+            "streamer_backlog_scans",
+            "Scans waiting in the device or LJM buffer after the latest read.",
+            &[("device", &device_label), ("buffer", buffer)],
+        )
+    });
+    let mut last_sequence: Option<u64> = None;
+    let mut status_ticker = status_interval
+        .map(|interval| tokio::time::interval_at(tokio::time::Instant::now() + interval, interval));
+    let health_subject = subjects::source_event_subject(&first_channel_subject, "health");
+    let frame_subject = subjects::frame_subject(&first_channel_subject);
+    let capture_subject = subjects::source_event_subject(&first_channel_subject, "capture");
+    let capture_event_subject =
+        subjects::source_event_subject(&first_channel_subject, "capture_event");
+    let alarms_subject = subjects::source_event_subject(&first_channel_subject, "alarms");
 
     loop {
         tokio::select! {
-            maybe_batch = scan_rx.recv() => {
-                let Some(batch) = maybe_batch else {
-                    eprintln!(
-                        "[run #{run_id}] Stream reader ended unexpectedly at sequence {}. Next expected first sample ns {}",
+            maybe_batch = next_read(&mut reader) => {
+                let Some(read) = maybe_batch else {
+                    if cfg.acquisition.finite_scans().is_some() {
+                        warn!(
+                            "{} stream ended without a complete capture; re-arming",
+                            cfg.acquisition.as_str()
+                        );
+                        stop_reader(&mut reader, device.as_ref()).await;
+                        if let Some(recorder) = recorder.as_mut() {
+                            recorder.discontinuity();
+                        }
+                        rearm_at = Some(tokio::time::Instant::now() + cfg.acquisition.rearm_delay());
+                        continue;
+                    }
+                    warn!(
+                        "Stream reader ended unexpectedly at sequence {}. Next expected first sample ns {}",
                         clock.sequence,
                         clock.next_first_sample_unix_ns
                     );
-                    running.store(false, Ordering::Relaxed);
-                    let _ = LJMLibrary::stream_stop(handle);
-                    let _ = read_handle.await;
+                    stop_reader(&mut reader, device.as_ref()).await;
                     return Err(LJMError::LibraryError(
                         "Stream reader terminated unexpectedly".to_string(),
                     ));
                 };
+                let batch = &read.values;
                 if batch.is_empty() {
-                    eprintln!("[run #{run_id}] Received empty batch; stopping run.");
-                    running.store(false, Ordering::Relaxed);
-                    let _ = LJMLibrary::stream_stop(handle);
-                    let _ = read_handle.await;
+                    error!("Received empty batch; stopping run.");
+                    stop_reader(&mut reader, device.as_ref()).await;
                     return Err(LJMError::LibraryError(
                         "Received empty batch from stream_read".to_string(),
                     ));
                 }
                 if batch.len() % num_channels != 0 {
-                    eprintln!(
-                        "[run #{run_id}] Batch length {} is not divisible by channel count {}; stopping run as discontinuity.",
+                    warn!(
+                        "Batch length {} is not divisible by channel count {}; stopping run as discontinuity.",
                         batch.len(),
                         num_channels
                     );
-                    running.store(false, Ordering::Relaxed);
-                    let _ = LJMLibrary::stream_stop(handle);
-                    let _ = read_handle.await;
+                    stop_reader(&mut reader, device.as_ref()).await;
                     return Err(LJMError::LibraryError(format!(
                         "Malformed stream batch: {} values for {} channels",
                         batch.len(),
@@ -776,71 +1713,292 @@ async fn sample_with_config(
                 }
 
                 let batch_samples = batch.len() / num_channels;
-                let (first_sample_unix_ns, sequence) = clock.next_batch(batch_samples)?;
-                let scans = batch.chunks(num_channels);
-                let mut per_channel: Vec<Vec<f64>> = (0..num_channels)
-                    .map(|_| Vec::with_capacity(scans.len()))
-                    .collect();
-
-                for scan in batch.chunks(num_channels) {
-                    for (i, v) in scan.iter().enumerate() {
-                        per_channel[i].push(*v);
-                    }
+                latest_backlog = (read.device_backlog, read.ljm_backlog);
+                scans_read.inc_by(batch_samples as u64);
+                backlog_gauges[0].set(f64::from(read.device_backlog));
+                backlog_gauges[1].set(f64::from(read.ljm_backlog));
+                if let Some(event) = backlog.observe(run_id, read.device_backlog, read.ljm_backlog) {
+                    warn!(
+                        "Stream backlog: device {} scans, LJM {} scans (warn at {}): {:?}",
+                        read.device_backlog,
+                        read.ljm_backlog,
+                        backlog.threshold_scans(),
+                        event
+                    );
+                    publish_health_event(client, &health_subject, &event).await;
                 }
 
-                for (i, values) in per_channel.into_iter().enumerate() {
-                    builder.reset();
-                    let values_fb = builder.create_vector(&values);
-                    let scan_args = ScanArgs {
-                        first_sample_unix_ns,
-                        sample_interval_ns,
-                        actual_scan_rate_hz: actual_rate,
-                        sequence,
-                        values: Some(values_fb),
+                clock.anchor(batch_samples)?;
+                let mut captured = false;
+                for segment in stream_health::split_skipped_scans(batch, num_channels) {
+                    let (start_scan, scans) = match segment {
+                        BatchSegment::Data { start_scan, scans } => (start_scan, scans),
+                        BatchSegment::Skipped { scans, .. } => {
+                            let first_skipped_unix_ns = clock.skip_scans(scans)?;
+                            total_skipped_scans += scans as u64;
+                            skipped_scans.inc_by(scans as u64);
+                            warn!(
+                                "LJM skipped {scans} scan(s) at {first_skipped_unix_ns} ns ({total_skipped_scans} this run); next batch resumes at {} ns",
+                                clock.next_first_sample_unix_ns
+                            );
+                            let event = HealthEvent::SkippedScans {
+                                run_id,
+                                first_skipped_unix_ns,
+                                skipped_scans: scans,
+                                total_skipped_scans,
+                                device_backlog: read.device_backlog,
+                                ljm_backlog: read.ljm_backlog,
+                            };
+                            publish_health_event(client, &health_subject, &event).await;
+                            decimators.reset();
+                            pipeline.reset();
+                            alarm_monitor.discontinuity();
+                            if let Some(recorder) = recorder.as_mut()
+                                && recorder.discontinuity()
+                            {
+                                warn!("Dropped the capture in progress at the skipped scans");
+                            }
+                            continue;
+                        }
                     };
-                    let scan_offset = sampler::Scan::create(&mut builder, &scan_args);
-                    builder.finish(scan_offset, None);
-
-                    let data = builder.finished_data().to_vec();
-
-                    let ch_num: u8 = cfg.channels[i];
-                    let subject = subjects::live_labjack_channel_subject(
-                        &cfg.nats_subject,
-                        cfg.asset_number,
-                        ch_num,
-                        cfg.site_id.as_deref(),
-                        cfg.box_id.as_deref(),
-                        Some(&cfg.labjack_name),
-                        cfg.source_type.as_deref(),
-                        cfg.source_id.as_deref(),
-                    );
 
-                    if let Err(e) = js.publish(subject, data.into()).await {
-                        eprintln!("[run #{run_id}] Failed to publish to NATS: {}", e);
+                    let (first_sample_unix_ns, sequence) = clock.next_batch(scans)?;
+                    last_sequence = Some(sequence);
+                    let segment = pipeline.process(layout.decode(&batch[start_scan * num_channels..(start_scan + scans) * num_channels]));
+                    if !alarm_monitor.is_empty() {
+                        let interval_ns = clock.published_interval_ns();
+                        for event in alarm_monitor.observe(run_id, first_sample_unix_ns, interval_ns, &segment) {
+                            publish_alarm_event(client, &alarms_subject, &event).await;
+                        }
+                    }
+                    if let Some(recorder) = recorder.as_mut() {
+                        let interval_ns = clock.published_interval_ns();
+                        for capture in recorder.push(first_sample_unix_ns, interval_ns, &segment) {
+                            publish_capture(
+                                &cfg,
+                                pipeline.published(),
+                                publisher,
+                                client,
+                                &mut builder,
+                                &capture_subject,
+                                &capture_event_subject,
+                                &run_uuid,
+                                info.serial_number,
+                                &host_clock,
+                                actual_rate,
+                                &capture,
+                            )
+                            .await;
+                            captured = true;
+                        }
+                        continue;
+                    }
+                    match cfg.publish_mode {
+                        PublishMode::Channel => {
+                            publish_channel_scans(
+                                &cfg,
+                                pipeline.published(),
+                                publisher,
+                                client,
+                                &mut builder,
+                                &run_uuid,
+                                info.serial_number,
+                                &host_clock,
+                                first_sample_unix_ns,
+                                clock.published_interval_ns(),
+                                actual_rate,
+                                sequence,
+                                &segment,
+                            )
+                            .await;
+                        }
+                        PublishMode::Frame => {
+                            publish_frame(
+                                &cfg,
+                                pipeline.published(),
+                                publisher,
+                                client,
+                                &mut builder,
+                                &frame_subject,
+                                &run_uuid,
+                                info.serial_number,
+                                &host_clock,
+                                first_sample_unix_ns,
+                                clock.published_interval_ns(),
+                                actual_rate,
+                                sequence,
+                                &segment,
+                            )
+                            .await;
+                        }
                     }
+                    if !decimators.is_empty() {
+                        let interval_ns = clock.published_interval_ns();
+                        let outputs = decimators.push(first_sample_unix_ns, interval_ns, &segment);
+                        publish_decimated(
+                            &cfg,
+                            pipeline.published(),
+                            client,
+                            &mut builder,
+                            &run_uuid,
+                            info.serial_number,
+                            &host_clock,
+                            interval_ns,
+                            actual_rate,
+                            sequence,
+                            outputs,
+                        )
+                        .await;
+                    }
+                }
+                if captured && cfg.acquisition.finite_scans().is_some() {
+                    // The device stopped after `STREAM_NUM_SCANS`; stop the
+                    // stream and arm the next capture.
+                    stop_reader(&mut reader, device.as_ref()).await;
+                    rearm_at = Some(tokio::time::Instant::now() + cfg.acquisition.rearm_delay());
+                }
+            }
+            _ = sleep_until(rearm_at) => {
+                rearm_at = None;
+                device.configure_acquisition(&cfg.acquisition)?;
+                device.stream_start(cfg.scans_per_read, cfg.scan_rate_hz, layout.registers())?;
+                // Each capture is timed from its own first read; keep the
+                // measured skew across restarts.
+                let skew_ppm = clock.skew_ppm;
+                clock = StreamClock::new(sample_interval_ns);
+                clock.set_skew_ppm(skew_ppm);
+                pipeline.reset();
+                alarm_monitor.discontinuity();
+                reader = Some(StreamReader::spawn(device.clone(), finite_values));
+                if cfg.acquisition.waits_for_trigger() {
+                    info!("Re-armed; waiting for the DIO trigger");
+                }
+            }
+            _ = clock_check_ticker.tick() => {
+                let latest = clock_quality::check(clock_quality_cfg.max_error);
+                if latest.quality != host_clock.quality {
+                    warn!(
+                        "Host clock changed from {} to {} (max error {:?} ns)",
+                        host_clock.quality.as_str(),
+                        latest.quality.as_str(),
+                        latest.max_error_ns
+                    );
+                    publish_status(
+                        client,
+                        &status_subject,
+                        &ConnectionState::HostClock {
+                            run_id,
+                            check: latest,
+                        },
+                    )
+                    .await;
+                }
+                host_clock = latest;
+            }
+            _ = next_tick(&mut status_ticker) => {
+                let temperature_device = device.clone();
+                let temperature_k = match tokio::task::spawn_blocking(move || temperature_device.read_temperature_k()).await {
+                    Ok(Ok(kelvin)) => Some(kelvin),
+                    Ok(Err(e)) => {
+                        warn!("{:?}", e);
+                        None
+                    }
+                    Err(e) => {
+                        warn!("Temperature read task failed: {}", e);
+                        None
+                    }
+                };
+                let heartbeat = Heartbeat {
+                    run_id,
+                    run_uuid: run_uuid.clone(),
+                    serial: info.serial_number,
+                    firmware_version,
+                    connection_type: heartbeat::connection_type_name(&info.connection_type),
+                    backend: device.backend_name(),
+                    acquisition: cfg.acquisition.as_str(),
+                    scan_rate_hz: actual_rate,
+                    device_backlog: latest_backlog.0,
+                    ljm_backlog: latest_backlog.1,
+                    temperature_k,
+                    skipped_scans: total_skipped_scans,
+                    publish: publisher.stats(),
+                    last_sequence,
+                    uptime_secs: STARTED_AT.elapsed().as_secs(),
+                    run_uptime_secs: run_started.elapsed().as_secs(),
+                };
+                publish_status(client, &status_subject, &ConnectionState::Heartbeat(heartbeat)).await;
+            }
+            _ = next_tick(&mut sync_ticker) => {
+                let sync_device = device.clone();
+                match tokio::task::spawn_blocking(move || clock_sync::correlate(sync_device.as_ref())).await {
+                    Ok(Ok(correlation)) => {
+                        let Some(estimate) = drift.as_mut().and_then(|d| d.observe(correlation)) else {
+                            continue;
+                        };
+                        clock.set_skew_ppm(estimate.skew_ppm);
+                        info!(
+                            "Device clock skew {:.3} ppm, drift {:?} ppm/h over {} reads; timestamps corrected by {} ns so far",
+                            estimate.skew_ppm,
+                            estimate.drift_ppm_per_hour,
+                            estimate.samples,
+                            clock.correction_ns()
+                        );
+                        publish_status(
+                            client,
+                            &status_subject,
+                            &ConnectionState::ClockSync {
+                                run_id,
+                                skew_ppm: estimate.skew_ppm,
+                                drift_ppm_per_hour: estimate.drift_ppm_per_hour,
+                                correction_ns: clock.correction_ns(),
+                                samples: estimate.samples,
+                                round_trip_ns: correlation.round_trip_ns,
+                            },
+                        )
+                        .await;
+                    }
+                    Ok(Err(e)) => warn!("CORE_TIMER correlation failed: {:?}", e),
+                    Err(e) => warn!("CORE_TIMER correlation task failed: {}", e),
                 }
             }
             _ = config_rx.changed() => {
-                println!(
-                    "[run #{run_id}] Config change detected. Stopping stream at sequence {}. Next expected first sample ns {}",
+                let updated = config_rx.borrow_and_update().clone();
+                if !cfg.requires_stream_restart(&updated) {
+                    info!(
+                        "Calibration/unit/publish mode/decimation/alarm update applied without restarting stream: {:?} {:?} {:?} {:?} {:?}",
+                        updated.calibrations, updated.measurement_units, updated.publish_mode, updated.decimation, updated.alarms
+                    );
+                    if updated.decimation != cfg.decimation {
+                        decimators = DecimationBank::new(&updated.decimation, pipeline.published().len());
+                    }
+                    if updated.alarms != cfg.alarms {
+                        // Raised alarms are forgotten; rules still in effect
+                        // raise again on the next scans.
+                        alarm_monitor = AlarmMonitor::new(&updated.alarms, pipeline.published(), &ain_ranges);
+                    }
+                    cfg.calibrations = updated.calibrations;
+                    cfg.measurement_units = updated.measurement_units;
+                    cfg.publish_mode = updated.publish_mode;
+                    cfg.decimation = updated.decimation;
+                    cfg.alarms = updated.alarms;
+                    continue;
+                }
+                info!(
+                    "Config change detected. Stopping stream at sequence {}. Next expected first sample ns {}",
                     clock.sequence,
                     clock.next_first_sample_unix_ns
                 );
-                running.store(false, Ordering::Relaxed);
-                let _ = LJMLibrary::stream_stop(handle);
-                let _ = read_handle.await;
+                stop_reader(&mut reader, device.as_ref()).await;
                 return Ok(());
             }
             _ = shutdown_rx.changed() => {
                 if *shutdown_rx.borrow() {
-                    println!(
-                        "[run #{run_id}] Shutdown signal received. Stopping stream at sequence {}. Next expected first sample ns {}",
+                    info!(
+                        "Shutdown signal received. Stopping stream at sequence {}. Next expected first sample ns {}",
                         clock.sequence,
                         clock.next_first_sample_unix_ns
                     );
-                    running.store(false, Ordering::Relaxed);
-                    let _ = LJMLibrary::stream_stop(handle);
-                    let _ = read_handle.await;
+                    stop_reader(&mut reader, device.as_ref()).await;
                     return Ok(());
                 }
             }
@@ -848,48 +2006,113 @@ async fn sample_with_config(
     }
 }
 
+fn restart_counter(device: &str, reason: &str) -> Arc<metrics::Counter> {
+    metrics::global().counter(
+        "streamer_restarts_total",
+        "Sampler runs restarted, by reason: a config change or an error.",
+        &[("device", device), ("reason", reason)],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn run_sampler(
     mut config_rx: tokio::sync::watch::Receiver<SampleConfig>,
     mut shutdown_rx: tokio::sync::watch::Receiver<bool>,
     js: jetstream::Context,
+    client: async_nats::Client,
+    publisher: Arc<Publisher>,
+    backend: DeviceBackend,
+    target: LabJackTarget,
+    reconnect: ReconnectPolicy,
 ) {
-    let mut run_id = 0;
+    let device = target.label();
+    let mut supervisor = Supervisor::new(reconnect);
     loop {
         if *shutdown_rx.borrow() {
-            println!("[run_sampler] Sampler shutting down...");
+            info!("Sampler shutting down...");
             break;
         }
-        run_id += 1;
+        let run_id = NEXT_RUN_ID.fetch_add(1, Ordering::Relaxed);
         let cfg = config_rx.borrow().clone();
-        println!(
-            "[run_sampler] Starting sampler run #{run_id} with {:?}",
+        info!(
+            "Starting sampler run #{run_id} with {:?}",
             cfg
         );
-
-        if let Err(e) = sample_with_config(run_id, cfg, &mut config_rx, &mut shutdown_rx, &js).await
-        {
-            eprintln!("[run_sampler] Sampler error: {:?}", e);
-        }
+        let status_subject =
+            subjects::source_event_subject(&first_channel_subject(&cfg), "status");
+
+        let result = sample_with_config(
+            run_id,
+            cfg,
+            &mut config_rx,
+            &mut shutdown_rx,
+            &js,
+            &client,
+            &publisher,
+            &backend,
+            &target,
+            &mut supervisor,
+        )
+        .instrument(info_span!("run", run_id))
+        .await;
+        info!(
+            "Publish stats after run #{run_id}: {:?}",
+            publisher.stats()
+        );
 
         if *shutdown_rx.borrow() {
-            println!("[run_sampler] Shutdown detected after sampler error/config change");
+            info!("Shutdown detected after sampler error/config change");
             break;
         }
 
-        println!("[run_sampler] Restarting sampler after config change...");
+        let Err(e) = result else {
+            info!("Restarting sampler after config change...");
+            restart_counter(&device, "config").inc();
+            continue;
+        };
+        error!("Sampler error: {:?}", e);
+        restart_counter(&device, "error").inc();
+        let retry = supervisor.on_failure(run_id, format!("{:?}", e));
+        if retry.alert {
+            error!(
+                "ALERT: LabJack unavailable after {} consecutive attempts; still retrying",
+                supervisor.failures()
+            );
+        }
+        publish_status(&client, &status_subject, &retry.state).await;
+        info!(
+            "Reconnecting in {} ms (attempt {})",
+            retry.delay.as_millis(),
+            supervisor.failures()
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(retry.delay) => {}
+            Ok(()) = config_rx.changed() => {
+                info!("Config changed during backoff; reconnecting now");
+                supervisor.reset();
+            }
+            _ = shutdown_rx.changed() => {}
+        }
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), LJMError> {
+    logging::init().map_err(LJMError::LibraryError)?;
+    std::sync::LazyLock::force(&STARTED_AT);
     let servers = nats_config::servers_from_env().map_err(LJMError::LibraryError)?;
     let nc = connect_nats_with_creds(servers, local_creds_path_from_env()).await?;
 
-    println!("Connected to NATS via creds!");
-    let js = nats_config::jetstream_context(nc);
+    info!("Connected to NATS via creds!");
+    let js = nats_config::jetstream_context(nc.clone());
 
     let bucket = std::env::var("CFG_BUCKET").unwrap_or_else(|_| "avenabox".into());
-    let key = std::env::var("CFG_KEY").unwrap_or_else(|_| "v1.macbook.unknown-source.config".into());
+    let devices = device::device_specs_from_env().map_err(LJMError::LibraryError)?;
+    info!(
+        "Managing {} LabJack(s): {:?}",
+        devices.len(),
+        devices
+    );
 
     let store = ensure_kv_bucket(&js, &bucket).await?;
     let central_sync_cfg = central_kv_sync_config_from_env()?;
@@ -902,69 +2125,152 @@ async fn main() -> Result<(), LJMError> {
                 );
                 match ensure_kv_bucket(&remote_js, &sync_cfg.bucket).await {
                     Ok(remote_store) => {
-                        if let Err(err) = mirror_remote_kv_entry_to_local(
-                            &remote_store,
-                            &sync_cfg.bucket,
-                            &sync_cfg.key,
-                            &store,
-                            &key,
-                        )
-                        .await
-                        {
-                            eprintln!("[central_kv_sync] bootstrap mirror failed: {:?}", err);
+                        for spec in &devices {
+                            if let Err(err) = mirror_remote_kv_entry_to_local(
+                                &remote_store,
+                                &sync_cfg.bucket,
+                                spec.central_key(),
+                                &store,
+                                &spec.cfg_key,
+                            )
+                            .await
+                            {
+                                warn!(
+                                    "bootstrap mirror failed for '{}': {:?}",
+                                    spec.cfg_key, err
+                                );
+                            }
                         }
                     }
                     Err(err) => {
-                        eprintln!("[central_kv_sync] bootstrap remote bucket failed: {:?}", err);
+                        warn!("bootstrap remote bucket failed: {:?}", err);
                     }
                 }
             }
             Err(err) => {
-                eprintln!("[central_kv_sync] bootstrap connect failed: {:?}", err);
+                warn!("bootstrap connect failed: {:?}", err);
             }
         }
     }
 
-    let cfg = load_config_from_kv(&store, &key).await?;
-    println!(
-        "[bootstrap] Loaded initial config from KV '{}:{}': {:?}",
-        bucket, key, cfg
-    );
+    let mut configs = Vec::with_capacity(devices.len());
+    for spec in &devices {
+        let cfg = load_config_from_kv(&store, &spec.cfg_key).await?;
+        info!(
+            "Loaded initial config for {} from KV '{}:{}': {:?}",
+            spec.target.label(),
+            bucket,
+            spec.cfg_key,
+            cfg
+        );
+        configs.push(cfg);
+    }
 
-    let (config_tx, config_rx) = watch::channel(cfg);
     let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
-    unsafe {
-        ljm_mode::init_ljm()?;
-    }
-
-    tokio::spawn(run_sampler(
-        config_rx.clone(),
-        shutdown_rx.clone(),
-        js.clone(),
-    ));
-    tokio::spawn(watch_kv_config(
-        store.clone(),
-        key.clone(),
-        config_tx.clone(),
-        shutdown_rx.clone(),
-    ));
-    if let Some(sync_cfg) = central_sync_cfg {
-        tokio::spawn(run_central_kv_sync(
-            sync_cfg,
-            store.clone(),
-            key.clone(),
+    let backend = DeviceBackend::from_env()?;
+    if backend.requires_ljm() {
+        unsafe {
+            ljm_mode::init_ljm()?;
+        }
+    } else {
+        info!("Using simulated LabJack backend; LJM is not loaded");
+    }
+
+    let publisher_config = PublisherConfig::from_env().map_err(LJMError::LibraryError)?;
+    let spool = match spool::SpoolConfig::from_env().map_err(LJMError::LibraryError)? {
+        Some(spool_config) => {
+            let spool = spool::Spool::open(&spool_config).map_err(|e| {
+                LJMError::LibraryError(format!(
+                    "Failed to open spool at {}: {}",
+                    spool_config.dir.display(),
+                    e
+                ))
+            })?;
+            info!(
+                "Spooling unpublished scans to {} (cap {} bytes, {} pending)",
+                spool_config.dir.display(),
+                spool_config.max_bytes,
+                spool.pending()
+            );
+            Some(spool)
+        }
+        None => {
+            info!("Spool disabled (STREAM_SPOOL_DIR unset); unpublished scans will be dropped");
+            None
+        }
+    };
+    let publisher = Publisher::spawn(js.clone(), publisher_config, spool);
+    info!("JetStream publisher: {:?}", publisher_config);
+    publisher.register_metrics();
+    metrics::serve_from_env("streamer")
+        .await
+        .map_err(LJMError::LibraryError)?;
+    let reconnect = ReconnectPolicy::from_env().map_err(LJMError::LibraryError)?;
+    info!("LabJack reconnect policy: {:?}", reconnect);
+
+    // Each device gets its own config watch, central mirror and sampler, so a
+    // config edit or a reconnect only restarts that device's stream.
+    let publisher = Arc::new(publisher);
+    let mut samplers = Vec::with_capacity(devices.len());
+    for (spec, cfg) in devices.into_iter().zip(configs) {
+        let (config_tx, config_rx) = watch::channel(cfg);
+        samplers.push(tokio::spawn(run_sampler(
+            config_rx,
             shutdown_rx.clone(),
-        ));
+            js.clone(),
+            nc.clone(),
+            publisher.clone(),
+            backend.clone(),
+            spec.target.clone(),
+            reconnect,
+        )
+        .instrument(info_span!("sampler", device = %spec.target.label()))));
+        tokio::spawn(
+            watch_kv_config(
+                store.clone(),
+                spec.cfg_key.clone(),
+                config_tx,
+                shutdown_rx.clone(),
+            )
+            .instrument(info_span!("kv_watch", key = %spec.cfg_key)),
+        );
+        if let Some(sync_cfg) = central_sync_cfg.clone() {
+            tokio::spawn(
+                run_central_kv_sync(
+                    sync_cfg,
+                    spec.central_key().to_string(),
+                    store.clone(),
+                    spec.cfg_key.clone(),
+                    shutdown_rx.clone(),
+                )
+                .instrument(info_span!("central_kv_sync", key = %spec.central_key())),
+            );
+        }
     }
 
     tokio::signal::ctrl_c()
         .await
         .map_err(|e| LJMError::LibraryError(format!("Failed to listen for Ctrl+C: {}", e)))?;
 
-    println!("Shutting down...");
+    info!("Shutting down...");
     let _ = shutdown_tx.send(true);
-    tokio::time::sleep(Duration::from_millis(300)).await;
+    let drained = tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, async {
+        for sampler in samplers {
+            let _ = sampler.await;
+        }
+        match Arc::try_unwrap(publisher) {
+            Ok(publisher) => {
+                let stats = publisher.close().await;
+                info!("Publisher drained: {:?}", stats);
+            }
+            Err(_) => warn!("Publisher still in use; not draining"),
+        }
+    })
+    .await;
+    if drained.is_err() {
+        error!("Timed out waiting for queued scans to publish; exiting anyway.");
+    }
     Ok(())
 }
 
@@ -993,7 +2299,10 @@ mod tests {
     "gains": 1,
     "data_formats": ["voltage", "voltage"],
     "measurement_units": ["V", "V"],
-    "labjack_on_off": true
+    "labjack_on_off": true,
+    "calibrations": {{
+      "11": {{ "type": "linear", "a": 2.0, "b": 0.5, "id": "strain-11" }}
+    }}
   }}
 }}"#
         )
@@ -1025,6 +2334,88 @@ mod tests {
         assert_eq!(reset_clock.sequence, 0);
     }
 
+    #[test]
+    fn clock_skips_gaps_without_consuming_sequences() {
+        let mut clock = StreamClock::new(1_000);
+        clock.anchor(10).expect("anchor");
+        let (first, seq0) = clock.next_batch(3).expect("leading scans");
+        let skipped_at = clock.skip_scans(2).expect("gap");
+        let (resumed, seq1) = clock.next_batch(5).expect("trailing scans");
+
+        assert_eq!(skipped_at, first + 3 * 1_000);
+        assert_eq!(resumed, first + 5 * 1_000);
+        assert_eq!((seq0, seq1), (0, 1));
+        assert_eq!(clock.next_first_sample_unix_ns, first + 10 * 1_000);
+    }
+
+    #[test]
+    fn clock_skew_correction_only_applies_to_later_scans() {
+        let mut clock = StreamClock::new(1_000_000);
+        let (first, _) = clock.next_batch(1_000).expect("first batch");
+        clock.set_skew_ppm(100.0);
+        let (second, _) = clock.next_batch(1_000).expect("second batch");
+        let (third, _) = clock.next_batch(1_000).expect("third batch");
+
+        assert_eq!(second, first + 1_000 * 1_000_000);
+        assert_eq!(third - second, 999_900_010);
+        assert_eq!(clock.published_interval_ns(), 999_900);
+        assert_eq!(clock.correction_ns(), -199_980);
+    }
+
+    #[test]
+    fn encoded_scan_carries_identity() {
+        let mut builder = FlatBufferBuilder::new();
+        let identity = ScanIdentity {
+            channel: 11,
+            channel_name: "ch11",
+            run_id: "run-a",
+            device_serial: 470036312,
+            unit: Some("kPa"),
+            calibration_id: Some("strain-11"),
+            clock_quality: "degraded",
+            clock_error_bound_ns: 250_000_000,
+        };
+        let data = encode_scan(&mut builder, 10, 200, 5000.0, 3, &[1.0, 2.0], &identity, None);
+        let scan = sampler::root_as_scan(&data).expect("valid scan");
+
+        assert_eq!(scan.channel(), 11);
+        assert_eq!(scan.channel_name(), Some("ch11"));
+        assert_eq!(scan.run_id(), Some("run-a"));
+        assert_eq!(scan.device_serial(), 470036312);
+        assert_eq!(scan.unit(), Some("kPa"));
+        assert_eq!(scan.calibration_id(), Some("strain-11"));
+        assert!(scan.calibrated());
+        assert_eq!(scan.clock_quality(), Some("degraded"));
+        assert_eq!(scan.clock_error_bound_ns(), 250_000_000);
+        assert_eq!(scan.values().map(|v| v.len()), Some(2));
+    }
+
+    #[test]
+    fn scans_without_identity_decode_with_defaults() {
+        let mut builder = FlatBufferBuilder::new();
+        let values = builder.create_vector(&[1.0]);
+        let legacy = sampler::Scan::create(
+            &mut builder,
+            &ScanArgs {
+                first_sample_unix_ns: 10,
+                sample_interval_ns: 200,
+                actual_scan_rate_hz: 5000.0,
+                sequence: 1,
+                values: Some(values),
+                ..Default::default()
+            },
+        );
+        builder.finish(legacy, None);
+        let scan = sampler::root_as_scan(builder.finished_data()).expect("valid scan");
+
+        assert_eq!(scan.channel(), -1);
+        assert_eq!(scan.run_id(), None);
+        assert_eq!(scan.device_serial(), 0);
+        assert!(!scan.calibrated());
+        assert_eq!(scan.clock_quality(), None);
+        assert_eq!(scan.clock_error_bound_ns(), -1);
+    }
+
     #[test]
     fn clock_rejects_empty_batch() {
         let mut clock = StreamClock::new(1_000);
@@ -1056,4 +2447,209 @@ mod tests {
         assert_eq!(config.scan_rate_hz, 1000.0);
         assert_eq!(config.rotate_secs, 300);
     }
+
+    #[test]
+    fn kv_config_parses_calibrations() {
+        let config = sample_config_from_json(
+            sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000").as_bytes(),
+        )
+        .expect("config with calibrations should parse");
+
+        let calibration = config.calibrations.get(&11).expect("channel 11 calibration");
+        assert_eq!(calibration.id_or_default(), "strain-11");
+        assert_eq!(calibration.apply(1.0), 2.5);
+        assert!(!config.calibrations.contains_key(&7));
+    }
+
+    #[test]
+    fn calibration_only_change_does_not_restart_stream() {
+        let config = sample_config_from_json(
+            sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000").as_bytes(),
+        )
+        .expect("config should parse");
+
+        let mut recalibrated = config.clone();
+        recalibrated.calibrations.clear();
+        assert!(!config.requires_stream_restart(&recalibrated));
+
+        let mut rescheduled = recalibrated.clone();
+        rescheduled.scan_rate_hz = 1000.0;
+        assert!(config.requires_stream_restart(&rescheduled));
+    }
+
+    #[test]
+    fn kv_config_publish_mode_defaults_to_channel_and_applies_live() {
+        let config = sample_config_from_json(
+            sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000").as_bytes(),
+        )
+        .expect("config should parse");
+        assert_eq!(config.publish_mode, PublishMode::Channel);
+
+        let json = sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000").replace(
+            r#""rotate_secs": 300,"#,
+            r#""rotate_secs": 300,
+  "publish_mode": "frame","#,
+        );
+        let framed = sample_config_from_json(json.as_bytes()).expect("frame mode should parse");
+        assert_eq!(framed.publish_mode, PublishMode::Frame);
+        assert!(!config.requires_stream_restart(&framed));
+    }
+
+    #[test]
+    fn kv_config_resolves_ain_settings() {
+        let json = sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000").replace(
+            r#""labjack_on_off": true,"#,
+            r#""labjack_on_off": true,
+    "ain_settings": { "11": { "range": 1.0, "resolution_index": 4 } },"#,
+        );
+        let config = sample_config_from_json(json.as_bytes()).expect("ain settings should parse");
+
+        assert_eq!(config.ain_channels.len(), 2);
+        assert_eq!(config.ain_channels[0].channel, 7);
+        assert_eq!(config.ain_channels[0].range, 10.0);
+        assert_eq!(config.ain_channels[1].range, 1.0);
+        assert_eq!(config.ain_channels[1].resolution_index, 4);
+    }
+
+    #[test]
+    fn kv_config_rejects_invalid_ain_settings() {
+        let json = sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000")
+            .replace(r#""gains": 1,"#, r#""gains": 0,"#);
+        assert!(sample_config_from_json(json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn kv_config_maps_off_table_gains_to_a_covering_range() {
+        let json = sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000")
+            .replace(r#""gains": 1,"#, r#""gains": 3,"#);
+        let config = sample_config_from_json(json.as_bytes()).expect("gains=3 should still load");
+        assert!(config.ain_channels.iter().all(|ain| ain.range == 10.0));
+    }
+
+    #[test]
+    fn kv_config_validates_against_declared_device_type() {
+        let t7 = sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000");
+        assert_eq!(
+            sample_config_from_json(t7.as_bytes()).unwrap().device_type,
+            None
+        );
+
+        let t8 = t7.replace(
+            r#""rotate_secs": 300,"#,
+            r#""rotate_secs": 300,
+  "device_type": "T8","#,
+        );
+        assert!(sample_config_from_json(t8.as_bytes()).is_err());
+        let t8 = t8.replace("[7, 11]", "[0, 1]");
+        let config = sample_config_from_json(t8.as_bytes()).expect("T8 channels");
+        assert_eq!(config.device_type, Some(DeviceModel::T8));
+        assert_eq!(config.ain_channels[0].range, 11.0);
+
+        let too_fast = t7.replace(r#""scan_rate_hz": 5000"#, r#""scan_rate_hz": 60000"#);
+        assert!(sample_config_from_json(too_fast.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn kv_config_reads_typed_digital_channels() {
+        let analog = sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000");
+        assert!(
+            sample_config_from_json(analog.as_bytes())
+                .unwrap()
+                .digital_channels
+                .is_empty()
+        );
+
+        let with_digital = analog.replace(
+            r#""labjack_on_off": true,"#,
+            r#""labjack_on_off": true,
+    "digital_channels": [{ "type": "dio", "dio": 0 }, { "type": "counter", "dio": 1 }],"#,
+        );
+        let config = sample_config_from_json(with_digital.as_bytes()).expect("digital channels");
+        assert_eq!(
+            config.digital_channels,
+            vec![
+                DigitalChannel::Dio { dio: 0 },
+                DigitalChannel::Counter { dio: 1 }
+            ]
+        );
+
+        let unsupported = with_digital.replace(
+            r#"{ "type": "counter", "dio": 1 }"#,
+            r#"{ "type": "frequency", "dio": 4 }"#,
+        );
+        assert!(sample_config_from_json(unsupported.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn kv_config_reads_filters_and_virtual_channels() {
+        let plain = sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000");
+        let with_dsp = plain.replace(
+            r#""labjack_on_off": true,"#,
+            r#""labjack_on_off": true,
+    "filters": { "11": [{ "type": "notch" }, { "type": "low_pass", "cutoff_hz": 500 }] },
+    "virtual_channels": [{ "id": 0, "type": "difference", "a": 7, "b": 11 }],"#,
+        );
+        let config = sample_config_from_json(with_dsp.as_bytes()).expect("dsp settings");
+        assert_eq!(config.filters[&11].len(), 2);
+        assert_eq!(config.virtual_channels[0].id, 0);
+
+        let above_nyquist = with_dsp.replace(r#""cutoff_hz": 500"#, r#""cutoff_hz": 2600"#);
+        assert!(sample_config_from_json(above_nyquist.as_bytes()).is_err());
+        let unknown_input = with_dsp.replace(r#""b": 11"#, r#""b": 3"#);
+        assert!(sample_config_from_json(unknown_input.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn kv_config_reads_alarms() {
+        let plain = sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000");
+        let with_alarms = plain.replace(
+            r#""labjack_on_off": true,"#,
+            r#""labjack_on_off": true,
+    "alarms": { "11": [{ "type": "high", "limit": 4.5, "hysteresis": 0.1, "debounce_secs": 2 }, { "type": "out_of_range" }] },"#,
+        );
+        let config = sample_config_from_json(with_alarms.as_bytes()).expect("alarms");
+        assert_eq!(config.alarms[&11].len(), 2);
+        let base = sample_config_from_json(plain.as_bytes()).unwrap();
+        assert!(!config.requires_stream_restart(&base));
+
+        let disabled_channel = with_alarms.replace(r#""alarms": { "11""#, r#""alarms": { "3""#);
+        assert!(sample_config_from_json(disabled_channel.as_bytes()).is_err());
+        let negative_debounce = with_alarms.replace(r#""debounce_secs": 2"#, r#""debounce_secs": -1"#);
+        assert!(sample_config_from_json(negative_debounce.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn kv_config_reads_acquisition_mode() {
+        let continuous = sample_kv_json("scans_per_read", "200", "scan_rate_hz", "5000");
+        let config = sample_config_from_json(continuous.as_bytes()).unwrap();
+        assert!(config.acquisition.is_continuous());
+
+        let burst = continuous.replace(
+            r#""rotate_secs": 300,"#,
+            r#""rotate_secs": 300,
+  "acquisition": { "mode": "burst", "num_scans": 1000, "interval_secs": 60 },"#,
+        );
+        let config = sample_config_from_json(burst.as_bytes()).expect("burst");
+        assert_eq!(config.acquisition.finite_scans(), Some(1000));
+        assert!(config.requires_stream_restart(&sample_config_from_json(continuous.as_bytes()).unwrap()));
+
+        let ragged = burst.replace("1000", "1050");
+        assert!(sample_config_from_json(ragged.as_bytes()).is_err());
+
+        let threshold = continuous.replace(
+            r#""rotate_secs": 300,"#,
+            r#""rotate_secs": 300,
+  "acquisition": {
+    "mode": "triggered",
+    "trigger": { "type": "threshold", "channel": 11, "level": 2.5 },
+    "pre_trigger_scans": 100,
+    "post_trigger_scans": 400
+  },"#,
+        );
+        let config = sample_config_from_json(threshold.as_bytes()).expect("threshold");
+        assert!(!config.acquisition.waits_for_trigger());
+
+        let missing_channel = threshold.replace(r#""channel": 11"#, r#""channel": 3"#);
+        assert!(sample_config_from_json(missing_channel.as_bytes()).is_err());
+    }
 }
@@ -0,0 +1,441 @@
+#![allow(dead_code)]
+
+//! Prometheus text exposition for the optional `/metrics` endpoint each
+//! binary serves on `METRICS_ADDR`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use axum::Router;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use tracing::{error, info};
+
+/// Latency buckets in seconds, from a single PubAck to a slow parquet flush.
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+/// Duration buckets in seconds for work that takes seconds to minutes.
+pub const DURATION_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0];
+
+static GLOBAL: LazyLock<Registry> = LazyLock::new(Registry::default);
+
+/// Registry the `/metrics` endpoint renders.
+pub fn global() -> &'static Registry {
+    &GLOBAL
+}
+
+/// Monotonic count.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Value that can go up and down, stored as `f64` bits.
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicU64);
+
+impl Gauge {
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn add(&self, delta: f64) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + delta).to_bits())
+            });
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Observations per bucket, not cumulative; the last one is `+Inf`.
+    buckets: Vec<AtomicU64>,
+    sum_bits: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum_bits: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let index = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        let _ = self
+            .sum_bits
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + value).to_bits())
+            });
+    }
+}
+
+type Callback = Box<dyn Fn() -> f64 + Send + Sync>;
+
+enum Series {
+    Counter(Arc<Counter>),
+    Gauge(Arc<Gauge>),
+    Histogram(Arc<Histogram>),
+    /// Read at scrape time from state the binary already keeps.
+    Callback(Callback),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Kind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+            Self::Histogram => "histogram",
+        }
+    }
+}
+
+struct Family {
+    help: &'static str,
+    kind: Kind,
+    /// Keyed by the rendered label set, e.g. `channel="ch11"`.
+    series: BTreeMap<String, Series>,
+}
+
+/// Metric families by name. Registering a name and label set again returns
+/// the existing series, so call sites can look handles up lazily.
+#[derive(Default)]
+pub struct Registry {
+    families: Mutex<BTreeMap<&'static str, Family>>,
+}
+
+impl Registry {
+    pub fn counter(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+    ) -> Arc<Counter> {
+        self.series(
+            name,
+            help,
+            Kind::Counter,
+            labels,
+            || Series::Counter(Arc::default()),
+            |series| match series {
+                Series::Counter(counter) => Some(counter.clone()),
+                _ => None,
+            },
+        )
+    }
+
+    pub fn gauge(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+    ) -> Arc<Gauge> {
+        self.series(
+            name,
+            help,
+            Kind::Gauge,
+            labels,
+            || Series::Gauge(Arc::default()),
+            |series| match series {
+                Series::Gauge(gauge) => Some(gauge.clone()),
+                _ => None,
+            },
+        )
+    }
+
+    pub fn histogram(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        bounds: &'static [f64],
+    ) -> Arc<Histogram> {
+        self.series(
+            name,
+            help,
+            Kind::Histogram,
+            labels,
+            || Series::Histogram(Arc::new(Histogram::new(bounds))),
+            |series| match series {
+                Series::Histogram(histogram) => Some(histogram.clone()),
+                _ => None,
+            },
+        )
+    }
+
+    /// Counter read from `read` at scrape time; replaces an earlier callback
+    /// with the same labels.
+    pub fn counter_fn(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        read: impl Fn() -> f64 + Send + Sync + 'static,
+    ) {
+        self.insert(
+            name,
+            help,
+            Kind::Counter,
+            labels,
+            Series::Callback(Box::new(read)),
+        );
+    }
+
+    /// Gauge read from `read` at scrape time; replaces an earlier callback
+    /// with the same labels.
+    pub fn gauge_fn(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        read: impl Fn() -> f64 + Send + Sync + 'static,
+    ) {
+        self.insert(
+            name,
+            help,
+            Kind::Gauge,
+            labels,
+            Series::Callback(Box::new(read)),
+        );
+    }
+
+    fn series<T>(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        kind: Kind,
+        labels: &[(&str, &str)],
+        create: impl FnOnce() -> Series,
+        get: impl Fn(&Series) -> Option<T>,
+    ) -> T {
+        let mut families = self.families.lock().unwrap_or_else(|p| p.into_inner());
+        let family = family(&mut families, name, help, kind);
+        let series = family
+            .series
+            .entry(render_labels(labels))
+            .or_insert_with(create);
+        get(series).unwrap_or_else(|| panic!("metric {name} registered with another type"))
+    }
+
+    fn insert(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        kind: Kind,
+        labels: &[(&str, &str)],
+        series: Series,
+    ) {
+        let mut families = self.families.lock().unwrap_or_else(|p| p.into_inner());
+        family(&mut families, name, help, kind)
+            .series
+            .insert(render_labels(labels), series);
+    }
+
+    /// Prometheus text format, version 0.0.4.
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap_or_else(|p| p.into_inner());
+        let mut out = String::new();
+        for (name, family) in families.iter() {
+            let _ = writeln!(out, "# HELP {name} {}", family.help);
+            let _ = writeln!(out, "# TYPE {name} {}", family.kind.as_str());
+            for (labels, series) in &family.series {
+                match series {
+                    Series::Counter(counter) => {
+                        sample(&mut out, name, labels, counter.get() as f64)
+                    }
+                    Series::Gauge(gauge) => sample(&mut out, name, labels, gauge.get()),
+                    Series::Callback(read) => sample(&mut out, name, labels, read()),
+                    Series::Histogram(histogram) => {
+                        let mut cumulative = 0;
+                        for (i, count) in histogram.buckets.iter().enumerate() {
+                            cumulative += count.load(Ordering::Relaxed);
+                            let le = histogram.bounds.get(i).copied().unwrap_or(f64::INFINITY);
+                            let mut bucket_labels = labels.clone();
+                            if !bucket_labels.is_empty() {
+                                bucket_labels.push(',');
+                            }
+                            let _ = write!(bucket_labels, "le=\"{}\"", format_value(le));
+                            sample(
+                                &mut out,
+                                &format!("{name}_bucket"),
+                                &bucket_labels,
+                                cumulative as f64,
+                            );
+                        }
+                        let sum = f64::from_bits(histogram.sum_bits.load(Ordering::Relaxed));
+                        sample(&mut out, &format!("{name}_sum"), labels, sum);
+                        sample(
+                            &mut out,
+                            &format!("{name}_count"),
+                            labels,
+                            cumulative as f64,
+                        );
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn family<'a>(
+    families: &'a mut BTreeMap<&'static str, Family>,
+    name: &'static str,
+    help: &'static str,
+    kind: Kind,
+) -> &'a mut Family {
+    let family = families.entry(name).or_insert_with(|| Family {
+        help,
+        kind,
+        series: BTreeMap::new(),
+    });
+    assert_eq!(
+        family.kind, kind,
+        "metric {name} registered with another type"
+    );
+    family
+}
+
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| {
+            let escaped = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{key}=\"{escaped}\"")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: f64) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{name} {}", format_value(value));
+    } else {
+        let _ = writeln!(out, "{name}{{{labels}}} {}", format_value(value));
+    }
+}
+
+async fn handle_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        global().render(),
+    )
+}
+
+/// Serves the global registry on `METRICS_ADDR` in the background. Unset or
+/// empty leaves the endpoint off; a bad address or busy port is an error.
+pub async fn serve_from_env(binary: &str) -> Result<(), String> {
+    let addr = match std::env::var("METRICS_ADDR") {
+        Ok(addr) if !addr.trim().is_empty() => addr.trim().to_string(),
+        _ => return Ok(()),
+    };
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .map_err(|e| format!("failed to bind METRICS_ADDR '{addr}': {e}"))?;
+    info!(binary, "Serving metrics on http://{addr}/metrics");
+    let app = Router::new().route("/metrics", get(handle_metrics));
+    let binary = binary.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            error!(binary = %binary, "Metrics endpoint stopped: {e}");
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters_gauges_and_callbacks() {
+        let registry = Registry::default();
+        let rows = registry.counter("rows_total", "Rows written.", &[("channel", "ch11")]);
+        rows.inc_by(3);
+        // Looking the series up again returns the same counter.
+        registry
+            .counter("rows_total", "Rows written.", &[("channel", "ch11")])
+            .inc();
+        registry.gauge("files_open", "Open files.", &[]).set(2.0);
+        registry.gauge_fn("backlog", "Backlog.", &[("buffer", "a\"b")], || 7.5);
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE rows_total counter\nrows_total{channel=\"ch11\"} 4\n"));
+        assert!(text.contains("files_open 2\n"));
+        assert!(text.contains("backlog{buffer=\"a\\\"b\"} 7.5\n"));
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let registry = Registry::default();
+        let latency = registry.histogram("latency_seconds", "Latency.", &[], &[0.1, 1.0]);
+        latency.observe(0.05);
+        latency.observe(0.5);
+        latency.observe(3.0);
+
+        let text = registry.render();
+        assert!(text.contains("latency_seconds_bucket{le=\"0.1\"} 1\n"));
+        assert!(text.contains("latency_seconds_bucket{le=\"1\"} 2\n"));
+        assert!(text.contains("latency_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("latency_seconds_sum 3.55\n"));
+        assert!(text.contains("latency_seconds_count 3\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "another type")]
+    fn rejects_a_name_reused_with_another_type() {
+        let registry = Registry::default();
+        registry.counter("jobs", "Jobs.", &[]);
+        registry.gauge("jobs", "Jobs.", &[]);
+    }
+}
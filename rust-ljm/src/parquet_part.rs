@@ -0,0 +1,636 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parquet::basic::{Compression, Encoding};
+use parquet::errors::{ParquetError, Result};
+use parquet::file::metadata::{
+    ColumnChunkMetaData, FileMetaData, KeyValue, ParquetMetaData, ParquetMetaDataWriter,
+    RowGroupMetaData,
+};
+use parquet::file::properties::{DEFAULT_CREATED_BY, WriterProperties, WriterPropertiesPtr};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::writer::{SerializedRowGroupWriter, TrackedWrite};
+use parquet::format::{self, PageHeader, PageType};
+use parquet::schema::types::{SchemaDescPtr, SchemaDescriptor, TypePtr};
+use parquet::thrift::TSerializable;
+use thrift::protocol::TCompactInputProtocol;
+use tracing::{info, warn};
+
+/// Appended to a part's final name while it is being written.
+pub const IN_PROGRESS_SUFFIX: &str = ".inprogress";
+/// Sidecar holding the footer metadata of an in-progress part.
+const METADATA_SUFFIX: &str = ".meta.json";
+/// Temporary name of a salvaged copy before it replaces the in-progress part.
+const SALVAGE_SUFFIX: &str = ".salvage";
+/// Directory under the parquet root that unrecoverable parts are moved to.
+pub const QUARANTINE_DIR: &str = "quarantine";
+const PARQUET_MAGIC: &[u8; 4] = b"PAR1";
+
+/// A parquet file written as `<path>.inprogress` and renamed to `path` once
+/// its footer is on disk, so a crash never leaves a footerless `.parquet`.
+///
+/// Each row group is flushed to the OS before `write_row_group` returns and is
+/// on disk once `sync` returns. Every column chunk is a single uncompressed PLAIN page, which lets
+/// [`recover`] rebuild a footer for the row groups of an interrupted part.
+pub struct PartWriter {
+    path: PathBuf,
+    in_progress: PathBuf,
+    buf: TrackedWrite<File>,
+    descr: SchemaDescPtr,
+    props: WriterPropertiesPtr,
+    row_groups: Vec<RowGroupMetaData>,
+    metadata: Vec<KeyValue>,
+    /// Row groups written since the last sync.
+    unsynced: bool,
+}
+
+impl PartWriter {
+    /// Starts a part that will end up at `path`. `metadata` goes into the
+    /// footer; a part salvaged by [`recover`] gets `metadata` followed by
+    /// `interrupted` instead of the entries appended before `close`.
+    pub fn create(
+        path: &Path,
+        schema: TypePtr,
+        metadata: Vec<KeyValue>,
+        interrupted: Vec<KeyValue>,
+    ) -> Result<Self> {
+        let in_progress = in_progress_path(path);
+        let sidecar: Vec<(String, Option<String>)> = metadata
+            .iter()
+            .chain(&interrupted)
+            .map(|kv| (kv.key.clone(), kv.value.clone()))
+            .collect();
+        let sidecar = serde_json::to_vec(&sidecar).map_err(|e| ParquetError::External(e.into()))?;
+        write_synced(&metadata_path(&in_progress), &sidecar)?;
+
+        let mut buf = TrackedWrite::new(File::create(&in_progress)?);
+        buf.write_all(PARQUET_MAGIC)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            in_progress,
+            buf,
+            descr: Arc::new(SchemaDescriptor::new(schema)),
+            props: Arc::new(writer_properties()),
+            row_groups: Vec::new(),
+            metadata,
+            unsynced: false,
+        })
+    }
+
+    /// Writes one row group through `write` and flushes it to the OS; call
+    /// `sync` before relying on it surviving a power loss.
+    pub fn write_row_group(
+        &mut self,
+        write: impl FnOnce(&mut SerializedRowGroupWriter<'_, File>) -> Result<()>,
+    ) -> Result<()> {
+        let ordinal = i16::try_from(self.row_groups.len())
+            .map_err(|_| ParquetError::General("too many row groups".to_string()))?;
+        let mut row_group = SerializedRowGroupWriter::new(
+            self.descr.clone(),
+            self.props.clone(),
+            &mut self.buf,
+            ordinal,
+            None,
+        );
+        write(&mut row_group)?;
+        let metadata = row_group.close()?;
+        self.row_groups.push(metadata.as_ref().clone());
+        self.buf.flush()?;
+        self.unsynced = true;
+        Ok(())
+    }
+
+    /// Syncs the row groups written so far to disk.
+    pub fn sync(&mut self) -> Result<()> {
+        if self.unsynced {
+            self.buf.inner().sync_data()?;
+            self.unsynced = false;
+        }
+        Ok(())
+    }
+
+    /// Whether every row group written so far is synced to disk.
+    pub fn is_synced(&self) -> bool {
+        !self.unsynced
+    }
+
+    pub fn append_key_value_metadata(&mut self, kv: KeyValue) {
+        self.metadata.push(kv);
+    }
+
+    /// Writes the footer, syncs the file and renames it into place. Returns
+    /// the final path, which gets a `-N` suffix if `path` already exists.
+    pub fn close(mut self) -> Result<PathBuf> {
+        let metadata = file_metadata(&self.descr, self.row_groups, self.metadata);
+        ParquetMetaDataWriter::new(&mut self.buf, &metadata).finish()?;
+        self.buf.flush()?;
+        self.buf.into_inner()?.sync_all()?;
+        let path = publish(&self.in_progress, &self.path)?;
+        remove_if_exists(&metadata_path(&self.in_progress))?;
+        Ok(path)
+    }
+}
+
+/// No dictionary, no compression and no page splitting, so each column chunk
+/// is one self-describing data page that [`recover`] can find by scanning.
+fn writer_properties() -> WriterProperties {
+    WriterProperties::builder()
+        .set_dictionary_enabled(false)
+        .set_compression(Compression::UNCOMPRESSED)
+        .set_data_page_size_limit(usize::MAX)
+        .set_data_page_row_count_limit(usize::MAX)
+        .build()
+}
+
+fn file_metadata(
+    descr: &SchemaDescPtr,
+    row_groups: Vec<RowGroupMetaData>,
+    metadata: Vec<KeyValue>,
+) -> ParquetMetaData {
+    let num_rows = row_groups.iter().map(RowGroupMetaData::num_rows).sum();
+    let file_metadata = FileMetaData::new(
+        1,
+        num_rows,
+        Some(DEFAULT_CREATED_BY.to_string()),
+        (!metadata.is_empty()).then_some(metadata),
+        descr.clone(),
+        None,
+    );
+    ParquetMetaData::new(file_metadata, row_groups)
+}
+
+pub fn in_progress_path(path: &Path) -> PathBuf {
+    append_to_name(path, IN_PROGRESS_SUFFIX)
+}
+
+fn metadata_path(in_progress: &Path) -> PathBuf {
+    append_to_name(in_progress, METADATA_SUFFIX)
+}
+
+fn append_to_name(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Renames `from` to `to`, or to the first free `<stem>-N.<ext>` when `to`
+/// exists, then syncs the directory so the rename survives a power loss.
+fn publish(from: &Path, to: &Path) -> io::Result<PathBuf> {
+    let mut target = to.to_path_buf();
+    let mut n = 1;
+    while target.exists() {
+        let stem = to.file_stem().and_then(|s| s.to_str()).unwrap_or("part");
+        let name = match to.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{stem}-{n}.{ext}"),
+            None => format!("{stem}-{n}"),
+        };
+        target = to.with_file_name(name);
+        n += 1;
+    }
+    fs::rename(from, &target)?;
+    if let Some(dir) = target.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(target)
+}
+
+fn write_synced(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// What [`recover`] did with the in-progress parts it found.
+#[derive(Debug, Default)]
+pub struct Recovery {
+    pub salvaged: Vec<Salvaged>,
+    /// Quarantined parts and why they could not be salvaged.
+    pub quarantined: Vec<(PathBuf, String)>,
+}
+
+#[derive(Debug)]
+pub struct Salvaged {
+    pub path: PathBuf,
+    pub row_groups: usize,
+    pub rows: i64,
+}
+
+/// Finds the in-progress parts a crash left under `root` and turns each into
+/// a readable `.parquet` holding its complete row groups, or moves it to
+/// `root/quarantine/` when nothing can be salvaged. `schema` is the schema
+/// every part under `root` was written with.
+pub fn recover(root: &Path, schema: &TypePtr) -> Recovery {
+    let mut recovery = Recovery::default();
+    let mut parts = Vec::new();
+    find_in_progress(root, &root.join(QUARANTINE_DIR), &mut parts);
+    parts.sort();
+    for part in parts {
+        match salvage(&part, schema) {
+            Ok(salvaged) => {
+                info!(
+                    "Salvaged {} row(s) in {} row group(s) from {} into {}",
+                    salvaged.rows,
+                    salvaged.row_groups,
+                    part.display(),
+                    salvaged.path.display()
+                );
+                recovery.salvaged.push(salvaged);
+            }
+            Err(reason) => match quarantine(root, &part) {
+                Ok(moved) => {
+                    warn!(
+                        "Quarantined {} as {}: {reason}",
+                        part.display(),
+                        moved.display()
+                    );
+                    recovery.quarantined.push((moved, reason));
+                }
+                Err(e) => warn!("Failed to quarantine {} ({reason}): {e}", part.display()),
+            },
+        }
+    }
+    recovery
+}
+
+fn find_in_progress(dir: &Path, skip: &Path, parts: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if path != skip {
+                find_in_progress(&path, skip, parts);
+            }
+        } else if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            if name.ends_with(&format!(".parquet{IN_PROGRESS_SUFFIX}")) {
+                parts.push(path);
+            } else if name.ends_with(SALVAGE_SUFFIX) {
+                // Left by a crash during a previous recovery; redone from the part.
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+fn salvage(part: &Path, schema: &TypePtr) -> std::result::Result<Salvaged, String> {
+    let sidecar = fs::read(metadata_path(part)).map_err(|e| format!("no footer metadata: {e}"))?;
+    let metadata: Vec<(String, Option<String>)> =
+        serde_json::from_slice(&sidecar).map_err(|e| format!("unreadable footer metadata: {e}"))?;
+    let final_path = PathBuf::from(
+        part.to_string_lossy()
+            .strip_suffix(IN_PROGRESS_SUFFIX)
+            .unwrap_or_default(),
+    );
+
+    // Interrupted between writing the footer and the rename.
+    let complete = File::open(part)
+        .map_err(ParquetError::from)
+        .and_then(SerializedFileReader::new)
+        .map(|reader| {
+            let metadata = reader.metadata();
+            (
+                metadata.num_row_groups(),
+                metadata.file_metadata().num_rows(),
+            )
+        });
+    if let Ok((row_groups, rows)) = complete {
+        let path = publish(part, &final_path).map_err(|e| e.to_string())?;
+        remove_if_exists(&metadata_path(part)).map_err(|e| e.to_string())?;
+        return Ok(Salvaged {
+            path,
+            row_groups,
+            rows,
+        });
+    }
+
+    let bytes = fs::read(part).map_err(|e| e.to_string())?;
+    if !bytes.starts_with(PARQUET_MAGIC) {
+        return Err("missing parquet header".to_string());
+    }
+    let descr: SchemaDescPtr = Arc::new(SchemaDescriptor::new(schema.clone()));
+    let (row_groups, end) = scan_row_groups(&bytes, &descr).map_err(|e| e.to_string())?;
+    if row_groups.is_empty() {
+        return Err("no complete row group".to_string());
+    }
+    let row_group_count = row_groups.len();
+    let rows: i64 = row_groups.iter().map(RowGroupMetaData::num_rows).sum();
+
+    let mut metadata: Vec<KeyValue> = metadata
+        .into_iter()
+        .map(|(key, value)| KeyValue { key, value })
+        .collect();
+    metadata.push(KeyValue::new("salvaged".to_string(), "true".to_string()));
+
+    let copy = append_to_name(&final_path, SALVAGE_SUFFIX);
+    let written = (|| -> Result<i64> {
+        let mut out = TrackedWrite::new(File::create(&copy)?);
+        out.write_all(&bytes[..end])?;
+        ParquetMetaDataWriter::new(&mut out, &file_metadata(&descr, row_groups, metadata))
+            .finish()?;
+        out.flush()?;
+        out.into_inner()?.sync_all()?;
+        // Read every row back before trusting the copy.
+        let reader = SerializedFileReader::new(File::open(&copy)?)?;
+        let mut read = 0;
+        for row in reader.get_row_iter(None)? {
+            row?;
+            read += 1;
+        }
+        Ok(read)
+    })();
+    match written {
+        Ok(read) if read == rows => {}
+        Ok(read) => {
+            let _ = fs::remove_file(&copy);
+            return Err(format!("salvaged copy holds {read} of {rows} rows"));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&copy);
+            return Err(format!("salvaged copy is unreadable: {e}"));
+        }
+    }
+
+    let path = publish(&copy, &final_path).map_err(|e| e.to_string())?;
+    fs::remove_file(part).map_err(|e| e.to_string())?;
+    remove_if_exists(&metadata_path(part)).map_err(|e| e.to_string())?;
+    Ok(Salvaged {
+        path,
+        row_groups: row_group_count,
+        rows,
+    })
+}
+
+/// Walks the data pages after the header, one page per column per row
+/// group, and returns the complete row groups and the offset they end at.
+fn scan_row_groups(bytes: &[u8], descr: &SchemaDescPtr) -> Result<(Vec<RowGroupMetaData>, usize)> {
+    let mut row_groups = Vec::new();
+    let mut offset = PARQUET_MAGIC.len();
+    'row_groups: loop {
+        let start = offset;
+        let mut columns = Vec::with_capacity(descr.num_columns());
+        let mut rows = None;
+        for column in descr.columns() {
+            let Some((header, header_len)) = read_page_header(&bytes[offset..]) else {
+                break 'row_groups;
+            };
+            let Some(data) = header.data_page_header.as_ref() else {
+                break 'row_groups;
+            };
+            let page_end = usize::try_from(header.compressed_page_size)
+                .ok()
+                .and_then(|len| (offset + header_len).checked_add(len));
+            let Some(page_end) = page_end.filter(|end| *end <= bytes.len()) else {
+                break 'row_groups;
+            };
+            if header.type_ != PageType::DATA_PAGE
+                || data.encoding != format::Encoding::PLAIN
+                || data.num_values <= 0
+                || rows.is_some_and(|rows| rows != data.num_values)
+            {
+                break 'row_groups;
+            }
+            rows = Some(data.num_values);
+            let size = (page_end - offset) as i64;
+            columns.push(
+                ColumnChunkMetaData::builder(column.clone())
+                    .set_encodings(vec![Encoding::PLAIN, Encoding::RLE])
+                    .set_compression(Compression::UNCOMPRESSED)
+                    .set_num_values(i64::from(data.num_values))
+                    .set_data_page_offset(offset as i64)
+                    .set_total_compressed_size(size)
+                    .set_total_uncompressed_size(size)
+                    .build()?,
+            );
+            offset = page_end;
+        }
+        let total_byte_size = (offset - start) as i64;
+        row_groups.push(
+            RowGroupMetaData::builder(descr.clone())
+                .set_num_rows(i64::from(rows.unwrap_or(0)))
+                .set_total_byte_size(total_byte_size)
+                .set_column_metadata(columns)
+                .set_ordinal(row_groups.len() as i16)
+                .set_file_offset(start as i64)
+                .build()?,
+        );
+    }
+    let end = row_groups
+        .last()
+        .map(|rg| (rg.file_offset().unwrap_or(0) + rg.total_byte_size()) as usize)
+        .unwrap_or(PARQUET_MAGIC.len());
+    Ok((row_groups, end))
+}
+
+fn read_page_header(bytes: &[u8]) -> Option<(PageHeader, usize)> {
+    let mut remaining = bytes;
+    let header =
+        PageHeader::read_from_in_protocol(&mut TCompactInputProtocol::new(&mut remaining)).ok()?;
+    Some((header, bytes.len() - remaining.len()))
+}
+
+/// Moves `part` and its metadata sidecar to the same relative path under
+/// `root/quarantine/`.
+fn quarantine(root: &Path, part: &Path) -> io::Result<PathBuf> {
+    let relative = part.strip_prefix(root).unwrap_or(part);
+    let target = root.join(QUARANTINE_DIR).join(relative);
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let moved = publish(part, &target)?;
+    let sidecar = metadata_path(part);
+    if sidecar.exists() {
+        fs::rename(&sidecar, metadata_path(&moved))?;
+    }
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::column::writer::ColumnWriter;
+    use parquet::schema::parser::parse_message_type;
+
+    fn temp_root() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("rust-ljm-parts-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join("asset001/2026-10-17/ch00")).unwrap();
+        root
+    }
+
+    fn schema() -> TypePtr {
+        let message_type = "
+            message schema {
+                REQUIRED INT64 timestamp_unix_ns;
+                REQUIRED DOUBLE value;
+            }
+        ";
+        Arc::new(parse_message_type(message_type).unwrap())
+    }
+
+    fn kv(key: &str, value: &str) -> KeyValue {
+        KeyValue::new(key.to_string(), value.to_string())
+    }
+
+    fn start_part(path: &Path) -> PartWriter {
+        PartWriter::create(
+            path,
+            schema(),
+            vec![kv("calibration", "{}")],
+            vec![kv("clock_quality", "unknown")],
+        )
+        .unwrap()
+    }
+
+    fn write_rows(writer: &mut PartWriter, first: i64, rows: i64) {
+        writer
+            .write_row_group(|rg| {
+                let mut column = rg.next_column()?.unwrap();
+                if let ColumnWriter::Int64ColumnWriter(typed) = column.untyped() {
+                    let values: Vec<i64> = (first..first + rows).collect();
+                    typed.write_batch(&values, None, None)?;
+                }
+                column.close()?;
+                let mut column = rg.next_column()?.unwrap();
+                if let ColumnWriter::DoubleColumnWriter(typed) = column.untyped() {
+                    let values: Vec<f64> = (first..first + rows).map(|v| v as f64).collect();
+                    typed.write_batch(&values, None, None)?;
+                }
+                column.close()
+            })
+            .unwrap();
+    }
+
+    fn read_back(path: &Path) -> (i64, Vec<(String, Option<String>)>) {
+        let reader = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+            .len() as i64;
+        let metadata = reader
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .map(|kvs| {
+                kvs.iter()
+                    .map(|kv| (kv.key.clone(), kv.value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        (rows, metadata)
+    }
+
+    fn has(metadata: &[(String, Option<String>)], key: &str, value: &str) -> bool {
+        metadata
+            .iter()
+            .any(|(k, v)| k == key && v.as_deref() == Some(value))
+    }
+
+    #[test]
+    fn close_renames_part_into_place() {
+        let root = temp_root();
+        let path = root.join("asset001/2026-10-17/ch00/part-0001.parquet");
+        let mut writer = start_part(&path);
+        assert!(in_progress_path(&path).exists());
+        write_rows(&mut writer, 0, 1000);
+        write_rows(&mut writer, 1000, 250);
+        writer.append_key_value_metadata(kv("clock_quality", "synced"));
+
+        assert_eq!(writer.close().unwrap(), path);
+        assert!(!in_progress_path(&path).exists());
+        assert!(!metadata_path(&in_progress_path(&path)).exists());
+        let (rows, metadata) = read_back(&path);
+        assert_eq!(rows, 1250);
+        assert!(has(&metadata, "calibration", "{}"));
+        assert!(has(&metadata, "clock_quality", "synced"));
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn row_groups_are_synced_on_request() {
+        let root = temp_root();
+        let path = root.join("asset001/2026-10-17/ch00/part-0001.parquet");
+        let mut writer = start_part(&path);
+        assert!(writer.is_synced());
+        write_rows(&mut writer, 0, 1000);
+        write_rows(&mut writer, 1000, 1000);
+        assert!(!writer.is_synced());
+        writer.sync().unwrap();
+        assert!(writer.is_synced());
+        writer.close().unwrap();
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn recover_salvages_complete_row_groups_of_a_torn_part() {
+        let root = temp_root();
+        let path = root.join("asset001/2026-10-17/ch00/part-0001.parquet");
+        let mut writer = start_part(&path);
+        write_rows(&mut writer, 0, 1000);
+        write_rows(&mut writer, 1000, 500);
+        drop(writer);
+        // A row group cut off mid-page by the crash.
+        let mut part = fs::OpenOptions::new()
+            .append(true)
+            .open(in_progress_path(&path))
+            .unwrap();
+        let bytes = fs::read(in_progress_path(&path)).unwrap();
+        part.write_all(&bytes[4..40]).unwrap();
+        drop(part);
+
+        let recovery = recover(&root, &schema());
+        assert!(recovery.quarantined.is_empty());
+        assert_eq!(recovery.salvaged.len(), 1);
+        assert_eq!(recovery.salvaged[0].path, path);
+        assert_eq!(recovery.salvaged[0].row_groups, 2);
+        assert_eq!(recovery.salvaged[0].rows, 1500);
+        assert!(!in_progress_path(&path).exists());
+
+        let (rows, metadata) = read_back(&path);
+        assert_eq!(rows, 1500);
+        assert!(has(&metadata, "calibration", "{}"));
+        assert!(has(&metadata, "clock_quality", "unknown"));
+        assert!(has(&metadata, "salvaged", "true"));
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn recover_quarantines_parts_with_nothing_to_salvage() {
+        let root = temp_root();
+        let empty = root.join("asset001/2026-10-17/ch00/part-0001.parquet");
+        drop(start_part(&empty));
+        let orphan = root.join("asset001/2026-10-17/ch00/part-0002.parquet");
+        fs::write(in_progress_path(&orphan), b"PAR1").unwrap();
+
+        let recovery = recover(&root, &schema());
+        assert!(recovery.salvaged.is_empty());
+        let reasons: Vec<&str> = recovery
+            .quarantined
+            .iter()
+            .map(|(_, r)| r.as_str())
+            .collect();
+        assert_eq!(reasons.len(), 2);
+        assert_eq!(reasons[0], "no complete row group");
+        assert!(reasons[1].starts_with("no footer metadata"));
+
+        let quarantined =
+            root.join("quarantine/asset001/2026-10-17/ch00/part-0001.parquet.inprogress");
+        assert_eq!(recovery.quarantined[0].0, quarantined);
+        assert!(metadata_path(&quarantined).exists());
+        assert!(!in_progress_path(&empty).exists());
+        // Quarantined parts are not picked up again.
+        let again = recover(&root, &schema());
+        assert!(again.salvaged.is_empty() && again.quarantined.is_empty());
+        fs::remove_dir_all(root).ok();
+    }
+}
@@ -0,0 +1,783 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_nats::header::NATS_MESSAGE_ID;
+use async_nats::{HeaderMap, jetstream};
+use bytes::Bytes;
+use futures_util::future::join_all;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::metrics::{self, Histogram};
+use crate::spool::{Spool, SpoolPosition, SpooledMessage};
+
+const DEFAULT_QUEUE_CAPACITY: usize = 4096;
+const DEFAULT_MAX_IN_FLIGHT: usize = 256;
+const DEFAULT_MAX_RETRIES: u32 = 5;
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 100;
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(5);
+const REPLAY_POLL: Duration = Duration::from_millis(250);
+const MAX_REPLAY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// One JetStream publish. Retries resend the same message, so the server can
+/// dedupe on `msg_id` if an earlier attempt landed but its ack was lost.
+#[derive(Debug, Clone)]
+pub struct OutboundMessage {
+    pub subject: String,
+    pub msg_id: String,
+    pub payload: Bytes,
+}
+
+/// Deterministic `Nats-Msg-Id` for one published batch: the streamer run,
+/// the channel token (`ch11`, or `frame`) and the batch `sequence`. A retry or
+/// spool replay of the same batch always carries the same id.
+pub fn message_id(run_id: &str, stream: &str, sequence: u64) -> String {
+    format!("{run_id}.{stream}.{sequence}")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublisherConfig {
+    /// Messages waiting to be published before new ones are dropped.
+    pub queue_capacity: usize,
+    /// Publishes awaiting a PubAck at the same time.
+    pub max_in_flight: usize,
+    /// Attempts after the first before a message is dropped.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each further attempt.
+    pub retry_backoff: Duration,
+}
+
+impl Default for PublisherConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF_MS),
+        }
+    }
+}
+
+pub fn env_number<T: std::str::FromStr + PartialOrd>(
+    name: &str,
+    default: T,
+    min: T,
+) -> Result<T, String> {
+    match std::env::var(name) {
+        Ok(raw) if !raw.trim().is_empty() => raw
+            .trim()
+            .parse::<T>()
+            .ok()
+            .filter(|value| *value >= min)
+            .ok_or_else(|| format!("invalid {name} '{raw}', expected an integer")),
+        _ => Ok(default),
+    }
+}
+
+impl PublisherConfig {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Self {
+            queue_capacity: env_number("STREAM_PUBLISH_QUEUE", DEFAULT_QUEUE_CAPACITY, 1)?,
+            max_in_flight: env_number("STREAM_PUBLISH_MAX_IN_FLIGHT", DEFAULT_MAX_IN_FLIGHT, 1)?,
+            max_retries: env_number("STREAM_PUBLISH_MAX_RETRIES", DEFAULT_MAX_RETRIES, 0)?,
+            retry_backoff: Duration::from_millis(env_number(
+                "STREAM_PUBLISH_RETRY_MS",
+                DEFAULT_RETRY_BACKOFF_MS,
+                1,
+            )?),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct PublishCounters {
+    enqueued: AtomicU64,
+    acked: AtomicU64,
+    retried: AtomicU64,
+    dropped: AtomicU64,
+    spooled: AtomicU64,
+    replayed: AtomicU64,
+    evicted: AtomicU64,
+}
+
+/// Snapshot of the publisher counters since the streamer started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PublishStats {
+    pub enqueued: u64,
+    pub acked: u64,
+    pub retried: u64,
+    /// Messages lost without reaching the spool: a full queue, exhausted
+    /// retries with the spool disabled, or a failed spool write.
+    pub dropped: u64,
+    pub spooled: u64,
+    pub replayed: u64,
+    /// Unsent spooled messages evicted to keep the spool under its cap.
+    pub evicted: u64,
+    pub spool_pending: u64,
+}
+
+/// State shared by the `Publisher` handle and its pipeline task.
+struct Shared {
+    counters: PublishCounters,
+    /// Mirrors `Spool::pending` after each spool write or commit, so stats
+    /// never wait on spool IO.
+    spool_pending: AtomicU64,
+    /// Dequeue to PubAck, retries included.
+    latency: Arc<Histogram>,
+}
+
+impl Shared {
+    fn snapshot(&self) -> PublishStats {
+        let counters = &self.counters;
+        PublishStats {
+            enqueued: counters.enqueued.load(Ordering::Relaxed),
+            acked: counters.acked.load(Ordering::Relaxed),
+            retried: counters.retried.load(Ordering::Relaxed),
+            dropped: counters.dropped.load(Ordering::Relaxed),
+            spooled: counters.spooled.load(Ordering::Relaxed),
+            replayed: counters.replayed.load(Ordering::Relaxed),
+            evicted: counters.evicted.load(Ordering::Relaxed),
+            spool_pending: self.spool_pending.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Bounded, pipelined JetStream publisher.
+///
+/// `enqueue` never waits and never touches the disk: the read loop hands
+/// messages to a queue and a background task keeps up to `max_in_flight` of
+/// them awaiting PubAcks. A slow or unreachable server therefore fills the
+/// queue instead of stalling `stream_read` until the LabJack buffer overflows.
+///
+/// With a spool, the pipeline task switches to spooling when a message runs
+/// out of retries or the queue backs up. It first stops dequeuing until every
+/// in-flight publish has settled, spools the failures in the order they were
+/// enqueued, and then drains the queue into the spool behind them until
+/// replay catches up, so the spool always replays in the original order.
+pub struct Publisher {
+    tx: mpsc::Sender<OutboundMessage>,
+    shared: Arc<Shared>,
+    saturated: AtomicBool,
+    queue_capacity: usize,
+    handle: JoinHandle<()>,
+}
+
+impl Publisher {
+    pub fn spawn(js: jetstream::Context, config: PublisherConfig, spool: Option<Spool>) -> Self {
+        Self::spawn_with(config, spool, move |msg: OutboundMessage| {
+            let js = js.clone();
+            async move {
+                let mut headers = HeaderMap::new();
+                headers.insert(NATS_MESSAGE_ID, msg.msg_id.as_str());
+                let ack = js
+                    .publish_with_headers(msg.subject, headers, msg.payload)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                ack.await.map(|_| ()).map_err(|e| e.to_string())
+            }
+        })
+    }
+
+    fn spawn_with<F, Fut>(config: PublisherConfig, spool: Option<Spool>, send: F) -> Self
+    where
+        F: Fn(OutboundMessage) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(config.queue_capacity);
+        let shared = Arc::new(Shared {
+            counters: PublishCounters::default(),
+            spool_pending: AtomicU64::new(spool.as_ref().map(Spool::pending).unwrap_or(0)),
+            latency: metrics::global().histogram(
+                "streamer_publish_latency_seconds",
+                "Time from leaving the publish queue to the JetStream PubAck, retries included.",
+                &[],
+                metrics::LATENCY_BUCKETS,
+            ),
+        });
+        let spool = spool.map(|spool| Arc::new(Mutex::new(spool)));
+        let handle = tokio::spawn(run_pipeline(
+            rx,
+            Arc::new(send),
+            config,
+            shared.clone(),
+            spool,
+        ));
+        Self {
+            tx,
+            shared,
+            saturated: AtomicBool::new(false),
+            queue_capacity: config.queue_capacity,
+            handle,
+        }
+    }
+
+    /// Queues `msg` for publishing. When the queue is full the message is
+    /// counted as dropped and handed back.
+    pub fn enqueue(&self, msg: OutboundMessage) -> Result<(), OutboundMessage> {
+        match self.tx.try_send(msg) {
+            Ok(()) => {
+                self.shared
+                    .counters
+                    .enqueued
+                    .fetch_add(1, Ordering::Relaxed);
+                if self.tx.capacity() > self.queue_capacity / 2
+                    && self.saturated.swap(false, Ordering::Relaxed)
+                {
+                    let stats = self.stats();
+                    info!(
+                        "Queue drained; publishing resumed ({} dropped, {} spooled so far)",
+                        stats.dropped, stats.spooled
+                    );
+                }
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Full(msg) | mpsc::error::TrySendError::Closed(msg)) => {
+                if !self.saturated.swap(true, Ordering::Relaxed) {
+                    warn!(
+                        "Queue full ({} messages); dropping scans until it drains",
+                        self.queue_capacity
+                    );
+                }
+                self.shared.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                Err(msg)
+            }
+        }
+    }
+
+    pub fn stats(&self) -> PublishStats {
+        self.shared.snapshot()
+    }
+
+    /// Exposes the counters on `/metrics`, read at scrape time.
+    pub fn register_metrics(&self) {
+        let registry = metrics::global();
+        for outcome in [
+            "enqueued", "acked", "retried", "dropped", "spooled", "replayed", "evicted",
+        ] {
+            let shared = self.shared.clone();
+            registry.counter_fn(
+                "streamer_publish_messages_total",
+                "JetStream messages by publisher outcome since the streamer started.",
+                &[("outcome", outcome)],
+                move || {
+                    let counters = &shared.counters;
+                    let counter = match outcome {
+                        "enqueued" => &counters.enqueued,
+                        "acked" => &counters.acked,
+                        "retried" => &counters.retried,
+                        "dropped" => &counters.dropped,
+                        "spooled" => &counters.spooled,
+                        "replayed" => &counters.replayed,
+                        _ => &counters.evicted,
+                    };
+                    counter.load(Ordering::Relaxed) as f64
+                },
+            );
+        }
+        let shared = self.shared.clone();
+        registry.gauge_fn(
+            "streamer_spool_pending_messages",
+            "Spooled messages not yet replayed.",
+            &[],
+            move || shared.spool_pending.load(Ordering::Relaxed) as f64,
+        );
+    }
+
+    /// Stops accepting messages, waits for queued and in-flight ones to be
+    /// acked, spooled or dropped, and returns the final counters. Anything
+    /// left in the spool is replayed on the next start.
+    pub async fn close(self) -> PublishStats {
+        let Self {
+            tx, shared, handle, ..
+        } = self;
+        drop(tx);
+        let _ = handle.await;
+        shared.snapshot()
+    }
+}
+
+async fn deliver<F, Fut>(
+    send: Arc<F>,
+    msg: OutboundMessage,
+    config: PublisherConfig,
+    shared: Arc<Shared>,
+) -> Result<(), (OutboundMessage, String)>
+where
+    F: Fn(OutboundMessage) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let mut backoff = config.retry_backoff;
+    let mut attempt = 0;
+    let started = Instant::now();
+    loop {
+        match send(msg.clone()).await {
+            Ok(()) => {
+                shared.latency.observe(started.elapsed().as_secs_f64());
+                return Ok(());
+            }
+            Err(err) if attempt >= config.max_retries => return Err((msg, err)),
+            Err(_) => {
+                attempt += 1;
+                shared.counters.retried.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+            }
+        }
+    }
+}
+
+/// Runs `op` against the spool on the blocking pool, so disk reads and
+/// writes never hold up a runtime worker.
+async fn spool_io<T, F>(spool: &Arc<Mutex<Spool>>, op: F) -> T
+where
+    F: FnOnce(&mut Spool) -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let spool = spool.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut spool = spool
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        op(&mut spool)
+    })
+    .await
+    .expect("spool task panicked")
+}
+
+/// Appends `messages` to the spool in order and syncs it once after the last
+/// one, so the batch survives a power loss. A message whose write fails is
+/// counted as dropped.
+async fn spool_messages(
+    spool: &Arc<Mutex<Spool>>,
+    messages: Vec<OutboundMessage>,
+    shared: &Shared,
+) {
+    let (results, synced, pending) = spool_io(spool, move |spool| {
+        let results: Vec<_> = messages
+            .into_iter()
+            .map(|msg| spool.append(&msg).map_err(|e| (msg.msg_id, e)))
+            .collect();
+        (results, spool.sync(), spool.pending())
+    })
+    .await;
+    if let Err(e) = synced {
+        error!("Failed to sync spool: {}", e);
+    }
+    shared.spool_pending.store(pending, Ordering::Relaxed);
+    for result in results {
+        match result {
+            Ok(evicted) => {
+                shared.counters.spooled.fetch_add(1, Ordering::Relaxed);
+                if evicted > 0 {
+                    shared.counters.evicted.fetch_add(evicted, Ordering::Relaxed);
+                    warn!("Size cap reached; evicted {evicted} oldest unsent message(s)");
+                }
+            }
+            Err((msg_id, e)) => {
+                shared.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                error!("Failed to spool message {}: {}", msg_id, e);
+            }
+        }
+    }
+}
+
+struct ReplayOutcome {
+    next: SpoolPosition,
+    messages: u64,
+    error: Option<String>,
+}
+
+/// Sends one batch from the spool head concurrently. The batch only counts
+/// as replayed if every message was acked; otherwise it is resent later and
+/// JetStream drops the copies that did land by their `Nats-Msg-Id`.
+async fn replay_batch<F, Fut>(send: Arc<F>, batch: Vec<SpooledMessage>) -> ReplayOutcome
+where
+    F: Fn(OutboundMessage) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let next = batch.last().map(|m| m.next).unwrap_or_default();
+    let messages = batch.len() as u64;
+    let results = join_all(batch.into_iter().map(|m| send(m.message))).await;
+    ReplayOutcome {
+        next,
+        messages,
+        error: results.into_iter().find_map(Result::err),
+    }
+}
+
+async fn next_replay_batch(spool: &Arc<Mutex<Spool>>, max: usize) -> Option<Vec<SpooledMessage>> {
+    match spool_io(spool, move |spool| spool.peek(max)).await {
+        Ok(batch) if !batch.is_empty() => Some(batch),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Failed to read spooled messages: {}", e);
+            None
+        }
+    }
+}
+
+async fn run_pipeline<F, Fut>(
+    mut rx: mpsc::Receiver<OutboundMessage>,
+    send: Arc<F>,
+    config: PublisherConfig,
+    shared: Arc<Shared>,
+    spool: Option<Arc<Mutex<Spool>>>,
+) where
+    F: Fn(OutboundMessage) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let mut in_flight = FuturesUnordered::new();
+    let mut replays = FuturesUnordered::new();
+    let mut open = true;
+    let mut replay_at = Instant::now();
+    let mut replay_backoff = config.retry_backoff;
+    let mut replay_failing = false;
+    let mut ticker = tokio::time::interval(REPLAY_POLL);
+    // Dequeue order of each publish, so failures that settle out of order
+    // are still spooled oldest first.
+    let mut dequeued: u64 = 0;
+    let mut failed: Vec<(u64, OutboundMessage, String)> = Vec::new();
+    // Set when the spool has to take over: nothing more is dequeued until
+    // every in-flight publish has settled and its failures are spooled.
+    let mut holding = false;
+    // While set, the queue drains into the spool instead of being published.
+    let mut spooling = shared.spool_pending.load(Ordering::Relaxed) > 0;
+
+    if spooling {
+        info!(
+            "{} spooled message(s) waiting to be replayed",
+            shared.spool_pending.load(Ordering::Relaxed)
+        );
+    }
+
+    while open || !in_flight.is_empty() {
+        tokio::select! {
+            Some((index, result)) = in_flight.next(), if !in_flight.is_empty() => {
+                match result {
+                    Ok(()) => {
+                        shared.counters.acked.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err((msg, err)) if spool.is_some() => {
+                        failed.push((index, msg, err));
+                        holding = true;
+                    }
+                    Err((msg, err)) => {
+                        shared.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                        error!(
+                            "Dropping message {} for '{}' after retries: {}",
+                            msg.msg_id, msg.subject, err
+                        );
+                    }
+                }
+            }
+            maybe = rx.recv(), if open && !holding && (spooling || in_flight.len() < config.max_in_flight) => {
+                match (maybe, spool.as_ref()) {
+                    (Some(msg), Some(spool)) if spooling => {
+                        let mut batch = vec![msg];
+                        while batch.len() < config.max_in_flight
+                            && let Ok(msg) = rx.try_recv()
+                        {
+                            batch.push(msg);
+                        }
+                        spool_messages(spool, batch, &shared).await;
+                    }
+                    (Some(msg), spool) => {
+                        let index = dequeued;
+                        dequeued += 1;
+                        let delivery = deliver(send.clone(), msg, config, shared.clone());
+                        in_flight.push(async move { (index, delivery.await) });
+                        if spool.is_some()
+                            && in_flight.len() >= config.max_in_flight
+                            && rx.len() >= config.queue_capacity / 2
+                        {
+                            warn!(
+                                "Publishes are backing up ({} queued); spooling scans to disk until replay catches up",
+                                rx.len()
+                            );
+                            holding = true;
+                        }
+                    }
+                    (None, _) => open = false,
+                }
+            }
+            Some(outcome) = replays.next(), if !replays.is_empty() => {
+                let outcome: ReplayOutcome = outcome;
+                let Some(spool) = spool.as_ref() else { continue };
+                match outcome.error {
+                    None => {
+                        let next = outcome.next;
+                        let (committed, pending) =
+                            spool_io(spool, move |spool| (spool.commit(next), spool.pending())).await;
+                        if let Err(e) = committed {
+                            warn!("Failed to commit replayed messages: {}", e);
+                        }
+                        shared.spool_pending.store(pending, Ordering::Relaxed);
+                        shared.counters.replayed.fetch_add(outcome.messages, Ordering::Relaxed);
+                        replay_backoff = config.retry_backoff;
+                        if replay_failing {
+                            replay_failing = false;
+                            info!("Replay resumed");
+                        }
+                        if let Some(batch) = next_replay_batch(spool, config.max_in_flight).await {
+                            replays.push(replay_batch(send.clone(), batch));
+                        } else {
+                            spooling = false;
+                            info!(
+                                "Spool drained ({} message(s) replayed so far)",
+                                shared.counters.replayed.load(Ordering::Relaxed)
+                            );
+                        }
+                    }
+                    Some(err) => {
+                        if !replay_failing {
+                            replay_failing = true;
+                            warn!("Replay failed, retrying with backoff: {}", err);
+                        }
+                        replay_at = Instant::now() + replay_backoff;
+                        replay_backoff = (replay_backoff * 2).min(MAX_REPLAY_BACKOFF);
+                    }
+                }
+            }
+            _ = ticker.tick(), if replays.is_empty() => {
+                if spooling
+                    && Instant::now() >= replay_at
+                    && let Some(spool) = spool.as_ref()
+                {
+                    match next_replay_batch(spool, config.max_in_flight).await {
+                        Some(batch) => replays.push(replay_batch(send.clone(), batch)),
+                        None => spooling = false,
+                    }
+                }
+            }
+        }
+
+        if holding
+            && in_flight.is_empty()
+            && let Some(spool) = spool.as_ref()
+        {
+            holding = false;
+            spooling = true;
+            failed.sort_by_key(|(index, _, _)| *index);
+            for (_, msg, err) in &failed {
+                warn!(
+                    "Spooling message {} for '{}' after retries: {}",
+                    msg.msg_id, msg.subject, err
+                );
+            }
+            let messages = failed.drain(..).map(|(_, msg, _)| msg).collect();
+            spool_messages(spool, messages, &shared).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> PublisherConfig {
+        PublisherConfig {
+            queue_capacity: 8,
+            max_in_flight: 4,
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(1),
+        }
+    }
+
+    fn message(id: &str) -> OutboundMessage {
+        OutboundMessage {
+            subject: "avenars.v1.box.lj.ch00".to_string(),
+            msg_id: id.to_string(),
+            payload: Bytes::from_static(b"scan"),
+        }
+    }
+
+    #[test]
+    fn message_ids_are_stable_per_run_channel_and_sequence() {
+        assert_eq!(message_id("0f3c", "ch11", 42), "0f3c.ch11.42");
+        assert_eq!(message_id("0f3c", "ch11", 42), message_id("0f3c", "ch11", 42));
+        assert_ne!(message_id("0f3c", "ch11", 42), message_id("0f3c", "ch13", 42));
+    }
+
+    #[tokio::test]
+    async fn retries_reuse_message_id_until_acked() {
+        let attempts: Arc<Mutex<Vec<String>>> = Arc::default();
+        let seen = attempts.clone();
+        let publisher = Publisher::spawn_with(test_config(), None, move |msg: OutboundMessage| {
+            let seen = seen.clone();
+            async move {
+                let mut seen = seen.lock().unwrap();
+                seen.push(msg.msg_id);
+                if seen.len() < 3 {
+                    Err("timed out".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        });
+
+        publisher.enqueue(message("run-1")).unwrap();
+        let stats = publisher.close().await;
+
+        assert_eq!(*attempts.lock().unwrap(), vec!["run-1"; 3]);
+        assert_eq!(
+            stats,
+            PublishStats {
+                enqueued: 1,
+                acked: 1,
+                retried: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn drops_after_retries_are_exhausted() {
+        let publisher = Publisher::spawn_with(test_config(), None, |_msg: OutboundMessage| async {
+            Err("no responders".to_string())
+        });
+
+        publisher.enqueue(message("a")).unwrap();
+        let stats = publisher.close().await;
+
+        assert_eq!(stats.acked, 0);
+        assert_eq!(stats.retried, 2);
+        assert_eq!(stats.dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn full_queue_drops_without_waiting() {
+        let config = PublisherConfig {
+            queue_capacity: 1,
+            ..test_config()
+        };
+        let publisher =
+            Publisher::spawn_with(config, None, |_msg: OutboundMessage| async { Ok(()) });
+
+        // The pipeline task has not run yet on this single-threaded runtime,
+        // so only the first message fits.
+        assert!(publisher.enqueue(message("a")).is_ok());
+        assert!(publisher.enqueue(message("b")).is_err());
+        assert!(publisher.enqueue(message("c")).is_err());
+
+        let stats = publisher.close().await;
+        assert_eq!(
+            stats,
+            PublishStats {
+                enqueued: 1,
+                acked: 1,
+                dropped: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn spooled_messages_replay_in_order_after_recovery() {
+        let dir = std::env::temp_dir().join(format!("rust-ljm-publisher-{}", uuid::Uuid::new_v4()));
+        let spool = Spool::open(&crate::spool::SpoolConfig {
+            dir: dir.clone(),
+            max_bytes: 1024 * 1024,
+        })
+        .unwrap();
+        let down = Arc::new(AtomicBool::new(true));
+        let delivered: Arc<Mutex<Vec<String>>> = Arc::default();
+        let config = PublisherConfig {
+            max_retries: 0,
+            ..test_config()
+        };
+        let (server_down, log) = (down.clone(), delivered.clone());
+        let publisher = Publisher::spawn_with(config, Some(spool), move |msg: OutboundMessage| {
+            let (server_down, log) = (server_down.clone(), log.clone());
+            async move {
+                if server_down.load(Ordering::Relaxed) {
+                    return Err("no responders".to_string());
+                }
+                log.lock().unwrap().push(msg.msg_id);
+                Ok(())
+            }
+        });
+
+        publisher.enqueue(message("0")).unwrap();
+        while publisher.stats().spooled == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        // Once something is spooled, later messages queue up behind it.
+        publisher.enqueue(message("1")).unwrap();
+        publisher.enqueue(message("2")).unwrap();
+        while publisher.stats().spool_pending < 3 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        down.store(false, Ordering::Relaxed);
+        while publisher.stats().spool_pending > 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let stats = publisher.close().await;
+
+        assert_eq!(*delivered.lock().unwrap(), vec!["0", "1", "2"]);
+        assert_eq!(stats.spooled, 3);
+        assert_eq!(stats.replayed, 3);
+        assert_eq!(stats.dropped, 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn in_flight_failures_are_spooled_ahead_of_queued_messages() {
+        let dir = std::env::temp_dir().join(format!("rust-ljm-publisher-{}", uuid::Uuid::new_v4()));
+        let spool = Spool::open(&crate::spool::SpoolConfig {
+            dir: dir.clone(),
+            max_bytes: 1024 * 1024,
+        })
+        .unwrap();
+        let down = Arc::new(AtomicBool::new(true));
+        let delivered: Arc<Mutex<Vec<String>>> = Arc::default();
+        let config = PublisherConfig {
+            max_retries: 0,
+            ..test_config()
+        };
+        let (server_down, log) = (down.clone(), delivered.clone());
+        let publisher = Publisher::spawn_with(config, Some(spool), move |msg: OutboundMessage| {
+            let (server_down, log) = (server_down.clone(), log.clone());
+            async move {
+                if server_down.load(Ordering::Relaxed) {
+                    // The oldest publish is the last one to give up.
+                    if msg.msg_id == "a" {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                    }
+                    return Err("no responders".to_string());
+                }
+                log.lock().unwrap().push(msg.msg_id);
+                Ok(())
+            }
+        });
+
+        for id in ["a", "b", "c", "d"] {
+            publisher.enqueue(message(id)).unwrap();
+        }
+        // b, c and d have failed while a is still in flight; e has to wait
+        // in the queue rather than overtake a in the spool.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        publisher.enqueue(message("e")).unwrap();
+        while publisher.stats().spool_pending < 5 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        down.store(false, Ordering::Relaxed);
+        while publisher.stats().spool_pending > 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let stats = publisher.close().await;
+
+        assert_eq!(*delivered.lock().unwrap(), vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(stats.spooled, 5);
+        assert_eq!(stats.replayed, 5);
+        assert_eq!(stats.dropped, 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,362 @@
+#![allow(dead_code)]
+
+use flatbuffers::FlatBufferBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::sample_data_generated::sampler::{self, ScanFrameArgs};
+use crate::subjects;
+
+/// How a source publishes its raw stream batches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishMode {
+    /// One `Scan` per channel and batch on the `.chNN` subjects.
+    #[default]
+    Channel,
+    /// One `ScanFrame` per batch with every channel on the `.frame` subject.
+    Frame,
+}
+
+/// Identity stamped on every published `ScanFrame`. `channel_names`, `units`
+/// and `calibration_ids` line up with `channels`.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameIdentity<'a> {
+    /// AIN numbers, -1 for digital channels.
+    pub channels: &'a [i16],
+    /// Subject tokens such as `ch11` or `ctr1`.
+    pub channel_names: &'a [&'a str],
+    pub run_id: &'a str,
+    pub device_serial: i32,
+    pub units: &'a [&'a str],
+    /// Set for calibrated companion frames only.
+    pub calibration_ids: Option<&'a [&'a str]>,
+    /// Host clock quality and error bound when the batch was timed.
+    pub clock_quality: &'a str,
+    pub clock_error_bound_ns: i64,
+    /// Set for frames of a burst or triggered capture.
+    pub capture: Option<FrameCapture<'a>>,
+}
+
+/// Capture a `.capture` frame belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameCapture<'a> {
+    pub id: &'a str,
+    /// Scans in the whole capture, across all of its frames.
+    pub scans: u64,
+    pub pre_trigger_scans: u64,
+}
+
+/// A decoded `.capture` frame: which capture it belongs to plus its columns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureFrame {
+    pub capture_id: String,
+    pub capture_scans: u64,
+    pub pre_trigger_scans: u64,
+    pub columns: Vec<(String, ChannelSamples)>,
+}
+
+/// One channel's samples, decoded from either a `Scan` or a column of a
+/// `ScanFrame`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelSamples {
+    /// Streamer run the batch belongs to; `None` for payloads from older
+    /// streamers.
+    pub run_id: Option<String>,
+    pub sequence: u64,
+    pub first_sample_unix_ns: u64,
+    pub sample_interval_ns: u64,
+    pub values: Vec<f64>,
+    /// Host clock quality the streamer reported; `None` from older streamers.
+    pub clock_quality: Option<String>,
+    /// Host clock error bound, `-1` when unknown.
+    pub clock_error_bound_ns: i64,
+}
+
+fn create_strings<'b>(
+    builder: &mut FlatBufferBuilder<'b>,
+    strings: &[&str],
+) -> flatbuffers::WIPOffset<flatbuffers::Vector<'b, flatbuffers::ForwardsUOffset<&'b str>>> {
+    let offsets: Vec<_> = strings.iter().map(|s| builder.create_string(s)).collect();
+    builder.create_vector(&offsets)
+}
+
+/// Encodes an interleaved batch (`values[scan * channels.len() + i]`, the
+/// layout `stream_read` returns) as a single `ScanFrame`.
+pub fn encode_frame(
+    builder: &mut FlatBufferBuilder,
+    first_sample_unix_ns: u64,
+    sample_interval_ns: u64,
+    actual_scan_rate_hz: f64,
+    sequence: u64,
+    values: &[f64],
+    identity: &FrameIdentity,
+) -> Vec<u8> {
+    builder.reset();
+    let channels = builder.create_vector(identity.channels);
+    let channel_names = create_strings(builder, identity.channel_names);
+    let values = builder.create_vector(values);
+    let run_id = builder.create_string(identity.run_id);
+    let units = create_strings(builder, identity.units);
+    let calibration_ids = identity
+        .calibration_ids
+        .map(|ids| create_strings(builder, ids));
+    let clock_quality = builder.create_string(identity.clock_quality);
+    let capture_id = identity
+        .capture
+        .map(|capture| builder.create_string(capture.id));
+    let frame_args = ScanFrameArgs {
+        first_sample_unix_ns,
+        sample_interval_ns,
+        actual_scan_rate_hz,
+        sequence,
+        channels: Some(channels),
+        values: Some(values),
+        run_id: Some(run_id),
+        device_serial: identity.device_serial,
+        units: Some(units),
+        calibration_ids,
+        calibrated: identity.calibration_ids.is_some(),
+        clock_quality: Some(clock_quality),
+        clock_error_bound_ns: identity.clock_error_bound_ns,
+        channel_names: Some(channel_names),
+        capture_id,
+        capture_scans: identity.capture.map_or(0, |capture| capture.scans),
+        pre_trigger_scans: identity.capture.map_or(0, |capture| capture.pre_trigger_scans),
+    };
+    let frame_offset = sampler::ScanFrame::create(builder, &frame_args);
+    builder.finish(frame_offset, None);
+    builder.finished_data().to_vec()
+}
+
+pub fn decode_scan(payload: &[u8]) -> Result<ChannelSamples, String> {
+    let scan = flatbuffers::root::<sampler::Scan>(payload)
+        .map_err(|e| format!("invalid Scan payload: {e}"))?;
+    Ok(ChannelSamples {
+        run_id: scan.run_id().map(str::to_string),
+        sequence: scan.sequence(),
+        first_sample_unix_ns: scan.first_sample_unix_ns(),
+        sample_interval_ns: scan.sample_interval_ns(),
+        values: scan
+            .values()
+            .map(|v| v.iter().collect())
+            .unwrap_or_default(),
+        clock_quality: scan.clock_quality().map(str::to_string),
+        clock_error_bound_ns: scan.clock_error_bound_ns(),
+    })
+}
+
+fn frame_layout(payload: &[u8]) -> Result<(sampler::ScanFrame<'_>, Vec<i16>), String> {
+    let frame = flatbuffers::root::<sampler::ScanFrame>(payload)
+        .map_err(|e| format!("invalid ScanFrame payload: {e}"))?;
+    let channels: Vec<i16> = frame
+        .channels()
+        .map(|channels| channels.iter().collect())
+        .unwrap_or_default();
+    if channels.is_empty() {
+        return Err("ScanFrame has no channels".to_string());
+    }
+    let values = frame.values().map(|v| v.len()).unwrap_or(0);
+    if !values.is_multiple_of(channels.len()) {
+        return Err(format!(
+            "ScanFrame has {values} values for {} channels",
+            channels.len()
+        ));
+    }
+    Ok((frame, channels))
+}
+
+fn frame_column(frame: &sampler::ScanFrame, index: usize, stride: usize) -> ChannelSamples {
+    ChannelSamples {
+        run_id: frame.run_id().map(str::to_string),
+        sequence: frame.sequence(),
+        first_sample_unix_ns: frame.first_sample_unix_ns(),
+        sample_interval_ns: frame.sample_interval_ns(),
+        values: frame
+            .values()
+            .map(|v| v.iter().skip(index).step_by(stride).collect())
+            .unwrap_or_default(),
+        clock_quality: frame.clock_quality().map(str::to_string),
+        clock_error_bound_ns: frame.clock_error_bound_ns(),
+    }
+}
+
+/// Splits a `ScanFrame` into one `ChannelSamples` per channel, in scan order,
+/// keyed by subject token. Frames from older streamers carry no names, so
+/// their AIN numbers are padded into `chNN`.
+pub fn decode_frame(payload: &[u8]) -> Result<Vec<(String, ChannelSamples)>, String> {
+    let (frame, channels) = frame_layout(payload)?;
+    let names = frame.channel_names();
+    channels
+        .iter()
+        .enumerate()
+        .map(|(index, ch)| {
+            let name = match names.filter(|names| names.len() == channels.len()) {
+                Some(names) => names.get(index).to_string(),
+                None => u8::try_from(*ch)
+                    .map(subjects::pad_channel)
+                    .map_err(|_| format!("invalid frame channel {ch}"))?,
+            };
+            Ok((name, frame_column(&frame, index, channels.len())))
+        })
+        .collect()
+}
+
+/// Decodes a frame from a source's `.capture` subject.
+pub fn decode_capture_frame(payload: &[u8]) -> Result<CaptureFrame, String> {
+    let columns = decode_frame(payload)?;
+    let frame = flatbuffers::root::<sampler::ScanFrame>(payload)
+        .map_err(|e| format!("invalid ScanFrame payload: {e}"))?;
+    let capture_id = frame
+        .capture_id()
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| "ScanFrame is not part of a capture".to_string())?;
+    Ok(CaptureFrame {
+        capture_id: capture_id.to_string(),
+        capture_scans: frame.capture_scans(),
+        pre_trigger_scans: frame.pre_trigger_scans(),
+        columns,
+    })
+}
+
+/// Extracts a single channel's column from a `ScanFrame`, or `None` when the
+/// frame does not carry that channel.
+pub fn decode_frame_channel(payload: &[u8], channel: u8) -> Result<Option<ChannelSamples>, String> {
+    let (frame, channels) = frame_layout(payload)?;
+    Ok(channels
+        .iter()
+        .position(|ch| *ch == i16::from(channel))
+        .map(|index| frame_column(&frame, index, channels.len())))
+}
+
+/// Extracts the column whose channel name is `name`, e.g. a virtual channel
+/// that has no AIN number.
+pub fn decode_frame_named(payload: &[u8], name: &str) -> Result<Option<ChannelSamples>, String> {
+    Ok(decode_frame(payload)?
+        .into_iter()
+        .find(|(column, _)| column == name)
+        .map(|(_, samples)| samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_frame() -> Vec<u8> {
+        let mut builder = FlatBufferBuilder::new();
+        encode_frame(
+            &mut builder,
+            1_000,
+            100,
+            10_000.0,
+            7,
+            &[0.1, 1.1, 0.2, 1.2, 0.3, 1.3],
+            &FrameIdentity {
+                channels: &[0, -1],
+                channel_names: &["ch00", "ctr1"],
+                run_id: "run",
+                device_serial: 470012345,
+                units: &["V", "V"],
+                calibration_ids: None,
+                clock_quality: "synchronized",
+                clock_error_bound_ns: 5_000_000,
+                capture: None,
+            },
+        )
+    }
+
+    #[test]
+    fn frame_round_trips_per_channel_columns() {
+        let decoded = decode_frame(&sample_frame()).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].0, "ch00");
+        assert_eq!(decoded[0].1.values, vec![0.1, 0.2, 0.3]);
+        assert_eq!(decoded[1].0, "ctr1");
+        assert_eq!(
+            decoded[1].1,
+            ChannelSamples {
+                run_id: Some("run".to_string()),
+                sequence: 7,
+                first_sample_unix_ns: 1_000,
+                sample_interval_ns: 100,
+                values: vec![1.1, 1.2, 1.3],
+                clock_quality: Some("synchronized".to_string()),
+                clock_error_bound_ns: 5_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn frame_channel_extracts_single_column() {
+        let payload = sample_frame();
+        assert_eq!(
+            decode_frame_channel(&payload, 0).unwrap().unwrap().values,
+            vec![0.1, 0.2, 0.3]
+        );
+        assert!(decode_frame_channel(&payload, 3).unwrap().is_none());
+        assert_eq!(
+            decode_frame_named(&payload, "ctr1").unwrap().unwrap().values,
+            vec![1.1, 1.2, 1.3]
+        );
+        assert!(decode_frame_named(&payload, "vch0").unwrap().is_none());
+    }
+
+    #[test]
+    fn capture_frames_carry_their_capture() {
+        assert!(decode_capture_frame(&sample_frame()).is_err());
+
+        let mut builder = FlatBufferBuilder::new();
+        let payload = encode_frame(
+            &mut builder,
+            1_000,
+            100,
+            10_000.0,
+            2,
+            &[0.5, 0.6],
+            &FrameIdentity {
+                channels: &[11],
+                channel_names: &["ch11"],
+                run_id: "run",
+                device_serial: 0,
+                units: &["V"],
+                calibration_ids: None,
+                clock_quality: "synchronized",
+                clock_error_bound_ns: 5_000_000,
+                capture: Some(FrameCapture {
+                    id: "cap-1",
+                    scans: 600,
+                    pre_trigger_scans: 100,
+                }),
+            },
+        );
+        let frame = decode_capture_frame(&payload).unwrap();
+        assert_eq!(frame.capture_id, "cap-1");
+        assert_eq!((frame.capture_scans, frame.pre_trigger_scans), (600, 100));
+        assert_eq!(frame.columns[0].0, "ch11");
+        assert_eq!(frame.columns[0].1.sequence, 2);
+    }
+
+    #[test]
+    fn frame_with_ragged_values_is_rejected() {
+        let mut builder = FlatBufferBuilder::new();
+        let payload = encode_frame(
+            &mut builder,
+            0,
+            100,
+            10_000.0,
+            0,
+            &[0.1, 1.1, 0.2],
+            &FrameIdentity {
+                channels: &[0, 11],
+                channel_names: &["ch00", "ch11"],
+                run_id: "run",
+                device_serial: 0,
+                units: &["V", "V"],
+                calibration_ids: None,
+                clock_quality: "synchronized",
+                clock_error_bound_ns: 5_000_000,
+                capture: None,
+            },
+        );
+        assert!(decode_frame(&payload).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Runs remembered per channel, so a late replay from the previous run is
+/// still recognised after a new run has started.
+const MAX_TRACKED_RUNS: usize = 4;
+/// Gaps remembered per run; the oldest is forgotten first.
+const MAX_MISSING_RANGES: usize = 1024;
+
+/// How a batch relates to what the archiver has already written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceCheck {
+    /// First batch seen from this run.
+    NewRun,
+    /// The next expected batch.
+    InOrder,
+    /// Ahead of the next expected batch; everything from `expected` up to it
+    /// is now missing.
+    Gap { expected: u64 },
+    /// Fills an earlier gap, e.g. a retried publish stored out of order.
+    Late,
+    /// Already written; drop it.
+    Duplicate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct RunSequences {
+    run_id: String,
+    /// Highest sequence written.
+    high: u64,
+    /// Inclusive ranges below `high` that have not been written.
+    missing: Vec<(u64, u64)>,
+}
+
+/// Batch sequences a channel logger has written, per streamer run.
+///
+/// Persisted next to the channel's parquet directories so a restarted
+/// archiver can discard redelivered batches instead of writing their rows
+/// twice.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceTracker {
+    runs: VecDeque<RunSequences>,
+}
+
+pub fn state_path(parquet_root: &Path, asset: u32, channel: &str) -> PathBuf {
+    parquet_root
+        .join(format!("asset{:03}", asset))
+        .join(format!("{channel}.sequences.json"))
+}
+
+impl SequenceTracker {
+    /// Loads saved state, starting empty when there is none or it is unreadable.
+    pub fn load(path: &Path) -> Self {
+        match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                warn!(
+                    "Ignoring unreadable sequence state {}: {}",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(tmp, path)
+    }
+
+    /// Classifies `sequence` of `run_id` and records it as written unless it
+    /// is a duplicate.
+    pub fn check(&mut self, run_id: &str, sequence: u64) -> SequenceCheck {
+        let Some(run) = self.runs.iter_mut().find(|run| run.run_id == run_id) else {
+            // Sequences restart at 0 per run, so anything below the first
+            // batch seen may still arrive.
+            let missing = if sequence > 0 {
+                vec![(0, sequence - 1)]
+            } else {
+                Vec::new()
+            };
+            self.runs.push_back(RunSequences {
+                run_id: run_id.to_string(),
+                high: sequence,
+                missing,
+            });
+            if self.runs.len() > MAX_TRACKED_RUNS {
+                self.runs.pop_front();
+            }
+            return SequenceCheck::NewRun;
+        };
+
+        let expected = run.high + 1;
+        if sequence == expected {
+            run.high = sequence;
+            return SequenceCheck::InOrder;
+        }
+        if sequence > expected {
+            run.missing.push((expected, sequence - 1));
+            if run.missing.len() > MAX_MISSING_RANGES {
+                run.missing.remove(0);
+            }
+            run.high = sequence;
+            return SequenceCheck::Gap { expected };
+        }
+
+        let Some(index) = run
+            .missing
+            .iter()
+            .position(|(first, last)| (*first..=*last).contains(&sequence))
+        else {
+            return SequenceCheck::Duplicate;
+        };
+        let (first, last) = run.missing.remove(index);
+        if sequence < last {
+            run.missing.insert(index, (sequence + 1, last));
+        }
+        if sequence > first {
+            run.missing.insert(index, (first, sequence - 1));
+        }
+        SequenceCheck::Late
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_in_order_gap_late_and_duplicate_batches() {
+        let mut tracker = SequenceTracker::default();
+        assert_eq!(tracker.check("run-a", 0), SequenceCheck::NewRun);
+        assert_eq!(tracker.check("run-a", 1), SequenceCheck::InOrder);
+        assert_eq!(tracker.check("run-a", 1), SequenceCheck::Duplicate);
+        assert_eq!(tracker.check("run-a", 5), SequenceCheck::Gap { expected: 2 });
+        assert_eq!(tracker.check("run-a", 3), SequenceCheck::Late);
+        assert_eq!(tracker.check("run-a", 3), SequenceCheck::Duplicate);
+        assert_eq!(tracker.check("run-a", 2), SequenceCheck::Late);
+        assert_eq!(tracker.check("run-a", 4), SequenceCheck::Late);
+        assert_eq!(tracker.check("run-a", 4), SequenceCheck::Duplicate);
+        assert_eq!(tracker.check("run-a", 6), SequenceCheck::InOrder);
+    }
+
+    #[test]
+    fn runs_are_tracked_independently() {
+        let mut tracker = SequenceTracker::default();
+        assert_eq!(tracker.check("run-a", 0), SequenceCheck::NewRun);
+        assert_eq!(tracker.check("run-a", 1), SequenceCheck::InOrder);
+        assert_eq!(tracker.check("run-b", 0), SequenceCheck::NewRun);
+        assert_eq!(tracker.check("run-a", 1), SequenceCheck::Duplicate);
+        assert_eq!(tracker.check("run-a", 2), SequenceCheck::InOrder);
+    }
+
+    #[test]
+    fn state_survives_a_restart() {
+        let root = std::env::temp_dir().join(format!("rust-ljm-seq-{}", uuid::Uuid::new_v4()));
+        let path = state_path(&root, 1, "ch11");
+        let mut tracker = SequenceTracker::default();
+        tracker.check("run-a", 0);
+        tracker.check("run-a", 3);
+        tracker.save(&path).unwrap();
+
+        let mut restored = SequenceTracker::load(&path);
+        assert_eq!(restored, tracker);
+        assert_eq!(restored.check("run-a", 3), SequenceCheck::Duplicate);
+        assert_eq!(restored.check("run-a", 1), SequenceCheck::Late);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
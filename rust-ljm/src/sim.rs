@@ -0,0 +1,637 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ljmrs::LJMError;
+use ljmrs::handle::{ConnectionType, DeviceHandleInfo, DeviceType};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::acquisition::Acquisition;
+use crate::ain_config::AinChannelConfig;
+use crate::channel_spec::DigitalChannel;
+use crate::device::StreamDevice;
+use crate::device_model::DeviceModel;
+use crate::ljm_stream::StreamRead;
+
+fn default_amplitude() -> f64 {
+    1.0
+}
+
+fn default_frequency_hz() -> f64 {
+    1.0
+}
+
+fn default_duty_cycle() -> f64 {
+    0.5
+}
+
+/// Signal generated for one simulated analog input, in volts.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Waveform {
+    Sine {
+        #[serde(default = "default_amplitude")]
+        amplitude: f64,
+        #[serde(default = "default_frequency_hz")]
+        frequency_hz: f64,
+        #[serde(default)]
+        offset: f64,
+        #[serde(default)]
+        phase_deg: f64,
+    },
+    Square {
+        #[serde(default = "default_amplitude")]
+        amplitude: f64,
+        #[serde(default = "default_frequency_hz")]
+        frequency_hz: f64,
+        #[serde(default)]
+        offset: f64,
+        #[serde(default = "default_duty_cycle")]
+        duty_cycle: f64,
+    },
+    /// Sawtooth from `offset - amplitude` up to `offset + amplitude`.
+    Ramp {
+        #[serde(default = "default_amplitude")]
+        amplitude: f64,
+        #[serde(default = "default_frequency_hz")]
+        frequency_hz: f64,
+        #[serde(default)]
+        offset: f64,
+    },
+    /// Gaussian noise around `offset`.
+    Noise {
+        #[serde(default = "default_amplitude")]
+        std_dev: f64,
+        #[serde(default)]
+        offset: f64,
+    },
+    /// Replays a recorded CSV column in a loop, one row per scan. The column
+    /// defaults to `raw_value`, which matches subscriber and exporter CSVs.
+    Replay {
+        path: String,
+        #[serde(default)]
+        column: Option<String>,
+    },
+}
+
+impl Default for Waveform {
+    fn default() -> Self {
+        Self::Sine {
+            amplitude: default_amplitude(),
+            frequency_hz: default_frequency_hz(),
+            offset: 0.0,
+            phase_deg: 0.0,
+        }
+    }
+}
+
+/// Simulated backend settings, read from `SIM_*` environment variables.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimConfig {
+    pub serial_number: i32,
+    pub default_waveform: Waveform,
+    pub channels: HashMap<u8, Waveform>,
+    /// How fast the simulated crystal runs against host time, in ppm. Scans
+    /// are paced and `CORE_TIMER` counts at the skewed rate.
+    pub clock_skew_ppm: f64,
+    /// Model reported in the handle info.
+    pub device_type: DeviceModel,
+    /// How long a DIO-triggered stream waits for its simulated edge.
+    pub trigger_delay: Duration,
+}
+
+impl SimConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let mut cfg = SimConfig::default();
+
+        if let Some(raw) = env_nonempty("SIM_SERIAL") {
+            cfg.serial_number = raw
+                .parse()
+                .map_err(|e| format!("invalid SIM_SERIAL '{raw}': {e}"))?;
+        }
+        if let Some(raw) = env_nonempty("SIM_DEFAULT_SIGNAL") {
+            cfg.default_waveform = serde_json::from_str(&raw)
+                .map_err(|e| format!("invalid SIM_DEFAULT_SIGNAL: {e}"))?;
+        }
+        if let Some(raw) = env_nonempty("SIM_SIGNALS") {
+            let by_key: HashMap<String, Waveform> =
+                serde_json::from_str(&raw).map_err(|e| format!("invalid SIM_SIGNALS: {e}"))?;
+            for (key, waveform) in by_key {
+                let ch = key
+                    .parse::<u8>()
+                    .map_err(|_| format!("invalid SIM_SIGNALS channel key '{key}'"))?;
+                cfg.channels.insert(ch, waveform);
+            }
+        }
+
+        if let Some(raw) = env_nonempty("SIM_DEVICE_TYPE") {
+            cfg.device_type = serde_json::from_value(serde_json::Value::String(raw.clone()))
+                .map_err(|_| format!("invalid SIM_DEVICE_TYPE '{raw}', expected T4, T7 or T8"))?;
+        }
+
+        if let Some(raw) = env_nonempty("SIM_TRIGGER_DELAY_MS") {
+            cfg.trigger_delay = raw
+                .parse()
+                .map(Duration::from_millis)
+                .map_err(|e| format!("invalid SIM_TRIGGER_DELAY_MS '{raw}': {e}"))?;
+        }
+
+        if let Some(raw) = env_nonempty("SIM_CLOCK_SKEW_PPM") {
+            cfg.clock_skew_ppm = raw
+                .parse()
+                .map_err(|e| format!("invalid SIM_CLOCK_SKEW_PPM '{raw}': {e}"))?;
+        }
+
+        Ok(cfg)
+    }
+
+    fn clock_scale(&self) -> f64 {
+        1.0 + self.clock_skew_ppm * 1e-6
+    }
+
+    fn waveform_for(&self, channel: u8) -> &Waveform {
+        self.channels
+            .get(&channel)
+            .unwrap_or(&self.default_waveform)
+    }
+}
+
+fn env_nonempty(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Small xorshift generator so noise does not pull in an RNG dependency.
+#[derive(Debug, Clone)]
+struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /// Uniform sample in `(0, 1]`.
+    fn next_f64(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+}
+
+enum SignalSource {
+    Analytic(Waveform),
+    Replay(Vec<f64>),
+    /// `FIO_EIO_STATE` with every line toggling once a second.
+    DioState,
+    /// Low or high word of a DIO_EF register that counts one edge per scan.
+    EdgeCount { high_word: bool },
+}
+
+impl SignalSource {
+    fn load(waveform: &Waveform) -> Result<Self, String> {
+        match waveform {
+            Waveform::Replay { path, column } => {
+                load_replay(path, column.as_deref()).map(SignalSource::Replay)
+            }
+            other => Ok(SignalSource::Analytic(other.clone())),
+        }
+    }
+
+    /// Source for a non-AIN register of the scan list.
+    fn for_register(name: &str) -> Result<Self, String> {
+        match name {
+            "FIO_EIO_STATE" => Ok(SignalSource::DioState),
+            "STREAM_DATA_CAPTURE_16" => Ok(SignalSource::EdgeCount { high_word: true }),
+            _ if name.ends_with("_EF_READ_A") => Ok(SignalSource::EdgeCount { high_word: false }),
+            _ => Err(format!("simulated device cannot stream register {name}")),
+        }
+    }
+
+    fn sample(&self, scan_index: u64, scan_rate_hz: f64, rng: &mut XorShift64) -> f64 {
+        let t = scan_index as f64 / scan_rate_hz;
+        match self {
+            SignalSource::Replay(values) => values[(scan_index % values.len() as u64) as usize],
+            SignalSource::DioState => {
+                if (t as u64).is_multiple_of(2) {
+                    0.0
+                } else {
+                    f64::from(u16::MAX)
+                }
+            }
+            SignalSource::EdgeCount { high_word } => {
+                let word = if *high_word { scan_index >> 16 } else { scan_index };
+                (word & 0xFFFF) as f64
+            }
+            SignalSource::Analytic(waveform) => match *waveform {
+                Waveform::Sine {
+                    amplitude,
+                    frequency_hz,
+                    offset,
+                    phase_deg,
+                } => {
+                    offset
+                        + amplitude * (2.0 * PI * frequency_hz * t + phase_deg.to_radians()).sin()
+                }
+                Waveform::Square {
+                    amplitude,
+                    frequency_hz,
+                    offset,
+                    duty_cycle,
+                } => {
+                    if (frequency_hz * t).fract() < duty_cycle {
+                        offset + amplitude
+                    } else {
+                        offset - amplitude
+                    }
+                }
+                Waveform::Ramp {
+                    amplitude,
+                    frequency_hz,
+                    offset,
+                } => offset - amplitude + 2.0 * amplitude * (frequency_hz * t).fract(),
+                Waveform::Noise { std_dev, offset } => offset + std_dev * rng.next_gaussian(),
+                Waveform::Replay { .. } => unreachable!("replay sources are loaded up front"),
+            },
+        }
+    }
+}
+
+fn load_replay(path: &str, column: Option<&str>) -> Result<Vec<f64>, String> {
+    let mut reader =
+        csv::Reader::from_path(path).map_err(|e| format!("failed to open replay '{path}': {e}"))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("failed to read replay header '{path}': {e}"))?
+        .clone();
+    let wanted = column.unwrap_or("raw_value");
+    let index = headers
+        .iter()
+        .position(|name| name.trim() == wanted)
+        .or_else(|| column.is_none().then(|| headers.len().saturating_sub(1)))
+        .ok_or_else(|| format!("replay '{path}' has no column '{wanted}'"))?;
+
+    let mut values = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("failed to read replay '{path}': {e}"))?;
+        if let Some(value) = record.get(index).and_then(|v| v.trim().parse::<f64>().ok()) {
+            values.push(value);
+        }
+    }
+
+    if values.is_empty() {
+        return Err(format!("replay '{path}' contains no numeric samples"));
+    }
+    Ok(values)
+}
+
+struct SimStream {
+    scans_per_read: usize,
+    scan_rate_hz: f64,
+    /// `scan_rate_hz` as measured by the host, after the simulated skew.
+    host_scan_rate_hz: f64,
+    sources: Vec<SignalSource>,
+    ranges: Vec<f64>,
+    started_at: Instant,
+    scans_emitted: u64,
+    /// `STREAM_NUM_SCANS`, after which the stream has nothing more to read.
+    limit: Option<u64>,
+    rng: XorShift64,
+}
+
+impl SimStream {
+    fn next_due(&self) -> Instant {
+        let scans = self.scans_emitted + self.scans_per_read as u64;
+        self.started_at + Duration::from_secs_f64(scans as f64 / self.host_scan_rate_hz)
+    }
+
+    /// Scans that were due by now but not yet handed out, reported as the
+    /// LJM backlog when the reader falls behind.
+    fn backlog(&self) -> i32 {
+        let due = (self.started_at.elapsed().as_secs_f64() * self.host_scan_rate_hz) as u64;
+        i32::try_from(due.saturating_sub(self.scans_emitted)).unwrap_or(i32::MAX)
+    }
+
+    fn generate(&mut self) -> Vec<f64> {
+        let mut batch = Vec::with_capacity(self.scans_per_read * self.sources.len());
+        for offset in 0..self.scans_per_read as u64 {
+            let scan_index = self.scans_emitted + offset;
+            for (source, range) in self.sources.iter().zip(&self.ranges) {
+                let value = source.sample(scan_index, self.scan_rate_hz, &mut self.rng);
+                batch.push(value.clamp(-range, *range));
+            }
+        }
+        self.scans_emitted += self.scans_per_read as u64;
+        batch
+    }
+}
+
+const SIM_FIRMWARE_VERSION: f64 = 1.0299;
+const SIM_ROOM_TEMPERATURE_K: f64 = 295.15;
+
+/// A LabJack stand-in that paces generated scans at the requested scan rate.
+pub struct SimulatedDevice {
+    cfg: SimConfig,
+    info: DeviceHandleInfo,
+    ranges: Mutex<HashMap<u8, f64>>,
+    acquisition: Mutex<Acquisition>,
+    stream: Mutex<Option<SimStream>>,
+    powered_on: Instant,
+}
+
+impl SimulatedDevice {
+    pub fn new(cfg: SimConfig) -> Self {
+        let info = DeviceHandleInfo {
+            device_type: match cfg.device_type {
+                DeviceModel::T4 => DeviceType::T4,
+                DeviceModel::T7 => DeviceType::T7,
+                DeviceModel::T8 => DeviceType::T8,
+            },
+            connection_type: ConnectionType::ANY,
+            ip_address: 0,
+            max_bytes_per_megabyte: 0,
+            serial_number: cfg.serial_number,
+            port: 0,
+        };
+        info!(
+            "simulated {} ready, serial {}, {} channel override(s)",
+            cfg.device_type.as_str(),
+            info.serial_number,
+            cfg.channels.len()
+        );
+        Self {
+            cfg,
+            info,
+            ranges: Mutex::new(HashMap::new()),
+            acquisition: Mutex::new(Acquisition::Continuous),
+            stream: Mutex::new(None),
+            powered_on: Instant::now(),
+        }
+    }
+}
+
+impl StreamDevice for SimulatedDevice {
+    fn backend_name(&self) -> &'static str {
+        "sim"
+    }
+
+    fn info(&self) -> &DeviceHandleInfo {
+        &self.info
+    }
+
+    /// Only the range matters here: generated values are clipped to it, the
+    /// way the ADC would rail.
+    fn configure(&self, channels: &[AinChannelConfig]) -> Result<(), LJMError> {
+        let mut ranges = self.ranges.lock().map_err(|_| LJMError::PoisonedLock)?;
+        ranges.clear();
+        for ain in channels {
+            ranges.insert(ain.channel, ain.range);
+        }
+        Ok(())
+    }
+
+    /// Nothing to set up: extended feature registers stream a synthetic
+    /// edge count.
+    fn configure_digital(&self, _channels: &[DigitalChannel]) -> Result<(), LJMError> {
+        Ok(())
+    }
+
+    /// A DIO trigger fires `trigger_delay` after the stream starts.
+    fn configure_acquisition(&self, acquisition: &Acquisition) -> Result<(), LJMError> {
+        *self.acquisition.lock().map_err(|_| LJMError::PoisonedLock)? = *acquisition;
+        Ok(())
+    }
+
+    fn stream_start(
+        &self,
+        scans_per_read: i32,
+        scan_rate_hz: f64,
+        registers: &[String],
+    ) -> Result<f64, LJMError> {
+        if scans_per_read <= 0 || !scan_rate_hz.is_finite() || scan_rate_hz <= 0.0 {
+            return Err(LJMError::LibraryError(format!(
+                "Simulated stream needs positive scans_per_read and scan rate, got {scans_per_read} @ {scan_rate_hz} Hz"
+            )));
+        }
+
+        let analog: Vec<Option<u8>> = registers
+            .iter()
+            .map(|name| name.strip_prefix("AIN").and_then(|ch| ch.parse().ok()))
+            .collect();
+        let sources = registers
+            .iter()
+            .zip(&analog)
+            .map(|(name, ch)| match ch {
+                Some(ch) => SignalSource::load(self.cfg.waveform_for(*ch)),
+                None => SignalSource::for_register(name),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(LJMError::LibraryError)?;
+        let ranges = {
+            let configured = self.ranges.lock().map_err(|_| LJMError::PoisonedLock)?;
+            analog
+                .iter()
+                .map(|ch| match ch {
+                    Some(ch) => configured.get(ch).copied().unwrap_or(10.0),
+                    None => f64::INFINITY,
+                })
+                .collect()
+        };
+
+        let acquisition = *self.acquisition.lock().map_err(|_| LJMError::PoisonedLock)?;
+        let mut started_at = Instant::now();
+        if acquisition.waits_for_trigger() {
+            started_at += self.cfg.trigger_delay;
+        }
+        let seed = uuid::Uuid::new_v4().as_u128() as u64;
+        let mut stream = self.stream.lock().map_err(|_| LJMError::PoisonedLock)?;
+        *stream = Some(SimStream {
+            scans_per_read: scans_per_read as usize,
+            scan_rate_hz,
+            host_scan_rate_hz: scan_rate_hz * self.cfg.clock_scale(),
+            sources,
+            ranges,
+            started_at,
+            scans_emitted: 0,
+            limit: acquisition.finite_scans(),
+            rng: XorShift64::new(seed),
+        });
+        Ok(scan_rate_hz)
+    }
+
+    fn stream_read(&self) -> Result<StreamRead, LJMError> {
+        let due = {
+            let stream = self.stream.lock().map_err(|_| LJMError::PoisonedLock)?;
+            let stream = stream.as_ref().ok_or(LJMError::StreamNotStarted)?;
+            if stream.limit.is_some_and(|limit| stream.scans_emitted >= limit) {
+                return Err(LJMError::LibraryError(format!(
+                    "Simulated burst of {} scans is complete",
+                    stream.scans_emitted
+                )));
+            }
+            stream.next_due()
+        };
+        let now = Instant::now();
+        if due > now {
+            std::thread::sleep(due - now);
+        }
+
+        let mut stream = self.stream.lock().map_err(|_| LJMError::PoisonedLock)?;
+        let stream = stream.as_mut().ok_or(LJMError::StreamNotStarted)?;
+        let values = stream.generate();
+        Ok(StreamRead {
+            values,
+            device_backlog: 0,
+            ljm_backlog: stream.backlog(),
+        })
+    }
+
+    fn stream_stop(&self) -> Result<(), LJMError> {
+        let mut stream = self.stream.lock().map_err(|_| LJMError::PoisonedLock)?;
+        *stream = None;
+        Ok(())
+    }
+
+    fn read_core_timer(&self) -> Result<u32, LJMError> {
+        let ticks = self.powered_on.elapsed().as_secs_f64()
+            * crate::clock_sync::CORE_TIMER_HZ
+            * self.cfg.clock_scale();
+        Ok((ticks as u64) as u32)
+    }
+
+    fn read_firmware_version(&self) -> Result<f64, LJMError> {
+        Ok(SIM_FIRMWARE_VERSION)
+    }
+
+    /// Warms from room temperature towards +8 K over the first half hour.
+    fn read_temperature_k(&self) -> Result<f64, LJMError> {
+        let minutes = self.powered_on.elapsed().as_secs_f64() / 60.0;
+        Ok(SIM_ROOM_TEMPERATURE_K + 8.0 * (1.0 - (-minutes / 10.0).exp()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(waveform: Waveform, scan_index: u64, scan_rate_hz: f64) -> f64 {
+        let mut rng = XorShift64::new(7);
+        SignalSource::Analytic(waveform).sample(scan_index, scan_rate_hz, &mut rng)
+    }
+
+    #[test]
+    fn analytic_waveforms_follow_their_shape() {
+        let sine = Waveform::Sine {
+            amplitude: 2.0,
+            frequency_hz: 1.0,
+            offset: 0.5,
+            phase_deg: 0.0,
+        };
+        assert!((sample(sine.clone(), 0, 100.0) - 0.5).abs() < 1e-12);
+        assert!((sample(sine, 25, 100.0) - 2.5).abs() < 1e-9);
+
+        let square = Waveform::Square {
+            amplitude: 1.0,
+            frequency_hz: 1.0,
+            offset: 0.0,
+            duty_cycle: 0.5,
+        };
+        assert_eq!(sample(square.clone(), 10, 100.0), 1.0);
+        assert_eq!(sample(square, 60, 100.0), -1.0);
+
+        let ramp = Waveform::Ramp {
+            amplitude: 1.0,
+            frequency_hz: 1.0,
+            offset: 0.0,
+        };
+        assert_eq!(sample(ramp.clone(), 0, 100.0), -1.0);
+        assert!((sample(ramp, 50, 100.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn noise_is_centered_on_offset() {
+        let mut rng = XorShift64::new(42);
+        let source = SignalSource::Analytic(Waveform::Noise {
+            std_dev: 0.1,
+            offset: 3.0,
+        });
+        let n = 10_000;
+        let mean: f64 = (0..n)
+            .map(|i| source.sample(i, 1000.0, &mut rng))
+            .sum::<f64>()
+            / n as f64;
+        assert!((mean - 3.0).abs() < 0.01, "mean {mean}");
+    }
+
+    #[test]
+    fn replay_loops_over_recorded_column() {
+        let path = std::env::temp_dir().join(format!("sim-replay-{}.csv", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "sequence,timestamp,raw_value\n0,a,1.5\n0,b,2.5\n").unwrap();
+
+        let source = SignalSource::load(&Waveform::Replay {
+            path: path.to_string_lossy().into_owned(),
+            column: None,
+        })
+        .expect("replay should load");
+        let mut rng = XorShift64::new(1);
+        let values: Vec<f64> = (0..3).map(|i| source.sample(i, 10.0, &mut rng)).collect();
+        assert_eq!(values, vec![1.5, 2.5, 1.5]);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn device_streams_interleaved_scans_clipped_to_range() {
+        let mut cfg = SimConfig::default();
+        cfg.channels.insert(
+            3,
+            Waveform::Sine {
+                amplitude: 0.0,
+                frequency_hz: 1.0,
+                offset: 5.0,
+                phase_deg: 0.0,
+            },
+        );
+        let device = SimulatedDevice::new(cfg);
+        device
+            .configure(&[AinChannelConfig {
+                channel: 3,
+                range: 1.0,
+                resolution_index: 0,
+                settling_us: 0,
+                negative_channel: 199,
+            }])
+            .unwrap();
+
+        let registers = ["AIN1", "AIN3", "DIO1_EF_READ_A", "STREAM_DATA_CAPTURE_16"]
+            .map(String::from);
+        let rate = device.stream_start(4, 100_000.0, &registers).unwrap();
+        assert_eq!(rate, 100_000.0);
+        let batch = device.stream_read().unwrap().values;
+        assert_eq!(batch.len(), 16);
+        assert!(batch.iter().skip(1).step_by(4).all(|v| *v == 1.0));
+        let counts: Vec<f64> = batch.iter().skip(2).step_by(4).copied().collect();
+        assert_eq!(counts, vec![0.0, 1.0, 2.0, 3.0]);
+
+        device.stream_stop().unwrap();
+        assert!(device.stream_read().is_err());
+    }
+}
@@ -0,0 +1,549 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use tracing::warn;
+
+use crate::publisher::OutboundMessage;
+
+const DEFAULT_SPOOL_MAX_MB: u64 = 1024;
+const MAX_SEGMENT_BYTES: u64 = 16 * 1024 * 1024;
+const SEGMENT_SUFFIX: &str = ".spool";
+const CURSOR_FILE: &str = "cursor";
+/// Upper bound on one record, so a garbled length cannot trigger a huge read.
+const MAX_RECORD_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpoolConfig {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+}
+
+impl SpoolConfig {
+    /// Reads `STREAM_SPOOL_DIR` and `STREAM_SPOOL_MAX_MB`. The spool is off
+    /// unless a directory is set, and a cap of `0` also disables it.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(dir) = std::env::var("STREAM_SPOOL_DIR")
+            .ok()
+            .filter(|dir| !dir.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+        let max_mb = match std::env::var("STREAM_SPOOL_MAX_MB") {
+            Ok(raw) if !raw.trim().is_empty() => raw
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("invalid STREAM_SPOOL_MAX_MB '{raw}', expected an integer"))?,
+            _ => DEFAULT_SPOOL_MAX_MB,
+        };
+        if max_mb == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            dir: PathBuf::from(dir.trim()),
+            max_bytes: max_mb.saturating_mul(1024 * 1024),
+        }))
+    }
+}
+
+/// Where replay resumes: a record boundary inside a segment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpoolPosition {
+    segment: u64,
+    offset: u64,
+    record: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpooledMessage {
+    pub message: OutboundMessage,
+    /// Position just past this record; committing it marks the record sent.
+    pub next: SpoolPosition,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    id: u64,
+    bytes: u64,
+    records: u64,
+}
+
+/// Append-only on-disk write-ahead spool for messages that could not be
+/// published.
+///
+/// Records go into numbered segment files and are replayed oldest first from
+/// a persisted cursor. When the spool outgrows its cap, whole segments are
+/// evicted from the oldest end. A record torn by a crash ends its segment.
+pub struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+    segment_bytes: u64,
+    segments: VecDeque<Segment>,
+    active: Option<File>,
+    cursor: SpoolPosition,
+}
+
+fn segment_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id:020}{SEGMENT_SUFFIX}"))
+}
+
+/// FNV-1a, enough to tell a torn or garbled record from a whole one.
+fn checksum(parts: &[&[u8]]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for part in parts {
+        for byte in *part {
+            hash ^= u32::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+    }
+    hash
+}
+
+fn encode_record(msg: &OutboundMessage) -> Vec<u8> {
+    let subject = msg.subject.as_bytes();
+    let msg_id = msg.msg_id.as_bytes();
+    let mut record = Vec::with_capacity(16 + subject.len() + msg_id.len() + msg.payload.len());
+    record.extend_from_slice(&(subject.len() as u32).to_le_bytes());
+    record.extend_from_slice(&(msg_id.len() as u32).to_le_bytes());
+    record.extend_from_slice(&(msg.payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&checksum(&[subject, msg_id, &msg.payload]).to_le_bytes());
+    record.extend_from_slice(subject);
+    record.extend_from_slice(msg_id);
+    record.extend_from_slice(&msg.payload);
+    record
+}
+
+/// Reads the next record, or `None` at the end of the segment or at a torn
+/// or corrupt record.
+fn read_record(reader: &mut impl Read) -> io::Result<Option<(OutboundMessage, u64)>> {
+    let mut header = [0u8; 16];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let field = |i: usize| u32::from_le_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+    let (subject_len, id_len, payload_len) =
+        (field(0) as usize, field(1) as usize, field(2) as usize);
+    let body_len = subject_len + id_len + payload_len;
+    if body_len > MAX_RECORD_BYTES {
+        return Ok(None);
+    }
+    let mut body = vec![0u8; body_len];
+    match reader.read_exact(&mut body) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let (subject, rest) = body.split_at(subject_len);
+    let (msg_id, payload) = rest.split_at(id_len);
+    if checksum(&[subject, msg_id, payload]) != field(3) {
+        return Ok(None);
+    }
+    let (Ok(subject), Ok(msg_id)) = (
+        String::from_utf8(subject.to_vec()),
+        String::from_utf8(msg_id.to_vec()),
+    ) else {
+        return Ok(None);
+    };
+    let message = OutboundMessage {
+        subject,
+        msg_id,
+        payload: Bytes::copy_from_slice(payload),
+    };
+    Ok(Some((message, (header.len() + body.len()) as u64)))
+}
+
+/// Counts the whole records of a segment and the bytes they cover.
+fn scan_segment(path: &Path) -> io::Result<(u64, u64)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let (mut bytes, mut records) = (0, 0);
+    while let Some((_, len)) = read_record(&mut reader)? {
+        bytes += len;
+        records += 1;
+    }
+    Ok((bytes, records))
+}
+
+impl Spool {
+    pub fn open(config: &SpoolConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        let mut ids: Vec<u64> = fs::read_dir(&config.dir)?
+            .flatten()
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(SEGMENT_SUFFIX))
+                    .and_then(|id| id.parse::<u64>().ok())
+            })
+            .collect();
+        ids.sort_unstable();
+
+        let mut segments = VecDeque::with_capacity(ids.len());
+        for id in ids {
+            let path = segment_path(&config.dir, id);
+            let (bytes, records) = scan_segment(&path)?;
+            let file_len = fs::metadata(&path)?.len();
+            if file_len != bytes {
+                warn!(
+                    "Truncating {} from {} to {} bytes after a torn record",
+                    path.display(),
+                    file_len,
+                    bytes
+                );
+                OpenOptions::new().write(true).open(&path)?.set_len(bytes)?;
+            }
+            segments.push_back(Segment { id, bytes, records });
+        }
+
+        let mut spool = Self {
+            dir: config.dir.clone(),
+            max_bytes: config.max_bytes,
+            segment_bytes: (config.max_bytes / 4).clamp(1, MAX_SEGMENT_BYTES),
+            segments,
+            active: None,
+            cursor: SpoolPosition::default(),
+        };
+        spool.cursor = spool.load_cursor();
+        Ok(spool)
+    }
+
+    fn load_cursor(&self) -> SpoolPosition {
+        let head = self.segments.front().map(|segment| SpoolPosition {
+            segment: segment.id,
+            ..Default::default()
+        });
+        let saved = fs::read_to_string(self.dir.join(CURSOR_FILE))
+            .ok()
+            .and_then(|raw| {
+                let mut parts = raw.split_whitespace().map(|part| part.parse::<u64>().ok());
+                Some(SpoolPosition {
+                    segment: parts.next()??,
+                    offset: parts.next()??,
+                    record: parts.next()??,
+                })
+            });
+        match saved {
+            Some(cursor)
+                if self.segments.iter().any(|segment| {
+                    segment.id == cursor.segment
+                        && cursor.offset <= segment.bytes
+                        && cursor.record <= segment.records
+                }) =>
+            {
+                cursor
+            }
+            _ => head.unwrap_or_default(),
+        }
+    }
+
+    fn save_cursor(&self) -> io::Result<()> {
+        let tmp = self.dir.join(format!("{CURSOR_FILE}.tmp"));
+        fs::write(
+            &tmp,
+            format!(
+                "{} {} {}\n",
+                self.cursor.segment, self.cursor.offset, self.cursor.record
+            ),
+        )?;
+        fs::rename(tmp, self.dir.join(CURSOR_FILE))
+    }
+
+    /// Records not yet committed as sent.
+    pub fn pending(&self) -> u64 {
+        self.segments
+            .iter()
+            .filter(|segment| segment.id >= self.cursor.segment)
+            .map(|segment| segment.records)
+            .sum::<u64>()
+            .saturating_sub(self.cursor.record)
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.segments.iter().map(|segment| segment.bytes).sum()
+    }
+
+    /// Appends `msg` and returns how many unsent records were evicted to
+    /// stay under the size cap. The record is durable once `sync` returns, so
+    /// call it after the last append of a batch.
+    pub fn append(&mut self, msg: &OutboundMessage) -> io::Result<u64> {
+        let record = encode_record(msg);
+        let rotate = match self.segments.back() {
+            Some(segment) => {
+                self.active.is_none() || segment.bytes + record.len() as u64 > self.segment_bytes
+            }
+            None => true,
+        };
+        if rotate {
+            self.rotate()?;
+        }
+
+        let Some(file) = self.active.as_mut() else {
+            return Err(io::Error::other("spool has no active segment"));
+        };
+        file.write_all(&record)?;
+        if let Some(segment) = self.segments.back_mut() {
+            segment.bytes += record.len() as u64;
+            segment.records += 1;
+        }
+        self.evict()
+    }
+
+    /// Syncs the records appended to the active segment; rotated segments
+    /// were synced when they were closed.
+    pub fn sync(&mut self) -> io::Result<()> {
+        match self.active.as_ref() {
+            Some(file) => file.sync_data(),
+            None => Ok(()),
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if let Some(file) = self.active.take() {
+            file.sync_data()?;
+        }
+        // Reopening after a restart starts a fresh segment instead of
+        // appending behind a possibly truncated tail.
+        let id = self
+            .segments
+            .back()
+            .map(|segment| segment.id + 1)
+            .unwrap_or(0);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(segment_path(&self.dir, id))?;
+        self.active = Some(file);
+        self.segments.push_back(Segment {
+            id,
+            bytes: 0,
+            records: 0,
+        });
+        if self.segments.len() == 1 {
+            self.cursor = SpoolPosition {
+                segment: id,
+                ..Default::default()
+            };
+        }
+        Ok(())
+    }
+
+    fn evict(&mut self) -> io::Result<u64> {
+        let mut evicted = 0;
+        while self.bytes() > self.max_bytes && self.segments.len() > 1 {
+            let Some(oldest) = self.segments.pop_front() else {
+                break;
+            };
+            if oldest.id >= self.cursor.segment {
+                evicted += oldest.records - self.cursor.record.min(oldest.records);
+                if let Some(next) = self.segments.front() {
+                    self.cursor = SpoolPosition {
+                        segment: next.id,
+                        ..Default::default()
+                    };
+                }
+            }
+            fs::remove_file(segment_path(&self.dir, oldest.id))?;
+        }
+        if evicted > 0 {
+            self.save_cursor()?;
+        }
+        Ok(evicted)
+    }
+
+    /// Reads up to `max` unsent records from the cursor without consuming them.
+    pub fn peek(&self, max: usize) -> io::Result<Vec<SpooledMessage>> {
+        let mut out = Vec::new();
+        for segment in self.segments.iter().filter(|s| s.id >= self.cursor.segment) {
+            let mut position = if segment.id == self.cursor.segment {
+                self.cursor
+            } else {
+                SpoolPosition {
+                    segment: segment.id,
+                    ..Default::default()
+                }
+            };
+            let mut file = File::open(segment_path(&self.dir, segment.id))?;
+            file.seek(SeekFrom::Start(position.offset))?;
+            let mut reader = BufReader::new(file).take(segment.bytes - position.offset);
+            while out.len() < max {
+                let Some((message, len)) = read_record(&mut reader)? else {
+                    break;
+                };
+                position.offset += len;
+                position.record += 1;
+                out.push(SpooledMessage {
+                    message,
+                    next: position,
+                });
+            }
+            if out.len() >= max {
+                break;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Marks everything before `next` as sent and removes fully sent segments.
+    /// A `next` behind the cursor is ignored: its segment was evicted while
+    /// the batch was being sent, and the cursor already moved past it.
+    pub fn commit(&mut self, next: SpoolPosition) -> io::Result<()> {
+        if (next.segment, next.record) < (self.cursor.segment, self.cursor.record) {
+            return Ok(());
+        }
+        self.cursor = next;
+        while let Some(oldest) = self.segments.front().copied() {
+            let consumed = oldest.id < self.cursor.segment
+                || (oldest.id == self.cursor.segment && self.cursor.record >= oldest.records);
+            if !consumed {
+                break;
+            }
+            self.segments.pop_front();
+            if self.segments.is_empty() {
+                self.active = None;
+            }
+            fs::remove_file(segment_path(&self.dir, oldest.id))?;
+            if let Some(next) = self.segments.front()
+                && next.id > self.cursor.segment
+            {
+                self.cursor = SpoolPosition {
+                    segment: next.id,
+                    ..Default::default()
+                };
+            }
+        }
+        self.save_cursor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config(max_bytes: u64) -> SpoolConfig {
+        SpoolConfig {
+            dir: std::env::temp_dir().join(format!("rust-ljm-spool-{}", uuid::Uuid::new_v4())),
+            max_bytes,
+        }
+    }
+
+    fn message(sequence: u64) -> OutboundMessage {
+        OutboundMessage {
+            subject: "avenars.v1.box.lj.ch00".to_string(),
+            msg_id: format!("run-1.ch00.{sequence}"),
+            payload: Bytes::from(vec![sequence as u8; 64]),
+        }
+    }
+
+    #[test]
+    fn replays_in_order_and_resumes_after_reopen() {
+        let config = temp_config(1024 * 1024);
+        let mut spool = Spool::open(&config).unwrap();
+        for sequence in 0..5 {
+            spool.append(&message(sequence)).unwrap();
+        }
+        assert_eq!(spool.pending(), 5);
+
+        let batch = spool.peek(2).unwrap();
+        let ids: Vec<_> = batch.iter().map(|m| m.message.msg_id.as_str()).collect();
+        assert_eq!(ids, vec!["run-1.ch00.0", "run-1.ch00.1"]);
+        spool.commit(batch[1].next).unwrap();
+        drop(spool);
+
+        let mut spool = Spool::open(&config).unwrap();
+        assert_eq!(spool.pending(), 3);
+        let batch = spool.peek(10).unwrap();
+        assert_eq!(batch[0].message.msg_id, "run-1.ch00.2");
+        assert_eq!(batch[0].message.payload, message(2).payload);
+        assert_eq!(batch.len(), 3);
+        spool.commit(batch[2].next).unwrap();
+        assert_eq!(spool.pending(), 0);
+        assert_eq!(spool.bytes(), 0);
+
+        spool.append(&message(5)).unwrap();
+        assert_eq!(spool.peek(10).unwrap()[0].message.msg_id, "run-1.ch00.5");
+        fs::remove_dir_all(&config.dir).unwrap();
+    }
+
+    #[test]
+    fn commits_behind_an_eviction_keep_the_cursor() {
+        let record = encode_record(&message(0)).len() as u64;
+        let config = temp_config(record * 16);
+        let mut spool = Spool::open(&config).unwrap();
+        for seq in 0..8 {
+            spool.append(&message(seq)).unwrap();
+        }
+        let batch = spool.peek(2).unwrap();
+
+        // Appending past the cap evicts the segment the batch came from
+        // while it is being sent.
+        let evicted: u64 = (8..24)
+            .map(|seq| spool.append(&message(seq)).unwrap())
+            .sum();
+        assert!(evicted > 0);
+        let pending = spool.pending();
+        assert_eq!(pending, 24 - evicted);
+
+        spool.commit(batch[1].next).unwrap();
+        assert_eq!(spool.pending(), pending);
+        let oldest = format!("run-1.ch00.{evicted}");
+        assert_eq!(spool.peek(1).unwrap()[0].message.msg_id, oldest);
+        drop(spool);
+
+        let spool = Spool::open(&config).unwrap();
+        assert_eq!(spool.pending(), pending);
+        assert_eq!(spool.peek(1).unwrap()[0].message.msg_id, oldest);
+        fs::remove_dir_all(&config.dir).unwrap();
+    }
+
+    #[test]
+    fn evicts_oldest_segments_past_the_cap() {
+        // ~100 byte records, 4 per segment, cap of 16 records.
+        let record = encode_record(&message(0)).len() as u64;
+        let config = temp_config(record * 16);
+        let mut spool = Spool::open(&config).unwrap();
+        let evicted: u64 = (0..40)
+            .map(|seq| spool.append(&message(seq)).unwrap())
+            .sum();
+
+        assert!(evicted > 0);
+        assert_eq!(spool.pending(), 40 - evicted);
+        assert!(spool.bytes() <= record * 16);
+        let oldest = &spool.peek(1).unwrap()[0].message.msg_id;
+        assert_eq!(oldest, &format!("run-1.ch00.{evicted}"));
+        fs::remove_dir_all(&config.dir).unwrap();
+    }
+
+    #[test]
+    fn torn_tail_record_is_truncated_on_open() {
+        let config = temp_config(1024 * 1024);
+        let mut spool = Spool::open(&config).unwrap();
+        spool.append(&message(0)).unwrap();
+        spool.append(&message(1)).unwrap();
+        drop(spool);
+
+        let path = segment_path(&config.dir, 0);
+        let len = fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 10)
+            .unwrap();
+
+        let mut spool = Spool::open(&config).unwrap();
+        assert_eq!(spool.pending(), 1);
+        spool.append(&message(2)).unwrap();
+        let ids: Vec<_> = spool
+            .peek(10)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.message.msg_id)
+            .collect();
+        assert_eq!(ids, vec!["run-1.ch00.0", "run-1.ch00.2"]);
+        fs::remove_dir_all(&config.dir).unwrap();
+    }
+}
@@ -1,34 +1,55 @@
-use async_nats;
 use async_nats::jetstream;
 use async_nats::jetstream::consumer::pull;
 use async_nats::ConnectOptions;
 use async_nats::jetstream::kv::Operation;
 use chrono::{DateTime, NaiveDate, Utc};
-use futures_util::StreamExt;
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use futures_util::{Stream, StreamExt};
 use parquet::{
     column::writer::ColumnWriter,
-    file::{metadata::KeyValue, properties::WriterProperties, writer::SerializedFileWriter},
-    schema::parser::parse_message_type,
+    file::metadata::KeyValue,
+    schema::{parser::parse_message_type, types::TypePtr},
 };
 use std::{
-    collections::HashMap,
-    fs,
+    collections::{BTreeSet, HashMap, hash_map::Entry},
+    fmt, fs,
     path::{Path, PathBuf},
     sync::Arc,
 };
 use tokio::sync::watch;
 use tokio::time::Duration;
+use tracing::{Instrument, error, info, info_span, warn};
+
+/// How long `main` waits after Ctrl+C or SIGTERM for the loggers to flush and
+/// close their parquet files. The ctl scripts send SIGKILL after ten seconds.
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(8);
+/// How often buffered rows are flushed so their messages can be acked well
+/// inside the consumers' 30 second ack wait.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+/// Unacked messages a logger holds before flushing early, below JetStream's
+/// default `max_ack_pending` of 1000.
+const MAX_PENDING_ACKS: usize = 500;
 
 mod calibration;
+mod clock_quality;
+mod logging;
+mod metrics;
 mod nats_config;
+mod parquet_part;
+mod scan_frame;
+mod sequence_tracker;
 mod subjects;
 mod sample_data_generated {
     #![allow(dead_code, unused_imports)]
     include!("data_generated.rs");
 }
-use sample_data_generated::sampler;
 
 use calibration::CalibrationSpec;
+use parquet_part::PartWriter;
+use clock_quality::ClockQuality;
+use scan_frame::{ChannelSamples, PublishMode};
+use sequence_tracker::{SequenceCheck, SequenceTracker};
 use serde::{Deserialize, Serialize};
 
 #[allow(dead_code)]
@@ -48,9 +69,27 @@ struct NestedConfig {
     nats_subject: String,
     nats_stream: String,
     rotate_secs: u64,
+    #[serde(default)]
+    publish_mode: PublishMode,
+    #[serde(default)]
+    acquisition: AcquisitionConfig,
     sensor_settings: SensorConfig,
 }
 
+/// The archiver only needs to know whether the streamer publishes captures;
+/// the trigger itself is the streamer's business.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct AcquisitionConfig {
+    #[serde(default)]
+    mode: Option<String>,
+}
+
+impl AcquisitionConfig {
+    fn publishes_captures(&self) -> bool {
+        self.mode.as_deref().is_some_and(|mode| mode != "continuous")
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize)]
 struct SensorConfig {
@@ -64,6 +103,14 @@ struct SensorConfig {
     measurement_units: Vec<String>,
     labjack_on_off: bool,
     calibrations: Option<HashMap<String, CalibrationSpec>>,
+    #[serde(default)]
+    virtual_channels: Vec<VirtualChannelConfig>,
+}
+
+/// The archiver only needs the id of each streamer virtual channel.
+#[derive(Debug, Clone, Deserialize)]
+struct VirtualChannelConfig {
+    id: u8,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -71,6 +118,7 @@ struct SampleConfig {
     scans_per_read: i32,
     scan_rate_hz: f64,
     channels: Vec<u8>,
+    virtual_channels: Vec<u8>,
     asset_number: u32,
     labjack_name: String,
     site_id: Option<String>,
@@ -81,6 +129,8 @@ struct SampleConfig {
     nats_stream: String,
     rotate_secs: u64,
     calibrations: HashMap<u8, CalibrationSpec>,
+    publish_mode: PublishMode,
+    captures: bool,
 }
 
 impl From<(SensorConfig, &SampleConfig)> for SampleConfig {
@@ -90,6 +140,7 @@ impl From<(SensorConfig, &SampleConfig)> for SampleConfig {
             scans_per_read: raw.scans_per_read,
             scan_rate_hz: raw.scan_rate_hz,
             channels: raw.channels_enabled,
+            virtual_channels: raw.virtual_channels.iter().map(|v| v.id).collect(),
             asset_number: base.asset_number,
             labjack_name: base.labjack_name.clone(),
             site_id: base.site_id.clone(),
@@ -100,26 +151,14 @@ impl From<(SensorConfig, &SampleConfig)> for SampleConfig {
             nats_stream: base.nats_stream.clone(),
             rotate_secs: base.rotate_secs,
             calibrations,
+            publish_mode: base.publish_mode,
+            captures: base.captures,
         }
     }
 }
 
 fn parse_calibrations(raw: &SensorConfig) -> HashMap<u8, CalibrationSpec> {
-    let mut out = HashMap::new();
-    let Some(calibrations) = raw.calibrations.as_ref() else {
-        return out;
-    };
-    for (key, spec) in calibrations {
-        match key.parse::<u8>() {
-            Ok(ch) => {
-                out.insert(ch, spec.clone());
-            }
-            Err(_) => {
-                eprintln!("[logger] Invalid calibration channel key '{key}', expected u8.");
-            }
-        }
-    }
-    out
+    calibration::parse_channel_calibrations(raw.calibrations.as_ref())
 }
 
 fn sample_config_from_nested(nested: NestedConfig) -> SampleConfig {
@@ -129,6 +168,7 @@ fn sample_config_from_nested(nested: NestedConfig) -> SampleConfig {
         scans_per_read: raw.scans_per_read,
         scan_rate_hz: raw.scan_rate_hz,
         channels: raw.channels_enabled,
+        virtual_channels: raw.virtual_channels.iter().map(|v| v.id).collect(),
         asset_number: nested.asset_number,
         labjack_name: nested.labjack_name,
         site_id: nested.site_id,
@@ -139,22 +179,148 @@ fn sample_config_from_nested(nested: NestedConfig) -> SampleConfig {
         nats_stream: nested.nats_stream,
         rotate_secs: nested.rotate_secs,
         calibrations,
+        publish_mode: nested.publish_mode,
+        captures: nested.acquisition.publishes_captures(),
     }
 }
 
-#[allow(dead_code)]
+/// A channel the archiver writes to its own parquet directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ArchivedChannel {
+    Analog(u8),
+    Virtual(u8),
+}
+
+impl ArchivedChannel {
+    /// Directory and subject token: `ch11`, `vch0`.
+    fn token(self) -> String {
+        match self {
+            Self::Analog(ch) => subjects::pad_channel(ch),
+            Self::Virtual(id) => subjects::pad_virtual(id),
+        }
+    }
+
+    /// Analog channels keep the bare number in their durable consumer name,
+    /// so existing consumers resume where they left off.
+    fn consumer_token(self) -> String {
+        match self {
+            Self::Analog(ch) => ch.to_string(),
+            Self::Virtual(_) => self.token(),
+        }
+    }
+}
+
+impl fmt::Display for ArchivedChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Analog(ch) => write!(f, "Channel {ch:02}"),
+            Self::Virtual(id) => write!(f, "Virtual channel {id}"),
+        }
+    }
+}
+
+fn rows_written(channel: &str, stream: &str) -> Arc<metrics::Counter> {
+    metrics::global().counter(
+        "archiver_rows_written_total",
+        "Rows written to parquet, by channel and by live or capture stream.",
+        &[("channel", channel), ("stream", stream)],
+    )
+}
+
+fn sequence_gaps(channel: &str) -> Arc<metrics::Counter> {
+    metrics::global().counter(
+        "archiver_sequence_gaps_total",
+        "Batches that arrived after a gap in their run's sequence numbers.",
+        &[("channel", channel)],
+    )
+}
+
+fn flush_seconds() -> Arc<metrics::Histogram> {
+    metrics::global().histogram(
+        "archiver_parquet_flush_seconds",
+        "Time to write one buffered row group to its parquet file.",
+        &[],
+        metrics::LATENCY_BUCKETS,
+    )
+}
+
+fn ack_failures(consumer: &str) -> Arc<metrics::Counter> {
+    metrics::global().counter(
+        "archiver_ack_failures_total",
+        "JetStream messages whose ack failed and will be redelivered.",
+        &[("consumer", consumer)],
+    )
+}
+
+fn files_open() -> Arc<metrics::Gauge> {
+    metrics::global().gauge("archiver_files_open", "Parquet files open for writing.", &[])
+}
+
+/// Virtual channels are computed from volts and are never calibrated.
+fn channel_calibration(cfg: &SampleConfig, channel: ArchivedChannel) -> CalibrationSpec {
+    match channel {
+        ArchivedChannel::Analog(ch) => cfg.calibrations.get(&ch).cloned().unwrap_or_default(),
+        ArchivedChannel::Virtual(_) => CalibrationSpec::default(),
+    }
+}
+
+/// Channels that get their own logger: all of them in channel mode, none in
+/// frame mode, where the frame logger writes every channel.
+fn channel_mode_channels(cfg: &SampleConfig) -> Vec<ArchivedChannel> {
+    match cfg.publish_mode {
+        PublishMode::Channel => archived_channels(cfg),
+        PublishMode::Frame => Vec::new(),
+    }
+}
+
+/// Analog inputs followed by the streamer's virtual channels.
+fn archived_channels(cfg: &SampleConfig) -> Vec<ArchivedChannel> {
+    cfg.channels
+        .iter()
+        .map(|ch| ArchivedChannel::Analog(*ch))
+        .chain(cfg.virtual_channels.iter().map(|id| ArchivedChannel::Virtual(*id)))
+        .collect()
+}
+
 struct ParquetLogger {
-    writer: SerializedFileWriter<fs::File>,
+    writer: PartWriter,
     buffer: Vec<(i64, f64)>,
     max_rows: usize,
     date: NaiveDate,
-    asset: u32,
-    channel: u8,
-    file_index: usize,
+    /// Worst host clock quality and largest error bound among rows written.
+    clock_quality: Option<ClockQuality>,
+    clock_error_bound_ns: i64,
+    flush_seconds: Arc<metrics::Histogram>,
 }
 
-struct ChannelLogger {
+/// A logger task that closes its open parquet files when asked to stop,
+/// rather than being aborted mid-write.
+struct LoggerTask {
     handle: tokio::task::JoinHandle<()>,
+    stop_tx: watch::Sender<bool>,
+}
+
+impl LoggerTask {
+    fn spawn<F>(task: impl FnOnce(watch::Receiver<bool>) -> F) -> Self
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let handle = tokio::spawn(task(stop_rx));
+        Self { handle, stop_tx }
+    }
+
+    /// Signals the task and waits for it to flush and close its files.
+    async fn stop(self) {
+        let _ = self.stop_tx.send(true);
+        if let Err(err) = self.handle.await {
+            error!("Logger task failed: {err}");
+        }
+    }
+}
+
+struct ChannelLogger {
+    task: LoggerTask,
     calibration_tx: watch::Sender<CalibrationSpec>,
     calibration: CalibrationSpec,
     subject: String,
@@ -167,121 +333,184 @@ struct ChannelLogger {
 impl ParquetLogger {
     fn new(
         asset: u32,
-        channel: u8,
+        channel: ArchivedChannel,
         file_index: usize,
         date: NaiveDate,
         calibration: CalibrationSpec,
         parquet_root: &Path,
-    ) -> Self {
+    ) -> parquet::errors::Result<Self> {
         let dir = parquet_root
             .join(format!("asset{:03}", asset))
             .join(date.format("%Y-%m-%d").to_string())
-            .join(format!("ch{:02}", channel));
+            .join(channel.token());
 
-        fs::create_dir_all(&dir).unwrap();
+        fs::create_dir_all(&dir)?;
         let file_path = dir.join(format!("part-{:04}.parquet", file_index));
-
-        let message_type = "
-            message schema {
-                REQUIRED INT64 timestamp_unix_ns;
-                REQUIRED DOUBLE value;
-            }
-        ";
-        let schema = Arc::new(parse_message_type(message_type).unwrap());
         let calibration_json =
             serde_json::to_string(&calibration).unwrap_or_else(|_| "{}".to_string());
-        let props = Arc::new(
-            WriterProperties::builder()
-                .set_key_value_metadata(Some(vec![KeyValue::new(
-                    "calibration".to_string(),
-                    calibration_json,
-                )]))
-                .build(),
-        );
-        let file = fs::File::create(file_path).unwrap();
-        let writer = SerializedFileWriter::new(file, schema, props).unwrap();
+        Self::create(
+            &file_path,
+            date,
+            vec![KeyValue::new("calibration".to_string(), calibration_json)],
+            Vec::new(),
+        )
+    }
 
-        Self {
+    /// Opens a timestamp/value file at `file_path` with `metadata` written
+    /// into its footer. `interrupted` is added to the footer instead of the
+    /// close-time entries if the file has to be salvaged after a crash.
+    fn create(
+        file_path: &Path,
+        date: NaiveDate,
+        metadata: Vec<KeyValue>,
+        mut interrupted: Vec<KeyValue>,
+    ) -> parquet::errors::Result<Self> {
+        interrupted.extend([
+            KeyValue::new(
+                "clock_quality".to_string(),
+                ClockQuality::Unknown.as_str().to_string(),
+            ),
+            KeyValue::new("clock_error_bound_ns".to_string(), "-1".to_string()),
+        ]);
+        let writer = PartWriter::create(file_path, timeseries_schema(), metadata, interrupted)?;
+        files_open().add(1.0);
+
+        Ok(Self {
             writer,
             buffer: Vec::with_capacity(1000),
             max_rows: 1000,
             date,
-            asset,
-            channel,
-            file_index,
-        }
+            clock_quality: None,
+            clock_error_bound_ns: -1,
+            flush_seconds: flush_seconds(),
+        })
+    }
+
+    fn observe_clock(&mut self, quality: ClockQuality, error_bound_ns: i64) {
+        self.clock_quality = self.clock_quality.max(Some(quality));
+        self.clock_error_bound_ns = self.clock_error_bound_ns.max(error_bound_ns);
     }
 
     fn write_row(&mut self, timestamp_unix_ns: i64, val: f64) {
         self.buffer.push((timestamp_unix_ns, val));
+    }
+
+    /// Writes a row group once `max_rows` are buffered, without syncing it;
+    /// `flush` syncs it on the flush interval, before its messages are acked.
+    fn flush_if_full(&mut self) -> parquet::errors::Result<()> {
         if self.buffer.len() >= self.max_rows {
-            self.flush();
+            self.write_buffer()?;
         }
+        Ok(())
     }
 
-    fn flush(&mut self) {
-        if self.buffer.is_empty() {
-            return;
-        }
-        let mut rg = self.writer.next_row_group().unwrap();
+    /// Whether every row written so far is synced to disk.
+    fn is_durable(&self) -> bool {
+        self.buffer.is_empty() && self.writer.is_synced()
+    }
 
-        // column 0: timestamps
-        {
-            let mut scw = rg.next_column().unwrap().expect("timestamp col");
-            let mut cw = scw.untyped();
-            if let ColumnWriter::Int64ColumnWriter(typed) = &mut cw {
-                let values: Vec<i64> = self.buffer.iter().map(|(ts, _)| *ts).collect();
-                typed.write_batch(&values, None, None).unwrap();
-            }
-            scw.close().unwrap();
-        }
+    /// Writes the buffered rows and syncs the file.
+    fn flush(&mut self) -> parquet::errors::Result<()> {
+        self.write_buffer()?;
+        self.writer.sync()
+    }
 
-        // column 1: values
-        {
-            let mut scw = rg.next_column().unwrap().expect("value col");
-            let mut cw = scw.untyped();
-            if let ColumnWriter::DoubleColumnWriter(typed) = &mut cw {
-                let values: Vec<f64> = self.buffer.iter().map(|(_, v)| *v).collect();
-                typed.write_batch(&values, None, None).unwrap();
-            }
-            scw.close().unwrap();
+    fn write_buffer(&mut self) -> parquet::errors::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
         }
+        let started = std::time::Instant::now();
+        let buffer = &self.buffer;
+        self.writer
+            .write_row_group(|rg| {
+                // column 0: timestamps
+                {
+                    let mut scw = rg.next_column()?.expect("timestamp col");
+                    let mut cw = scw.untyped();
+                    if let ColumnWriter::Int64ColumnWriter(typed) = &mut cw {
+                        let values: Vec<i64> = buffer.iter().map(|(ts, _)| *ts).collect();
+                        typed.write_batch(&values, None, None)?;
+                    }
+                    scw.close()?;
+                }
 
-        rg.close().unwrap();
+                // column 1: values
+                {
+                    let mut scw = rg.next_column()?.expect("value col");
+                    let mut cw = scw.untyped();
+                    if let ColumnWriter::DoubleColumnWriter(typed) = &mut cw {
+                        let values: Vec<f64> = buffer.iter().map(|(_, v)| *v).collect();
+                        typed.write_batch(&values, None, None)?;
+                    }
+                    scw.close()?;
+                }
+                Ok(())
+            })?;
         self.buffer.clear();
+        self.flush_seconds.observe(started.elapsed().as_secs_f64());
+        Ok(())
     }
 
-    fn close(mut self) {
-        self.flush();
-        if let Err(e) = self.writer.close() {
-            eprintln!("Failed to close parquet file: {e}");
-        }
+    fn append_metadata(&mut self, key: &str, value: String) {
+        self.writer
+            .append_key_value_metadata(KeyValue::new(key.to_string(), value));
+    }
+
+    /// Writes the footer; the rows are durable only if this succeeds.
+    fn close(mut self) -> parquet::errors::Result<()> {
+        files_open().add(-1.0);
+        self.write_buffer()?;
+        let clock_quality = self.clock_quality.unwrap_or(ClockQuality::Unknown);
+        self.writer.append_key_value_metadata(KeyValue::new(
+            "clock_quality".to_string(),
+            clock_quality.as_str().to_string(),
+        ));
+        self.writer.append_key_value_metadata(KeyValue::new(
+            "clock_error_bound_ns".to_string(),
+            self.clock_error_bound_ns.to_string(),
+        ));
+        self.writer.close()?;
+        Ok(())
     }
 }
 
+fn timeseries_schema() -> TypePtr {
+    let message_type = "
+        message schema {
+            REQUIRED INT64 timestamp_unix_ns;
+            REQUIRED DOUBLE value;
+        }
+    ";
+    Arc::new(parse_message_type(message_type).unwrap())
+}
+
 /// Scan channel/day directory to find the next available parquet file index
-fn next_file_index(parquet_root: &Path, asset: u32, channel: u8, date: NaiveDate) -> usize {
+fn next_file_index(
+    parquet_root: &Path,
+    asset: u32,
+    channel: ArchivedChannel,
+    date: NaiveDate,
+) -> std::io::Result<usize> {
     let dir = parquet_root
         .join(format!("asset{:03}", asset))
         .join(date.format("%Y-%m-%d").to_string())
-        .join(format!("ch{:02}", channel));
+        .join(channel.token());
 
-    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::create_dir_all(&dir)?;
     let mut max_idx = 0;
-    for entry in std::fs::read_dir(&dir).unwrap() {
-        if let Ok(e) = entry {
-            if let Some(name) = e.file_name().to_str() {
-                if let Some(num) = name
-                    .strip_prefix("part-")
-                    .and_then(|s| s.strip_suffix(".parquet"))
-                    .and_then(|s| s.parse::<usize>().ok())
-                {
-                    max_idx = max_idx.max(num);
-                }
-            }
+    for e in std::fs::read_dir(&dir)?.flatten() {
+        if let Some(num) = e
+            .file_name()
+            .to_str()
+            .map(|name| name.trim_end_matches(parquet_part::IN_PROGRESS_SUFFIX))
+            .and_then(|name| name.strip_prefix("part-"))
+            .and_then(|s| s.strip_suffix(".parquet"))
+            .and_then(|s| s.parse::<usize>().ok())
+        {
+            max_idx = max_idx.max(num);
         }
     }
-    max_idx + 1
+    Ok(max_idx + 1)
 }
 
 fn sanitize_consumer_token(raw: &str) -> String {
@@ -302,7 +531,12 @@ fn sanitize_consumer_token(raw: &str) -> String {
     }
 }
 
-fn archiver_consumer_name(cfg: &SampleConfig, channel: u8) -> String {
+fn archiver_consumer_name(cfg: &SampleConfig, channel: ArchivedChannel) -> String {
+    archiver_source_consumer_name(cfg, &channel.consumer_token())
+}
+
+/// `archiver-<box>-<type>-<source>-<suffix>`.
+fn archiver_source_consumer_name(cfg: &SampleConfig, suffix: &str) -> String {
     format!(
         "archiver-{}-{}-{}-{}",
         sanitize_consumer_token(cfg.box_id.as_deref().unwrap_or("unknown-box")),
@@ -312,241 +546,1155 @@ fn archiver_consumer_name(cfg: &SampleConfig, channel: u8) -> String {
                 .as_deref()
                 .unwrap_or(cfg.labjack_name.as_str())
         ),
-        channel
+        suffix
     )
 }
 
-fn process_scan_payload(
-    payload: &[u8],
-    channel: u8,
+/// The subject the source publishes `channel` on, or the first analog
+/// channel's subject for a virtual channel; the base for the other subjects.
+fn source_channel_subject(cfg: &SampleConfig, channel: ArchivedChannel) -> String {
+    let analog = match channel {
+        ArchivedChannel::Analog(ch) => ch,
+        ArchivedChannel::Virtual(_) => cfg.channels.first().copied().unwrap_or_default(),
+    };
+    subjects::live_labjack_channel_subject(
+        &cfg.nats_subject,
+        cfg.asset_number,
+        analog,
+        cfg.site_id.as_deref(),
+        cfg.box_id.as_deref(),
+        Some(&cfg.labjack_name),
+        cfg.source_type.as_deref(),
+        cfg.source_id.as_deref(),
+    )
+}
+
+/// Subject a channel logger consumes in channel mode.
+fn archiver_subject(cfg: &SampleConfig, channel: ArchivedChannel) -> String {
+    let subject = source_channel_subject(cfg, channel);
+    match channel {
+        ArchivedChannel::Analog(_) => subject,
+        ArchivedChannel::Virtual(_) => subjects::source_event_subject(&subject, &channel.token()),
+    }
+}
+
+/// Durable consumer for a frame-mode source. It has its own name, since an
+/// existing durable keeps the filter subject it was created with.
+fn archiver_frame_consumer_name(cfg: &SampleConfig) -> String {
+    archiver_source_consumer_name(cfg, "frame")
+}
+
+/// The source's `.frame` subject, beside its channel subjects.
+fn archiver_frame_subject(cfg: &SampleConfig) -> String {
+    let first = ArchivedChannel::Analog(cfg.channels.first().copied().unwrap_or_default());
+    subjects::frame_subject(&source_channel_subject(cfg, first))
+}
+
+/// Durable consumer for a source's captures.
+fn archiver_capture_consumer_name(cfg: &SampleConfig) -> String {
+    archiver_source_consumer_name(cfg, "capture")
+}
+
+/// The source's `capture` subject, beside its channel subjects.
+fn archiver_capture_subject(cfg: &SampleConfig) -> String {
+    let first = ArchivedChannel::Analog(cfg.channels.first().copied().unwrap_or_default());
+    subjects::source_event_subject(&source_channel_subject(cfg, first), "capture")
+}
+
+fn log_legacy_sequence(channel: ArchivedChannel, sequence: u64, last_sequence: Option<u64>) {
+    match last_sequence {
+        Some(previous) if sequence == previous + 1 => {}
+        Some(previous) if sequence > previous + 1 => {
+            warn!(
+                expected = previous + 1,
+                got = sequence,
+                "sequence gap: expected {}, got {}",
+                previous + 1,
+                sequence
+            );
+            sequence_gaps(&channel.token()).inc();
+        }
+        Some(previous) if sequence <= previous => {
+            info!(
+                "sequence reset/new run: previous {}, current {}",
+                previous,
+                sequence
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Where one archived channel's rows go: its open parquet file, the
+/// calibration stamped into it and the batch sequences already written.
+struct ChannelSink {
+    channel: ArchivedChannel,
     asset: u32,
-    parquet_root: &Path,
-    active_calibration: &CalibrationSpec,
-    logger: &mut Option<ParquetLogger>,
-    file_index: &mut usize,
-    last_sequence: &mut Option<u64>,
-) {
-    if let Ok(scan) = flatbuffers::root::<sampler::Scan>(payload) {
-        let sequence = scan.sequence();
-        match *last_sequence {
-            Some(previous) if sequence == previous + 1 => {}
-            Some(previous) if sequence > previous + 1 => {
-                eprintln!(
-                    "[logger] Channel {channel:02} sequence gap: expected {}, got {}",
-                    previous + 1,
-                    sequence
-                );
+    parquet_root: PathBuf,
+    calibration: CalibrationSpec,
+    logger: Option<ParquetLogger>,
+    file_index: usize,
+    state_path: PathBuf,
+    sequences: SequenceTracker,
+    last_sequence: Option<u64>,
+    /// Entered around writes, so a frame logger's lines name the channel.
+    span: tracing::Span,
+    rows_written: Arc<metrics::Counter>,
+    sequence_gaps: Arc<metrics::Counter>,
+}
+
+impl ChannelSink {
+    fn open(
+        parquet_root: &Path,
+        asset: u32,
+        channel: ArchivedChannel,
+        calibration: CalibrationSpec,
+        span: tracing::Span,
+    ) -> Self {
+        let state_path = sequence_tracker::state_path(parquet_root, asset, &channel.token());
+        Self {
+            channel,
+            asset,
+            parquet_root: parquet_root.to_path_buf(),
+            calibration,
+            logger: None,
+            file_index: 0,
+            sequences: SequenceTracker::load(&state_path),
+            state_path,
+            last_sequence: None,
+            span,
+            rows_written: rows_written(&channel.token(), "live"),
+            sequence_gaps: sequence_gaps(&channel.token()),
+        }
+    }
+
+    /// Appends one batch, unless its sequence was already written. A batch
+    /// that fails to open or write its file is not recorded as written, so
+    /// its redelivery is.
+    fn write(&mut self, samples: &ChannelSamples) -> parquet::errors::Result<()> {
+        let recorded = (self.sequences.clone(), self.last_sequence);
+        let written = self.write_rows(samples);
+        if written.is_err() {
+            (self.sequences, self.last_sequence) = recorded;
+        }
+        written
+    }
+
+    fn write_rows(&mut self, samples: &ChannelSamples) -> parquet::errors::Result<()> {
+        let span = self.span.clone();
+        let _entered = span.enter();
+        let channel = self.channel;
+        let sequence = samples.sequence;
+        if let Some(run_id) = samples.run_id.as_deref() {
+            match self.sequences.check(run_id, sequence) {
+                SequenceCheck::Duplicate => {
+                    info!(
+                        "discarding already-written sequence {} of run {}",
+                        sequence, run_id
+                    );
+                    return Ok(());
+                }
+                SequenceCheck::NewRun => {
+                    info!("new run {} starting at sequence {}", run_id, sequence);
+                }
+                SequenceCheck::Gap { expected } => {
+                    warn!(
+                        expected,
+                        got = sequence,
+                        "sequence gap: expected {}, got {}",
+                        expected, sequence
+                    );
+                    self.sequence_gaps.inc();
+                }
+                SequenceCheck::Late => {
+                    info!("late sequence {} fills an earlier gap", sequence);
+                }
+                SequenceCheck::InOrder => {}
             }
-            Some(previous) if sequence <= previous => {
-                println!(
-                    "[logger] Channel {channel:02} sequence reset/new run: previous {}, current {}",
-                    previous,
-                    sequence
-                );
+        } else {
+            // Older streamers do not stamp a run id, so only gaps are reported.
+            log_legacy_sequence(channel, sequence, self.last_sequence);
+        }
+        self.last_sequence = Some(sequence);
+
+        let clock_quality = samples
+            .clock_quality
+            .as_deref()
+            .and_then(ClockQuality::parse)
+            .unwrap_or(ClockQuality::Unknown);
+        let mut written = 0;
+        for (index, v) in samples.values.iter().copied().enumerate() {
+            let timestamp_unix_ns = match sample_timestamp_ns(
+                samples.first_sample_unix_ns,
+                samples.sample_interval_ns,
+                index,
+            ) {
+                Ok(ts) => ts,
+                Err(err) => {
+                    warn!(
+                        "timestamp overflow at sequence {} sample {}: {}",
+                        sequence,
+                        index,
+                        err
+                    );
+                    break;
+                }
+            };
+
+            let sample_date = timestamp_ns_to_utc_date(timestamp_unix_ns);
+            if self.logger.as_ref().map(|l| l.date != sample_date).unwrap_or(true) {
+                self.close_file()?;
+                self.file_index = next_file_index(&self.parquet_root, self.asset, channel, sample_date)?;
+                self.logger = Some(ParquetLogger::new(
+                    self.asset,
+                    channel,
+                    self.file_index,
+                    sample_date,
+                    self.calibration.clone(),
+                    &self.parquet_root,
+                )?);
+            }
+
+            if let Some(log) = self.logger.as_mut() {
+                log.observe_clock(clock_quality, samples.clock_error_bound_ns);
+                log.write_row(timestamp_unix_ns, v);
+                written += 1;
             }
-            _ => {}
         }
-        *last_sequence = Some(sequence);
+        if let Some(log) = self.logger.as_mut() {
+            log.flush_if_full()?;
+        }
+        self.rows_written.inc_by(written);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> parquet::errors::Result<()> {
+        match self.logger.as_mut() {
+            Some(l) => l.flush(),
+            None => Ok(()),
+        }
+    }
 
-        if let Some(vals) = scan.values() {
-            let first_sample_unix_ns = scan.first_sample_unix_ns();
-            let sample_interval_ns = scan.sample_interval_ns();
+    fn is_durable(&self) -> bool {
+        self.logger.as_ref().is_none_or(ParquetLogger::is_durable)
+    }
 
-            for (index, v) in vals.iter().enumerate() {
-                let timestamp_unix_ns = match sample_timestamp_ns(
-                    first_sample_unix_ns,
-                    sample_interval_ns,
-                    index,
-                ) {
-                    Ok(ts) => ts,
-                    Err(err) => {
-                        eprintln!(
-                            "[logger] Channel {channel:02} timestamp overflow at sequence {} sample {}: {}",
-                            sequence,
-                            index,
-                            err
+    fn save_sequences(&self) {
+        if self.sequences != SequenceTracker::default()
+            && let Err(err) = self.sequences.save(&self.state_path)
+        {
+            warn!(
+                "failed to save sequence state {}: {}",
+                self.state_path.display(),
+                err
+            );
+        }
+    }
+
+    fn close_file(&mut self) -> parquet::errors::Result<()> {
+        if let Some(l) = self.logger.take() {
+            let _entered = self.span.enter();
+            l.close()?;
+            info!("Closed file {}", self.file_index);
+        }
+        Ok(())
+    }
+
+    /// Closes the open file; the next batch starts a new one.
+    fn rotate(&mut self) -> parquet::errors::Result<()> {
+        self.close_file()
+    }
+
+    /// Closes the open file so the next one is stamped with `updated`, and no
+    /// file mixes two calibrations.
+    fn recalibrate(&mut self, updated: CalibrationSpec) -> parquet::errors::Result<()> {
+        self.close_file()?;
+        let _entered = self.span.enter();
+        info!("Calibration updated for {}; rotating file.", self.channel);
+        self.calibration = updated;
+        Ok(())
+    }
+}
+
+fn process_scan_payload(payload: &[u8], sink: &mut ChannelSink) -> parquet::errors::Result<()> {
+    match scan_frame::decode_scan(payload) {
+        Ok(samples) => sink.write(&samples),
+        Err(err) => {
+            warn!("received invalid FlatBuffer payload: {err}");
+            Ok(())
+        }
+    }
+}
+
+/// Decodes a frame once and hands each archived channel its column.
+fn process_frame_payload(payload: &[u8], sinks: &mut [ChannelSink]) -> parquet::errors::Result<()> {
+    let columns = match scan_frame::decode_frame(payload) {
+        Ok(columns) => columns,
+        Err(err) => {
+            warn!("received invalid FlatBuffer payload: {err}");
+            return Ok(());
+        }
+    };
+    for sink in sinks {
+        let token = sink.channel.token();
+        match columns.iter().find(|(name, _)| *name == token) {
+            Some((_, samples)) => sink.write(samples)?,
+            None => {
+                let _entered = sink.span.enter();
+                warn!("missing from received frame; skipping");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A JetStream message as the loggers see it, so their loops can be driven
+/// without a server.
+#[async_trait]
+trait Delivery: Send {
+    fn payload(&self) -> &[u8];
+    async fn ack(&self) -> Result<(), String>;
+}
+
+#[async_trait]
+impl Delivery for jetstream::Message {
+    fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    async fn ack(&self) -> Result<(), String> {
+        jetstream::Message::ack(self).await.map_err(|e| e.to_string())
+    }
+}
+
+/// An interval whose first tick is one `period` out, rather than immediate.
+fn delayed_interval(period: Duration) -> tokio::time::Interval {
+    tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+}
+
+/// Saves the sequence state of `sinks` and acks `pending` once every sink's
+/// rows are synced, so after a crash JetStream redelivers the batches that
+/// never reached disk. Does nothing while any rows are unsynced.
+async fn commit_batches<'a>(
+    pending: &mut Vec<impl Delivery>,
+    sinks: impl IntoIterator<Item = &'a ChannelSink> + Clone,
+    consumer_name: &str,
+) {
+    if pending.is_empty() || !sinks.clone().into_iter().all(ChannelSink::is_durable) {
+        return;
+    }
+    for sink in sinks {
+        sink.save_sequences();
+    }
+    ack_pending(pending, consumer_name).await;
+}
+
+/// Logs why a logger is stopping. Its unacked messages are redelivered once
+/// it is restarted, and written then.
+fn log_write_failure(err: &parquet::errors::ParquetError) {
+    error!("Failed to write parquet file: {err}; stopping logger without acking");
+}
+
+async fn ack_pending(pending: &mut Vec<impl Delivery>, consumer_name: &str) {
+    for msg in pending.drain(..) {
+        if let Err(err) = msg.ack().await {
+            warn!("Failed to ack JetStream message: {}", err);
+            ack_failures(consumer_name).inc();
+        }
+    }
+}
+
+async fn create_consumer(
+    js: &jetstream::Context,
+    stream_name: &str,
+    consumer_name: &str,
+    subject: &str,
+) -> Result<jetstream::consumer::Consumer<pull::Config>, Box<dyn std::error::Error>> {
+    let stream = js.get_stream(stream_name).await?;
+    Ok(stream
+        .get_or_create_consumer(
+            consumer_name,
+            pull::Config {
+                durable_name: Some(consumer_name.to_string()),
+                filter_subject: subject.to_string(),
+                ack_policy: jetstream::consumer::AckPolicy::Explicit,
+                ack_wait: Duration::from_secs(30),
+                ..Default::default()
+            },
+        )
+        .await?)
+}
+
+/// Writes `messages` to `sink` until stopped, then closes its file and acks
+/// the messages it wrote.
+async fn run_channel_logger<M: Delivery, E: fmt::Display>(
+    mut messages: impl Stream<Item = Result<M, E>> + Unpin,
+    mut stop_rx: watch::Receiver<bool>,
+    mut calibration_rx: watch::Receiver<CalibrationSpec>,
+    mut sink: ChannelSink,
+    rotate_secs: u64,
+    subject: &str,
+    consumer_name: &str,
+) {
+    let mut ticker = delayed_interval(Duration::from_secs(rotate_secs));
+    // Acked only once their rows are synced; see `commit_batches`.
+    let mut pending: Vec<M> = Vec::new();
+    let mut flush_ticker = delayed_interval(FLUSH_INTERVAL);
+
+    let mut failed = false;
+    loop {
+        let written = tokio::select! {
+            _ = stop_rx.changed() => break,
+            maybe = messages.next() => {
+                match maybe {
+                    Some(Ok(msg)) => {
+                        process_scan_payload(msg.payload(), &mut sink).and_then(|()| {
+                            pending.push(msg);
+                            if pending.len() >= MAX_PENDING_ACKS {
+                                sink.flush()
+                            } else {
+                                Ok(())
+                            }
+                        })
+                    }
+                    Some(Err(err)) => {
+                        warn!(
+                            "JetStream consumer '{}' error on {}: {}",
+                            consumer_name, subject, err
                         );
                         break;
                     }
-                };
-
-                let sample_date = timestamp_ns_to_utc_date(timestamp_unix_ns);
-                if logger.as_ref().map(|l| l.date != sample_date).unwrap_or(true) {
-                    if let Some(l) = logger.take() {
-                        l.close();
-                        println!("[logger] Closed file {}", *file_index);
+                    None => {
+                        warn!("JetStream consumer '{}' ended for {}", consumer_name, subject);
+                        break;
                     }
-                    *file_index = next_file_index(parquet_root, asset, channel, sample_date);
-                    *logger = Some(ParquetLogger::new(
-                        asset,
-                        channel,
-                        *file_index,
-                        sample_date,
-                        active_calibration.clone(),
-                        parquet_root,
-                    ));
                 }
-
-                if let Some(log) = logger.as_mut() {
-                    log.write_row(timestamp_unix_ns, v);
+            }
+            _ = flush_ticker.tick() => sink.flush(),
+            _ = ticker.tick() => sink.rotate(),
+            changed = calibration_rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let updated = calibration_rx.borrow().clone();
+                if updated != sink.calibration {
+                    sink.recalibrate(updated)
+                } else {
+                    Ok(())
                 }
             }
+        };
+        if let Err(err) = written {
+            log_write_failure(&err);
+            failed = true;
+            break;
         }
-    } else {
-        eprintln!("[logger] Channel {channel:02} received invalid FlatBuffer payload");
+        commit_batches(&mut pending, [&sink], consumer_name).await;
+    }
+    match sink.close_file() {
+        Ok(()) if !failed => commit_batches(&mut pending, [&sink], consumer_name).await,
+        Ok(()) => {}
+        Err(err) => log_write_failure(&err),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn spawn_channel_logger(
     js: jetstream::Context,
     stream_name: String,
     consumer_name: String,
     subject: String,
     asset: u32,
-    channel: u8,
+    channel: ArchivedChannel,
     rotate_secs: u64,
     calibration: CalibrationSpec,
     parquet_root: PathBuf,
 ) -> Result<ChannelLogger, Box<dyn std::error::Error>> {
-    let stream = js.get_stream(stream_name.as_str()).await?;
-    let consumer = stream
-        .get_or_create_consumer(
-            consumer_name.as_str(),
-            pull::Config {
-                durable_name: Some(consumer_name.clone()),
-                filter_subject: subject.clone(),
-                ack_policy: jetstream::consumer::AckPolicy::Explicit,
-                ack_wait: Duration::from_secs(30),
-                ..Default::default()
-            },
-        )
-        .await?;
+    let consumer = create_consumer(&js, &stream_name, &consumer_name, &subject).await?;
 
     let logger_subject = subject.clone();
     let logger_consumer_name = consumer_name.clone();
-    let (calibration_tx, mut calibration_rx) = watch::channel(calibration.clone());
+    let (calibration_tx, calibration_rx) = watch::channel(calibration.clone());
     let calibration_for_task = calibration.clone();
-    let handle = tokio::spawn(async move {
-        let mut messages = match consumer.messages().await {
+    let span = info_span!(
+        "channel_logger",
+        channel = %channel.token(),
+        subject = %subject,
+        consumer = %consumer_name
+    );
+    let task = LoggerTask::spawn(|stop_rx| async move {
+        let messages = match consumer.messages().await {
             Ok(messages) => messages,
             Err(err) => {
-                eprintln!(
-                    "[logger] Failed to attach JetStream consumer '{}' for {}: {}",
+                error!(
+                    "Failed to attach JetStream consumer '{}' for {}: {}",
                     logger_consumer_name, logger_subject, err
                 );
                 return;
             }
         };
-        println!(
-            "[logger] Attached JetStream consumer '{}' to {}",
+        info!(
+            "Attached JetStream consumer '{}' to {}",
             logger_consumer_name, logger_subject
         );
-
-        let mut ticker = tokio::time::interval(Duration::from_secs(rotate_secs));
-        let mut logger: Option<ParquetLogger> = None;
-        let mut file_index =
-            next_file_index(&parquet_root, asset, channel, Utc::now().date_naive());
-        let mut active_calibration = calibration_for_task;
-        let mut last_sequence: Option<u64> = None;
-
-        loop {
-            tokio::select! {
-                maybe = messages.next() => {
+        let sink = ChannelSink::open(
+            &parquet_root,
+            asset,
+            channel,
+            calibration_for_task,
+            tracing::Span::none(),
+        );
+        run_channel_logger(
+            messages,
+            stop_rx,
+            calibration_rx,
+            sink,
+            rotate_secs,
+            &logger_subject,
+            &logger_consumer_name,
+        )
+        .await;
+    }
+    .instrument(span));
+
+    Ok(ChannelLogger {
+        task,
+        calibration_tx,
+        calibration,
+        subject,
+        stream_name,
+        consumer_name,
+        asset,
+        rotate_secs,
+    })
+}
+
+/// Everything a frame logger depends on apart from calibrations, which are
+/// applied to the running task; a change respawns it.
+#[derive(Debug, Clone, PartialEq)]
+struct FrameSettings {
+    subject: String,
+    stream_name: String,
+    consumer_name: String,
+    asset: u32,
+    rotate_secs: u64,
+    channels: Vec<ArchivedChannel>,
+}
+
+impl FrameSettings {
+    /// `None` when the source publishes one subject per channel.
+    fn from_config(cfg: &SampleConfig) -> Option<Self> {
+        (cfg.publish_mode == PublishMode::Frame).then(|| Self {
+            subject: archiver_frame_subject(cfg),
+            stream_name: cfg.nats_stream.clone(),
+            consumer_name: archiver_frame_consumer_name(cfg),
+            asset: cfg.asset_number,
+            rotate_secs: cfg.rotate_secs,
+            channels: archived_channels(cfg),
+        })
+    }
+}
+
+fn frame_calibrations(cfg: &SampleConfig) -> HashMap<ArchivedChannel, CalibrationSpec> {
+    archived_channels(cfg)
+        .into_iter()
+        .map(|ch| (ch, channel_calibration(cfg, ch)))
+        .collect()
+}
+
+/// Archives a frame-mode source: one durable consumer pulls each frame once
+/// and fans its columns out to the per-channel files.
+struct FrameLogger {
+    task: LoggerTask,
+    settings: FrameSettings,
+    calibration_tx: watch::Sender<HashMap<ArchivedChannel, CalibrationSpec>>,
+}
+
+async fn spawn_frame_logger(
+    js: jetstream::Context,
+    settings: FrameSettings,
+    calibrations: HashMap<ArchivedChannel, CalibrationSpec>,
+    parquet_root: PathBuf,
+) -> Result<FrameLogger, Box<dyn std::error::Error>> {
+    let consumer = create_consumer(
+        &js,
+        &settings.stream_name,
+        &settings.consumer_name,
+        &settings.subject,
+    )
+    .await?;
+
+    let task_settings = settings.clone();
+    let (calibration_tx, mut calibration_rx) = watch::channel(calibrations.clone());
+    let span = info_span!(
+        "frame_logger",
+        subject = %settings.subject,
+        consumer = %settings.consumer_name
+    );
+    let task = LoggerTask::spawn(|mut stop_rx| async move {
+        let settings = task_settings;
+        let mut messages = match consumer.messages().await {
+            Ok(messages) => messages,
+            Err(err) => {
+                error!(
+                    "Failed to attach JetStream consumer '{}' for {}: {}",
+                    settings.consumer_name, settings.subject, err
+                );
+                return;
+            }
+        };
+        info!(
+            "Attached JetStream consumer '{}' to {}",
+            settings.consumer_name, settings.subject
+        );
+
+        let mut sinks: Vec<ChannelSink> = settings
+            .channels
+            .iter()
+            .map(|ch| {
+                ChannelSink::open(
+                    &parquet_root,
+                    settings.asset,
+                    *ch,
+                    calibrations.get(ch).cloned().unwrap_or_default(),
+                    info_span!("channel", channel = %ch.token()),
+                )
+            })
+            .collect();
+        let mut ticker = delayed_interval(Duration::from_secs(settings.rotate_secs));
+        // Acked once every channel's rows are synced; see `commit_batches`.
+        let mut pending: Vec<jetstream::Message> = Vec::new();
+        let mut flush_ticker = delayed_interval(FLUSH_INTERVAL);
+
+        let mut failed = false;
+        loop {
+            let written = tokio::select! {
+                _ = stop_rx.changed() => break,
+                maybe = messages.next() => {
                     match maybe {
                         Some(Ok(msg)) => {
-                            process_scan_payload(
-                                &msg.payload,
-                                channel,
-                                asset,
-                                &parquet_root,
-                                &active_calibration,
-                                &mut logger,
-                                &mut file_index,
-                                &mut last_sequence,
-                            );
-                            if let Err(err) = msg.ack().await {
-                                eprintln!(
-                                    "[logger] Failed to ack JetStream message for channel {channel:02}: {}",
-                                    err
-                                );
-                            }
+                            process_frame_payload(msg.payload(), &mut sinks).and_then(|()| {
+                                pending.push(msg);
+                                if pending.len() >= MAX_PENDING_ACKS {
+                                    sinks.iter_mut().try_for_each(ChannelSink::flush)
+                                } else {
+                                    Ok(())
+                                }
+                            })
                         }
                         Some(Err(err)) => {
-                            eprintln!(
-                                "[logger] JetStream consumer '{}' error on {}: {}",
-                                logger_consumer_name, logger_subject, err
+                            warn!(
+                                "JetStream consumer '{}' error on {}: {}",
+                                settings.consumer_name, settings.subject, err
                             );
                             break;
                         }
                         None => {
-                            eprintln!(
-                                "[logger] JetStream consumer '{}' ended for {}",
-                                logger_consumer_name, logger_subject
+                            warn!(
+                                "JetStream consumer '{}' ended for {}",
+                                settings.consumer_name, settings.subject
                             );
                             break;
                         }
                     }
                 }
-                _ = ticker.tick() => {
-                    let today = Utc::now().date_naive();
-                    if let Some(l) = logger.take() {
-                        l.close();
-                        println!("[logger] Closed file {}", file_index);
-                    }
-                    file_index += 1;
-                    logger = Some(ParquetLogger::new(
-                        asset,
-                        channel,
-                        file_index,
-                        today,
-                        active_calibration.clone(),
-                        &parquet_root,
-                    ));
-                }
+                _ = flush_ticker.tick() => sinks.iter_mut().try_for_each(ChannelSink::flush),
+                _ = ticker.tick() => sinks.iter_mut().try_for_each(ChannelSink::rotate),
                 changed = calibration_rx.changed() => {
                     if changed.is_err() {
                         break;
                     }
                     let updated = calibration_rx.borrow().clone();
-                    if updated != active_calibration {
-                        let today = Utc::now().date_naive();
-                        if let Some(l) = logger.take() {
-                            l.close();
-                            println!("[logger] Closed file {}", file_index);
-                            file_index += 1;
+                    sinks.iter_mut().try_for_each(|sink| {
+                        let calibration = updated.get(&sink.channel).cloned().unwrap_or_default();
+                        if calibration != sink.calibration {
+                            sink.recalibrate(calibration)
                         } else {
-                            file_index = next_file_index(&parquet_root, asset, channel, today);
+                            Ok(())
                         }
-                        println!(
-                            "[logger] Calibration updated for channel {channel:02}; rotating file."
-                        );
-                        logger = Some(ParquetLogger::new(
-                            asset,
-                            channel,
-                            file_index,
-                            today,
-                            updated.clone(),
-                            &parquet_root,
-                        ));
-                        active_calibration = updated;
-                    }
+                    })
                 }
+            };
+            if let Err(err) = written {
+                log_write_failure(&err);
+                failed = true;
+                break;
             }
+            commit_batches(&mut pending, &sinks, &settings.consumer_name).await;
         }
-    });
+        // Every file is closed, even after one fails.
+        let closed: Vec<_> = sinks.iter_mut().map(ChannelSink::close_file).collect();
+        match closed.into_iter().find_map(Result::err) {
+            None if !failed => commit_batches(&mut pending, &sinks, &settings.consumer_name).await,
+            None => {}
+            Some(err) => log_write_failure(&err),
+        }
+    }
+    .instrument(span));
 
-    Ok(ChannelLogger {
-        handle,
+    Ok(FrameLogger {
+        task,
+        settings,
         calibration_tx,
-        calibration,
-        subject,
-        stream_name,
-        consumer_name,
-        asset,
-        rotate_secs,
     })
 }
 
+/// Everything a capture logger depends on; a change respawns it.
+#[derive(Debug, Clone, PartialEq)]
+struct CaptureSettings {
+    subject: String,
+    stream_name: String,
+    consumer_name: String,
+    asset: u32,
+    rotate_secs: u64,
+    calibrations: HashMap<u8, CalibrationSpec>,
+}
+
+impl CaptureSettings {
+    /// `None` when the source streams continuously.
+    fn from_config(cfg: &SampleConfig) -> Option<Self> {
+        cfg.captures.then(|| Self {
+            subject: archiver_capture_subject(cfg),
+            stream_name: cfg.nats_stream.clone(),
+            consumer_name: archiver_capture_consumer_name(cfg),
+            asset: cfg.asset_number,
+            rotate_secs: cfg.rotate_secs,
+            calibrations: cfg.calibrations.clone(),
+        })
+    }
+}
+
+struct CaptureLogger {
+    task: LoggerTask,
+    settings: CaptureSettings,
+}
+
+/// Frames of one capture already written to parquet.
+///
+/// Saved before their messages are acked, like `SequenceTracker` for
+/// channels, so a frame redelivered after its capture was closed complete is
+/// discarded instead of starting a new file set. A capture closed incomplete
+/// resumes in the same directory without rewriting the frames it has.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CaptureState {
+    dir: PathBuf,
+    date: NaiveDate,
+    frames: BTreeSet<u64>,
+    written_scans: u64,
+    complete: bool,
+}
+
+fn capture_state_path(parquet_root: &Path, asset: u32, capture_id: &str) -> PathBuf {
+    parquet_root
+        .join("captures")
+        .join(format!("asset{:03}", asset))
+        .join(format!("{capture_id}.capture.json"))
+}
+
+impl CaptureState {
+    /// Loads saved state, or `None` when the capture has none yet or it is
+    /// unreadable.
+    fn load(path: &Path) -> Option<Self> {
+        let bytes = fs::read(path).ok()?;
+        serde_json::from_slice(&bytes)
+            .inspect_err(|e| {
+                warn!(
+                    "Ignoring unreadable capture state {}: {}",
+                    path.display(),
+                    e
+                )
+            })
+            .ok()
+    }
+
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(tmp, path)
+    }
+}
+
+/// The file set of one capture: one parquet file per channel in
+/// `captures/assetNNN/<date>/<capture id>/`.
+struct CaptureFiles {
+    metadata: Vec<KeyValue>,
+    loggers: HashMap<String, ParquetLogger>,
+    state_path: PathBuf,
+    state: CaptureState,
+    /// Frames written since `state` was last saved.
+    unsaved: bool,
+    capture_scans: u64,
+    last_frame: tokio::time::Instant,
+}
+
+impl CaptureFiles {
+    fn flush(&mut self) -> parquet::errors::Result<()> {
+        self.loggers.values_mut().try_for_each(ParquetLogger::flush)
+    }
+
+    fn is_durable(&self) -> bool {
+        self.loggers.values().all(ParquetLogger::is_durable)
+    }
+
+    /// Records the written frames; call once they are durable and before
+    /// their messages are acked.
+    fn save_state(&mut self) {
+        if !self.unsaved {
+            return;
+        }
+        match self.state.save(&self.state_path) {
+            Ok(()) => self.unsaved = false,
+            Err(err) => warn!(
+                "Failed to save capture state {}: {}",
+                self.state_path.display(),
+                err
+            ),
+        }
+    }
+
+    /// Closes every file, returning the first error once all were tried.
+    fn close_files(&mut self, complete: bool) -> parquet::errors::Result<usize> {
+        let mut closed = Ok(self.loggers.len());
+        for (_, mut logger) in self.loggers.drain() {
+            logger.append_metadata("capture_complete", complete.to_string());
+            if let Err(err) = logger.close()
+                && closed.is_ok()
+            {
+                closed = Err(err);
+            }
+        }
+        closed
+    }
+
+    /// Closes the files and records the capture as closed, unless a file
+    /// failed to close; its frames are then redelivered and resume it.
+    fn close(mut self, capture_id: &str, complete: bool) -> parquet::errors::Result<()> {
+        let files = self.close_files(complete)?;
+        self.state.complete = complete;
+        self.unsaved = true;
+        self.save_state();
+        if complete {
+            info!(
+                "Closed capture {capture_id}: {} scans in {files} file(s) under {}",
+                self.state.written_scans,
+                self.state.dir.display()
+            );
+        } else {
+            warn!(
+                "Closed incomplete capture {capture_id}: {} of {} scans under {}",
+                self.state.written_scans,
+                self.capture_scans,
+                self.state.dir.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Saves the state of the open captures and acks `pending` once all of their
+/// files are synced. Does nothing while any rows are unsynced.
+async fn commit_captures(
+    pending: &mut Vec<jetstream::Message>,
+    captures: &mut HashMap<String, CaptureFiles>,
+    consumer_name: &str,
+) {
+    if pending.is_empty() || !captures.values().all(CaptureFiles::is_durable) {
+        return;
+    }
+    captures.values_mut().for_each(CaptureFiles::save_state);
+    ack_pending(pending, consumer_name).await;
+}
+
+fn process_capture_payload(
+    payload: &[u8],
+    asset: u32,
+    parquet_root: &Path,
+    calibrations: &HashMap<u8, CalibrationSpec>,
+    captures: &mut HashMap<String, CaptureFiles>,
+) -> parquet::errors::Result<()> {
+    let frame = match scan_frame::decode_capture_frame(payload) {
+        Ok(frame) => frame,
+        Err(err) => {
+            warn!("Received invalid capture frame: {err}");
+            return Ok(());
+        }
+    };
+    let Some((_, first)) = frame.columns.first() else {
+        return Ok(());
+    };
+    let sequence = first.sequence;
+    let frame_scans = first.values.len() as u64;
+
+    let files = match captures.entry(frame.capture_id.clone()) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let state_path = capture_state_path(parquet_root, asset, &frame.capture_id);
+            let state = match CaptureState::load(&state_path) {
+                Some(state) if state.complete => {
+                    info!(
+                        "Discarding frame {} of capture {}, which is already closed",
+                        sequence, frame.capture_id
+                    );
+                    return Ok(());
+                }
+                Some(state) => {
+                    info!(
+                        "Resuming capture {} in {} ({} frame(s) already written)",
+                        frame.capture_id,
+                        state.dir.display(),
+                        state.frames.len()
+                    );
+                    state
+                }
+                None => {
+                    let date = i64::try_from(first.first_sample_unix_ns)
+                        .map(timestamp_ns_to_utc_date)
+                        .unwrap_or_else(|_| Utc::now().date_naive());
+                    let dir = parquet_root
+                        .join("captures")
+                        .join(format!("asset{:03}", asset))
+                        .join(date.format("%Y-%m-%d").to_string())
+                        .join(&frame.capture_id);
+                    info!("Writing capture {} to {}", frame.capture_id, dir.display());
+                    CaptureState {
+                        dir,
+                        date,
+                        frames: BTreeSet::new(),
+                        written_scans: 0,
+                        complete: false,
+                    }
+                }
+            };
+            fs::create_dir_all(&state.dir)?;
+            let metadata = vec![
+                KeyValue::new("capture_id".to_string(), frame.capture_id.clone()),
+                KeyValue::new(
+                    "run_id".to_string(),
+                    first.run_id.clone().unwrap_or_default(),
+                ),
+                KeyValue::new("capture_scans".to_string(), frame.capture_scans.to_string()),
+                KeyValue::new(
+                    "pre_trigger_scans".to_string(),
+                    frame.pre_trigger_scans.to_string(),
+                ),
+            ];
+            entry.insert(CaptureFiles {
+                metadata,
+                loggers: HashMap::new(),
+                state_path,
+                state,
+                unsaved: false,
+                capture_scans: frame.capture_scans,
+                last_frame: tokio::time::Instant::now(),
+            })
+        }
+    };
+    files.last_frame = tokio::time::Instant::now();
+    if files.state.frames.contains(&sequence) {
+        info!(
+            "Discarding already-written frame {} of capture {}",
+            sequence, frame.capture_id
+        );
+        return Ok(());
+    }
+
+    // Every file is opened before any row is written, so a frame that fails
+    // here is not recorded and its redelivery is written in full.
+    for (name, _) in &frame.columns {
+        if let Entry::Vacant(entry) = files.loggers.entry(name.clone()) {
+            let calibration = name
+                .strip_prefix("ch")
+                .and_then(|n| n.parse::<u8>().ok())
+                .and_then(|ch| calibrations.get(&ch))
+                .cloned()
+                .unwrap_or_default();
+            let mut metadata = files.metadata.clone();
+            metadata.push(KeyValue::new(
+                "calibration".to_string(),
+                serde_json::to_string(&calibration).unwrap_or_else(|_| "{}".to_string()),
+            ));
+            entry.insert(ParquetLogger::create(
+                &files.state.dir.join(format!("{name}.parquet")),
+                files.state.date,
+                metadata,
+                vec![KeyValue::new("capture_complete".to_string(), "false".to_string())],
+            )?);
+        }
+    }
+    files.state.frames.insert(sequence);
+
+    for (name, samples) in &frame.columns {
+        let Some(logger) = files.loggers.get_mut(name) else {
+            continue;
+        };
+        let clock_quality = samples
+            .clock_quality
+            .as_deref()
+            .and_then(ClockQuality::parse)
+            .unwrap_or(ClockQuality::Unknown);
+        logger.observe_clock(clock_quality, samples.clock_error_bound_ns);
+        let mut written = 0;
+        for (index, v) in samples.values.iter().copied().enumerate() {
+            match sample_timestamp_ns(samples.first_sample_unix_ns, samples.sample_interval_ns, index) {
+                Ok(ts) => {
+                    logger.write_row(ts, v);
+                    written += 1;
+                }
+                Err(err) => {
+                    warn!(
+                        "Timestamp overflow in capture {} frame {}: {}",
+                        frame.capture_id, sequence, err
+                    );
+                    break;
+                }
+            }
+        }
+        if let Err(err) = logger.flush_if_full() {
+            files.state.frames.remove(&sequence);
+            return Err(err);
+        }
+        rows_written(name, "capture").inc_by(written);
+    }
+    files.state.written_scans += frame_scans;
+    files.unsaved = true;
+
+    if files.state.written_scans >= files.capture_scans
+        && let Some(files) = captures.remove(&frame.capture_id)
+    {
+        files.close(&frame.capture_id, true)?;
+    }
+    Ok(())
+}
+
+async fn spawn_capture_logger(
+    js: jetstream::Context,
+    settings: CaptureSettings,
+    parquet_root: PathBuf,
+) -> Result<CaptureLogger, Box<dyn std::error::Error>> {
+    let consumer = create_consumer(
+        &js,
+        &settings.stream_name,
+        &settings.consumer_name,
+        &settings.subject,
+    )
+    .await?;
+
+    let task_settings = settings.clone();
+    let span = info_span!(
+        "capture_logger",
+        subject = %settings.subject,
+        consumer = %settings.consumer_name
+    );
+    let task = LoggerTask::spawn(|mut stop_rx| async move {
+        let settings = task_settings;
+        let mut messages = match consumer.messages().await {
+            Ok(messages) => messages,
+            Err(err) => {
+                error!(
+                    "Failed to attach JetStream consumer '{}' for {}: {}",
+                    settings.consumer_name, settings.subject, err
+                );
+                return;
+            }
+        };
+        info!(
+            "Attached JetStream consumer '{}' to {}",
+            settings.consumer_name, settings.subject
+        );
+
+        // Captures that stop receiving frames for a rotation period are
+        // closed as incomplete rather than held open forever.
+        let idle = Duration::from_secs(settings.rotate_secs);
+        let mut ticker = delayed_interval(idle);
+        let mut captures: HashMap<String, CaptureFiles> = HashMap::new();
+        let mut pending: Vec<jetstream::Message> = Vec::new();
+        let mut flush_ticker = delayed_interval(FLUSH_INTERVAL);
+        let mut failed = false;
+        loop {
+            let written = tokio::select! {
+                _ = stop_rx.changed() => break,
+                maybe = messages.next() => {
+                    match maybe {
+                        Some(Ok(msg)) => {
+                            process_capture_payload(
+                                msg.payload(),
+                                settings.asset,
+                                &parquet_root,
+                                &settings.calibrations,
+                                &mut captures,
+                            )
+                            .and_then(|()| {
+                                pending.push(msg);
+                                if pending.len() >= MAX_PENDING_ACKS {
+                                    captures.values_mut().try_for_each(CaptureFiles::flush)
+                                } else {
+                                    Ok(())
+                                }
+                            })
+                        }
+                        Some(Err(err)) => {
+                            warn!(
+                                "JetStream consumer '{}' error on {}: {}",
+                                settings.consumer_name, settings.subject, err
+                            );
+                            break;
+                        }
+                        None => {
+                            warn!(
+                                "JetStream consumer '{}' ended for {}",
+                                settings.consumer_name, settings.subject
+                            );
+                            break;
+                        }
+                    }
+                }
+                _ = flush_ticker.tick() => captures.values_mut().try_for_each(CaptureFiles::flush),
+                _ = ticker.tick() => {
+                    let stale: Vec<String> = captures
+                        .iter()
+                        .filter(|(_, files)| files.last_frame.elapsed() >= idle)
+                        .map(|(id, _)| id.clone())
+                        .collect();
+                    stale.into_iter().try_for_each(|id| match captures.remove(&id) {
+                        Some(files) => files.close(&id, false),
+                        None => Ok(()),
+                    })
+                }
+            };
+            if let Err(err) = written {
+                log_write_failure(&err);
+                failed = true;
+                break;
+            }
+            commit_captures(&mut pending, &mut captures, &settings.consumer_name).await;
+        }
+        if failed {
+            // Closed without recording their frames, so the unacked ones
+            // are written when redelivered.
+            for (_, mut files) in captures.drain() {
+                if let Err(err) = files.close_files(false) {
+                    log_write_failure(&err);
+                }
+            }
+        } else {
+            let closed: Vec<_> = captures
+                .drain()
+                .map(|(id, files)| files.close(&id, false))
+                .collect();
+            match closed.into_iter().find_map(Result::err) {
+                None => ack_pending(&mut pending, &settings.consumer_name).await,
+                Some(err) => log_write_failure(&err),
+            }
+        }
+    }
+    .instrument(span));
+
+    Ok(CaptureLogger { task, settings })
+}
+
 fn sample_timestamp_ns(
     first_sample_unix_ns: u64,
     sample_interval_ns: u64,
@@ -562,12 +1710,208 @@ fn timestamp_ns_to_utc_date(timestamp_unix_ns: i64) -> NaiveDate {
     DateTime::<Utc>::from_timestamp_nanos(timestamp_unix_ns).date_naive()
 }
 
+/// Brings the running loggers in line with `new_cfg`. Loggers that are
+/// removed or respawned are stopped first, so their files get a footer.
+async fn apply_config(
+    js: &jetstream::Context,
+    parquet_root: &Path,
+    new_cfg: &SampleConfig,
+    active: &mut HashMap<ArchivedChannel, ChannelLogger>,
+    frame_logger: &mut Option<FrameLogger>,
+    capture_logger: &mut Option<CaptureLogger>,
+) {
+    let frame_settings = FrameSettings::from_config(new_cfg);
+    let frame_calibrations = frame_calibrations(new_cfg);
+    let frame_running = frame_logger.as_ref().is_some_and(|logger| {
+        Some(&logger.settings) == frame_settings.as_ref() && !logger.calibration_tx.is_closed()
+    });
+    if frame_running {
+        if let Some(logger) = frame_logger.as_ref() {
+            logger.calibration_tx.send_if_modified(|current| {
+                let modified = *current != frame_calibrations;
+                *current = frame_calibrations.clone();
+                modified
+            });
+        }
+    } else {
+        if let Some(logger) = frame_logger.take() {
+            info!("Stopping frame logger for {}", logger.settings.subject);
+            logger.task.stop().await;
+        }
+        if let Some(settings) = frame_settings {
+            match spawn_frame_logger(
+                js.clone(),
+                settings,
+                frame_calibrations,
+                parquet_root.to_path_buf(),
+            )
+            .await
+            {
+                Ok(logger) => *frame_logger = Some(logger),
+                Err(err) => error!("Failed to start frame logger: {err}"),
+            }
+        }
+    }
+
+    let capture_settings = CaptureSettings::from_config(new_cfg);
+    if capture_logger.as_ref().map(|logger| &logger.settings) != capture_settings.as_ref() {
+        if let Some(logger) = capture_logger.take() {
+            info!("Stopping capture logger for {}", logger.settings.subject);
+            logger.task.stop().await;
+        }
+        if let Some(settings) = capture_settings {
+            match spawn_capture_logger(js.clone(), settings, parquet_root.to_path_buf()).await {
+                Ok(logger) => *capture_logger = Some(logger),
+                Err(err) => error!("Failed to start capture logger: {err}"),
+            }
+        }
+    }
+
+    // remove old channels
+    let channels = channel_mode_channels(new_cfg);
+    let removed: Vec<ArchivedChannel> = active
+        .keys()
+        .filter(|ch| !channels.contains(ch))
+        .copied()
+        .collect();
+    for ch in removed {
+        if let Some(entry) = active.remove(&ch) {
+            info!("Removing channel {ch}");
+            entry.task.stop().await;
+        }
+    }
+
+    // add new channels
+    for ch in &channels {
+        let subject = archiver_subject(new_cfg, *ch);
+        let consumer_name = archiver_consumer_name(new_cfg, *ch);
+        let calibration = channel_calibration(new_cfg, *ch);
+
+        if !active.contains_key(ch) {
+            info!("Adding channel {ch}");
+            match spawn_channel_logger(
+                js.clone(),
+                new_cfg.nats_stream.clone(),
+                consumer_name,
+                subject,
+                new_cfg.asset_number,
+                *ch,
+                new_cfg.rotate_secs,
+                calibration,
+                parquet_root.to_path_buf(),
+            ).await {
+                Ok(h) => {
+                    active.insert(*ch, h);
+                }
+                Err(err) => {
+                    error!("Failed to add channel {ch}: {err}");
+                }
+            }
+        } else {
+            let mut needs_respawn = false;
+            if let Some(entry) = active.get_mut(ch) {
+                needs_respawn = entry.subject != subject
+                    || entry.stream_name != new_cfg.nats_stream
+                    || entry.consumer_name != consumer_name
+                    || entry.asset != new_cfg.asset_number
+                    || entry.rotate_secs != new_cfg.rotate_secs;
+                if entry.calibration != calibration {
+                    if needs_respawn {
+                        entry.calibration = calibration.clone();
+                    } else {
+                        if entry
+                            .calibration_tx
+                            .send(calibration.clone())
+                            .is_ok()
+                        {
+                            entry.calibration = calibration.clone();
+                        } else {
+                            needs_respawn = true;
+                        }
+                    }
+                }
+            }
+            if needs_respawn {
+                if let Some(entry) = active.remove(ch) {
+                    entry.task.stop().await;
+                }
+                match spawn_channel_logger(
+                    js.clone(),
+                    new_cfg.nats_stream.clone(),
+                    consumer_name,
+                    subject,
+                    new_cfg.asset_number,
+                    *ch,
+                    new_cfg.rotate_secs,
+                    calibration,
+                    parquet_root.to_path_buf(),
+                ).await {
+                    Ok(h) => {
+                        active.insert(*ch, h);
+                    }
+                    Err(err) => {
+                        error!(
+                            "Failed to respawn channel {ch}: {err}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Salvages or quarantines the in-progress files a crash left behind, before
+/// any logger opens new ones. Batches whose rows were lost were never acked,
+/// so their durable consumers redeliver them.
+fn recover_parquet(parquet_root: &Path) {
+    let recovery = parquet_part::recover(parquet_root, &timeseries_schema());
+    let outcomes = |outcome: &str| {
+        metrics::global().counter(
+            "archiver_recovered_files_total",
+            "In-progress parquet files found at startup, by outcome.",
+            &[("outcome", outcome)],
+        )
+    };
+    outcomes("salvaged").inc_by(recovery.salvaged.len() as u64);
+    outcomes("quarantined").inc_by(recovery.quarantined.len() as u64);
+    if recovery.salvaged.is_empty() && recovery.quarantined.is_empty() {
+        return;
+    }
+    let rows: i64 = recovery.salvaged.iter().map(|s| s.rows).sum();
+    info!(
+        "Recovered {} in-progress file(s) with {} row(s); quarantined {} under {}",
+        recovery.salvaged.len(),
+        rows,
+        recovery.quarantined.len(),
+        parquet_root.join(parquet_part::QUARANTINE_DIR).display()
+    );
+}
+
+/// Resolves on Ctrl+C or SIGTERM, naming the signal received.
+async fn shutdown_signal() -> std::io::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        let mut sigterm =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map(|()| "Ctrl+C"),
+            _ = sigterm.recv() => Ok("SIGTERM"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.map(|()| "Ctrl+C")
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init().map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
     let servers = nats_config::servers_from_env()
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
     let parquet_root =
         PathBuf::from(std::env::var("PARQUET_DIR").unwrap_or_else(|_| "parquet".into()));
+    recover_parquet(&parquet_root);
 
     // Connect using creds
     let creds_path = std::env::var("NATS_CREDS_FILE").unwrap_or_else(|_| "apt.creds".into());
@@ -580,8 +1924,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await
         .map_err(|e| format!("NATS connect failed: {}", e))?;
 
-    println!("Connected to NATS via creds!");
+    info!("Connected to NATS via creds!");
     let js = nats_config::jetstream_context(nc.clone());
+    files_open();
+    metrics::serve_from_env("archiver").await?;
 
     // Step 2: load config from KV
     let bucket = std::env::var("CFG_BUCKET").unwrap_or_else(|_| "avenabox".into());
@@ -592,25 +1938,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let nested = serde_json::from_slice::<NestedConfig>(&entry.value)?;
     let cfg: SampleConfig = sample_config_from_nested(nested);
 
-    println!("[logger] Loaded config: {:?}", cfg);
+    info!("Loaded config: {:?}", cfg);
 
     // Step 4: spawn dynamic watcher for KV config changes
     let mut watch = store.watch(key.as_str()).await?;
-    let mut active: HashMap<u8, ChannelLogger> = HashMap::new();
+    let mut active: HashMap<ArchivedChannel, ChannelLogger> = HashMap::new();
 
     // initial subscriptions
-    for ch in &cfg.channels {
-        let subject = subjects::live_labjack_channel_subject(
-            &cfg.nats_subject,
-            cfg.asset_number,
-            *ch,
-            cfg.site_id.as_deref(),
-            cfg.box_id.as_deref(),
-            Some(&cfg.labjack_name),
-            cfg.source_type.as_deref(),
-            cfg.source_id.as_deref(),
-        );
-        let calibration = cfg.calibrations.get(ch).cloned().unwrap_or_default();
+    for ch in &channel_mode_channels(&cfg) {
+        let subject = archiver_subject(&cfg, *ch);
+        let calibration = channel_calibration(&cfg, *ch);
         let consumer_name = archiver_consumer_name(&cfg, *ch);
         let h = spawn_channel_logger(
             js.clone(),
@@ -625,125 +1962,387 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         ).await?;
         active.insert(*ch, h);
     }
+    let mut frame_logger = match FrameSettings::from_config(&cfg) {
+        Some(settings) => Some(
+            spawn_frame_logger(js.clone(), settings, frame_calibrations(&cfg), parquet_root.clone())
+                .await?,
+        ),
+        None => None,
+    };
+    let mut capture_logger = match CaptureSettings::from_config(&cfg) {
+        Some(settings) => Some(spawn_capture_logger(js.clone(), settings, parquet_root.clone()).await?),
+        None => None,
+    };
 
-    tokio::spawn({
-        let js = js.clone();
-        async move {
-            println!("[logger] Watching KV for config changes...");
-            while let Some(ev) = watch.next().await {
-                if let Ok(entry) = ev {
-                    if entry.operation == Operation::Put {
-                        if let Ok(new_cfg) = serde_json::from_slice::<NestedConfig>(&entry.value)
-                            .map(sample_config_from_nested)
-                        {
-                            println!("[logger] KV config update detected: {:?}", new_cfg);
-
-                            // remove old channels
-                            active.retain(|ch, entry| {
-                                if new_cfg.channels.contains(ch) {
-                                    true
-                                } else {
-                                    println!("[logger] Removing channel {ch}");
-                                    entry.handle.abort();
-                                    false
-                                }
-                            });
-
-                            // add new channels
-                            for ch in &new_cfg.channels {
-                                let subject = subjects::live_labjack_channel_subject(
-                                    &new_cfg.nats_subject,
-                                    new_cfg.asset_number,
-                                    *ch,
-                                    new_cfg.site_id.as_deref(),
-                                    new_cfg.box_id.as_deref(),
-                                    Some(&new_cfg.labjack_name),
-                                    new_cfg.source_type.as_deref(),
-                                    new_cfg.source_id.as_deref(),
-                                );
-                                let consumer_name = archiver_consumer_name(&new_cfg, *ch);
-                                let calibration =
-                                    new_cfg.calibrations.get(ch).cloned().unwrap_or_default();
-
-                                if !active.contains_key(ch) {
-                                    println!("[logger] Adding channel {ch}");
-                                    match spawn_channel_logger(
-                                        js.clone(),
-                                        new_cfg.nats_stream.clone(),
-                                        consumer_name,
-                                        subject,
-                                        new_cfg.asset_number,
-                                        *ch,
-                                        new_cfg.rotate_secs,
-                                        calibration,
-                                        parquet_root.clone(),
-                                    ).await {
-                                        Ok(h) => {
-                                            active.insert(*ch, h);
-                                        }
-                                        Err(err) => {
-                                            eprintln!("[logger] Failed to add channel {ch}: {err}");
-                                        }
-                                    }
-                                } else {
-                                    let mut needs_respawn = false;
-                                    if let Some(entry) = active.get_mut(ch) {
-                                        needs_respawn = entry.subject != subject
-                                            || entry.stream_name != new_cfg.nats_stream
-                                            || entry.consumer_name != consumer_name
-                                            || entry.asset != new_cfg.asset_number
-                                            || entry.rotate_secs != new_cfg.rotate_secs;
-                                        if entry.calibration != calibration {
-                                            if needs_respawn {
-                                                entry.calibration = calibration.clone();
-                                            } else {
-                                                if entry
-                                                    .calibration_tx
-                                                    .send(calibration.clone())
-                                                    .is_ok()
-                                                {
-                                                    entry.calibration = calibration.clone();
-                                                } else {
-                                                    needs_respawn = true;
-                                                }
-                                            }
-                                        }
-                                    }
-                                    if needs_respawn {
-                                        if let Some(entry) = active.remove(ch) {
-                                            entry.handle.abort();
-                                        }
-                                        match spawn_channel_logger(
-                                            js.clone(),
-                                            new_cfg.nats_stream.clone(),
-                                            consumer_name,
-                                            subject,
-                                            new_cfg.asset_number,
-                                            *ch,
-                                            new_cfg.rotate_secs,
-                                            calibration,
-                                            parquet_root.clone(),
-                                        ).await {
-                                            Ok(h) => {
-                                                active.insert(*ch, h);
-                                            }
-                                            Err(err) => {
-                                                eprintln!(
-                                                    "[logger] Failed to respawn channel {ch}: {err}"
-                                                );
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    let mut watching = true;
+    info!("Watching KV for config changes...");
+    loop {
+        tokio::select! {
+            signal = &mut shutdown => {
+                info!("Received {}; shutting down logger...", signal?);
+                break;
+            }
+            ev = watch.next(), if watching => {
+                let Some(ev) = ev else {
+                    warn!("KV watch ended; no further config changes will apply");
+                    watching = false;
+                    continue;
+                };
+                if let Ok(entry) = ev
+                    && entry.operation == Operation::Put
+                    && let Ok(new_cfg) = serde_json::from_slice::<NestedConfig>(&entry.value)
+                        .map(sample_config_from_nested)
+                {
+                    info!("KV config update detected: {:?}", new_cfg);
+                    apply_config(
+                        &js,
+                        &parquet_root,
+                        &new_cfg,
+                        &mut active,
+                        &mut frame_logger,
+                        &mut capture_logger,
+                    )
+                    .await;
                 }
             }
         }
-    });
+    }
 
-    tokio::signal::ctrl_c().await?;
-    println!("Shutting down logger...");
+    let stopped = tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, async {
+        let mut tasks: Vec<LoggerTask> = active.into_values().map(|logger| logger.task).collect();
+        tasks.extend(frame_logger.map(|logger| logger.task));
+        tasks.extend(capture_logger.map(|logger| logger.task));
+        join_all(tasks.into_iter().map(LoggerTask::stop)).await;
+    })
+    .await;
+    match stopped {
+        Ok(()) => info!("All parquet files closed"),
+        Err(_) => error!("Timed out waiting for loggers to close their files; exiting anyway."),
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flatbuffers::FlatBufferBuilder;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use sample_data_generated::sampler::{self, ScanArgs};
+    use scan_frame::{FrameCapture, FrameIdentity};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct TestDelivery {
+        payload: Vec<u8>,
+        acked: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Delivery for TestDelivery {
+        fn payload(&self) -> &[u8] {
+            &self.payload
+        }
+
+        async fn ack(&self) -> Result<(), String> {
+            self.acked.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn scan(sequence: u64, values: &[f64]) -> Vec<u8> {
+        let mut builder = FlatBufferBuilder::new();
+        let values = builder.create_vector(values);
+        let run_id = builder.create_string("run");
+        let scan = sampler::Scan::create(
+            &mut builder,
+            &ScanArgs {
+                first_sample_unix_ns: 1_760_000_000_000_000_000 + sequence * 1_000,
+                sample_interval_ns: 100,
+                actual_scan_rate_hz: 10_000.0,
+                sequence,
+                values: Some(values),
+                channel: 0,
+                run_id: Some(run_id),
+                ..Default::default()
+            },
+        );
+        builder.finish(scan, None);
+        builder.finished_data().to_vec()
+    }
+
+    fn temp_root() -> PathBuf {
+        std::env::temp_dir().join(format!("rust-ljm-store-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn capture_frame(capture_id: &str, sequence: u64, values: &[f64], capture_scans: u64) -> Vec<u8> {
+        scan_frame::encode_frame(
+            &mut FlatBufferBuilder::new(),
+            1_760_000_000_000_000_000 + sequence * 300,
+            100,
+            10_000.0,
+            sequence,
+            values,
+            &FrameIdentity {
+                channels: &[0],
+                channel_names: &["ch00"],
+                run_id: "run",
+                device_serial: 470012345,
+                units: &["V"],
+                calibration_ids: None,
+                clock_quality: "synchronized",
+                clock_error_bound_ns: 1_000,
+                capture: Some(FrameCapture {
+                    id: capture_id,
+                    scans: capture_scans,
+                    pre_trigger_scans: 0,
+                }),
+            },
+        )
+    }
+
+    fn live_frame(sequence: u64, values: &[f64]) -> Vec<u8> {
+        scan_frame::encode_frame(
+            &mut FlatBufferBuilder::new(),
+            1_760_000_000_000_000_000 + sequence * 300,
+            100,
+            10_000.0,
+            sequence,
+            values,
+            &FrameIdentity {
+                channels: &[0, 1],
+                channel_names: &["ch00", "ch01"],
+                run_id: "run",
+                device_serial: 470012345,
+                units: &["V", "V"],
+                calibration_ids: None,
+                clock_quality: "synchronized",
+                clock_error_bound_ns: 1_000,
+                capture: None,
+            },
+        )
+    }
+
+    fn parquet_files(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn row_count(path: &Path) -> i64 {
+        SerializedFileReader::new(fs::File::open(path).unwrap())
+            .unwrap()
+            .metadata()
+            .file_metadata()
+            .num_rows()
+    }
+
+    #[tokio::test]
+    async fn capture_frames_redelivered_after_close_are_discarded() {
+        let root = temp_root();
+        let calibrations = HashMap::new();
+        let mut captures = HashMap::new();
+        let frames = [
+            capture_frame("cap-1", 0, &[0.1, 0.2, 0.3], 6),
+            capture_frame("cap-1", 1, &[0.4, 0.5, 0.6], 6),
+        ];
+        for frame in &frames {
+            process_capture_payload(frame, 7, &root, &calibrations, &mut captures).unwrap();
+        }
+        assert!(captures.is_empty(), "a complete capture is closed");
+        let state = CaptureState::load(&capture_state_path(&root, 7, "cap-1")).unwrap();
+        assert!(state.complete);
+        assert_eq!(state.frames, BTreeSet::from([0, 1]));
+        assert_eq!(parquet_files(&state.dir), vec!["ch00.parquet"]);
+
+        // A later redelivery, or one after an archiver restart, finds the
+        // capture already closed.
+        let mut restarted = HashMap::new();
+        process_capture_payload(&frames[1], 7, &root, &calibrations, &mut restarted).unwrap();
+        assert!(restarted.is_empty());
+        assert_eq!(parquet_files(&state.dir), vec!["ch00.parquet"]);
+        assert_eq!(row_count(&state.dir.join("ch00.parquet")), 6);
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[tokio::test]
+    async fn incomplete_captures_resume_without_rewriting_frames() {
+        let root = temp_root();
+        let calibrations = HashMap::new();
+        let mut captures = HashMap::new();
+        let first = capture_frame("cap-2", 0, &[0.1, 0.2, 0.3], 6);
+        process_capture_payload(&first, 7, &root, &calibrations, &mut captures).unwrap();
+        for (id, files) in captures.drain() {
+            files.close(&id, false).unwrap();
+        }
+
+        let mut restarted = HashMap::new();
+        process_capture_payload(&first, 7, &root, &calibrations, &mut restarted).unwrap();
+        let second = capture_frame("cap-2", 1, &[0.4, 0.5, 0.6], 6);
+        process_capture_payload(&second, 7, &root, &calibrations, &mut restarted).unwrap();
+        assert!(restarted.is_empty(), "the resumed capture completes");
+
+        let state = CaptureState::load(&capture_state_path(&root, 7, "cap-2")).unwrap();
+        assert!(state.complete);
+        assert_eq!(state.written_scans, 6);
+        assert_eq!(
+            parquet_files(&state.dir),
+            vec!["ch00-1.parquet", "ch00.parquet"]
+        );
+        assert_eq!(row_count(&state.dir.join("ch00.parquet")), 3);
+        assert_eq!(row_count(&state.dir.join("ch00-1.parquet")), 3);
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[tokio::test]
+    async fn frames_fan_out_once_to_each_channel_file() {
+        let root = temp_root();
+        let mut sinks: Vec<ChannelSink> = [ArchivedChannel::Analog(0), ArchivedChannel::Analog(1)]
+            .into_iter()
+            .map(|ch| {
+                ChannelSink::open(&root, 7, ch, CalibrationSpec::default(), tracing::Span::none())
+            })
+            .collect();
+        let frame = live_frame(0, &[0.1, 1.1, 0.2, 1.2, 0.3, 1.3]);
+        process_frame_payload(&frame, &mut sinks).unwrap();
+        // A redelivered frame is already in both files.
+        process_frame_payload(&frame, &mut sinks).unwrap();
+        sinks.iter_mut().try_for_each(ChannelSink::close_file).unwrap();
+
+        let date = timestamp_ns_to_utc_date(1_760_000_000_000_000_000);
+        for token in ["ch00", "ch01"] {
+            let dir = root
+                .join("asset007")
+                .join(date.format("%Y-%m-%d").to_string())
+                .join(token);
+            assert_eq!(parquet_files(&dir), vec!["part-0001.parquet"]);
+            assert_eq!(row_count(&dir.join("part-0001.parquet")), 3);
+        }
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[tokio::test]
+    async fn batches_whose_file_cannot_be_opened_are_not_recorded() {
+        let root = temp_root();
+        let date = timestamp_ns_to_utc_date(1_760_000_000_000_000_000);
+        let day = root.join("asset007").join(date.format("%Y-%m-%d").to_string());
+        fs::create_dir_all(&day).unwrap();
+        // A file where the channel directory belongs makes the open fail.
+        fs::write(day.join("ch00"), b"").unwrap();
+        let mut sink = ChannelSink::open(
+            &root,
+            7,
+            ArchivedChannel::Analog(0),
+            CalibrationSpec::default(),
+            tracing::Span::none(),
+        );
+        let samples = ChannelSamples {
+            run_id: Some("run".to_string()),
+            sequence: 0,
+            first_sample_unix_ns: 1_760_000_000_000_000_000,
+            sample_interval_ns: 100,
+            values: vec![0.1, 0.2, 0.3],
+            clock_quality: None,
+            clock_error_bound_ns: -1,
+        };
+        assert!(sink.write(&samples).is_err());
+        assert_eq!(sink.sequences, SequenceTracker::default());
+
+        // The redelivered batch is written once the file can be opened.
+        fs::remove_file(day.join("ch00")).unwrap();
+        sink.write(&samples).unwrap();
+        sink.close_file().unwrap();
+        assert_eq!(row_count(&day.join("ch00").join("part-0001.parquet")), 3);
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[tokio::test]
+    async fn stopping_a_channel_logger_closes_its_file_and_acks_buffered_batches() {
+        let root = temp_root();
+        let acked = Arc::new(AtomicUsize::new(0));
+        let batches: Vec<Result<TestDelivery, String>> = (0..3)
+            .map(|sequence| {
+                Ok(TestDelivery {
+                    payload: scan(sequence, &[0.1, 0.2, 0.3, 0.4]),
+                    acked: acked.clone(),
+                })
+            })
+            .collect();
+        // Stays open, so only the stop signal ends the logger.
+        let messages = futures_util::stream::iter(batches).chain(futures_util::stream::pending());
+        let sink = ChannelSink::open(
+            &root,
+            7,
+            ArchivedChannel::Analog(0),
+            CalibrationSpec::default(),
+            tracing::Span::none(),
+        );
+        let (_calibration_tx, calibration_rx) = watch::channel(CalibrationSpec::default());
+        let task = LoggerTask::spawn(|stop_rx| {
+            run_channel_logger(messages, stop_rx, calibration_rx, sink, 3600, "subject", "consumer")
+        });
+
+        let date = timestamp_ns_to_utc_date(1_760_000_000_000_000_000);
+        let dir = root
+            .join("asset007")
+            .join(date.format("%Y-%m-%d").to_string())
+            .join("ch00");
+        while !dir.join("part-0001.parquet.inprogress").exists() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        // The rows are still buffered, so nothing has been acked.
+        assert_eq!(acked.load(Ordering::SeqCst), 0);
+
+        task.stop().await;
+        assert_eq!(acked.load(Ordering::SeqCst), 3, "pending batches are acked");
+        assert_eq!(parquet_files(&dir), vec!["part-0001.parquet"]);
+        assert_eq!(row_count(&dir.join("part-0001.parquet")), 12);
+        let state_path = sequence_tracker::state_path(&root, 7, "ch00");
+        assert_ne!(SequenceTracker::load(&state_path), SequenceTracker::default());
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[tokio::test]
+    async fn batches_are_left_unacked_when_their_file_fails_to_close() {
+        let root = temp_root();
+        let acked = Arc::new(AtomicUsize::new(0));
+        let batches: Vec<Result<TestDelivery, String>> = vec![Ok(TestDelivery {
+            payload: scan(0, &[0.1, 0.2, 0.3, 0.4]),
+            acked: acked.clone(),
+        })];
+        let messages = futures_util::stream::iter(batches).chain(futures_util::stream::pending());
+        let sink = ChannelSink::open(
+            &root,
+            7,
+            ArchivedChannel::Analog(0),
+            CalibrationSpec::default(),
+            tracing::Span::none(),
+        );
+        let (_calibration_tx, calibration_rx) = watch::channel(CalibrationSpec::default());
+        let task = LoggerTask::spawn(|stop_rx| {
+            run_channel_logger(messages, stop_rx, calibration_rx, sink, 3600, "subject", "consumer")
+        });
+
+        let date = timestamp_ns_to_utc_date(1_760_000_000_000_000_000);
+        let dir = root
+            .join("asset007")
+            .join(date.format("%Y-%m-%d").to_string())
+            .join("ch00");
+        while !dir.join("part-0001.parquet.inprogress").exists() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        // Removing the directory makes the rename into place fail.
+        fs::remove_dir_all(&dir).unwrap();
+
+        task.stop().await;
+        assert_eq!(acked.load(Ordering::SeqCst), 0, "the batch is redelivered");
+        let state_path = sequence_tracker::state_path(&root, 7, "ch00");
+        assert_eq!(SequenceTracker::load(&state_path), SequenceTracker::default());
+        fs::remove_dir_all(root).ok();
+    }
+}
@@ -0,0 +1,220 @@
+use serde::Serialize;
+
+/// Value LJM writes into every address of a scan it could not deliver, e.g.
+/// while auto-recovering from a device buffer overflow.
+pub const SKIPPED_SCAN_VALUE: f64 = -9999.0;
+
+const DEFAULT_BACKLOG_WARN_READS: u32 = 4;
+
+/// A contiguous run of scans inside one `stream_read` batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchSegment {
+    Data { start_scan: usize, scans: usize },
+    Skipped { start_scan: usize, scans: usize },
+}
+
+fn is_skipped_scan(scan: &[f64]) -> bool {
+    scan.iter().all(|value| *value == SKIPPED_SCAN_VALUE)
+}
+
+/// Splits an interleaved batch into runs of real and skipped scans, in order.
+/// Only scans where every address reads `-9999` count as skipped, so a single
+/// channel legitimately sitting at that value is not mistaken for a gap.
+pub fn split_skipped_scans(batch: &[f64], num_channels: usize) -> Vec<BatchSegment> {
+    let mut segments: Vec<BatchSegment> = Vec::new();
+    if num_channels == 0 {
+        return segments;
+    }
+
+    for (index, scan) in batch.chunks(num_channels).enumerate() {
+        let skipped = is_skipped_scan(scan);
+        match segments.last_mut() {
+            Some(BatchSegment::Skipped { scans, .. }) if skipped => *scans += 1,
+            Some(BatchSegment::Data { scans, .. }) if !skipped => *scans += 1,
+            _ if skipped => segments.push(BatchSegment::Skipped {
+                start_scan: index,
+                scans: 1,
+            }),
+            _ => segments.push(BatchSegment::Data {
+                start_scan: index,
+                scans: 1,
+            }),
+        }
+    }
+    segments
+}
+
+/// Health event published on the source's `.health` subject.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HealthEvent {
+    SkippedScans {
+        run_id: usize,
+        first_skipped_unix_ns: u64,
+        skipped_scans: usize,
+        total_skipped_scans: u64,
+        device_backlog: i32,
+        ljm_backlog: i32,
+    },
+    BacklogHigh {
+        run_id: usize,
+        device_backlog: i32,
+        ljm_backlog: i32,
+        threshold_scans: i32,
+    },
+    BacklogRecovered {
+        run_id: usize,
+        device_backlog: i32,
+        ljm_backlog: i32,
+        peak_backlog: i32,
+    },
+}
+
+/// Tracks device + LJM backlog across reads and reports threshold crossings.
+///
+/// A warning fires when the combined backlog first exceeds the threshold and
+/// again each time it doubles, so a steadily growing backlog stays visible
+/// without logging every read. Falling back under half the threshold clears it.
+#[derive(Debug, Clone)]
+pub struct BacklogMonitor {
+    threshold_scans: i32,
+    next_warn_scans: i32,
+    peak_scans: i32,
+    elevated: bool,
+}
+
+impl BacklogMonitor {
+    pub fn new(threshold_scans: i32) -> Self {
+        let threshold_scans = threshold_scans.max(1);
+        Self {
+            threshold_scans,
+            next_warn_scans: threshold_scans,
+            peak_scans: 0,
+            elevated: false,
+        }
+    }
+
+    /// Threshold of `STREAM_BACKLOG_WARN_READS` reads' worth of scans,
+    /// defaulting to four reads.
+    pub fn from_env(scans_per_read: i32) -> Result<Self, String> {
+        let reads = match std::env::var("STREAM_BACKLOG_WARN_READS") {
+            Ok(raw) if !raw.trim().is_empty() => raw
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|reads| *reads > 0)
+                .ok_or_else(|| {
+                    format!(
+                        "invalid STREAM_BACKLOG_WARN_READS '{raw}', expected a positive integer"
+                    )
+                })?,
+            _ => DEFAULT_BACKLOG_WARN_READS,
+        };
+        let threshold = i64::from(scans_per_read.max(1)) * i64::from(reads);
+        Ok(Self::new(i32::try_from(threshold).unwrap_or(i32::MAX)))
+    }
+
+    pub fn threshold_scans(&self) -> i32 {
+        self.threshold_scans
+    }
+
+    pub fn observe(
+        &mut self,
+        run_id: usize,
+        device_backlog: i32,
+        ljm_backlog: i32,
+    ) -> Option<HealthEvent> {
+        let total = device_backlog.saturating_add(ljm_backlog);
+        self.peak_scans = self.peak_scans.max(total);
+
+        if total >= self.next_warn_scans {
+            self.elevated = true;
+            self.next_warn_scans = total.saturating_mul(2);
+            return Some(HealthEvent::BacklogHigh {
+                run_id,
+                device_backlog,
+                ljm_backlog,
+                threshold_scans: self.threshold_scans,
+            });
+        }
+
+        if self.elevated && total < self.threshold_scans / 2 {
+            let peak_backlog = self.peak_scans;
+            *self = Self::new(self.threshold_scans);
+            return Some(HealthEvent::BacklogRecovered {
+                run_id,
+                device_backlog,
+                ljm_backlog,
+                peak_backlog,
+            });
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: f64 = SKIPPED_SCAN_VALUE;
+
+    #[test]
+    fn splits_batches_around_skipped_scans() {
+        let batch = [1.0, 2.0, S, S, S, S, 3.0, 4.0, 5.0, 6.0];
+        assert_eq!(
+            split_skipped_scans(&batch, 2),
+            vec![
+                BatchSegment::Data {
+                    start_scan: 0,
+                    scans: 1
+                },
+                BatchSegment::Skipped {
+                    start_scan: 1,
+                    scans: 2
+                },
+                BatchSegment::Data {
+                    start_scan: 3,
+                    scans: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn one_channel_at_marker_value_is_not_a_gap() {
+        let batch = [S, 1.0, S, 2.0];
+        assert_eq!(
+            split_skipped_scans(&batch, 2),
+            vec![BatchSegment::Data {
+                start_scan: 0,
+                scans: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn backlog_monitor_warns_on_growth_and_recovery() {
+        let mut monitor = BacklogMonitor::new(100);
+        assert!(monitor.observe(1, 10, 20).is_none());
+        assert!(matches!(
+            monitor.observe(1, 60, 60),
+            Some(HealthEvent::BacklogHigh { .. })
+        ));
+        assert!(monitor.observe(1, 100, 100).is_none());
+        assert!(matches!(
+            monitor.observe(1, 200, 100),
+            Some(HealthEvent::BacklogHigh { .. })
+        ));
+        assert_eq!(
+            monitor.observe(1, 0, 10),
+            Some(HealthEvent::BacklogRecovered {
+                run_id: 1,
+                device_backlog: 0,
+                ljm_backlog: 10,
+                peak_backlog: 300,
+            })
+        );
+        assert!(monitor.observe(1, 0, 10).is_none());
+    }
+}
@@ -22,6 +22,22 @@ pub fn pad_channel(ch: u8) -> String {
     format!("ch{ch:02}")
 }
 
+/// Subject token of a virtual channel computed by the streamer.
+pub fn pad_virtual(id: u8) -> String {
+    format!("vch{id}")
+}
+
+/// Whether a subject's last token names a data channel (`ch11`, `dio0`,
+/// `ctr1`, `freq2`, `quad6`, `vch0`) rather than a source event such as
+/// `health`.
+pub fn is_channel_token(token: &str) -> bool {
+    ["ch", "dio", "ctr", "freq", "quad", "vch"].iter().any(|prefix| {
+        token.strip_prefix(prefix).is_some_and(|number| {
+            !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit())
+        })
+    })
+}
+
 pub fn pad_asset(n: u32) -> String {
     format!("{n:03}")
 }
@@ -30,6 +46,7 @@ fn uses_v1_namespace(nats_subject: &str, box_id: Option<&str>, source_id: Option
     nats_subject.trim() == "avenars" || box_id.is_some() || source_id.is_some()
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn live_labjack_channel_subject(
     nats_subject: &str,
     asset: u32,
@@ -63,6 +80,33 @@ pub fn live_labjack_channel_subject(
     format!("{root}.v1.{box_id}.{source_id}.{}", pad_channel(channel))
 }
 
+/// Companion subject carrying calibrated (engineering unit) values for a
+/// live channel subject. It sits one token below the channel subject, so it is
+/// not captured by the raw JetStream stream and is published on core NATS.
+pub fn calibrated_channel_subject(channel_subject: &str) -> String {
+    format!("{channel_subject}.cal")
+}
+
+/// Filtered companion of a channel subject decimated by `factor`, e.g.
+/// `.ch11.d10`; like `.cal` it is outside the raw JetStream stream.
+pub fn decimated_channel_subject(channel_subject: &str, factor: u32) -> String {
+    format!("{channel_subject}.d{factor}")
+}
+
+/// Source-level subject (e.g. `health`) that sits beside the channel subjects
+/// of the same source, so it is covered by the source's JetStream subject.
+pub fn source_event_subject(channel_subject: &str, event: &str) -> String {
+    match channel_subject.rsplit_once('.') {
+        Some((source, _channel)) => format!("{source}.{event}"),
+        None => event.to_string(),
+    }
+}
+
+/// Source-level subject carrying `ScanFrame` payloads in frame publish mode.
+pub fn frame_subject(channel_subject: &str) -> String {
+    source_event_subject(channel_subject, "frame")
+}
+
 pub fn live_labjack_stream_subject(
     nats_subject: &str,
     site_id: Option<&str>,
@@ -97,6 +141,36 @@ pub fn stream_subject_is_compatible(existing: &str, desired_namespace: &str) ->
     false
 }
 
+/// Subjects a shared JetStream stream should carry once `desired` is added.
+/// Sources of the same box keep their subjects, so several devices can feed
+/// one stream; legacy and other-namespace subjects are replaced.
+pub fn merge_stream_subjects(existing: &[String], desired: &str) -> Vec<String> {
+    let Some(source_prefix) = desired.strip_suffix(".*") else {
+        return vec![desired.to_string()];
+    };
+    let Some((box_prefix, _source)) = source_prefix.rsplit_once('.') else {
+        return vec![desired.to_string()];
+    };
+    if !box_prefix.contains(".v1.") {
+        return vec![desired.to_string()];
+    }
+
+    let box_prefix = format!("{box_prefix}.");
+    let mut merged: Vec<String> = existing
+        .iter()
+        .filter(|subject| {
+            subject.starts_with(&box_prefix)
+                && subject.ends_with(".*")
+                && !subject[box_prefix.len()..subject.len() - 2].contains(['.', '*', '>'])
+        })
+        .cloned()
+        .collect();
+    merged.push(desired.to_string());
+    merged.sort();
+    merged.dedup();
+    merged
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,4 +203,78 @@ mod tests {
             "avenars.v1.i69-mu1.i69-lj2.ch11"
         );
     }
+
+    #[test]
+    fn calibrated_subject_extends_channel_subject() {
+        assert_eq!(
+            calibrated_channel_subject("avenars.v1.i69-mu1.i69-lj2.ch11"),
+            "avenars.v1.i69-mu1.i69-lj2.ch11.cal"
+        );
+    }
+
+    #[test]
+    fn decimated_subject_extends_channel_subject() {
+        assert_eq!(
+            decimated_channel_subject("avenars.v1.i69-mu1.i69-lj2.ch11", 100),
+            "avenars.v1.i69-mu1.i69-lj2.ch11.d100"
+        );
+    }
+
+    #[test]
+    fn source_event_subject_replaces_channel_token() {
+        assert_eq!(
+            source_event_subject("avenars.v1.i69-mu1.i69-lj2.ch11", "health"),
+            "avenars.v1.i69-mu1.i69-lj2.health"
+        );
+        assert_eq!(
+            source_event_subject("avenabox.1456.data.ch11", "health"),
+            "avenabox.1456.data.health"
+        );
+    }
+
+    #[test]
+    fn channel_tokens_cover_analog_and_digital_channels() {
+        for token in ["ch11", "dio0", "ctr1", "freq2", "quad6", "vch0"] {
+            assert!(is_channel_token(token), "{token}");
+        }
+        for token in ["health", "frame", "status", "ctr", "chX"] {
+            assert!(!is_channel_token(token), "{token}");
+        }
+    }
+
+    #[test]
+    fn frame_subject_sits_beside_channel_subjects() {
+        assert_eq!(
+            frame_subject("avenars.v1.i69-mu1.i69-lj2.ch11"),
+            "avenars.v1.i69-mu1.i69-lj2.frame"
+        );
+        assert_eq!(
+            frame_subject("avenabox.1456.data.ch11"),
+            "avenabox.1456.data.frame"
+        );
+    }
+
+    #[test]
+    fn stream_subjects_are_shared_within_a_box() {
+        let existing = vec![
+            "avenars.v1.i69-mu1.i69-lj2.*".to_string(),
+            "avenars.v1.other-box.lj9.*".to_string(),
+            "avenabox.*.data.*".to_string(),
+        ];
+        assert_eq!(
+            merge_stream_subjects(&existing, "avenars.v1.i69-mu1.i69-lj1.*"),
+            vec![
+                "avenars.v1.i69-mu1.i69-lj1.*".to_string(),
+                "avenars.v1.i69-mu1.i69-lj2.*".to_string(),
+            ]
+        );
+        assert_eq!(
+            merge_stream_subjects(&existing, "avenars.v1.i69-mu1.i69-lj2.*"),
+            vec!["avenars.v1.i69-mu1.i69-lj2.*".to_string()]
+        );
+        assert_eq!(
+            merge_stream_subjects(&existing, "avenabox.*.data.*"),
+            vec!["avenabox.*.data.*".to_string()]
+        );
+    }
 }
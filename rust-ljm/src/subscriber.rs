@@ -1,23 +1,26 @@
 use async_nats::{self, ConnectOptions};
-use flatbuffers::root;
 use futures_util::stream::StreamExt;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use tracing::{info, warn};
 
 // Import your generated FlatBuffers schema
 mod sample_data_generated {
     #![allow(dead_code, unused_imports)]
     include!("data_generated.rs"); // path relative to examples/
 }
+mod logging;
 mod nats_config;
+mod scan_frame;
 mod subjects;
-use sample_data_generated::sampler;
+
+use scan_frame::ChannelSamples;
 
 fn extract_channel_token(subject: &str) -> Option<String> {
-    subject.split('.').last().map(|s| s.to_string())
+    subject.split('.').next_back().map(|s| s.to_string())
 }
 
 fn open_csv_for_channel(out_dir: &Path, asset: u32, ch_token: &str) -> std::io::Result<File> {
@@ -32,12 +35,40 @@ fn open_csv_for_channel(out_dir: &Path, asset: u32, ch_token: &str) -> std::io::
     Ok(file)
 }
 
+fn write_samples(
+    file: &mut File,
+    subject: &str,
+    samples: &ChannelSamples,
+) -> std::io::Result<()> {
+    for (index, value) in samples.values.iter().enumerate() {
+        let timestamp_unix_ns = (samples.first_sample_unix_ns as u128).saturating_add(
+            (samples.sample_interval_ns as u128).saturating_mul(index as u128),
+        );
+        let timestamp_unix_ns = match i64::try_from(timestamp_unix_ns) {
+            Ok(ts) => ts,
+            Err(_) => {
+                warn!(
+                    "timestamp overflow for subject '{}' sequence {} sample {}",
+                    subject, samples.sequence, index
+                );
+                break;
+            }
+        };
+        let Some(timestamp) = timestamp_unix_ns_to_rfc3339(timestamp_unix_ns) else {
+            continue;
+        };
+        writeln!(file, "{},{},{}", samples.sequence, timestamp, value)?;
+    }
+    file.flush()
+}
+
 fn timestamp_unix_ns_to_rfc3339(timestamp_unix_ns: i64) -> Option<String> {
     Some(chrono::DateTime::<chrono::Utc>::from_timestamp_nanos(timestamp_unix_ns).to_rfc3339())
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    logging::init()?;
     // match JSON config keys
     let subject_prefix = std::env::var("NATS_SUBJECT").unwrap_or_else(|_| "avenabox".to_string());
     let asset_number: u32 = std::env::var("ASSET_NUMBER")
@@ -54,9 +85,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let out_dir = PathBuf::from(&out_dir_str);
     if !out_dir.exists() {
         std::fs::create_dir_all(&out_dir)?;
-        println!("Created output directory: {}", out_dir.display());
+        info!("Created output directory: {}", out_dir.display());
     } else {
-        println!("Using output directory: {}", out_dir.display());
+        info!("Using output directory: {}", out_dir.display());
     }
 
     // Subscribe to all per-channel subjects for this asset
@@ -68,7 +99,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         source_type.as_deref(),
         source_id.as_deref(),
     );
-    println!("Subscribing to subject '{}'", wildcard);
+    info!("Subscribing to subject '{}'", wildcard);
 
     let servers = nats_config::servers_from_env()
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
@@ -84,7 +115,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .await
         .map_err(|e| format!("NATS connect failed: {}", e))?;
 
-    println!("Connected to NATS with creds, subscribed at '{}'", wildcard);
+    info!("Connected to NATS with creds, subscribed at '{}'", wildcard);
 
     let mut sub = nc.subscribe(wildcard.clone()).await?;
     let mut files: HashMap<String, File> = HashMap::new();
@@ -93,50 +124,36 @@ async fn main() -> Result<(), Box<dyn Error>> {
         let ch_token = match extract_channel_token(&msg.subject) {
             Some(tok) => tok,
             None => {
-                eprintln!("Subject '{}' missing channel token; skipping.", msg.subject);
+                warn!("Subject '{}' missing channel token; skipping.", msg.subject);
                 continue;
             }
         };
 
-        match root::<sampler::Scan>(&msg.payload) {
-            Ok(scan) => {
-                let values = match scan.values() {
-                    Some(values) => values,
-                    None => continue,
-                };
-                let sequence = scan.sequence();
-                let first_sample_unix_ns = scan.first_sample_unix_ns();
-                let sample_interval_ns = scan.sample_interval_ns();
-
-                let out_dir_clone = out_dir.clone();
-                let file = files.entry(ch_token.clone()).or_insert_with(move || {
-                    open_csv_for_channel(&out_dir_clone, asset_number, &ch_token)
-                        .expect("failed to open per-channel csv")
-                });
-
-                for (index, value) in values.iter().enumerate() {
-                    let timestamp_unix_ns = (first_sample_unix_ns as u128)
-                        .saturating_add((sample_interval_ns as u128).saturating_mul(index as u128));
-                    let timestamp_unix_ns = match i64::try_from(timestamp_unix_ns) {
-                        Ok(ts) => ts,
-                        Err(_) => {
-                            eprintln!(
-                                "timestamp overflow for subject '{}' sequence {} sample {}",
-                                msg.subject, sequence, index
-                            );
-                            break;
-                        }
-                    };
-                    let Some(timestamp) = timestamp_unix_ns_to_rfc3339(timestamp_unix_ns) else {
-                        continue;
-                    };
-                    writeln!(file, "{},{},{}", sequence, timestamp, value)?;
+        // Frame payloads carry every channel; split them into the same
+        // per-channel files the per-channel subjects produce.
+        let decoded = if ch_token == "frame" {
+            scan_frame::decode_frame(&msg.payload)
+        } else if subjects::is_channel_token(&ch_token) {
+            scan_frame::decode_scan(&msg.payload).map(|samples| vec![(ch_token, samples)])
+        } else {
+            // Source events such as `.health` share the wildcard.
+            continue;
+        };
+
+        match decoded {
+            Ok(columns) => {
+                for (ch_token, samples) in columns {
+                    let out_dir_clone = out_dir.clone();
+                    let file = files.entry(ch_token.clone()).or_insert_with(move || {
+                        open_csv_for_channel(&out_dir_clone, asset_number, &ch_token)
+                            .expect("failed to open per-channel csv")
+                    });
+                    write_samples(file, &msg.subject, &samples)?;
                 }
-                file.flush()?;
             }
             Err(e) => {
-                eprintln!(
-                    "FlatBuffer decode error ({:?}) for subject '{}'",
+                warn!(
+                    "FlatBuffer decode error ({}) for subject '{}'",
                     e, msg.subject
                 );
             }
@@ -0,0 +1,264 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::clock_quality::ClockCheck;
+use crate::heartbeat::Heartbeat;
+use crate::publisher::env_number;
+
+const DEFAULT_INITIAL_BACKOFF_MS: u64 = 500;
+const DEFAULT_MAX_BACKOFF_MS: u64 = 30_000;
+const DEFAULT_ALERT_ATTEMPTS: u32 = 10;
+/// Each delay is spread by up to this fraction either way, so boxes that lost
+/// the same switch do not all retry in lockstep.
+const JITTER_FRACTION: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first reconnect, doubled on each further failure.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between attempts.
+    pub max_backoff: Duration,
+    /// Consecutive failures before the connection is reported as `failed`.
+    pub alert_attempts: u32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(DEFAULT_INITIAL_BACKOFF_MS),
+            max_backoff: Duration::from_millis(DEFAULT_MAX_BACKOFF_MS),
+            alert_attempts: DEFAULT_ALERT_ATTEMPTS,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn from_env() -> Result<Self, String> {
+        let initial_backoff = Duration::from_millis(env_number(
+            "STREAM_RECONNECT_INITIAL_MS",
+            DEFAULT_INITIAL_BACKOFF_MS,
+            1,
+        )?);
+        let max_backoff = Duration::from_millis(env_number(
+            "STREAM_RECONNECT_MAX_MS",
+            DEFAULT_MAX_BACKOFF_MS,
+            1,
+        )?);
+        if max_backoff < initial_backoff {
+            return Err(format!(
+                "STREAM_RECONNECT_MAX_MS ({} ms) is below STREAM_RECONNECT_INITIAL_MS ({} ms)",
+                max_backoff.as_millis(),
+                initial_backoff.as_millis()
+            ));
+        }
+        Ok(Self {
+            initial_backoff,
+            max_backoff,
+            alert_attempts: env_number(
+                "STREAM_RECONNECT_ALERT_ATTEMPTS",
+                DEFAULT_ALERT_ATTEMPTS,
+                1,
+            )?,
+        })
+    }
+
+    /// Delay before reconnect `attempt` (1-based). `jitter` in `[0, 1)` picks
+    /// a point in the ±`JITTER_FRACTION` band around the exponential delay.
+    pub fn backoff(&self, attempt: u32, jitter: f64) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(31);
+        let base = self
+            .initial_backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff);
+        let scale = 1.0 - JITTER_FRACTION + 2.0 * JITTER_FRACTION * jitter.clamp(0.0, 1.0);
+        base.mul_f64(scale).min(self.max_backoff)
+    }
+}
+
+/// Device state published on the source's `.status` subject.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connected {
+        run_id: usize,
+        serial: i32,
+        device_type: String,
+        backend: String,
+    },
+    Streaming {
+        run_id: usize,
+        scan_rate_hz: f64,
+        channels: usize,
+    },
+    Reconnecting {
+        run_id: usize,
+        attempt: u32,
+        retry_in_ms: u64,
+        error: String,
+    },
+    /// Still retrying, but `alert_attempts` consecutive attempts have failed.
+    Failed {
+        run_id: usize,
+        attempts: u32,
+        retry_in_ms: u64,
+        error: String,
+    },
+    /// Host clock discipline at run start and whenever its quality changes.
+    HostClock {
+        run_id: usize,
+        #[serde(flatten)]
+        check: ClockCheck,
+    },
+    /// Latest device clock estimate while streaming with clock sync enabled.
+    ClockSync {
+        run_id: usize,
+        skew_ppm: f64,
+        drift_ppm_per_hour: Option<f64>,
+        /// Corrected minus uncorrected timestamp of the next scan.
+        correction_ns: i64,
+        samples: usize,
+        round_trip_ns: u64,
+    },
+    /// Sent every `STREAM_STATUS_SECS` during a run.
+    Heartbeat(Heartbeat),
+}
+
+/// What to do after a failed sampler run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Retry {
+    pub state: ConnectionState,
+    pub delay: Duration,
+    /// Set on the failure that first crosses `alert_attempts`.
+    pub alert: bool,
+}
+
+/// Counts consecutive failed sampler runs and paces reconnects.
+#[derive(Debug, Clone)]
+pub struct Supervisor {
+    policy: ReconnectPolicy,
+    failures: u32,
+}
+
+impl Supervisor {
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        Self {
+            policy,
+            failures: 0,
+        }
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// The device is streaming again, so the next failure starts over at the
+    /// initial delay.
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+
+    pub fn on_failure(&mut self, run_id: usize, error: String) -> Retry {
+        self.on_failure_with_jitter(run_id, error, random_unit())
+    }
+
+    fn on_failure_with_jitter(&mut self, run_id: usize, error: String, jitter: f64) -> Retry {
+        self.failures = self.failures.saturating_add(1);
+        let delay = self.policy.backoff(self.failures, jitter);
+        let retry_in_ms = delay.as_millis() as u64;
+        let state = if self.failures >= self.policy.alert_attempts {
+            ConnectionState::Failed {
+                run_id,
+                attempts: self.failures,
+                retry_in_ms,
+                error,
+            }
+        } else {
+            ConnectionState::Reconnecting {
+                run_id,
+                attempt: self.failures,
+                retry_in_ms,
+                error,
+            }
+        };
+        Retry {
+            state,
+            delay,
+            alert: self.failures == self.policy.alert_attempts,
+        }
+    }
+}
+
+/// Uniform value in `[0, 1)` from the v4 UUID generator's OS randomness.
+fn random_unit() -> f64 {
+    let bits = (uuid::Uuid::new_v4().as_u128() >> 75) as u64;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ReconnectPolicy {
+        ReconnectPolicy {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(4),
+            alert_attempts: 3,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = policy();
+        let delays: Vec<u128> = (1..=6)
+            .map(|attempt| policy.backoff(attempt, 0.5).as_millis())
+            .collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 4000, 4000, 4000]);
+    }
+
+    #[test]
+    fn jitter_stays_within_band_and_cap() {
+        let policy = policy();
+        assert_eq!(policy.backoff(1, 0.0), Duration::from_millis(400));
+        assert_eq!(policy.backoff(1, 1.0), Duration::from_millis(600));
+        assert_eq!(policy.backoff(10, 1.0), policy.max_backoff);
+        for _ in 0..100 {
+            let unit = random_unit();
+            assert!((0.0..1.0).contains(&unit));
+        }
+    }
+
+    #[test]
+    fn reports_failed_after_alert_attempts_until_reset() {
+        let mut supervisor = Supervisor::new(policy());
+        let first = supervisor.on_failure_with_jitter(1, "cable".into(), 0.5);
+        assert!(matches!(
+            first.state,
+            ConnectionState::Reconnecting { attempt: 1, retry_in_ms: 500, .. }
+        ));
+        assert!(!first.alert);
+        supervisor.on_failure_with_jitter(2, "cable".into(), 0.5);
+        let third = supervisor.on_failure_with_jitter(3, "cable".into(), 0.5);
+        assert!(matches!(third.state, ConnectionState::Failed { attempts: 3, .. }));
+        assert!(third.alert);
+        let fourth = supervisor.on_failure_with_jitter(4, "cable".into(), 0.5);
+        assert!(matches!(fourth.state, ConnectionState::Failed { attempts: 4, .. }));
+        assert!(!fourth.alert);
+
+        supervisor.reset();
+        let after_reset = supervisor.on_failure_with_jitter(5, "cable".into(), 0.5);
+        assert_eq!(after_reset.delay, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn state_serializes_with_tag() {
+        let json = serde_json::to_value(ConnectionState::Streaming {
+            run_id: 2,
+            scan_rate_hz: 1000.0,
+            channels: 3,
+        })
+        .unwrap();
+        assert_eq!(json["state"], "streaming");
+        assert_eq!(json["channels"], 3);
+    }
+}
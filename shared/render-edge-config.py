@@ -181,6 +181,10 @@ def streamer_env(config: dict) -> dict:
     if stream_max_bytes is not None:
         env["STREAM_MAX_BYTES"] = stream_max_bytes
 
+    streamer_metrics = config.get("metrics", {}).get("streamer_listen")
+    if streamer_metrics:
+        env["METRICS_ADDR"] = streamer_metrics
+
     return {"env": env}
 
 
@@ -188,22 +192,26 @@ def archiver_env(config: dict) -> dict:
     nats = config["nats"]
     paths = config["paths"]
     source = config["source"]
-    return {
-        "env": {
-            "NATS_SUBJECT": nats["root_subject"],
-            "NATS_SERVERS": nats["local_servers"],
-            "JS_DOMAIN": nats["jetstream_domain"],
-            "SITE_ID": config["site_id"],
-            "BOX_ID": config["box_id"],
-            "SOURCE_TYPE": source["type"],
-            "SOURCE_ID": source["id"],
-            "NATS_CREDS_FILE": paths["rust_creds_file"],
-            "CFG_BUCKET": nats["kv_bucket"],
-            "CFG_KEY": nats["kv_key"],
-            "PARQUET_DIR": paths["parquet_dir"],
-        }
+    env = {
+        "NATS_SUBJECT": nats["root_subject"],
+        "NATS_SERVERS": nats["local_servers"],
+        "JS_DOMAIN": nats["jetstream_domain"],
+        "SITE_ID": config["site_id"],
+        "BOX_ID": config["box_id"],
+        "SOURCE_TYPE": source["type"],
+        "SOURCE_ID": source["id"],
+        "NATS_CREDS_FILE": paths["rust_creds_file"],
+        "CFG_BUCKET": nats["kv_bucket"],
+        "CFG_KEY": nats["kv_key"],
+        "PARQUET_DIR": paths["parquet_dir"],
     }
 
+    archiver_metrics = config.get("metrics", {}).get("archiver_listen")
+    if archiver_metrics:
+        env["METRICS_ADDR"] = archiver_metrics
+
+    return {"env": env}
+
 
 def validate(config: dict):
     required = [
@@ -7,6 +7,13 @@ export interface ScanData {
     actualScanRateHz: number;
     sequence: bigint;
     values: Float64Array;
+    // Identity fields; null when the payload predates them.
+    channel: number | null;
+    runId: string | null;
+    deviceSerial: number | null;
+    unit: string | null;
+    calibrationId: string | null;
+    calibrated: boolean;
 }
 
 function nsToMs(timestampNs: bigint): number {
@@ -31,7 +38,13 @@ export class FlatBufferParser {
                 sampleIntervalNs: scan.sampleIntervalNs(),
                 actualScanRateHz: scan.actualScanRateHz(),
                 sequence: scan.sequence(),
-                values
+                values,
+                channel: scan.channel() >= 0 ? scan.channel() : null,
+                runId: scan.runId(),
+                deviceSerial: scan.deviceSerial() !== 0 ? scan.deviceSerial() : null,
+                unit: scan.unit(),
+                calibrationId: scan.calibrationId(),
+                calibrated: scan.calibrated()
             };
         } catch (error) {
             console.error('FlatBuffer parsing error:', error);
@@ -3,3 +3,4 @@
 /* eslint-disable @typescript-eslint/no-unused-vars, @typescript-eslint/no-explicit-any, @typescript-eslint/no-non-null-assertion */
 
 export { Scan, ScanT } from './sampler/scan.js';
+export { ScanFrame, ScanFrameT } from './sampler/scan-frame.js';
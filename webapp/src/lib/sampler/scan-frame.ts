@@ -0,0 +1,424 @@
+// automatically generated by the FlatBuffers compiler, do not modify
+
+/* eslint-disable @typescript-eslint/no-unused-vars, @typescript-eslint/no-explicit-any, @typescript-eslint/no-non-null-assertion */
+
+import * as flatbuffers from 'flatbuffers';
+
+
+
+export class ScanFrame implements flatbuffers.IUnpackableObject<ScanFrameT> {
+  bb: flatbuffers.ByteBuffer|null = null;
+  bb_pos = 0;
+  __init(i:number, bb:flatbuffers.ByteBuffer):ScanFrame {
+  this.bb_pos = i;
+  this.bb = bb;
+  return this;
+}
+
+static getRootAsScanFrame(bb:flatbuffers.ByteBuffer, obj?:ScanFrame):ScanFrame {
+  return (obj || new ScanFrame()).__init(bb.readInt32(bb.position()) + bb.position(), bb);
+}
+
+static getSizePrefixedRootAsScanFrame(bb:flatbuffers.ByteBuffer, obj?:ScanFrame):ScanFrame {
+  bb.setPosition(bb.position() + flatbuffers.SIZE_PREFIX_LENGTH);
+  return (obj || new ScanFrame()).__init(bb.readInt32(bb.position()) + bb.position(), bb);
+}
+
+firstSampleUnixNs():bigint {
+  const offset = this.bb!.__offset(this.bb_pos, 4);
+  return offset ? this.bb!.readUint64(this.bb_pos + offset) : BigInt('0');
+}
+
+sampleIntervalNs():bigint {
+  const offset = this.bb!.__offset(this.bb_pos, 6);
+  return offset ? this.bb!.readUint64(this.bb_pos + offset) : BigInt('0');
+}
+
+actualScanRateHz():number {
+  const offset = this.bb!.__offset(this.bb_pos, 8);
+  return offset ? this.bb!.readFloat64(this.bb_pos + offset) : 0.0;
+}
+
+sequence():bigint {
+  const offset = this.bb!.__offset(this.bb_pos, 10);
+  return offset ? this.bb!.readUint64(this.bb_pos + offset) : BigInt('0');
+}
+
+channels(index: number):number|null {
+  const offset = this.bb!.__offset(this.bb_pos, 12);
+  return offset ? this.bb!.readInt16(this.bb!.__vector(this.bb_pos + offset) + index * 2) : 0;
+}
+
+channelsLength():number {
+  const offset = this.bb!.__offset(this.bb_pos, 12);
+  return offset ? this.bb!.__vector_len(this.bb_pos + offset) : 0;
+}
+
+channelsArray():Int16Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 12);
+  return offset ? new Int16Array(this.bb!.bytes().buffer, this.bb!.bytes().byteOffset + this.bb!.__vector(this.bb_pos + offset), this.bb!.__vector_len(this.bb_pos + offset)) : null;
+}
+
+values(index: number):number|null {
+  const offset = this.bb!.__offset(this.bb_pos, 14);
+  return offset ? this.bb!.readFloat64(this.bb!.__vector(this.bb_pos + offset) + index * 8) : 0;
+}
+
+valuesLength():number {
+  const offset = this.bb!.__offset(this.bb_pos, 14);
+  return offset ? this.bb!.__vector_len(this.bb_pos + offset) : 0;
+}
+
+valuesArray():Float64Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 14);
+  return offset ? new Float64Array(this.bb!.bytes().buffer, this.bb!.bytes().byteOffset + this.bb!.__vector(this.bb_pos + offset), this.bb!.__vector_len(this.bb_pos + offset)) : null;
+}
+
+runId():string|null
+runId(optionalEncoding:flatbuffers.Encoding):string|Uint8Array|null
+runId(optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 16);
+  return offset ? this.bb!.__string(this.bb_pos + offset, optionalEncoding) : null;
+}
+
+deviceSerial():number {
+  const offset = this.bb!.__offset(this.bb_pos, 18);
+  return offset ? this.bb!.readInt32(this.bb_pos + offset) : 0;
+}
+
+units(index: number):string
+units(index: number,optionalEncoding:flatbuffers.Encoding):string|Uint8Array
+units(index: number,optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 20);
+  return offset ? this.bb!.__string(this.bb!.__vector(this.bb_pos + offset) + index * 4, optionalEncoding) : null;
+}
+
+unitsLength():number {
+  const offset = this.bb!.__offset(this.bb_pos, 20);
+  return offset ? this.bb!.__vector_len(this.bb_pos + offset) : 0;
+}
+
+calibrationIds(index: number):string
+calibrationIds(index: number,optionalEncoding:flatbuffers.Encoding):string|Uint8Array
+calibrationIds(index: number,optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 22);
+  return offset ? this.bb!.__string(this.bb!.__vector(this.bb_pos + offset) + index * 4, optionalEncoding) : null;
+}
+
+calibrationIdsLength():number {
+  const offset = this.bb!.__offset(this.bb_pos, 22);
+  return offset ? this.bb!.__vector_len(this.bb_pos + offset) : 0;
+}
+
+calibrated():boolean {
+  const offset = this.bb!.__offset(this.bb_pos, 24);
+  return offset ? !!this.bb!.readInt8(this.bb_pos + offset) : false;
+}
+
+clockQuality():string|null
+clockQuality(optionalEncoding:flatbuffers.Encoding):string|Uint8Array|null
+clockQuality(optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 26);
+  return offset ? this.bb!.__string(this.bb_pos + offset, optionalEncoding) : null;
+}
+
+clockErrorBoundNs():bigint {
+  const offset = this.bb!.__offset(this.bb_pos, 28);
+  return offset ? this.bb!.readInt64(this.bb_pos + offset) : BigInt('-1');
+}
+
+channelNames(index: number):string
+channelNames(index: number,optionalEncoding:flatbuffers.Encoding):string|Uint8Array
+channelNames(index: number,optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 30);
+  return offset ? this.bb!.__string(this.bb!.__vector(this.bb_pos + offset) + index * 4, optionalEncoding) : null;
+}
+
+channelNamesLength():number {
+  const offset = this.bb!.__offset(this.bb_pos, 30);
+  return offset ? this.bb!.__vector_len(this.bb_pos + offset) : 0;
+}
+
+captureId():string|null
+captureId(optionalEncoding:flatbuffers.Encoding):string|Uint8Array|null
+captureId(optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 32);
+  return offset ? this.bb!.__string(this.bb_pos + offset, optionalEncoding) : null;
+}
+
+captureScans():bigint {
+  const offset = this.bb!.__offset(this.bb_pos, 34);
+  return offset ? this.bb!.readUint64(this.bb_pos + offset) : BigInt('0');
+}
+
+preTriggerScans():bigint {
+  const offset = this.bb!.__offset(this.bb_pos, 36);
+  return offset ? this.bb!.readUint64(this.bb_pos + offset) : BigInt('0');
+}
+
+static startScanFrame(builder:flatbuffers.Builder) {
+  builder.startObject(17);
+}
+
+static addFirstSampleUnixNs(builder:flatbuffers.Builder, firstSampleUnixNs:bigint) {
+  builder.addFieldInt64(0, firstSampleUnixNs, BigInt('0'));
+}
+
+static addSampleIntervalNs(builder:flatbuffers.Builder, sampleIntervalNs:bigint) {
+  builder.addFieldInt64(1, sampleIntervalNs, BigInt('0'));
+}
+
+static addActualScanRateHz(builder:flatbuffers.Builder, actualScanRateHz:number) {
+  builder.addFieldFloat64(2, actualScanRateHz, 0.0);
+}
+
+static addSequence(builder:flatbuffers.Builder, sequence:bigint) {
+  builder.addFieldInt64(3, sequence, BigInt('0'));
+}
+
+static addChannels(builder:flatbuffers.Builder, channelsOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(4, channelsOffset, 0);
+}
+
+static createChannelsVector(builder:flatbuffers.Builder, data:number[]|Int16Array):flatbuffers.Offset;
+/**
+ * @deprecated This Uint8Array overload will be removed in the future.
+ */
+static createChannelsVector(builder:flatbuffers.Builder, data:number[]|Uint8Array):flatbuffers.Offset;
+static createChannelsVector(builder:flatbuffers.Builder, data:number[]|Int16Array|Uint8Array):flatbuffers.Offset {
+  builder.startVector(2, data.length, 2);
+  for (let i = data.length - 1; i >= 0; i--) {
+    builder.addInt16(data[i]!);
+  }
+  return builder.endVector();
+}
+
+static startChannelsVector(builder:flatbuffers.Builder, numElems:number) {
+  builder.startVector(2, numElems, 2);
+}
+
+static addValues(builder:flatbuffers.Builder, valuesOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(5, valuesOffset, 0);
+}
+
+static createValuesVector(builder:flatbuffers.Builder, data:number[]|Float64Array):flatbuffers.Offset;
+/**
+ * @deprecated This Uint8Array overload will be removed in the future.
+ */
+static createValuesVector(builder:flatbuffers.Builder, data:number[]|Uint8Array):flatbuffers.Offset;
+static createValuesVector(builder:flatbuffers.Builder, data:number[]|Float64Array|Uint8Array):flatbuffers.Offset {
+  builder.startVector(8, data.length, 8);
+  for (let i = data.length - 1; i >= 0; i--) {
+    builder.addFloat64(data[i]!);
+  }
+  return builder.endVector();
+}
+
+static startValuesVector(builder:flatbuffers.Builder, numElems:number) {
+  builder.startVector(8, numElems, 8);
+}
+
+static addRunId(builder:flatbuffers.Builder, runIdOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(6, runIdOffset, 0);
+}
+
+static addDeviceSerial(builder:flatbuffers.Builder, deviceSerial:number) {
+  builder.addFieldInt32(7, deviceSerial, 0);
+}
+
+static addUnits(builder:flatbuffers.Builder, unitsOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(8, unitsOffset, 0);
+}
+
+static createUnitsVector(builder:flatbuffers.Builder, data:flatbuffers.Offset[]):flatbuffers.Offset {
+  builder.startVector(4, data.length, 4);
+  for (let i = data.length - 1; i >= 0; i--) {
+    builder.addOffset(data[i]!);
+  }
+  return builder.endVector();
+}
+
+static startUnitsVector(builder:flatbuffers.Builder, numElems:number) {
+  builder.startVector(4, numElems, 4);
+}
+
+static addCalibrationIds(builder:flatbuffers.Builder, calibrationIdsOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(9, calibrationIdsOffset, 0);
+}
+
+static createCalibrationIdsVector(builder:flatbuffers.Builder, data:flatbuffers.Offset[]):flatbuffers.Offset {
+  builder.startVector(4, data.length, 4);
+  for (let i = data.length - 1; i >= 0; i--) {
+    builder.addOffset(data[i]!);
+  }
+  return builder.endVector();
+}
+
+static startCalibrationIdsVector(builder:flatbuffers.Builder, numElems:number) {
+  builder.startVector(4, numElems, 4);
+}
+
+static addCalibrated(builder:flatbuffers.Builder, calibrated:boolean) {
+  builder.addFieldInt8(10, +calibrated, +false);
+}
+
+static addClockQuality(builder:flatbuffers.Builder, clockQualityOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(11, clockQualityOffset, 0);
+}
+
+static addClockErrorBoundNs(builder:flatbuffers.Builder, clockErrorBoundNs:bigint) {
+  builder.addFieldInt64(12, clockErrorBoundNs, BigInt('-1'));
+}
+
+static addChannelNames(builder:flatbuffers.Builder, channelNamesOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(13, channelNamesOffset, 0);
+}
+
+static createChannelNamesVector(builder:flatbuffers.Builder, data:flatbuffers.Offset[]):flatbuffers.Offset {
+  builder.startVector(4, data.length, 4);
+  for (let i = data.length - 1; i >= 0; i--) {
+    builder.addOffset(data[i]!);
+  }
+  return builder.endVector();
+}
+
+static startChannelNamesVector(builder:flatbuffers.Builder, numElems:number) {
+  builder.startVector(4, numElems, 4);
+}
+
+static addCaptureId(builder:flatbuffers.Builder, captureIdOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(14, captureIdOffset, 0);
+}
+
+static addCaptureScans(builder:flatbuffers.Builder, captureScans:bigint) {
+  builder.addFieldInt64(15, captureScans, BigInt('0'));
+}
+
+static addPreTriggerScans(builder:flatbuffers.Builder, preTriggerScans:bigint) {
+  builder.addFieldInt64(16, preTriggerScans, BigInt('0'));
+}
+
+static endScanFrame(builder:flatbuffers.Builder):flatbuffers.Offset {
+  const offset = builder.endObject();
+  return offset;
+}
+
+static createScanFrame(builder:flatbuffers.Builder, firstSampleUnixNs:bigint, sampleIntervalNs:bigint, actualScanRateHz:number, sequence:bigint, channelsOffset:flatbuffers.Offset, valuesOffset:flatbuffers.Offset, runIdOffset:flatbuffers.Offset, deviceSerial:number, unitsOffset:flatbuffers.Offset, calibrationIdsOffset:flatbuffers.Offset, calibrated:boolean, clockQualityOffset:flatbuffers.Offset, clockErrorBoundNs:bigint, channelNamesOffset:flatbuffers.Offset, captureIdOffset:flatbuffers.Offset, captureScans:bigint, preTriggerScans:bigint):flatbuffers.Offset {
+  ScanFrame.startScanFrame(builder);
+  ScanFrame.addFirstSampleUnixNs(builder, firstSampleUnixNs);
+  ScanFrame.addSampleIntervalNs(builder, sampleIntervalNs);
+  ScanFrame.addActualScanRateHz(builder, actualScanRateHz);
+  ScanFrame.addSequence(builder, sequence);
+  ScanFrame.addChannels(builder, channelsOffset);
+  ScanFrame.addValues(builder, valuesOffset);
+  ScanFrame.addRunId(builder, runIdOffset);
+  ScanFrame.addDeviceSerial(builder, deviceSerial);
+  ScanFrame.addUnits(builder, unitsOffset);
+  ScanFrame.addCalibrationIds(builder, calibrationIdsOffset);
+  ScanFrame.addCalibrated(builder, calibrated);
+  ScanFrame.addClockQuality(builder, clockQualityOffset);
+  ScanFrame.addClockErrorBoundNs(builder, clockErrorBoundNs);
+  ScanFrame.addChannelNames(builder, channelNamesOffset);
+  ScanFrame.addCaptureId(builder, captureIdOffset);
+  ScanFrame.addCaptureScans(builder, captureScans);
+  ScanFrame.addPreTriggerScans(builder, preTriggerScans);
+  return ScanFrame.endScanFrame(builder);
+}
+
+unpack(): ScanFrameT {
+  return new ScanFrameT(
+    this.firstSampleUnixNs(),
+    this.sampleIntervalNs(),
+    this.actualScanRateHz(),
+    this.sequence(),
+    this.bb!.createScalarList<number>(this.channels.bind(this), this.channelsLength()),
+    this.bb!.createScalarList<number>(this.values.bind(this), this.valuesLength()),
+    this.runId(),
+    this.deviceSerial(),
+    this.bb!.createScalarList<string>(this.units.bind(this), this.unitsLength()),
+    this.bb!.createScalarList<string>(this.calibrationIds.bind(this), this.calibrationIdsLength()),
+    this.calibrated(),
+    this.clockQuality(),
+    this.clockErrorBoundNs(),
+    this.bb!.createScalarList<string>(this.channelNames.bind(this), this.channelNamesLength()),
+    this.captureId(),
+    this.captureScans(),
+    this.preTriggerScans()
+  );
+}
+
+
+unpackTo(_o: ScanFrameT): void {
+  _o.firstSampleUnixNs = this.firstSampleUnixNs();
+  _o.sampleIntervalNs = this.sampleIntervalNs();
+  _o.actualScanRateHz = this.actualScanRateHz();
+  _o.sequence = this.sequence();
+  _o.channels = this.bb!.createScalarList<number>(this.channels.bind(this), this.channelsLength());
+  _o.values = this.bb!.createScalarList<number>(this.values.bind(this), this.valuesLength());
+  _o.runId = this.runId();
+  _o.deviceSerial = this.deviceSerial();
+  _o.units = this.bb!.createScalarList<string>(this.units.bind(this), this.unitsLength());
+  _o.calibrationIds = this.bb!.createScalarList<string>(this.calibrationIds.bind(this), this.calibrationIdsLength());
+  _o.calibrated = this.calibrated();
+  _o.clockQuality = this.clockQuality();
+  _o.clockErrorBoundNs = this.clockErrorBoundNs();
+  _o.channelNames = this.bb!.createScalarList<string>(this.channelNames.bind(this), this.channelNamesLength());
+  _o.captureId = this.captureId();
+  _o.captureScans = this.captureScans();
+  _o.preTriggerScans = this.preTriggerScans();
+}
+}
+
+export class ScanFrameT implements flatbuffers.IGeneratedObject {
+constructor(
+  public firstSampleUnixNs: bigint = BigInt('0'),
+  public sampleIntervalNs: bigint = BigInt('0'),
+  public actualScanRateHz: number = 0.0,
+  public sequence: bigint = BigInt('0'),
+  public channels: (number)[] = [],
+  public values: (number)[] = [],
+  public runId: string|Uint8Array|null = null,
+  public deviceSerial: number = 0,
+  public units: (string)[] = [],
+  public calibrationIds: (string)[] = [],
+  public calibrated: boolean = false,
+  public clockQuality: string|Uint8Array|null = null,
+  public clockErrorBoundNs: bigint = BigInt('-1'),
+  public channelNames: (string)[] = [],
+  public captureId: string|Uint8Array|null = null,
+  public captureScans: bigint = BigInt('0'),
+  public preTriggerScans: bigint = BigInt('0')
+){}
+
+
+pack(builder:flatbuffers.Builder): flatbuffers.Offset {
+  const channels = ScanFrame.createChannelsVector(builder, this.channels);
+  const values = ScanFrame.createValuesVector(builder, this.values);
+  const runId = (this.runId !== null ? builder.createString(this.runId!) : 0);
+  const units = ScanFrame.createUnitsVector(builder, builder.createObjectOffsetList(this.units));
+  const calibrationIds = ScanFrame.createCalibrationIdsVector(builder, builder.createObjectOffsetList(this.calibrationIds));
+  const clockQuality = (this.clockQuality !== null ? builder.createString(this.clockQuality!) : 0);
+  const channelNames = ScanFrame.createChannelNamesVector(builder, builder.createObjectOffsetList(this.channelNames));
+  const captureId = (this.captureId !== null ? builder.createString(this.captureId!) : 0);
+
+  return ScanFrame.createScanFrame(builder,
+    this.firstSampleUnixNs,
+    this.sampleIntervalNs,
+    this.actualScanRateHz,
+    this.sequence,
+    channels,
+    values,
+    runId,
+    this.deviceSerial,
+    units,
+    calibrationIds,
+    this.calibrated,
+    clockQuality,
+    this.clockErrorBoundNs,
+    channelNames,
+    captureId,
+    this.captureScans,
+    this.preTriggerScans
+  );
+}
+}
@@ -59,8 +59,98 @@ valuesArray():Float64Array|null {
   return offset ? new Float64Array(this.bb!.bytes().buffer, this.bb!.bytes().byteOffset + this.bb!.__vector(this.bb_pos + offset), this.bb!.__vector_len(this.bb_pos + offset)) : null;
 }
 
+channel():number {
+  const offset = this.bb!.__offset(this.bb_pos, 14);
+  return offset ? this.bb!.readInt16(this.bb_pos + offset) : -1;
+}
+
+runId():string|null
+runId(optionalEncoding:flatbuffers.Encoding):string|Uint8Array|null
+runId(optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 16);
+  return offset ? this.bb!.__string(this.bb_pos + offset, optionalEncoding) : null;
+}
+
+deviceSerial():number {
+  const offset = this.bb!.__offset(this.bb_pos, 18);
+  return offset ? this.bb!.readInt32(this.bb_pos + offset) : 0;
+}
+
+unit():string|null
+unit(optionalEncoding:flatbuffers.Encoding):string|Uint8Array|null
+unit(optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 20);
+  return offset ? this.bb!.__string(this.bb_pos + offset, optionalEncoding) : null;
+}
+
+calibrationId():string|null
+calibrationId(optionalEncoding:flatbuffers.Encoding):string|Uint8Array|null
+calibrationId(optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 22);
+  return offset ? this.bb!.__string(this.bb_pos + offset, optionalEncoding) : null;
+}
+
+calibrated():boolean {
+  const offset = this.bb!.__offset(this.bb_pos, 24);
+  return offset ? !!this.bb!.readInt8(this.bb_pos + offset) : false;
+}
+
+clockQuality():string|null
+clockQuality(optionalEncoding:flatbuffers.Encoding):string|Uint8Array|null
+clockQuality(optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 26);
+  return offset ? this.bb!.__string(this.bb_pos + offset, optionalEncoding) : null;
+}
+
+clockErrorBoundNs():bigint {
+  const offset = this.bb!.__offset(this.bb_pos, 28);
+  return offset ? this.bb!.readInt64(this.bb_pos + offset) : BigInt('-1');
+}
+
+channelName():string|null
+channelName(optionalEncoding:flatbuffers.Encoding):string|Uint8Array|null
+channelName(optionalEncoding?:any):string|Uint8Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 30);
+  return offset ? this.bb!.__string(this.bb_pos + offset, optionalEncoding) : null;
+}
+
+decimation():number {
+  const offset = this.bb!.__offset(this.bb_pos, 32);
+  return offset ? this.bb!.readUint32(this.bb_pos + offset) : 1;
+}
+
+minValues(index: number):number|null {
+  const offset = this.bb!.__offset(this.bb_pos, 34);
+  return offset ? this.bb!.readFloat64(this.bb!.__vector(this.bb_pos + offset) + index * 8) : 0;
+}
+
+minValuesLength():number {
+  const offset = this.bb!.__offset(this.bb_pos, 34);
+  return offset ? this.bb!.__vector_len(this.bb_pos + offset) : 0;
+}
+
+minValuesArray():Float64Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 34);
+  return offset ? new Float64Array(this.bb!.bytes().buffer, this.bb!.bytes().byteOffset + this.bb!.__vector(this.bb_pos + offset), this.bb!.__vector_len(this.bb_pos + offset)) : null;
+}
+
+maxValues(index: number):number|null {
+  const offset = this.bb!.__offset(this.bb_pos, 36);
+  return offset ? this.bb!.readFloat64(this.bb!.__vector(this.bb_pos + offset) + index * 8) : 0;
+}
+
+maxValuesLength():number {
+  const offset = this.bb!.__offset(this.bb_pos, 36);
+  return offset ? this.bb!.__vector_len(this.bb_pos + offset) : 0;
+}
+
+maxValuesArray():Float64Array|null {
+  const offset = this.bb!.__offset(this.bb_pos, 36);
+  return offset ? new Float64Array(this.bb!.bytes().buffer, this.bb!.bytes().byteOffset + this.bb!.__vector(this.bb_pos + offset), this.bb!.__vector_len(this.bb_pos + offset)) : null;
+}
+
 static startScan(builder:flatbuffers.Builder) {
-  builder.startObject(5);
+  builder.startObject(17);
 }
 
 static addFirstSampleUnixNs(builder:flatbuffers.Builder, firstSampleUnixNs:bigint) {
@@ -100,6 +190,88 @@ static startValuesVector(builder:flatbuffers.Builder, numElems:number) {
   builder.startVector(8, numElems, 8);
 }
 
+static addChannel(builder:flatbuffers.Builder, channel:number) {
+  builder.addFieldInt16(5, channel, -1);
+}
+
+static addRunId(builder:flatbuffers.Builder, runIdOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(6, runIdOffset, 0);
+}
+
+static addDeviceSerial(builder:flatbuffers.Builder, deviceSerial:number) {
+  builder.addFieldInt32(7, deviceSerial, 0);
+}
+
+static addUnit(builder:flatbuffers.Builder, unitOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(8, unitOffset, 0);
+}
+
+static addCalibrationId(builder:flatbuffers.Builder, calibrationIdOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(9, calibrationIdOffset, 0);
+}
+
+static addCalibrated(builder:flatbuffers.Builder, calibrated:boolean) {
+  builder.addFieldInt8(10, +calibrated, +false);
+}
+
+static addClockQuality(builder:flatbuffers.Builder, clockQualityOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(11, clockQualityOffset, 0);
+}
+
+static addClockErrorBoundNs(builder:flatbuffers.Builder, clockErrorBoundNs:bigint) {
+  builder.addFieldInt64(12, clockErrorBoundNs, BigInt('-1'));
+}
+
+static addChannelName(builder:flatbuffers.Builder, channelNameOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(13, channelNameOffset, 0);
+}
+
+static addDecimation(builder:flatbuffers.Builder, decimation:number) {
+  builder.addFieldInt32(14, decimation, 1);
+}
+
+static addMinValues(builder:flatbuffers.Builder, minValuesOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(15, minValuesOffset, 0);
+}
+
+static createMinValuesVector(builder:flatbuffers.Builder, data:number[]|Float64Array):flatbuffers.Offset;
+/**
+ * @deprecated This Uint8Array overload will be removed in the future.
+ */
+static createMinValuesVector(builder:flatbuffers.Builder, data:number[]|Uint8Array):flatbuffers.Offset;
+static createMinValuesVector(builder:flatbuffers.Builder, data:number[]|Float64Array|Uint8Array):flatbuffers.Offset {
+  builder.startVector(8, data.length, 8);
+  for (let i = data.length - 1; i >= 0; i--) {
+    builder.addFloat64(data[i]!);
+  }
+  return builder.endVector();
+}
+
+static startMinValuesVector(builder:flatbuffers.Builder, numElems:number) {
+  builder.startVector(8, numElems, 8);
+}
+
+static addMaxValues(builder:flatbuffers.Builder, maxValuesOffset:flatbuffers.Offset) {
+  builder.addFieldOffset(16, maxValuesOffset, 0);
+}
+
+static createMaxValuesVector(builder:flatbuffers.Builder, data:number[]|Float64Array):flatbuffers.Offset;
+/**
+ * @deprecated This Uint8Array overload will be removed in the future.
+ */
+static createMaxValuesVector(builder:flatbuffers.Builder, data:number[]|Uint8Array):flatbuffers.Offset;
+static createMaxValuesVector(builder:flatbuffers.Builder, data:number[]|Float64Array|Uint8Array):flatbuffers.Offset {
+  builder.startVector(8, data.length, 8);
+  for (let i = data.length - 1; i >= 0; i--) {
+    builder.addFloat64(data[i]!);
+  }
+  return builder.endVector();
+}
+
+static startMaxValuesVector(builder:flatbuffers.Builder, numElems:number) {
+  builder.startVector(8, numElems, 8);
+}
+
 static endScan(builder:flatbuffers.Builder):flatbuffers.Offset {
   const offset = builder.endObject();
   return offset;
@@ -113,13 +285,25 @@ static finishSizePrefixedScanBuffer(builder:flatbuffers.Builder, offset:flatbuff
   builder.finish(offset, undefined, true);
 }
 
-static createScan(builder:flatbuffers.Builder, firstSampleUnixNs:bigint, sampleIntervalNs:bigint, actualScanRateHz:number, sequence:bigint, valuesOffset:flatbuffers.Offset):flatbuffers.Offset {
+static createScan(builder:flatbuffers.Builder, firstSampleUnixNs:bigint, sampleIntervalNs:bigint, actualScanRateHz:number, sequence:bigint, valuesOffset:flatbuffers.Offset, channel:number, runIdOffset:flatbuffers.Offset, deviceSerial:number, unitOffset:flatbuffers.Offset, calibrationIdOffset:flatbuffers.Offset, calibrated:boolean, clockQualityOffset:flatbuffers.Offset, clockErrorBoundNs:bigint, channelNameOffset:flatbuffers.Offset, decimation:number, minValuesOffset:flatbuffers.Offset, maxValuesOffset:flatbuffers.Offset):flatbuffers.Offset {
   Scan.startScan(builder);
   Scan.addFirstSampleUnixNs(builder, firstSampleUnixNs);
   Scan.addSampleIntervalNs(builder, sampleIntervalNs);
   Scan.addActualScanRateHz(builder, actualScanRateHz);
   Scan.addSequence(builder, sequence);
   Scan.addValues(builder, valuesOffset);
+  Scan.addChannel(builder, channel);
+  Scan.addRunId(builder, runIdOffset);
+  Scan.addDeviceSerial(builder, deviceSerial);
+  Scan.addUnit(builder, unitOffset);
+  Scan.addCalibrationId(builder, calibrationIdOffset);
+  Scan.addCalibrated(builder, calibrated);
+  Scan.addClockQuality(builder, clockQualityOffset);
+  Scan.addClockErrorBoundNs(builder, clockErrorBoundNs);
+  Scan.addChannelName(builder, channelNameOffset);
+  Scan.addDecimation(builder, decimation);
+  Scan.addMinValues(builder, minValuesOffset);
+  Scan.addMaxValues(builder, maxValuesOffset);
   return Scan.endScan(builder);
 }
 
@@ -129,7 +313,19 @@ unpack(): ScanT {
     this.sampleIntervalNs(),
     this.actualScanRateHz(),
     this.sequence(),
-    this.bb!.createScalarList<number>(this.values.bind(this), this.valuesLength())
+    this.bb!.createScalarList<number>(this.values.bind(this), this.valuesLength()),
+    this.channel(),
+    this.runId(),
+    this.deviceSerial(),
+    this.unit(),
+    this.calibrationId(),
+    this.calibrated(),
+    this.clockQuality(),
+    this.clockErrorBoundNs(),
+    this.channelName(),
+    this.decimation(),
+    this.bb!.createScalarList<number>(this.minValues.bind(this), this.minValuesLength()),
+    this.bb!.createScalarList<number>(this.maxValues.bind(this), this.maxValuesLength())
   );
 }
 
@@ -140,6 +336,18 @@ unpackTo(_o: ScanT): void {
   _o.actualScanRateHz = this.actualScanRateHz();
   _o.sequence = this.sequence();
   _o.values = this.bb!.createScalarList<number>(this.values.bind(this), this.valuesLength());
+  _o.channel = this.channel();
+  _o.runId = this.runId();
+  _o.deviceSerial = this.deviceSerial();
+  _o.unit = this.unit();
+  _o.calibrationId = this.calibrationId();
+  _o.calibrated = this.calibrated();
+  _o.clockQuality = this.clockQuality();
+  _o.clockErrorBoundNs = this.clockErrorBoundNs();
+  _o.channelName = this.channelName();
+  _o.decimation = this.decimation();
+  _o.minValues = this.bb!.createScalarList<number>(this.minValues.bind(this), this.minValuesLength());
+  _o.maxValues = this.bb!.createScalarList<number>(this.maxValues.bind(this), this.maxValuesLength());
 }
 }
 
@@ -149,19 +357,50 @@ constructor(
   public sampleIntervalNs: bigint = BigInt('0'),
   public actualScanRateHz: number = 0.0,
   public sequence: bigint = BigInt('0'),
-  public values: (number)[] = []
+  public values: (number)[] = [],
+  public channel: number = -1,
+  public runId: string|Uint8Array|null = null,
+  public deviceSerial: number = 0,
+  public unit: string|Uint8Array|null = null,
+  public calibrationId: string|Uint8Array|null = null,
+  public calibrated: boolean = false,
+  public clockQuality: string|Uint8Array|null = null,
+  public clockErrorBoundNs: bigint = BigInt('-1'),
+  public channelName: string|Uint8Array|null = null,
+  public decimation: number = 1,
+  public minValues: (number)[] = [],
+  public maxValues: (number)[] = []
 ){}
 
 
 pack(builder:flatbuffers.Builder): flatbuffers.Offset {
   const values = Scan.createValuesVector(builder, this.values);
+  const runId = (this.runId !== null ? builder.createString(this.runId!) : 0);
+  const unit = (this.unit !== null ? builder.createString(this.unit!) : 0);
+  const calibrationId = (this.calibrationId !== null ? builder.createString(this.calibrationId!) : 0);
+  const clockQuality = (this.clockQuality !== null ? builder.createString(this.clockQuality!) : 0);
+  const channelName = (this.channelName !== null ? builder.createString(this.channelName!) : 0);
+  const minValues = Scan.createMinValuesVector(builder, this.minValues);
+  const maxValues = Scan.createMaxValuesVector(builder, this.maxValues);
 
   return Scan.createScan(builder,
     this.firstSampleUnixNs,
     this.sampleIntervalNs,
     this.actualScanRateHz,
     this.sequence,
-    values
+    values,
+    this.channel,
+    runId,
+    this.deviceSerial,
+    unit,
+    calibrationId,
+    this.calibrated,
+    clockQuality,
+    this.clockErrorBoundNs,
+    channelName,
+    this.decimation,
+    minValues,
+    maxValues
   );
 }
 }